@@ -0,0 +1,152 @@
+// Document-level file attachments (the EmbeddedFiles name tree). They belong
+// to no page, so they are not copied with the pages; the attachment policy
+// decides which parts get them. Attachments are read once into plain objects
+// so worker threads can add them without the source document.
+
+const { PDFArray, PDFDict, PDFHexString, PDFName, PDFRawStream, PDFString, decodePDFRawStream } = require('pdf-lib');
+
+// Which parts get the attachments: all parts, only the first part, or none
+const ATTACHMENT_POLICIES = ['all', 'first', 'none'];
+const DEFAULT_ATTACHMENT_POLICY = 'all';
+
+/**
+ * @param {*} value PDF object
+ * @returns {string|null} Text of a PDF string, or null for anything else
+ */
+function decodeString(value) {
+  return value instanceof PDFString || value instanceof PDFHexString ? value.decodeText() : null;
+}
+
+/**
+ * Reads the attachments of a document
+ *
+ * @param {PDFDocument} pdf Loaded document
+ * @returns {{attachments: Array<Object>, unreadable: Array<string>}} Attachments with name, fileName, description,
+ *   mimeType and data, and the names of those whose data could not be decoded
+ */
+function readAttachments(pdf) {
+  const attachments = [];
+  const unreadable = [];
+  const names = pdf.catalog.lookupMaybe(PDFName.of('Names'), PDFDict);
+  const tree = names && names.lookupMaybe(PDFName.of('EmbeddedFiles'), PDFDict);
+
+  const readFileSpec = (name, fileSpec) => {
+    const embeddedFiles = fileSpec.lookupMaybe(PDFName.of('EF'), PDFDict);
+    const stream = embeddedFiles && (embeddedFiles.lookup(PDFName.of('UF')) || embeddedFiles.lookup(PDFName.of('F')));
+    if (!(stream instanceof PDFRawStream)) {
+      unreadable.push(name);
+      return;
+    }
+    let data;
+    try {
+      data = decodePDFRawStream(stream).decode();
+    } catch (error) {
+      unreadable.push(name);
+      return;
+    }
+    const subtype = stream.dict.lookup(PDFName.of('Subtype'));
+    attachments.push({
+      name,
+      fileName: decodeString(fileSpec.lookup(PDFName.of('UF'))) || decodeString(fileSpec.lookup(PDFName.of('F'))) || name,
+      description: decodeString(fileSpec.lookup(PDFName.of('Desc'))),
+      mimeType: subtype instanceof PDFName ? subtype.decodeText() : null,
+      data
+    });
+  };
+
+  // Damaged files can contain loops, so every node is read only once
+  const visited = new Set();
+  const readTree = (node) => {
+    if (!node || visited.has(node)) {
+      return;
+    }
+    visited.add(node);
+    const entries = node.lookupMaybe(PDFName.of('Names'), PDFArray);
+    for (let i = 0; entries && i + 1 < entries.size(); i += 2) {
+      const name = decodeString(entries.lookup(i));
+      const fileSpec = entries.lookup(i + 1);
+      if (name !== null && fileSpec instanceof PDFDict) {
+        readFileSpec(name, fileSpec);
+      }
+    }
+    const kids = node.lookupMaybe(PDFName.of('Kids'), PDFArray);
+    for (let i = 0; kids && i < kids.size(); i++) {
+      readTree(kids.lookupMaybe(i, PDFDict));
+    }
+  };
+  readTree(tree);
+
+  return { attachments, unreadable };
+}
+
+/**
+ * Chooses the attachments of one part
+ *
+ * @param {Array<Object>} attachments Attachments read with readAttachments
+ * @param {number} index 1-based part number
+ * @param {string} [policy] Attachment policy (see ATTACHMENT_POLICIES)
+ * @returns {Array<Object>} Attachments to add to the part
+ */
+function partAttachments(attachments, index, policy = DEFAULT_ATTACHMENT_POLICY) {
+  if (policy === 'all' || (policy === 'first' && index === 1)) {
+    return attachments;
+  }
+  return [];
+}
+
+/**
+ * Describes what the attachment policy did, for the split warnings
+ *
+ * @param {{attachments: Array<Object>, unreadable: Array<string>}} sourceAttachments Result of readAttachments
+ * @param {string} [policy] Attachment policy (see ATTACHMENT_POLICIES)
+ * @returns {Array<{code: string, message: string}>} Warnings, empty when the source has no attachments
+ */
+function attachmentWarnings(sourceAttachments, policy = DEFAULT_ATTACHMENT_POLICY) {
+  const warnings = [];
+  const { attachments, unreadable } = sourceAttachments;
+  if (attachments.length > 0) {
+    const list = attachments.map(attachment => attachment.fileName).join(', ');
+    const messages = {
+      all: `Copied ${attachments.length} attachment(s) into every part: ${list}`,
+      first: `Copied ${attachments.length} attachment(s) into the first part only: ${list}`,
+      none: `Dropped ${attachments.length} attachment(s): ${list}`
+    };
+    warnings.push({ code: 'attachments', message: messages[policy] });
+  }
+  if (unreadable.length > 0) {
+    warnings.push({
+      code: 'attachmentsUnreadable',
+      message: `Dropped ${unreadable.length} attachment(s) whose data could not be read: ${unreadable.join(', ')}`
+    });
+  }
+  return warnings;
+}
+
+/**
+ * Adds attachments to a new document
+ *
+ * @param {PDFDocument} pdf Document to update
+ * @param {Array<Object>} attachments Attachments chosen with partAttachments
+ * @param {string|null} [timestamp] Creation and modification date of the attachments as an ISO string,
+ *   instead of the current time
+ */
+async function writeAttachments(pdf, attachments, timestamp = null) {
+  const date = timestamp ? new Date(timestamp) : undefined;
+  for (const attachment of attachments) {
+    await pdf.attach(attachment.data, attachment.fileName, {
+      mimeType: attachment.mimeType || undefined,
+      description: attachment.description || undefined,
+      creationDate: date,
+      modificationDate: date
+    });
+  }
+}
+
+module.exports = {
+  ATTACHMENT_POLICIES,
+  DEFAULT_ATTACHMENT_POLICY,
+  readAttachments,
+  partAttachments,
+  attachmentWarnings,
+  writeAttachments
+};
@@ -0,0 +1,49 @@
+// Registry of PDF backends. Every backend implements the same interface:
+//
+// - open(sourceBytes): parses the source and returns an opaque source handle
+//   whose `pdf` property is a pdf-lib PDFDocument for read-only inspection
+// - pageCount(source): number of pages in the source
+// - copyPages(source, pageIndices, copyOptions): new pdf-lib PDFDocument with
+//   copies of the pages, calling the optional copyOptions.onPageCopied with the
+//   number of pages copied so far as it goes and stopping with a cancellation
+//   error (see throwIfCancelled in src/part-builder.js) once the optional
+//   copyOptions.signal fires
+// - save(partPdf): serialized bytes of a part
+//
+// Optionally, a backend can implement:
+//
+// - close(source): releases resources held by an opened source
+// - share(source) / fromShared(sharedSource): hands an opened source to
+//   worker threads instead of each worker opening the source bytes again
+
+const pdfLibBackend = require('./pdf-lib');
+const qpdfBackend = require('./qpdf');
+
+const BACKENDS = {
+  [pdfLibBackend.name]: pdfLibBackend,
+  [qpdfBackend.name]: qpdfBackend
+};
+
+const DEFAULT_BACKEND = pdfLibBackend.name;
+
+/**
+ * Looks up a backend by name
+ *
+ * @param {string} [name] Backend name (defaults to pdf-lib)
+ * @returns {Object} Backend implementation
+ */
+function getBackend(name = DEFAULT_BACKEND) {
+  const backend = BACKENDS[name];
+  if (!backend) {
+    const error = new Error(`Unknown backend: ${name}. Available backends: ${Object.keys(BACKENDS).join(', ')}`);
+    error.code = 2; // Exit code 2 for invalid CLI arguments
+    throw error;
+  }
+  return backend;
+}
+
+module.exports = {
+  BACKENDS,
+  DEFAULT_BACKEND,
+  getBackend
+};
@@ -0,0 +1,72 @@
+// Default backend: pdf-lib, a pure JavaScript PDF library with no native dependencies.
+
+const { PDFDocument, PDFObjectCopier, PDFPage } = require('pdf-lib');
+const { copyForms } = require('../forms');
+const { throwIfCancelled } = require('../part-builder');
+
+/**
+ * Parses the source PDF
+ *
+ * @param {Uint8Array} sourceBytes Bytes of the source PDF
+ * @returns {Promise<{pdf: PDFDocument}>} Opened source
+ */
+async function open(sourceBytes) {
+  const pdf = await PDFDocument.load(sourceBytes);
+  return { pdf };
+}
+
+/**
+ * @param {{pdf: PDFDocument}} source Opened source
+ * @returns {number} Number of pages in the source
+ */
+function pageCount(source) {
+  return source.pdf.getPageCount();
+}
+
+/**
+ * Creates a new document holding copies of the given source pages
+ *
+ * @param {{pdf: PDFDocument}} source Opened source
+ * @param {Array<number>} pageIndices 0-based page indices, in output order
+ * @param {Object} [copyOptions] Copy options
+ * @param {Function} [copyOptions.onPageCopied] Called with the number of pages copied so far after each page
+ * @param {AbortSignal} [copyOptions.signal] Signal cancelling the copy between pages
+ * @returns {Promise<PDFDocument>} New document with the copied pages
+ */
+async function copyPages(source, pageIndices, copyOptions = {}) {
+  const partPdf = await PDFDocument.create();
+
+  // Copy the pages one by one, as PDFDocument#copyPages does, to report each
+  // of them; a single copier copies the resources they share only once
+  await source.pdf.flush();
+  const copier = PDFObjectCopier.for(source.pdf.context, partPdf.context);
+  const sourcePages = source.pdf.getPages();
+  for (const [position, pageIndex] of pageIndices.entries()) {
+    throwIfCancelled(copyOptions.signal);
+    const pageNode = copier.copy(sourcePages[pageIndex].node);
+    partPdf.addPage(PDFPage.of(pageNode, partPdf.context.register(pageNode), partPdf));
+    if (copyOptions.onPageCopied) {
+      copyOptions.onPageCopied(position + 1);
+    }
+  }
+  // Keep annotations linked to their pages and widgets working as form fields
+  copyForms(source.pdf, partPdf);
+  return partPdf;
+}
+
+/**
+ * @param {PDFDocument} partPdf Document to serialize
+ * @param {Object} [saveOptions] pdf-lib save options, e.g. useObjectStreams
+ * @returns {Promise<Uint8Array>} Bytes of the saved document
+ */
+async function save(partPdf, saveOptions = {}) {
+  return partPdf.save(saveOptions);
+}
+
+module.exports = {
+  name: 'pdf-lib',
+  open,
+  pageCount,
+  copyPages,
+  save
+};
@@ -0,0 +1,135 @@
+// qpdf backend: qpdf rewrites (and thereby repairs) the source and extracts
+// the pages of each part. qpdf copes with some damaged or unusual files that
+// pdf-lib rejects. The extracted parts are loaded with pdf-lib afterwards so
+// they can be processed like parts of any other backend.
+
+const os = require('os');
+const path = require('path');
+const fs = require('fs');
+const crypto = require('crypto');
+const { PDFDocument } = require('pdf-lib');
+const { runQpdf, toQpdfPageRange } = require('../qpdf');
+const { throwIfCancelled } = require('../part-builder');
+
+// Temporary directories owned by this thread, removed when it exits
+const ownedTempDirs = new Set();
+process.once('exit', () => {
+  for (const tempDir of ownedTempDirs) {
+    fs.rmSync(tempDir, { recursive: true, force: true });
+  }
+});
+
+/**
+ * Writes the source to a temporary directory and lets qpdf rewrite it
+ *
+ * @param {Uint8Array} sourceBytes Bytes of the source PDF
+ * @returns {Promise<Object>} Opened source
+ */
+async function open(sourceBytes) {
+  const tempDir = await fs.promises.mkdtemp(path.join(os.tmpdir(), 'splitpdf-qpdf-'));
+  ownedTempDirs.add(tempDir);
+
+  const originalPath = path.join(tempDir, 'original.pdf');
+  const sourcePath = path.join(tempDir, 'source.pdf');
+  await fs.promises.writeFile(originalPath, sourceBytes);
+  await runQpdf([originalPath, sourcePath]);
+
+  const pdf = await PDFDocument.load(await fs.promises.readFile(sourcePath));
+  return { pdf, sourcePath, tempDir, owned: true };
+}
+
+/**
+ * Describes an opened source so worker threads can use it without rewriting it again
+ *
+ * @param {Object} source Opened source
+ * @returns {Object} Structured-cloneable description of the source
+ */
+function share(source) {
+  return { sourcePath: source.sourcePath, tempDir: source.tempDir };
+}
+
+/**
+ * Uses a source opened (and owned) by another thread
+ *
+ * @param {Object} sharedSource Description created by share()
+ * @returns {Object} Source that can be used to copy pages
+ */
+function fromShared(sharedSource) {
+  return { pdf: null, sourcePath: sharedSource.sourcePath, tempDir: sharedSource.tempDir, owned: false };
+}
+
+/**
+ * @param {Object} source Opened source
+ * @returns {number} Number of pages in the source
+ */
+function pageCount(source) {
+  return source.pdf.getPageCount();
+}
+
+/**
+ * Extracts pages with qpdf into a new document
+ *
+ * @param {Object} source Opened source
+ * @param {Array<number>} pageIndices 0-based page indices, in output order
+ * @param {Object} [copyOptions] Copy options
+ * @param {Function} [copyOptions.onPageCopied] Called with the number of pages copied once qpdf has copied them all
+ * @param {AbortSignal} [copyOptions.signal] Signal killing qpdf, cancelling the copy
+ * @returns {Promise<PDFDocument>} New document with the copied pages
+ */
+async function copyPages(source, pageIndices, copyOptions = {}) {
+  const pageNumbers = [];
+  for (const pageIndex of pageIndices) {
+    pageNumbers.push(pageIndex + 1);
+  }
+
+  const partPath = path.join(source.tempDir, `part-${crypto.randomUUID()}.pdf`);
+  try {
+    try {
+      await runQpdf(['--empty', '--pages', source.sourcePath, toQpdfPageRange(pageNumbers), '--', partPath], { signal: copyOptions.signal });
+    } catch (error) {
+      // qpdf killed by a cancellation fails like any other run
+      throwIfCancelled(copyOptions.signal);
+      throw error;
+    }
+    const partPdf = await PDFDocument.load(await fs.promises.readFile(partPath));
+    // qpdf copies every page in one run
+    if (copyOptions.onPageCopied) {
+      copyOptions.onPageCopied(pageIndices.length);
+    }
+    return partPdf;
+  } finally {
+    await fs.promises.rm(partPath, { force: true });
+  }
+}
+
+/**
+ * @param {PDFDocument} partPdf Document to serialize
+ * @param {Object} [saveOptions] pdf-lib save options, e.g. useObjectStreams
+ * @returns {Promise<Uint8Array>} Bytes of the saved document
+ */
+async function save(partPdf, saveOptions = {}) {
+  return partPdf.save(saveOptions);
+}
+
+/**
+ * Removes the temporary files of a source opened by this thread
+ *
+ * @param {Object} source Opened source
+ */
+async function close(source) {
+  if (source.owned) {
+    await fs.promises.rm(source.tempDir, { recursive: true, force: true });
+    ownedTempDirs.delete(source.tempDir);
+  }
+}
+
+module.exports = {
+  name: 'qpdf',
+  open,
+  share,
+  fromShared,
+  pageCount,
+  copyPages,
+  save,
+  close
+};
@@ -0,0 +1,88 @@
+// Splits several PDFs in one run, reporting per-file events and a combined summary.
+
+const { resolveEventSink } = require('./events');
+const fs = require('fs');
+const { Splitter } = require('./splitter');
+const { CANCELLED_EXIT_CODE } = require('./part-builder');
+const { isS3Url } = require('./s3');
+
+/**
+ * Splits one file of a batch
+ *
+ * @param {Object} options Options for the file, as accepted by splitPdf
+ * @param {Object} fileSink Sink receiving the file's events
+ * @returns {Promise<Object>} Split result
+ */
+async function splitFile(options, fileSink) {
+  // Mirrored output directories may not exist yet; object storage has no directories
+  if (!options.dryRun && !isS3Url(options.outputDir)) {
+    await fs.promises.mkdir(options.outputDir, { recursive: true });
+  }
+  const splitter = await Splitter.open(options.filePath, { backend: options.backend, password: options.password, spreads: options.spreads, lowMemory: options.lowMemory });
+  try {
+    return await splitter.split({ ...options, eventSink: fileSink });
+  } finally {
+    await splitter.close();
+  }
+}
+
+/**
+ * Splits each source PDF in turn with its own options
+ *
+ * @param {Array<Object>} optionsList Options for each file, as accepted by splitPdf
+ * @param {Object} [batchOptions] Batch options
+ * @param {Object} [batchOptions.eventSink] Sink receiving every file's events, each with a file field, plus batch events
+ * @param {Function} [batchOptions.onProgress] Callback receiving each event, used when no eventSink is given
+ * @param {boolean} [batchOptions.continueOnError] If true, a failing file is reported and skipped instead of aborting the batch
+ * @returns {Promise<Object>} Combined result with the split result of every file and the files that failed
+ */
+async function splitPdfBatch(optionsList, batchOptions = {}) {
+  const eventSink = resolveEventSink(batchOptions);
+
+  const files = [];
+  const failed = [];
+  const outputFiles = [];
+  for (const options of optionsList) {
+    // Tag each event with its source file so consumers can tell the files apart
+    const fileSink = {
+      emit(event) {
+        eventSink.emit({ ...event, file: options.filePath });
+      }
+    };
+
+    let result;
+    try {
+      result = await splitFile(options, fileSink);
+    } catch (error) {
+      // Cancellation always stops the whole batch
+      if (!batchOptions.continueOnError || error.code === CANCELLED_EXIT_CODE) {
+        throw error;
+      }
+      const failure = {
+        filePath: options.filePath,
+        error: { message: error.message, code: typeof error.code === 'number' ? error.code : 1 }
+      };
+      failed.push(failure);
+      eventSink.emit({ event: 'fileFailed', file: options.filePath, error: failure.error });
+      continue;
+    }
+    files.push({ filePath: options.filePath, parts: result.parts, summary: result.summary, warnings: result.warnings });
+    for (const part of result.parts) {
+      outputFiles.push(part.outputPath);
+    }
+  }
+
+  eventSink.emit({
+    event: 'batchComplete',
+    files: files.length,
+    failed: failed.length,
+    parts: outputFiles.length,
+    outputFiles
+  });
+
+  return { files, failed };
+}
+
+module.exports = {
+  splitPdfBatch
+};
@@ -0,0 +1,44 @@
+// Bates numbers: a prefix and a zero-padded counter stamped onto every page,
+// numbering the pages of all parts in one sequence, as legal productions do.
+
+const { embedStampFont, drawStampText } = require('./stamp');
+
+const DEFAULT_BATES_DIGITS = 6;
+const BATES_FONT_SIZE = 9;
+
+/**
+ * Formats a Bates number
+ *
+ * @param {Object} bates Bates options with prefix and digits
+ * @param {number} number Counter value
+ * @returns {string} Bates number, e.g. ABC000042
+ */
+function formatBatesNumber(bates, number) {
+  return `${bates.prefix}${String(number).padStart(bates.digits, '0')}`;
+}
+
+/**
+ * Stamps consecutive Bates numbers onto every page of a part, in the bottom right corner
+ *
+ * @param {PDFDocument} pdf Part document
+ * @param {Object} bates Bates numbering of the part
+ * @param {string} bates.prefix Text before the counter
+ * @param {number} bates.digits Minimum number of counter digits
+ * @param {number} bates.first Counter value of the part's first page
+ */
+async function stampBatesNumbers(pdf, bates) {
+  const font = await embedStampFont(pdf);
+  for (const [pageIndex, page] of pdf.getPages().entries()) {
+    drawStampText(page, formatBatesNumber(bates, bates.first + pageIndex), {
+      font,
+      size: BATES_FONT_SIZE,
+      position: 'bottom-right'
+    });
+  }
+}
+
+module.exports = {
+  DEFAULT_BATES_DIGITS,
+  formatBatesNumber,
+  stampBatesNumbers
+};
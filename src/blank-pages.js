@@ -0,0 +1,99 @@
+// Blank pages, such as the empty backs in duplex scanner output. Every page is
+// rendered in grayscale at a low resolution (by poppler's pdftoppm, see
+// src/poppler.js) and counts as blank when too few of its pixels carry ink.
+// Blank pages are left out of the planned parts, which keep the source page
+// numbers of the pages that remain.
+
+const path = require('path');
+const fs = require('fs/promises');
+const { runPoppler, withTemporaryPdf } = require('./poppler');
+const { dropPages } = require('./plan');
+
+// Share of inked pixels, in percent, below which a page is blank
+const DEFAULT_BLANK_THRESHOLD = 0.5;
+// Low enough to be quick and to blur scanner dust away
+const DETECTION_DPI = 30;
+// Gray levels darker than this (of 255) count as ink
+const INK_LEVEL = 200;
+
+/**
+ * Measures the share of inked pixels in a binary grayscale PGM image
+ *
+ * @param {Buffer} pgm Image written by pdftoppm -gray
+ * @returns {number} Inked pixels in percent
+ */
+function inkCoverage(pgm) {
+  // The header holds the magic number, width, height and maximum gray level, separated by whitespace or comments
+  const fields = [];
+  let offset = 0;
+  while (fields.length < 4) {
+    while (/\s/.test(String.fromCharCode(pgm[offset]))) {
+      offset++;
+    }
+    if (pgm[offset] === 0x23) {
+      while (pgm[offset] !== 0x0a) {
+        offset++;
+      }
+      continue;
+    }
+    let field = '';
+    while (!/\s/.test(String.fromCharCode(pgm[offset]))) {
+      field += String.fromCharCode(pgm[offset++]);
+    }
+    fields.push(field);
+  }
+  // A single whitespace character separates the header from the pixels
+  const [, width, height, maxLevel] = fields.map(Number);
+  const pixels = pgm.subarray(offset + 1, offset + 1 + width * height);
+  const inkLevel = INK_LEVEL * maxLevel / 255;
+  let inked = 0;
+  for (const level of pixels) {
+    if (level < inkLevel) {
+      inked++;
+    }
+  }
+  return pixels.length === 0 ? 0 : inked / pixels.length * 100;
+}
+
+/**
+ * Finds the blank pages of a document
+ *
+ * @param {Uint8Array} pdfBytes Bytes of the unencrypted document
+ * @param {number} threshold Inked pixels in percent below which a page is blank
+ * @returns {Promise<Array<number>>} 1-based numbers of the blank pages
+ */
+async function findBlankPages(pdfBytes, threshold) {
+  return withTemporaryPdf(pdfBytes, async (pdfPath) => {
+    const tempDir = path.dirname(pdfPath);
+    await runPoppler('pdftoppm', ['-gray', '-r', String(DETECTION_DPI), pdfPath, path.join(tempDir, 'page')]);
+    const blankPages = [];
+    for (const name of await fs.readdir(tempDir)) {
+      const match = /^page-(\d+)\.pgm$/.exec(name);
+      if (match && inkCoverage(await fs.readFile(path.join(tempDir, name))) < threshold) {
+        blankPages.push(Number(match[1]));
+      }
+    }
+    return blankPages.sort((a, b) => a - b);
+  });
+}
+
+/**
+ * Leaves the blank pages out of planned parts (see dropPages in src/plan.js)
+ *
+ * @param {Array<Object>} partInfos Parts planned with Splitter#plan, updated in place with the droppedPages of each part
+ * @param {Array<number>} blankPages 1-based numbers of the blank pages
+ * @returns {Array<{code: string, message: string}>} Warning listing the dropped pages, or none
+ */
+function dropBlankPages(partInfos, blankPages) {
+  const dropped = dropPages(partInfos, blankPages);
+  if (dropped.length === 0) {
+    return [];
+  }
+  return [{ code: 'blankPagesDropped', message: `Dropped ${dropped.length} blank page(s): ${dropped.join(', ')}` }];
+}
+
+module.exports = {
+  DEFAULT_BLANK_THRESHOLD,
+  findBlankPages,
+  dropBlankPages
+};
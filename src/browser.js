@@ -0,0 +1,45 @@
+// Browser entry point (see the "browser" field in package.json).
+// Works entirely in memory: no file system, worker threads or child processes,
+// so only the pdf-lib backend is available.
+
+const pdfLibBackend = require('./backends/pdf-lib');
+const { calculateRanges } = require('./plan');
+const { throwIfCancelled, buildPart } = require('./part-builder');
+
+/**
+ * Splits a PDF held in memory and returns the bytes of every part
+ *
+ * @param {Uint8Array|ArrayBuffer} sourceBytes Bytes of the source PDF
+ * @param {Object} options Configuration options
+ * @param {number} options.parts Number of parts to split into
+ * @param {Object|null} options.intro Intro page range (1-based, inclusive)
+ * @param {string} options.tooFewPages Optional; with fewer content pages than parts, error (default) throws and reduce writes one part per page
+ * @param {Object} options.eventSink Optional sink receiving progress events
+ * @param {AbortSignal} options.signal Optional signal cancelling the split
+ * @returns {Promise<Array<Object>>} Parts with page ranges and their bytes
+ */
+async function splitPdfBytes(sourceBytes, options) {
+  const source = await pdfLibBackend.open(sourceBytes);
+  const partRanges = calculateRanges(pdfLibBackend.pageCount(source), options.parts, options.intro || null, options.tooFewPages);
+
+  const parts = [];
+  for (const partRange of partRanges) {
+    throwIfCancelled(options.signal);
+    const bytes = await buildPart(pdfLibBackend, source, partRange, { signal: options.signal });
+    parts.push({ ...partRange, bytes });
+
+    if (options.eventSink) {
+      options.eventSink.emit({
+        event: 'partComplete',
+        part: partRange.index,
+        totalParts: partRanges.length
+      });
+    }
+  }
+  return parts;
+}
+
+module.exports = {
+  calculateRanges,
+  splitPdfBytes
+};
@@ -0,0 +1,101 @@
+// Checkpoint state files for long splits. The plan of a split and the parts
+// written so far are saved after every part, so a job that was interrupted
+// (killed, crashed or cancelled) can be run again with the same checkpoint and
+// only builds the parts that are missing. Unlike skipping outputs that exist,
+// the saved plan is compared with the new one first, so parts of a changed
+// source or a different division of its pages are never mixed.
+
+const crypto = require('crypto');
+const fs = require('fs/promises');
+
+const CHECKPOINT_VERSION = 1;
+
+/**
+ * Describes a split for comparison with a saved checkpoint
+ *
+ * @param {Uint8Array} sourceBytes Bytes of the source PDF
+ * @param {Array<Object>} partInfos Planned parts
+ * @returns {Object} SHA-256 of the source and each part's index, output path and pages
+ */
+function checkpointPlan(sourceBytes, partInfos) {
+  return {
+    source: crypto.createHash('sha256').update(sourceBytes).digest('hex'),
+    parts: partInfos.map(partInfo => ({
+      index: partInfo.index,
+      outputPath: partInfo.outputPath,
+      pages: partInfo.pages
+    }))
+  };
+}
+
+/**
+ * Reads the parts a checkpoint records as written
+ *
+ * @param {string} checkpointPath Path of the checkpoint file
+ * @param {Object} plan Plan of the split about to run (see checkpointPlan)
+ * @returns {Promise<Array<number>>} Indices of the written parts; none if the file doesn't exist yet
+ * @throws {Error} With code 2 if the file isn't a checkpoint or was saved for another split
+ */
+async function readCheckpoint(checkpointPath, plan) {
+  let text;
+  try {
+    text = await fs.readFile(checkpointPath, 'utf8');
+  } catch (error) {
+    if (error.code === 'ENOENT') {
+      return [];
+    }
+    const ioError = new Error(`I/O error: can't read the checkpoint ${checkpointPath}: ${error.message}`);
+    ioError.code = 3; // I/O error
+    throw ioError;
+  }
+
+  const invalidCheckpoint = (message) => {
+    const error = new Error(`Checkpoint ${checkpointPath} ${message}; remove it to start the split again.`);
+    error.code = 2; // Invalid option
+    return error;
+  };
+  let checkpoint;
+  try {
+    checkpoint = JSON.parse(text);
+  } catch (error) {
+    throw invalidCheckpoint('is not a checkpoint file');
+  }
+  if (!checkpoint || checkpoint.version !== CHECKPOINT_VERSION || !Array.isArray(checkpoint.completedParts)) {
+    throw invalidCheckpoint('is not a checkpoint file');
+  }
+  if (checkpoint.source !== plan.source) {
+    throw invalidCheckpoint('was saved for a different source PDF');
+  }
+  if (JSON.stringify(checkpoint.parts) !== JSON.stringify(plan.parts)) {
+    throw invalidCheckpoint('was saved for different parts (part count, page ranges or output names changed)');
+  }
+  return checkpoint.completedParts;
+}
+
+/**
+ * Saves a checkpoint, replacing the previous one in a single rename so an
+ * interruption never leaves a half-written file
+ *
+ * @param {string} checkpointPath Path of the checkpoint file
+ * @param {Object} plan Plan of the split (see checkpointPlan)
+ * @param {Array<number>} completedParts Indices of the parts written so far
+ * @returns {Promise<void>} Resolves once the checkpoint is saved
+ */
+async function writeCheckpoint(checkpointPath, plan, completedParts) {
+  const checkpoint = { version: CHECKPOINT_VERSION, ...plan, completedParts };
+  const temporaryPath = `${checkpointPath}.tmp`;
+  try {
+    await fs.writeFile(temporaryPath, JSON.stringify(checkpoint));
+    await fs.rename(temporaryPath, checkpointPath);
+  } catch (error) {
+    const ioError = new Error(`I/O error: can't save the checkpoint ${checkpointPath}: ${error.message}`);
+    ioError.code = 3; // I/O error
+    throw ioError;
+  }
+}
+
+module.exports = {
+  checkpointPlan,
+  readCheckpoint,
+  writeCheckpoint
+};
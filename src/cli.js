@@ -1,130 +1,952 @@
 #!/usr/bin/env node
 
-const { Command } = require('commander');
 const path = require('path');
 const fs = require('fs');
-const { splitPdf } = require('./index');
+const { Command, Option } = require('commander');
+const { version } = require('../package.json');
+const { splitPdf, splitPdfBatch, getPdfInfo, getPdfOutline, getPdfPageCount, validatePdf, extractPages, mergePdfs } = require('./index');
+const { SplitOptions } = require('./options');
+const { createFdSink, createCallbackSink, combineSinks } = require('./events');
+const { startServer } = require('./server');
+const { runDaemon } = require('./daemon');
+const { parseJobs, runJobs } = require('./jobs');
+const { expandGlob, findPdfFiles } = require('./glob');
+const { loadConfig, getOptionDefaults } = require('./config');
+const { Splitter } = require('./splitter');
+const { runPlanner } = require('./planner');
+const { createProgressBarSink } = require('./progress-bar');
+const { promptPassword } = require('./password-prompt');
+const { STDIN_PATH, STDIN_BASENAME, spoolToTempFile } = require('./stdin');
+const { isS3Url, joinS3Url } = require('./s3');
+const { EXEC_FAILURE_MODES, DEFAULT_EXEC_FAILURE_MODE, createExecSink } = require('./exec-hook');
+const { DEFAULT_MEMORY_LIMIT_ACTION } = require('./memory');
+const { parseBookmarkSpec, parsePageLabelSpec, generatePdf } = require('./generate');
 
 const program = new Command();
 
+// Password of an encrypted source; without a value the user is asked for it in the terminal
+function passwordOption() {
+  return new Option('--password [password]', 'Password of an encrypted source PDF (requires qpdf); prompts when no value is given')
+    .env('SPLITPDF_PASSWORD');
+}
+
 program
   .name('splitpdf')
-  .description('Splits a PDF into multiple parts, optionally prepending an intro range.');
+  .description('Splits a PDF into multiple parts, optionally prepending an intro range.')
+  .version(version, '-V, --version')
+  .showHelpAfterError('(add --help for usage information)')
+  // Throw instead of exiting so usage errors get exit code 2 (set before adding subcommands, which inherit it)
+  .exitOverride();
+
+const splitCommand = program
+  .command('split', { isDefault: true })
+  .description('Split a PDF into parts (the default command)')
+  .option('-f, --file <path...>', 'Path to the source PDF file (several files may be given); - reads it from stdin')
+  .option('--input <pattern>', "Glob pattern selecting source PDF files, e.g., 'scans/*.pdf'")
+  .option('-r, --recursive <dir>', 'Split every PDF under a directory, mirroring its structure in the output directory')
+  .option('-p, --parts <integer>', 'Number of parts to split the PDF into (required unless set in the config file)', parseInt)
+  .option('-i, --intro <range>', 'Intro page range, e.g., 1:10 or 1-10 (1-based, inclusive)')
+  .option('--max-parts <count>', 'Refuse to write more parts than this (exit code 7)', parseInt)
+  .option('--too-few-pages <policy>', 'When there are fewer content pages than parts: error (exit code 2) or reduce (one part per page, with a warning)')
+  .option('--group-by <property>', 'Make one part of all pages with the same paper size (size), orientation (orientation) or use of color (color) instead of --parts')
+  .option('--dry-run', 'Print calculated page ranges as JSON and exit without writing files')
+  .option('-v, --verbose', 'Print human-readable progress messages; repeat (-vv) to print every event', increaseVerbosity, 0)
+  .option('-q, --quiet', 'Print nothing except errors (and the JSON result when it is requested)')
+  .option('--json-events', 'Print progress events as JSON lines and the final result as JSON on stdout')
+  .option('--events-fd <fd>', 'File descriptor receiving JSON events (defaults to 2, stderr)', parseInt)
+  .option('--output-dir <path>', 'Directory to output split PDF files (defaults to source file directory)')
+  .option('--output-basename <n>', 'Base name for output files (defaults to source file name without extension)')
+  .option('--name-template <template>', 'Output file name template with {basename}, {index}, {total} and, with --group-by, {group} (defaults to {basename}_part{index}.pdf)')
+  .option('--config <path>', 'TOML config file with default options (defaults to ~/.config/splitpdf/config.toml)')
+  .option('--profile <name>', 'Use the options of a [profile.<name>] table in the config file')
+  .option('--threads <integer>', 'Number of worker threads building parts concurrently', parseInt, 1)
+  .option('--backend <name>', 'PDF backend: pdf-lib or qpdf (requires the qpdf executable)', 'pdf-lib')
+  .addOption(passwordOption())
+  .option('--spreads', 'Split landscape pages (scanned two-page spreads) into left and right pages before planning the parts')
+  .option('--repair', 'Leave pages that can\'t be copied (e.g. of a damaged PDF) out of the parts instead of failing, listing them in warnings')
+  .option('--deterministic', 'Write byte-identical parts for the same source and options: fixed dates (SOURCE_DATE_EPOCH or 1970-01-01) and derived document IDs')
+  .option('--timeout <seconds>', 'Give up on a split that takes longer than this, removing its files (exit code 9); a batch moves on to the next file', parseFloat)
+  .option('--part-timeout <seconds>', 'Skip a part that takes longer than this to build, with a warning, and go on with the next part', parseFloat)
+  .option('--memory-interval <seconds>', 'Emit a memory event with the memory use of the process this often', parseFloat)
+  .option('--memory-limit <megabytes>', 'Soft limit on the memory use of the process (resident set size)', parseInt)
+  .option('--memory-limit-action <action>', 'When memory use exceeds --memory-limit: reduce (build the remaining parts on a single thread) or abort (exit code 10)', DEFAULT_MEMORY_LIMIT_ACTION)
+  .option('--low-memory', 'Build each part with qpdf straight from the source file into its output file, never holding the source or a part in memory; parts get their pages only (requires qpdf)')
+  .option('--checkpoint <file>', 'Record the plan and each written part in this state file, so that running the split again with it resumes where an interrupted run stopped')
+  .option('--drop-blank-pages', 'Leave blank pages (e.g. empty backs of duplex scans) out of the parts; pages are rendered to find them (requires poppler)')
+  .option('--blank-threshold <percent>', 'Share of inked pixels below which a page counts as blank', parseFloat, 0.5)
+  .option('--duplicate-pages <mode>', 'Pages repeating an earlier page exactly: ignore, report (list them in the result) or drop (also leave them out of the parts)', 'ignore')
+  .option('--part-titles', 'Append " (Part N)" to the title each part copies from the source')
+  .option('--title <template>', 'Title of each part, e.g. "{source_title} - Part {part}/{total}"')
+  .option('--author <template>', 'Author of each part (defaults to the source author)')
+  .option('--subject <template>', 'Subject of each part (defaults to the source subject)')
+  .option('--keywords <template>', 'Keywords of each part (defaults to the source keywords)')
+  .option('--bookmarks <mode>', 'Bookmarks of each part: scoped (those pointing into the part), nearest (all, pointing at the closest page of the part) or none', 'scoped')
+  .option('--toc', 'Start each part with a contents page listing its bookmarks (or source pages) with links')
+  .option('--cover [template]', 'Start each part with a cover page; the template may use {source_title}, {part}, {total}, {page_range}, {date} and \\n between lines')
+  .option('--flatten', 'Draw annotations and form field values into the page content of the parts')
+  .option('--cross-links <policy>', 'Links to pages in another part: drop, keep (without a destination) or external (to the sibling part file)', 'drop')
+  .option('--no-page-labels', 'Number the pages of each part from 1 instead of keeping the source page labels')
+  .option('--attachments <policy>', 'Parts receiving the file attachments of the source: all, first or none', 'all')
+  .option('--break-signatures', 'Split digitally signed PDFs although the parts\' signatures no longer verify')
+  .option('--keep-pdfa', 'Keep the PDF/A identification of the source in the parts, without verifying their conformance')
+  .option('--pdf-version <version>', 'PDF version the parts declare, e.g. 1.4 (defaults to the version of the source)')
+  .option('--bates <prefix>', 'Stamp Bates numbers (the prefix and a zero-padded counter) onto every page, continuing across parts; use "" for no prefix')
+  .option('--bates-start <integer>', 'First Bates number', parseInt, 1)
+  .option('--bates-digits <integer>', 'Minimum number of Bates counter digits', parseInt, 6)
+  .option('--watermark <text>', 'Stamp a text watermark onto every page, e.g. "COPY - distributed to X"')
+  .option('--watermark-image <path>', 'Stamp a PNG or JPEG image onto every page instead of a text')
+  .option('--watermark-position <position>', 'Watermark position: center, top-left, top-center, top-right, bottom-left, bottom-center or bottom-right', 'center')
+  .option('--watermark-opacity <number>', 'Watermark opacity from 0 to 1', parseFloat, 0.3)
+  .option('--watermark-rotate <degrees>', 'Counterclockwise watermark rotation', parseFloat, 45)
+  .option('--watermark-size <points>', 'Font size of text watermarks', parseFloat, 48)
+  .option('--header <template>', 'Print a line at the top of every page, e.g. "{source_title}"; also {page} and {pages} of the part')
+  .option('--footer <template>', 'Print a line at the bottom of every page, e.g. "Part {part} of {total} - {basename}"')
+  .option('--page-size <size>', 'Put every page onto this paper size, e.g. A4, Letter or 595x842 (points), in the orientation of the page')
+  .option('--page-fit <fit>', 'How pages are put onto --page-size: fit (scale to fit), fill (scale to fill, cutting off the rest) or center (unscaled)', 'fit')
+  .option('--n-up <pages>', 'Print 2, 4, 6, 8, 9 or 16 pages on each sheet of the parts, scaled down in reading order', parseInt)
+  .option('--booklet', 'Print the pages of each part two per side in saddle-stitch booklet order, padded with blank pages to a multiple of four')
+  .option('--no-prune-resources', 'Keep fonts, images and other resources of the source that the pages of a part don\'t use')
+  .option('--optimize [dpi]', 'Make the parts smaller: downsample images to the given resolution (defaults to 150), recompress streams and keep identical streams once', parseInt)
+  .option('--linearize', 'Save the parts linearized (fast web view) for viewers that load them page by page (requires qpdf)')
+  .option('--output-format <format>', 'Write parts as pdf, or render them to folders of png, jpeg or tiff page images (requires poppler)', 'pdf')
+  .option('--render-dpi <dpi>', 'Resolution of rendered page images', parseInt, 150)
+  .option('--exec <command>', 'Shell command run after each part is written, with {path}, {index}, {total} and {range} placeholders, e.g. \'upload.sh {path}\'')
+  .option('--exec-failure <mode>', 'When an --exec command fails: abort (cancel the split) or continue (warn)', DEFAULT_EXEC_FAILURE_MODE)
+  .option('--text-sidecar', 'Write the text of each part into a .txt file next to it, e.g. report_part3.txt (requires poppler)')
+  .option('--thumbnails [size]', 'Write a PNG thumbnail of the first page of each part next to it, e.g. report_part1.png, with the given longest side in pixels (defaults to 200; requires poppler)', parseInt)
+  .option('--document-ids <mode>', 'Document IDs of the parts: random (a fresh ID per part) or derived (from the source ID and the part number)', 'random')
+  .option('--decrypt-output', 'Write the parts of an encrypted source unencrypted (by default they keep its encryption)')
+  .option('--user-password <password>', 'Encrypt the parts so that this password is needed to open them (requires qpdf)')
+  .option('--owner-password <password>', 'Encrypt the parts with this password for full access (defaults to the user password)')
+  .option('--permissions <list>', 'Comma-separated permissions of encrypted parts without the owner password: print, modify, copy, annotate, form, assemble, accessibility (defaults to all)')
+  .action(runSplit);
 
 program
+  .command('plan')
+  .description('Choose where each part starts in an interactive terminal planner, then split')
   .requiredOption('-f, --file <path>', 'Path to the source PDF file')
-  .requiredOption('-p, --parts <integer>', 'Number of parts to split the PDF into', parseInt)
-  .option('-i, --intro <range>', 'Intro page range, e.g., 1:10 (1-based, inclusive)')
-  .option('--dry-run', 'Print calculated page ranges as JSON and exit without writing files')
-  .option('--verbose', 'Enable verbose logging (progress as JSON lines)')
+  .option('-p, --parts <integer>', 'Number of parts to start with', parseInt, 2)
+  .option('-i, --intro <range>', 'Intro page range, e.g., 1:10 or 1-10 (1-based, inclusive)')
   .option('--output-dir <path>', 'Directory to output split PDF files (defaults to source file directory)')
-  .option('--output-basename <n>', 'Base name for output files (defaults to source file name without extension)');
+  .option('--output-basename <n>', 'Base name for output files (defaults to source file name without extension)')
+  .option('--name-template <template>', 'Output file name template with {basename}, {index} and {total} (defaults to {basename}_part{index}.pdf)')
+  .option('--backend <name>', 'PDF backend: pdf-lib or qpdf (requires the qpdf executable)', 'pdf-lib')
+  .addOption(passwordOption())
+  .action(runPlan);
+
+program
+  .command('info')
+  .description('Print page count, page sizes, PDF version, encryption, outline presence and metadata as JSON')
+  .argument('<file>', 'Path to the PDF file')
+  .action(runInfo);
 
-program.parse(process.argv);
+program
+  .command('pagecount')
+  .description('Print the number of pages of a PDF')
+  .argument('<file>', 'Path to the PDF file')
+  .option('--json', 'Print the count as JSON, e.g. {"pageCount": 12}')
+  .option('--backend <name>', 'PDF backend: pdf-lib or qpdf (requires the qpdf executable)', 'pdf-lib')
+  .addOption(passwordOption())
+  .action(runPageCount);
+
+program
+  .command('validate')
+  .description('Check that a PDF opens and its pages are sound; prints a report as JSON and exits with code 4 if problems are found')
+  .argument('<file>', 'Path to the PDF file')
+  .option('--max-pages <count>', 'Page count above which the page tree is taken to be damaged', parseInt)
+  .addOption(passwordOption())
+  .action(runValidate);
+
+program
+  .command('extract')
+  .description('Copy selected pages of a PDF into a new file')
+  .requiredOption('-f, --file <path>', 'Path to the source PDF file')
+  .requiredOption('--pages <list>', 'Pages to copy, e.g., 1-3,7,10- (1-based, in output order)')
+  .requiredOption('-o, --output <path>', 'Path of the PDF file to write')
+  .option('--backend <name>', 'PDF backend: pdf-lib or qpdf (requires the qpdf executable)', 'pdf-lib')
+  .addOption(passwordOption())
+  .option('--flatten', 'Draw annotations and form field values into the page content')
+  .option('--decrypt-output', 'Write the pages of an encrypted source unencrypted (by default they keep its encryption)')
+  .option('--break-signatures', 'Copy pages of a digitally signed PDF although its signatures no longer verify')
+  .action(runExtract);
 
-const options = program.opts();
+program
+  .command('merge')
+  .description('Concatenate PDFs into one file')
+  .argument('<files...>', 'Paths to the PDF files, in output order')
+  .requiredOption('-o, --output <path>', 'Path of the PDF file to write')
+  .action(runMerge);
+
+program
+  .command('jobs')
+  .description('Run the split jobs of a JSON or NDJSON file in one process, printing a JSON status line per job')
+  .argument('<file>', 'Path to the job file: a JSON array of jobs or one job per line, with the daemon\'s split parameters')
+  .option('-j, --concurrency <count>', 'Number of jobs to run at the same time', parseInt, 1)
+  .action(runJobFile);
+
+program
+  .command('generate')
+  .description('Write a synthetic PDF for tests and bug reports')
+  .argument('<output>', 'Path of the PDF file to write')
+  .requiredOption('--pages <count>', 'Number of pages', parseInt)
+  .option('--page-size <sizes>', 'Comma-separated page sizes used in turn, e.g. A4,Letter,842x595 (defaults to Letter)')
+  .option('--bookmarks <list>', 'Bookmarks as page=title entries separated by ;, with a leading > per nesting level, e.g. \'1=Intro;3=Chapter 1;>4=Section 1.1\'')
+  .option('--page-labels <list>', 'Page label ranges as page=style[:prefix] entries separated by ;, with style D, R, r, A, a or - for none, e.g. \'1=r;5=D\'')
+  .action(runGenerate);
+
+program
+  .command('serve')
+  .description('Run an HTTP server accepting split jobs')
+  .option('--listen <address>', 'Address to listen on as host:port', '127.0.0.1:8080')
+  .option('--max-upload <megabytes>', 'Refuse larger uploads with 413', parseFloat, 512)
+  .option('--max-jobs <count>', 'Jobs that may run at the same time; more uploads are refused with 429', parseInt, 4)
+  .option('--job-ttl <seconds>', 'Delete finished jobs and their files after this long', parseFloat, 3600)
+  .action(runServe);
+
+program
+  .command('serve-grpc')
+  .description('Run a gRPC server with Split, Plan and Info calls (see src/splitpdf.proto)')
+  .option('--listen <address>', 'Address to listen on as host:port', '127.0.0.1:50051')
+  .action(runServeGrpc);
+
+program
+  .command('daemon')
+  .description('Answer JSON-RPC requests (plan, split, cancel, pageCount) from stdin, one per line')
+  .action(async () => {
+    await runDaemon(process.stdin, process.stdout);
+    process.exit(0);
+  });
+
+// The default command is what most people run, so show its help at the top level too
+program.addHelpText('after', () => `\n${splitCommand.helpInformation()}`);
+
+/**
+ * Determines the exit code for an error thrown while running a command
+ *
+ * @param {Error} error Thrown error
+ * @returns {number} Process exit code
+ */
+function getExitCode(error) {
+  // Use the error code if available, or determine code based on error message
+  if (typeof error.code === 'number') return error.code;
+  if (error.message.includes('I/O error')) return 3;
+  if (error.message.includes('PDF error')) return 4;
+  if (error.message.includes('encrypted')) return 6;
+  return 1; // Default unknown error
+}
 
 function validateOptions(options) {
-  if (!fs.existsSync(options.file)) {
-    console.error(`Error: File not found at ${options.file}`);
-    process.exit(3); // Exit code 3 for I/O error (file not found)
+  if (options.eventsFd !== undefined && (isNaN(options.eventsFd) || options.eventsFd < 1)) {
+    console.error('Error: Events file descriptor must be a positive integer.');
+    process.exit(2); // Exit code 2 for invalid CLI arguments
   }
+}
 
-  if (!options.parts || options.parts <= 0) {
-    console.error('Error: Number of parts must be a positive integer.');
+// Counts repeated -v flags
+function increaseVerbosity(value, previous) {
+  return previous + 1;
+}
+
+/**
+ * Creates a function printing human-readable messages for the events that
+ * matter at the given verbosity level
+ *
+ * @param {number} verbosity 1 for part and progress messages, 2 or more for every event
+ * @returns {Function} Event callback
+ */
+function createHumanEventPrinter(verbosity) {
+  return (event) => {
+    if (event.event === 'partComplete') {
+      console.log(`Wrote part ${event.part} of ${event.totalParts}: ${event.outputPath}`);
+    } else if (event.event === 'progress') {
+      console.log(`Progress: ${event.percent}% (${event.pagesWritten}/${event.totalPages} pages), ETA ${Math.ceil(event.etaMs / 1000)}s`);
+    } else if (verbosity >= 2) {
+      const { event: name, ...details } = event;
+      console.log(`${name}: ${JSON.stringify(details)}`);
+    }
+  };
+}
+
+/**
+ * Fills in options that were not given on the command line from config file values
+ *
+ * @param {Object} options Parsed command options, updated in place
+ * @param {Command} command Command the options belong to
+ * @param {Object} defaults Option values from the config file
+ */
+function applyConfigDefaults(options, command, defaults) {
+  for (const [name, value] of Object.entries(defaults)) {
+    if (command.getOptionValueSource(name) !== 'cli') {
+      options[name] = value;
+    }
+  }
+}
+
+/**
+ * Joins a subdirectory to an output directory, which may be an s3:// URL
+ *
+ * @param {string} outputDir Output directory
+ * @param {string} relativeDir Subdirectory
+ * @returns {string} Joined directory
+ */
+function joinOutputDir(outputDir, relativeDir) {
+  return isS3Url(outputDir) ? joinS3Url(outputDir, relativeDir) : path.join(outputDir, relativeDir);
+}
+
+/**
+ * Writes the thumbnails of split parts next to them, e.g. report_part1.png,
+ * replacing their bytes with the file path in the result
+ *
+ * @param {Array<Object>} parts Parts of a split result, updated in place
+ */
+async function writeThumbnails(parts) {
+  for (const part of parts) {
+    if (!part.thumbnail) {
+      continue;
+    }
+    const extension = path.extname(part.outputPath);
+    const basePath = extension.toLowerCase() === '.pdf' ? part.outputPath.slice(0, -extension.length) : part.outputPath;
+    part.thumbnailPath = `${basePath}.png`;
+    await fs.promises.writeFile(part.thumbnailPath, part.thumbnail);
+    delete part.thumbnail;
+  }
+}
+
+/**
+ * Resolves the --password option, prompting for the password if it was given without a value
+ *
+ * @param {Object} options Parsed command options
+ * @returns {Promise<string|undefined>} Password, or undefined if none was given
+ */
+async function resolvePassword(options) {
+  if (options.password !== true) {
+    return options.password;
+  }
+  if (!process.stdin.isTTY) {
+    console.error('Error: --password needs a value (or SPLITPDF_PASSWORD) when not run in a terminal.');
     process.exit(2); // Exit code 2 for invalid CLI arguments
   }
+  return promptPassword('Password: ');
+}
+
+async function runSplit(options, command) {
+  // Config file values are defaults; flags given on the command line win
+  try {
+    applyConfigDefaults(options, command, getOptionDefaults(loadConfig(options.config), options.profile));
+  } catch (error) {
+    console.error(`Error: ${error.message}`);
+    process.exit(error.code);
+  }
+  // An explicit SPLITPDF_QPDF still takes precedence over the config file
+  if (options.qpdfPath && !process.env.SPLITPDF_QPDF) {
+    process.env.SPLITPDF_QPDF = options.qpdfPath;
+  }
+
+  validateOptions(options);
+
+  // The config file may set verbose = true as well as a level
+  const verbosity = options.verbose === true ? 1 : Number(options.verbose) || 0;
+  if (options.quiet && command.getOptionValueSource('verbose') === 'cli') {
+    console.error('Error: --quiet cannot be combined with --verbose.');
+    process.exit(2); // Exit code 2 for invalid CLI arguments
+  }
+
+  // JSON events and human-readable messages are independent of each other.
+  // JSON events go to stderr by default so stdout only carries the final result.
+  const eventSinks = [];
+  if (options.jsonEvents) {
+    eventSinks.push(createFdSink(options.eventsFd === undefined ? 2 : options.eventsFd));
+  }
+  // Quiet runs print only errors and requested JSON output
+  if (!options.quiet && verbosity > 0) {
+    eventSinks.push(createCallbackSink(createHumanEventPrinter(verbosity)));
+  } else if (!options.quiet && !options.jsonEvents && !options.dryRun && process.stdout.isTTY && process.stderr.isTTY) {
+    // Show a progress bar instead of staying silent when a person is watching
+    eventSinks.push(createProgressBarSink(process.stderr));
+  }
 
-  if (options.intro) {
-    const introRangeParts = options.intro.split(':');
-    const introRange = [];
-    for (const part of introRangeParts) {
-      introRange.push(Number(part));
+  // Cancel the split on Ctrl+C or termination so partial outputs get cleaned up
+  const abortController = new AbortController();
+  for (const signalName of ['SIGINT', 'SIGTERM']) {
+    process.on(signalName, () => abortController.abort());
+  }
+
+  // Run the --exec command for every part written; failing commands cancel the split unless told to continue
+  let execSink = null;
+  if (options.exec !== undefined) {
+    if (!EXEC_FAILURE_MODES.includes(options.execFailure)) {
+      console.error(`Error: Unknown --exec failure mode: ${options.execFailure}. Available modes: ${EXEC_FAILURE_MODES.join(', ')}`);
+      process.exit(2); // Exit code 2 for invalid CLI arguments
     }
-    
-    let hasInvalidNumber = false;
-    for (const num of introRange) {
-      if (isNaN(num)) {
-        hasInvalidNumber = true;
-        break;
-      }
+    execSink = createExecSink(options.exec, { onFailure: options.execFailure, abort: () => abortController.abort() });
+    eventSinks.push(execSink);
+  }
+
+  const password = await resolvePassword(options);
+
+  // Collect the source files from --file, --input and --recursive,
+  // each with the output directory its parts go to (null for the default)
+  const sources = [];
+  let stdinRead = false;
+  for (const filePath of options.file || []) {
+    if (filePath !== STDIN_PATH) {
+      sources.push({ filePath, outputDir: options.outputDir || null });
+      continue;
     }
-    
-    if (introRange.length !== 2 || hasInvalidNumber || introRange[0] < 1 || introRange[1] < introRange[0]) {
-      console.error('Error: Invalid intro range. Must be in format start:end, e.g., 1:10, with start >= 1 and end >= start.');
+    if (stdinRead) {
+      console.error('Error: Standard input can only be read once; give --file - a single time.');
       process.exit(2); // Exit code 2 for invalid CLI arguments
     }
-    options.introParsed = { start: introRange[0], end: introRange[1] };
-  } else {
-    options.introParsed = null;
+    if (process.stdin.isTTY) {
+      console.error('Error: --file - reads the PDF from standard input; pipe it in, e.g. curl ... | splitpdf --file -');
+      process.exit(2); // Exit code 2 for invalid CLI arguments
+    }
+    try {
+      // Spooled to a temporary file, removed when the process exits; parts go to the working directory by default
+      const spooled = await spoolToTempFile(process.stdin);
+      stdinRead = true;
+      process.on('exit', spooled.remove);
+      sources.push({ filePath: spooled.filePath, outputDir: options.outputDir || process.cwd(), outputBasename: STDIN_BASENAME });
+    } catch (error) {
+      console.error(`Error: ${error.message}`);
+      process.exit(getExitCode(error));
+    }
   }
-
-  // Set default output directory and basename if not provided
-  if (!options.outputDir) {
-    options.outputDir = path.dirname(path.resolve(options.file));
+  if (options.input) {
+    const matches = await expandGlob(options.input);
+    if (matches.length === 0) {
+      console.error(`Error: No files match ${options.input}`);
+      process.exit(3); // Exit code 3 for I/O error (file not found)
+    }
+    for (const filePath of matches) {
+      sources.push({ filePath, outputDir: options.outputDir || null });
+    }
+  }
+  if (options.recursive) {
+    if (!fs.existsSync(options.recursive) || !fs.statSync(options.recursive).isDirectory()) {
+      console.error(`Error: Directory not found at ${options.recursive}`);
+      process.exit(3); // Exit code 3 for I/O error (file not found)
+    }
+    for (const filePath of await findPdfFiles(options.recursive)) {
+      // Mirror the source tree below the output directory
+      const relativeDir = path.relative(options.recursive, path.dirname(filePath));
+      sources.push({
+        filePath,
+        outputDir: options.outputDir ? joinOutputDir(options.outputDir, relativeDir) : null
+      });
+    }
   }
-  
-  if (!options.outputBasename) {
-    options.outputBasename = path.basename(options.file, path.extname(options.file));
+  if (sources.length === 0 && !options.recursive) {
+    console.error('Error: Source PDF file is required (use --file, --input or --recursive).');
+    process.exit(2); // Exit code 2 for invalid CLI arguments
   }
-}
 
-validateOptions(options);
+  // Prepare options for the splitPdf function, one set per source file
+  const optionsList = [];
+  try {
+    for (const source of sources) {
+      const builder = SplitOptions.builder()
+        .input(source.filePath)
+        .parts(options.parts)
+        .dryRun(!!options.dryRun)
+        .threads(options.threads)
+        .backend(options.backend)
+        .bookmarks(options.bookmarks)
+        .crossLinks(options.crossLinks)
+        .pageLabels(options.pageLabels)
+        .pruneResources(options.pruneResources)
+        .attachments(options.attachments)
+        .documentIds(options.documentIds)
+        .duplicatePages(options.duplicatePages)
+        .outputFormat(options.outputFormat)
+        .renderDpi(options.renderDpi)
+        .eventSink(combineSinks(...eventSinks))
+        .signal(abortController.signal);
+      if (options.intro) {
+        builder.intro(options.intro);
+      }
+      if (options.groupBy) {
+        builder.groupBy(options.groupBy);
+      }
+      if (options.maxParts !== undefined) {
+        builder.maxParts(options.maxParts);
+      }
+      if (options.tooFewPages !== undefined) {
+        builder.tooFewPages(options.tooFewPages);
+      }
+      if (source.outputDir) {
+        builder.outputDir(source.outputDir);
+      }
+      if (options.outputBasename || source.outputBasename) {
+        builder.outputBasename(options.outputBasename || source.outputBasename);
+      }
+      if (options.nameTemplate) {
+        builder.nameTemplate(options.nameTemplate);
+      }
+      if (password !== undefined) {
+        builder.password(password);
+      }
+      if (options.partTitles) {
+        builder.partTitles();
+      }
+      if (options.title !== undefined || options.author !== undefined || options.subject !== undefined || options.keywords !== undefined) {
+        builder.metadata({
+          title: options.title,
+          author: options.author,
+          subject: options.subject,
+          keywords: options.keywords
+        });
+      }
+      if (options.flatten) {
+        builder.flatten();
+      }
+      if (options.spreads) {
+        builder.spreads();
+      }
+      if (options.repair) {
+        builder.repair();
+      }
+      if (options.checkpoint) {
+        builder.checkpoint(options.checkpoint);
+      }
+      if (options.lowMemory) {
+        builder.lowMemory();
+      }
+      if (options.deterministic) {
+        builder.deterministic();
+      }
+      if (options.timeout !== undefined) {
+        builder.timeout(options.timeout * 1000);
+      }
+      if (options.partTimeout !== undefined) {
+        builder.partTimeout(options.partTimeout * 1000);
+      }
+      if (options.memoryInterval !== undefined) {
+        builder.memoryInterval(options.memoryInterval * 1000);
+      }
+      if (options.memoryLimit !== undefined) {
+        builder.memoryLimit(options.memoryLimit * 1024 * 1024, options.memoryLimitAction);
+      }
+      if (options.dropBlankPages) {
+        builder.dropBlankPages().blankThreshold(options.blankThreshold);
+      }
+      if (options.breakSignatures) {
+        builder.breakSignatures();
+      }
+      if (options.keepPdfa) {
+        builder.keepPdfa();
+      }
+      if (options.pdfVersion) {
+        builder.pdfVersion(options.pdfVersion);
+      }
+      if (options.watermark !== undefined || options.watermarkImage !== undefined) {
+        builder.watermark({
+          text: options.watermark,
+          image: options.watermarkImage && path.resolve(options.watermarkImage),
+          position: options.watermarkPosition,
+          opacity: options.watermarkOpacity,
+          rotate: options.watermarkRotate,
+          size: options.watermarkSize
+        });
+      }
+      if (options.header !== undefined) {
+        builder.header(options.header);
+      }
+      if (options.footer !== undefined) {
+        builder.footer(options.footer);
+      }
+      if (options.bates !== undefined) {
+        builder.bates({ prefix: options.bates, start: options.batesStart, digits: options.batesDigits });
+      }
+      if (options.toc) {
+        builder.tableOfContents();
+      }
+      if (options.cover) {
+        builder.cover(options.cover);
+      }
+      if (options.pageSize) {
+        builder.pageSize(options.pageSize).pageFit(options.pageFit);
+      }
+      if (options.nUp !== undefined) {
+        builder.nUp(options.nUp);
+      }
+      if (options.booklet) {
+        builder.booklet();
+      }
+      if (options.optimize !== undefined) {
+        builder.optimize(options.optimize === true ? undefined : options.optimize);
+      }
+      if (options.linearize) {
+        builder.linearize();
+      }
+      if (options.textSidecar) {
+        builder.textSidecar();
+      }
+      if (options.thumbnails !== undefined) {
+        builder.thumbnails(options.thumbnails === true ? undefined : options.thumbnails);
+      }
+      if (options.decryptOutput) {
+        builder.decryptOutput();
+      }
+      if (options.userPassword !== undefined || options.ownerPassword !== undefined || options.permissions !== undefined) {
+        builder.encryption({
+          userPassword: options.userPassword,
+          ownerPassword: options.ownerPassword,
+          permissions: options.permissions
+        });
+      }
+      optionsList.push(builder.build());
+    }
+  } catch (error) {
+    console.error(`Error: ${error.message}`);
+    process.exit(error.code || 2);
+  }
 
-// Prepare options for the splitPdf function
-const splitterOptions = {
-  filePath: path.resolve(options.file),
-  parts: options.parts,
-  intro: options.introParsed,
-  outputDir: options.outputDir,
-  outputBasename: options.outputBasename,
-  dryRun: !!options.dryRun,
-  progressCallback: options.verbose ? (progress) => {
-    console.log(JSON.stringify(progress));
-  } : null
-};
+  // A checkpoint records the plan of one split
+  if (options.checkpoint && optionsList.length !== 1) {
+    console.error('Error: --checkpoint can only be used when splitting a single file.');
+    process.exit(2); // Exit code 2 for invalid CLI arguments
+  }
 
-// Execute PDF splitting
-async function run() {
+  // Execute PDF splitting
   try {
-    const result = await splitPdf(splitterOptions);
-    
-    if (options.dryRun) {
-      // Format and print the calculated page ranges
-      console.log(JSON.stringify({
-        parts: result
-      }, null, 2));
-    } else if (options.verbose) {
-      // Final summary in verbose mode
-      console.log(JSON.stringify({
-        event: 'complete',
-        parts: result.length,
-        outputFiles: result.map(part => part.outputPath)
-      }));
+    // A single file keeps the plain split result; several files get a combined one
+    const isBatch = optionsList.length !== 1 || !!options.input || !!options.recursive;
+    // One corrupt file in a batch is reported without aborting the remaining files
+    const result = isBatch
+      ? await splitPdfBatch(optionsList, { eventSink: combineSinks(...eventSinks), continueOnError: true })
+      : await splitPdf(optionsList[0]);
+    for (const parts of isBatch ? result.files.map(file => file.parts) : [result.parts]) {
+      await writeThumbnails(parts);
+    }
+    // Wait for the last --exec commands, whose failures are warnings unless they abort
+    if (execSink) {
+      const execWarnings = await execSink.finish();
+      result.warnings = [...(result.warnings || []), ...execWarnings];
+    }
+
+    if (options.dryRun || options.jsonEvents) {
+      // Print the split result (or the calculated page ranges) as the only JSON document on stdout
+      console.log(JSON.stringify(result, null, 2));
+    } else if (options.quiet) {
+      // Report failed batch files, which are errors, but nothing else
+      for (const failure of isBatch ? result.failed : []) {
+        console.error(`Error: ${failure.filePath}: ${failure.error.message}`);
+      }
+    } else if (isBatch) {
+      let partCount = 0;
+      for (const file of result.files) {
+        partCount += file.parts.length;
+      }
+      console.log(`Successfully split ${result.files.length} PDFs into ${partCount} parts.`);
+      console.log('Output files:');
+      for (const file of result.files) {
+        for (const part of file.parts) {
+          console.log(`  ${part.outputPath}`);
+        }
+      }
+      for (const file of result.files) {
+        for (const warning of file.warnings || []) {
+          console.error(`Warning: ${file.filePath}: ${warning.message}`);
+        }
+      }
+      for (const warning of result.warnings || []) {
+        console.error(`Warning: ${warning.message}`);
+      }
+      for (const failure of result.failed) {
+        console.error(`Error: ${failure.filePath}: ${failure.error.message}`);
+      }
     } else {
-      // Simple completion message in non-verbose mode
-      console.log(`Successfully split PDF into ${result.length} parts.`);
+      // Simple completion message
+      console.log(`Successfully split PDF into ${result.parts.length} parts.`);
       console.log('Output files:');
-      result.forEach(part => {
+      for (const part of result.parts) {
         console.log(`  ${part.outputPath}`);
+      }
+      for (const warning of result.warnings || []) {
+        console.error(`Warning: ${warning.message}`);
+      }
+    }
+
+    // A batch with failed files exits with the code of the first failure
+    process.exit(isBatch && result.failed.length > 0 ? result.failed[0].error.code : 0);
+  } catch (error) {
+    // A failed --exec command is what cancelled the split
+    let reportedError = error;
+    if (execSink) {
+      try {
+        await execSink.finish();
+      } catch (execError) {
+        reportedError = execError;
+      }
+    }
+    // Handle errors with specific exit codes
+    console.error(`Error: ${reportedError.message}`);
+    process.exit(getExitCode(reportedError));
+  }
+}
+
+async function runPlan(options) {
+  if (!process.stdin.isTTY || !process.stdout.isTTY) {
+    console.error('Error: The planner needs an interactive terminal. Use split --dry-run to preview a split in scripts.');
+    process.exit(2); // Exit code 2 for invalid CLI arguments
+  }
+
+  // Validate the options and start from an equal division into parts
+  let splitterOptions;
+  try {
+    const builder = SplitOptions.builder()
+      .input(options.file)
+      .parts(options.parts)
+      .backend(options.backend);
+    if (options.intro) {
+      builder.intro(options.intro);
+    }
+    if (options.outputDir) {
+      builder.outputDir(options.outputDir);
+    }
+    if (options.outputBasename) {
+      builder.outputBasename(options.outputBasename);
+    }
+    if (options.nameTemplate) {
+      builder.nameTemplate(options.nameTemplate);
+    }
+    const password = await resolvePassword(options);
+    if (password !== undefined) {
+      builder.password(password);
+    }
+    splitterOptions = builder.build();
+  } catch (error) {
+    console.error(`Error: ${error.message}`);
+    process.exit(error.code || 2);
+  }
+
+  try {
+    const splitter = await Splitter.open(splitterOptions.filePath, { backend: splitterOptions.backend, password: splitterOptions.password });
+    let ranges;
+    try {
+      const initialRanges = [];
+      for (const partInfo of splitter.plan(splitterOptions)) {
+        const content = partInfo.pages.content;
+        initialRanges.push({ start: content[0], end: content[content.length - 1] });
+      }
+
+      ranges = await runPlanner({
+        fileName: path.basename(splitterOptions.filePath),
+        totalPages: splitter.pageCount(),
+        fileSize: splitter.sourceBytes.length,
+        intro: splitterOptions.intro,
+        ranges: initialRanges,
+        outline: await getPdfOutline(splitterOptions.filePath, { password: splitterOptions.password }),
+        input: process.stdin,
+        output: process.stdout
       });
+      if (!ranges) {
+        console.log('Split cancelled.');
+        process.exit(0);
+      }
+    } finally {
+      await splitter.close();
+    }
+
+    const result = await splitPdf({ ...splitterOptions, ranges, parts: ranges.length });
+    console.log(`Successfully split PDF into ${result.parts.length} parts.`);
+    console.log('Output files:');
+    for (const part of result.parts) {
+      console.log(`  ${part.outputPath}`);
     }
-    
     process.exit(0);
   } catch (error) {
-    // Handle errors with specific exit codes
     console.error(`Error: ${error.message}`);
-    
-    // Use the error code if available, or determine code based on error message
-    const exitCode = error.code || (() => {
-      if (error.message.includes('I/O error')) return 3;
-      if (error.message.includes('PDF error')) return 4;
-      if (error.message.includes('encrypted')) return 5;
-      return 1; // Default unknown error
-    })();
-    
-    process.exit(exitCode);
+    process.exit(getExitCode(error));
+  }
+}
+
+/**
+ * Runs a command's library call, printing its result as JSON and exiting
+ * with the exit code matching any error
+ *
+ * @param {Function} operation Async function returning the result to print
+ */
+async function runJsonCommand(operation) {
+  try {
+    const result = await operation();
+    console.log(JSON.stringify(result, null, 2));
+    process.exit(0);
+  } catch (error) {
+    console.error(`Error: ${error.message}`);
+    process.exit(getExitCode(error));
+  }
+}
+
+async function runInfo(file) {
+  await runJsonCommand(() => getPdfInfo(path.resolve(file)));
+}
+
+async function runPageCount(file, options) {
+  try {
+    const password = await resolvePassword(options);
+    const pageCount = await getPdfPageCount(path.resolve(file), { backend: options.backend, password });
+    console.log(options.json ? JSON.stringify({ pageCount }) : String(pageCount));
+    process.exit(0);
+  } catch (error) {
+    console.error(`Error: ${error.message}`);
+    process.exit(getExitCode(error));
+  }
+}
+
+async function runValidate(file, options) {
+  try {
+    const password = await resolvePassword(options);
+    const report = await validatePdf(path.resolve(file), { password, maxPages: options.maxPages });
+    console.log(JSON.stringify(report, null, 2));
+    process.exit(report.valid ? 0 : 4); // Exit code 4 for PDF problems
+  } catch (error) {
+    console.error(`Error: ${error.message}`);
+    process.exit(getExitCode(error));
+  }
+}
+
+async function runExtract(options) {
+  const password = await resolvePassword(options);
+  await runJsonCommand(() => extractPages({
+    filePath: path.resolve(options.file),
+    pages: options.pages,
+    outputPath: path.resolve(options.output),
+    backend: options.backend,
+    password,
+    flatten: !!options.flatten,
+    decryptOutput: !!options.decryptOutput,
+    breakSignatures: !!options.breakSignatures
+  }));
+}
+
+async function runMerge(files, options) {
+  const filePaths = [];
+  for (const file of files) {
+    filePaths.push(path.resolve(file));
+  }
+  await runJsonCommand(() => mergePdfs({ filePaths, outputPath: path.resolve(options.output) }));
+}
+
+async function runJobFile(file, options) {
+  if (isNaN(options.concurrency) || options.concurrency < 1) {
+    console.error('Error: Concurrency must be a positive integer.');
+    process.exit(2); // Exit code 2 for invalid CLI arguments
+  }
+
+  let jobs;
+  try {
+    jobs = parseJobs(await fs.promises.readFile(path.resolve(file), 'utf8'));
+  } catch (error) {
+    console.error(`Error: I/O error: ${error.message}`);
+    process.exit(3); // Exit code 3 for I/O errors
+  }
+
+  // Cancel the running jobs and skip the rest on Ctrl+C or termination
+  const abortController = new AbortController();
+  for (const signalName of ['SIGINT', 'SIGTERM']) {
+    process.on(signalName, () => abortController.abort());
+  }
+
+  const statuses = await runJobs(jobs, {
+    concurrency: options.concurrency,
+    signal: abortController.signal,
+    onStatus: status => console.log(JSON.stringify(status))
+  });
+  if (abortController.signal.aborted) {
+    process.exit(130); // Exit code 130 for cancellation
+  }
+  // The first failed job decides the exit code, so scripts can tell what went wrong
+  const failed = statuses.find(status => status.status === 'failed');
+  process.exit(failed ? failed.error.code : 0);
+}
+
+async function runGenerate(output, options) {
+  try {
+    const bytes = await generatePdf({
+      pages: options.pages,
+      pageSizes: options.pageSize ? options.pageSize.split(',') : [],
+      bookmarks: options.bookmarks ? parseBookmarkSpec(options.bookmarks) : [],
+      pageLabels: options.pageLabels ? parsePageLabelSpec(options.pageLabels) : []
+    });
+    await fs.promises.writeFile(path.resolve(output), bytes);
+    console.log(`Generated ${options.pages} page(s) at ${output}`);
+    process.exit(0);
+  } catch (error) {
+    console.error(`Error: ${error.message}`);
+    process.exit(typeof error.code === 'number' ? error.code : 3); // Exit code 3 for I/O errors
+  }
+}
+
+async function runServe(options) {
+  const match = /^(.*):(\d+)$/.exec(options.listen);
+  if (!match) {
+    console.error('Error: Listen address must be in format host:port, e.g., 127.0.0.1:8080.');
+    process.exit(2); // Exit code 2 for invalid CLI arguments
+  }
+  for (const [name, value] of [['--max-upload', options.maxUpload], ['--max-jobs', options.maxJobs], ['--job-ttl', options.jobTtl]]) {
+    if (!(value > 0)) {
+      console.error(`Error: ${name} must be a positive number.`);
+      process.exit(2); // Exit code 2 for invalid CLI arguments
+    }
+  }
+
+  try {
+    const server = await startServer({
+      host: match[1],
+      port: Number(match[2]),
+      maxUploadSize: Math.round(options.maxUpload * 1024 * 1024),
+      maxJobs: options.maxJobs,
+      jobTtl: options.jobTtl * 1000
+    });
+    const address = server.address();
+    console.log(`Listening on http://${match[1]}:${address.port}`);
+
+    // Stop accepting jobs on Ctrl+C or termination
+    for (const signalName of ['SIGINT', 'SIGTERM']) {
+      process.on(signalName, () => {
+        server.close();
+        process.exit(0);
+      });
+    }
+  } catch (error) {
+    // Listening failures (address in use, permission denied) are I/O errors
+    console.error(`Error: ${error.message}`);
+    process.exit(typeof error.code === 'number' ? error.code : 3);
   }
 }
 
-run(); 
\ No newline at end of file
+async function runServeGrpc(options) {
+  const match = /^(.*):(\d+)$/.exec(options.listen);
+  if (!match) {
+    console.error('Error: Listen address must be in format host:port, e.g., 127.0.0.1:50051.');
+    process.exit(2); // Exit code 2 for invalid CLI arguments
+  }
+
+  try {
+    // Loaded here, so other commands don't pay for loading gRPC
+    const { startGrpcServer } = require('./grpc-server');
+    const { server, port } = await startGrpcServer({ host: match[1], port: Number(match[2]) });
+    console.log(`Listening for gRPC on ${match[1]}:${port}`);
+
+    // Stop accepting calls on Ctrl+C or termination
+    for (const signalName of ['SIGINT', 'SIGTERM']) {
+      process.on(signalName, () => {
+        server.forceShutdown();
+        process.exit(0);
+      });
+    }
+  } catch (error) {
+    // Listening failures (address in use, permission denied) are I/O errors
+    console.error(`Error: ${error.message}`);
+    process.exit(typeof error.code === 'number' ? error.code : 3);
+  }
+}
+
+try {
+  program.parse(process.argv);
+} catch (error) {
+  // Commander has already printed the message; --help and --version exit with 0
+  process.exit(error.exitCode === 0 ? 0 : 2); // Exit code 2 for invalid CLI arguments
+}
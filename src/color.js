@@ -0,0 +1,222 @@
+// Color pages, which print shops bill differently from black and white ones.
+// A page is found to use color by inspecting what it draws: the colors its
+// content streams set, the color spaces of its images and shadings, and the
+// same for the forms it draws, without rendering the page. Images in color
+// color spaces count as color even if their pixels happen to be gray, and
+// annotations are not inspected.
+
+const { PDFArray, PDFDict, PDFName, PDFNumber, PDFRef, PDFStream } = require('pdf-lib');
+const { contentOperations, pageContents, streamContents } = require('./content-stream');
+
+// Components of gray colors given in RGB or CMYK may differ by this much
+const COLOR_TOLERANCE = 0.02;
+
+// Kinds of color spaces: gray ones never give color, rgb and cmyk ones
+// depending on the color, and all others (patterns, spot colors) always
+const NAMED_COLOR_SPACES = {
+  DeviceGray: 'gray',
+  CalGray: 'gray',
+  G: 'gray',
+  DeviceRGB: 'rgb',
+  CalRGB: 'rgb',
+  RGB: 'rgb',
+  DeviceCMYK: 'cmyk',
+  CMYK: 'cmyk',
+  Pattern: 'color'
+};
+const ICC_COLOR_SPACES = { 1: 'gray', 3: 'rgb', 4: 'cmyk' };
+// Spot colorants that print with black ink
+const GRAY_COLORANTS = ['Black', 'All', 'None'];
+
+/**
+ * Finds the kind of a color space
+ *
+ * @param {PDFContext} context Document context
+ * @param {*} colorSpace Color space object, or the name of a color space as a string
+ * @param {PDFDict|undefined} resources Resources naming the color spaces in use
+ * @returns {string} gray, rgb, cmyk or color
+ */
+function colorSpaceKind(context, colorSpace, resources) {
+  const value = colorSpace instanceof PDFRef ? context.lookup(colorSpace) : colorSpace;
+  if (typeof value === 'string' || value instanceof PDFName) {
+    const name = typeof value === 'string' ? value : value.decodeText();
+    if (NAMED_COLOR_SPACES[name]) {
+      return NAMED_COLOR_SPACES[name];
+    }
+    const colorSpaces = resources && resources.lookupMaybe(PDFName.of('ColorSpace'), PDFDict);
+    const named = colorSpaces && colorSpaces.get(PDFName.of(name));
+    // Color spaces that can't be found are taken to be in color, so no color page is missed
+    return named ? colorSpaceKind(context, named, undefined) : 'color';
+  }
+  if (!(value instanceof PDFArray) || !(value.lookup(0) instanceof PDFName)) {
+    return 'color';
+  }
+
+  const family = value.lookup(0).decodeText();
+  if (family === 'ICCBased') {
+    const profile = value.lookup(1);
+    const components = profile instanceof PDFStream && profile.dict.lookup(PDFName.of('N'));
+    return (components instanceof PDFNumber && ICC_COLOR_SPACES[components.asNumber()]) || 'color';
+  }
+  if (family === 'Indexed') {
+    return colorSpaceKind(context, value.get(1), resources) === 'gray' ? 'gray' : 'color';
+  }
+  if (family === 'Separation' || family === 'DeviceN') {
+    const colorant = value.lookup(1);
+    const colorants = colorant instanceof PDFArray ? colorant.asArray().map(item => context.lookup(item)) : [colorant];
+    const gray = colorants.every(item => item instanceof PDFName && GRAY_COLORANTS.includes(item.decodeText()));
+    return gray ? 'gray' : 'color';
+  }
+  return NAMED_COLOR_SPACES[family] || 'color';
+}
+
+/**
+ * Tells whether a color is not a shade of gray
+ *
+ * @param {string} kind Kind of its color space (see colorSpaceKind)
+ * @param {Array<number|{name: string}>} operands Operands of the operator setting the color
+ * @returns {boolean} True for colors other than gray
+ */
+function isColor(kind, operands) {
+  if (kind === 'gray') {
+    return false;
+  }
+  const components = operands.filter(operand => typeof operand === 'number');
+  if (kind === 'rgb' || kind === 'cmyk') {
+    // Grays in CMYK have equal cyan, magenta and yellow, whatever their black
+    const chromatic = components.slice(0, 3);
+    return chromatic.length === 3 && Math.max(...chromatic) - Math.min(...chromatic) > COLOR_TOLERANCE;
+  }
+  return true;
+}
+
+/**
+ * Tells whether a content stream draws in color
+ *
+ * @param {PDFContext} context Document context
+ * @param {Uint8Array} contents Decoded content stream
+ * @param {PDFDict|undefined} resources Resources of the content stream
+ * @param {Set} visited Forms inspected so far, against loops
+ * @returns {boolean} True if the content sets a color or draws an image or shading in color
+ */
+function contentsHaveColor(context, contents, resources, visited) {
+  let fillKind = 'gray';
+  let strokeKind = 'gray';
+  const lookupResource = (category, operand) => {
+    const dict = resources && resources.lookupMaybe(PDFName.of(category), PDFDict);
+    return dict && operand && operand.name !== undefined ? dict.get(PDFName.of(operand.name)) : undefined;
+  };
+  const lastName = operands => [...operands].reverse().find(operand => operand.name !== undefined);
+
+  for (const { operator, operands } of contentOperations(contents)) {
+    switch (operator) {
+      case 'g':
+        fillKind = 'gray';
+        break;
+      case 'G':
+        strokeKind = 'gray';
+        break;
+      case 'rg':
+      case 'RG':
+      case 'k':
+      case 'K':
+        if (operator.toLowerCase() === operator) {
+          fillKind = operator === 'rg' ? 'rgb' : 'cmyk';
+        } else {
+          strokeKind = operator === 'RG' ? 'rgb' : 'cmyk';
+        }
+        if (isColor(operator.toLowerCase() === 'rg' ? 'rgb' : 'cmyk', operands)) {
+          return true;
+        }
+        break;
+      case 'cs':
+      case 'CS': {
+        const name = lastName(operands);
+        const kind = name ? colorSpaceKind(context, name.name, resources) : 'color';
+        if (operator === 'cs') {
+          fillKind = kind;
+        } else {
+          strokeKind = kind;
+        }
+        break;
+      }
+      case 'sc':
+      case 'scn':
+        if (isColor(fillKind, operands)) {
+          return true;
+        }
+        break;
+      case 'SC':
+      case 'SCN':
+        if (isColor(strokeKind, operands)) {
+          return true;
+        }
+        break;
+      case 'sh': {
+        const shading = context.lookup(lookupResource('Shading', lastName(operands)));
+        const shadingDict = shading instanceof PDFStream ? shading.dict : shading;
+        if (shadingDict instanceof PDFDict
+          && colorSpaceKind(context, shadingDict.get(PDFName.of('ColorSpace')), resources) !== 'gray') {
+          return true;
+        }
+        break;
+      }
+      case 'BI': {
+        // Image masks are painted in the fill color, which is checked where it is set
+        const entries = new Map();
+        for (let i = 0; i + 1 < operands.length; i += 2) {
+          if (operands[i].name !== undefined) {
+            entries.set(operands[i].name, operands[i + 1]);
+          }
+        }
+        const colorSpace = entries.get('CS') || entries.get('ColorSpace');
+        if (colorSpace && colorSpace.name !== undefined && colorSpaceKind(context, colorSpace.name, resources) !== 'gray') {
+          return true;
+        }
+        break;
+      }
+      case 'Do': {
+        const ref = lookupResource('XObject', lastName(operands));
+        const xObject = context.lookup(ref);
+        if (!(xObject instanceof PDFStream) || visited.has(ref || xObject)) {
+          break;
+        }
+        const subtype = xObject.dict.lookup(PDFName.of('Subtype'));
+        if (subtype === PDFName.of('Image')) {
+          const imageMask = xObject.dict.lookup(PDFName.of('ImageMask'));
+          if (!(imageMask && imageMask.asBoolean && imageMask.asBoolean())
+            && colorSpaceKind(context, xObject.dict.get(PDFName.of('ColorSpace')), resources) !== 'gray') {
+            return true;
+          }
+        } else if (subtype === PDFName.of('Form')) {
+          visited.add(ref || xObject);
+          const formContents = streamContents(xObject);
+          const formResources = xObject.dict.lookupMaybe(PDFName.of('Resources'), PDFDict) || resources;
+          if (formContents && contentsHaveColor(context, formContents, formResources, visited)) {
+            return true;
+          }
+        }
+        break;
+      }
+      default:
+        break;
+    }
+  }
+  return false;
+}
+
+/**
+ * Tells whether a page draws in color
+ *
+ * @param {PDFPage} page Page
+ * @returns {boolean} True if anything on the page is drawn in a color other than gray
+ */
+function pageHasColor(page) {
+  const contents = pageContents(page);
+  // Pages that can't be read are taken to be in color, so no color page is missed
+  return !contents || contentsHaveColor(page.doc.context, contents, page.node.Resources(), new Set());
+}
+
+module.exports = {
+  pageHasColor
+};
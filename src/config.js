@@ -0,0 +1,288 @@
+// Loads defaults for the CLI from a TOML config file, by default
+// ~/.config/splitpdf/config.toml (or $XDG_CONFIG_HOME/splitpdf/config.toml).
+//
+// Only the TOML subset a config file needs is supported: comments, [tables],
+// bare or quoted keys, strings, integers, floats, booleans and single-line arrays.
+
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+
+// Config keys and the CLI options they provide defaults for
+const CONFIG_KEYS = {
+  parts: 'parts',
+  intro: 'intro',
+  output_dir: 'outputDir',
+  output_basename: 'outputBasename',
+  name_template: 'nameTemplate',
+  verbose: 'verbose',
+  quiet: 'quiet',
+  json_events: 'jsonEvents',
+  threads: 'threads',
+  backend: 'backend',
+  qpdf_path: 'qpdfPath'
+};
+
+/**
+ * Creates an error for an unreadable or invalid config file
+ *
+ * @param {string} message Human-readable description of the problem
+ * @param {number} code Exit code
+ * @returns {Error} Error with the exit code
+ */
+function configError(message, code) {
+  const error = new Error(message);
+  error.code = code;
+  return error;
+}
+
+/**
+ * @returns {string} Path of the config file read when --config is not given
+ */
+function getDefaultConfigPath() {
+  const configHome = process.env.XDG_CONFIG_HOME || path.join(os.homedir(), '.config');
+  return path.join(configHome, 'splitpdf', 'config.toml');
+}
+
+/**
+ * Removes a trailing comment, keeping # characters inside strings
+ *
+ * @param {string} line Line of TOML
+ * @returns {string} Line without its comment
+ */
+function stripComment(line) {
+  let quote = null;
+  for (let i = 0; i < line.length; i++) {
+    const char = line[i];
+    if (quote) {
+      if (char === '\\' && quote === '"') {
+        i++;
+      } else if (char === quote) {
+        quote = null;
+      }
+    } else if (char === '"' || char === "'") {
+      quote = char;
+    } else if (char === '#') {
+      return line.slice(0, i);
+    }
+  }
+  return line;
+}
+
+/**
+ * Parses a key, which may be bare (letters, digits, - and _) or quoted
+ *
+ * @param {string} text Key text
+ * @returns {string|null} Key, or null if it is not valid
+ */
+function parseKey(text) {
+  const key = text.trim();
+  if (/^[A-Za-z0-9_-]+$/.test(key)) {
+    return key;
+  }
+  if (/^"[^"\\]*"$/.test(key) || /^'[^']*'$/.test(key)) {
+    return key.slice(1, -1);
+  }
+  return null;
+}
+
+/**
+ * Parses a value starting at the beginning of the text
+ *
+ * @param {string} text Text starting with a value
+ * @returns {{value: *, rest: string}|null} Parsed value and the text after it, or null if invalid
+ */
+function parseValue(text) {
+  if (text.startsWith('"')) {
+    let value = '';
+    for (let i = 1; i < text.length; i++) {
+      const char = text[i];
+      if (char === '"') {
+        return { value, rest: text.slice(i + 1) };
+      }
+      if (char !== '\\') {
+        value += char;
+        continue;
+      }
+      const escaped = text[++i];
+      const escapes = { n: '\n', t: '\t', r: '\r', '"': '"', '\\': '\\' };
+      if (escaped in escapes) {
+        value += escapes[escaped];
+      } else if (escaped === 'u' && /^[0-9A-Fa-f]{4}$/.test(text.slice(i + 1, i + 5))) {
+        value += String.fromCharCode(parseInt(text.slice(i + 1, i + 5), 16));
+        i += 4;
+      } else {
+        return null;
+      }
+    }
+    return null;
+  }
+
+  if (text.startsWith("'")) {
+    const end = text.indexOf("'", 1);
+    return end === -1 ? null : { value: text.slice(1, end), rest: text.slice(end + 1) };
+  }
+
+  if (text.startsWith('[')) {
+    const values = [];
+    let rest = text.slice(1).trimStart();
+    while (!rest.startsWith(']')) {
+      const item = parseValue(rest);
+      if (!item) {
+        return null;
+      }
+      values.push(item.value);
+      rest = item.rest.trimStart();
+      if (rest.startsWith(',')) {
+        rest = rest.slice(1).trimStart();
+      } else if (!rest.startsWith(']')) {
+        return null;
+      }
+    }
+    return { value: values, rest: rest.slice(1) };
+  }
+
+  const match = /^[^,\]\s]+/.exec(text);
+  if (!match) {
+    return null;
+  }
+  const token = match[0];
+  const rest = text.slice(token.length);
+  if (token === 'true' || token === 'false') {
+    return { value: token === 'true', rest };
+  }
+  if (/^[+-]?\d[\d_]*(\.\d[\d_]*)?([eE][+-]?\d+)?$/.test(token)) {
+    return { value: Number(token.replace(/_/g, '')), rest };
+  }
+  return null;
+}
+
+/**
+ * Parses TOML text
+ *
+ * @param {string} text TOML document
+ * @returns {Object} Parsed tables and values
+ */
+function parseToml(text) {
+  const root = {};
+  let table = root;
+
+  const lines = text.split(/\r?\n/);
+  for (let lineIndex = 0; lineIndex < lines.length; lineIndex++) {
+    const line = stripComment(lines[lineIndex]).trim();
+    const invalid = () => new Error(`Invalid TOML on line ${lineIndex + 1}: ${lines[lineIndex].trim()}`);
+    if (line === '') {
+      continue;
+    }
+
+    // [table] or [table.subtable]
+    if (line.startsWith('[')) {
+      if (!line.endsWith(']')) {
+        throw invalid();
+      }
+      table = root;
+      for (const keyText of line.slice(1, -1).split('.')) {
+        const key = parseKey(keyText);
+        if (key === null) {
+          throw invalid();
+        }
+        if (table[key] === undefined) {
+          table[key] = {};
+        } else if (typeof table[key] !== 'object' || Array.isArray(table[key])) {
+          throw invalid();
+        }
+        table = table[key];
+      }
+      continue;
+    }
+
+    // key = value
+    const equalsIndex = line.indexOf('=');
+    const key = equalsIndex === -1 ? null : parseKey(line.slice(0, equalsIndex));
+    const parsed = key === null ? null : parseValue(line.slice(equalsIndex + 1).trim());
+    if (!parsed || parsed.rest.trim() !== '' || key in table) {
+      throw invalid();
+    }
+    table[key] = parsed.value;
+  }
+
+  return root;
+}
+
+/**
+ * Converts the values of a config table into CLI option defaults
+ *
+ * @param {Object} table Config table
+ * @param {string} source Where the table comes from, for error messages
+ * @returns {Object} Option values keyed by option name
+ */
+function toOptionDefaults(table, source) {
+  const defaults = {};
+  for (const [key, value] of Object.entries(table)) {
+    // Tables such as [profile.*] are not option values
+    if (value !== null && typeof value === 'object' && !Array.isArray(value)) {
+      continue;
+    }
+    if (!(key in CONFIG_KEYS)) {
+      throw configError(`Unknown config key ${key} in ${source}`, 2);
+    }
+    defaults[CONFIG_KEYS[key]] = value;
+  }
+  return defaults;
+}
+
+/**
+ * Collects the option defaults of the config file, with a named profile's
+ * values taking precedence over the top-level ones
+ *
+ * @param {Object} config Parsed config
+ * @param {string} [profileName] Name of a [profile.<name>] table
+ * @returns {Object} Option values keyed by option name
+ */
+function getOptionDefaults(config, profileName) {
+  const defaults = toOptionDefaults(config, 'config file');
+  if (!profileName) {
+    return defaults;
+  }
+
+  const profiles = config.profile || {};
+  if (!Object.prototype.hasOwnProperty.call(profiles, profileName)) {
+    const names = Object.keys(profiles);
+    const available = names.length > 0 ? `Available profiles: ${names.join(', ')}` : 'The config file defines no profiles';
+    throw configError(`Unknown profile ${profileName}. ${available}.`, 2);
+  }
+  return { ...defaults, ...toOptionDefaults(profiles[profileName], `profile ${profileName}`) };
+}
+
+/**
+ * Reads the config file
+ *
+ * @param {string} [configPath] Path given with --config; the default path is optional and may not exist
+ * @returns {Object} Parsed config, empty if there is no config file
+ */
+function loadConfig(configPath) {
+  const filePath = configPath || getDefaultConfigPath();
+  let text;
+  try {
+    text = fs.readFileSync(filePath, 'utf8');
+  } catch (error) {
+    if (!configPath && error.code === 'ENOENT') {
+      return {};
+    }
+    throw configError(`Cannot read config file at ${filePath}: ${error.message}`, 3); // Exit code 3 for I/O error
+  }
+
+  try {
+    return parseToml(text);
+  } catch (error) {
+    throw configError(`${error.message} (in ${filePath})`, 2); // Exit code 2 for invalid arguments
+  }
+}
+
+module.exports = {
+  getDefaultConfigPath,
+  getOptionDefaults,
+  loadConfig,
+  parseToml,
+  toOptionDefaults
+};
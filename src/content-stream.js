@@ -0,0 +1,134 @@
+// Reading page content streams: decoding them and splitting them into
+// operations, for features that inspect what pages draw (see src/color.js
+// and src/optimize.js). Only numbers and names are kept as operands, which is
+// what these features need.
+
+const { PDFArray, PDFFlateStream, PDFRawStream, decodePDFRawStream } = require('pdf-lib');
+
+const WHITESPACE = /[\0\t\n\f\r ]/;
+const DELIMITER = /[()<>[\]{}/%]/;
+const NUMBER = /^[+-]?(\d+\.?\d*|\.\d+)$/;
+
+/**
+ * Decodes a stream, or returns null if its filters are not supported
+ *
+ * @param {*} stream PDF object
+ * @returns {Uint8Array|null} Decoded data
+ */
+function streamContents(stream) {
+  if (stream instanceof PDFRawStream) {
+    try {
+      return decodePDFRawStream(stream).decode();
+    } catch (error) {
+      return null;
+    }
+  }
+  // Streams created by pdf-lib, such as the content of stamps, are only compressed when saved
+  return stream instanceof PDFFlateStream ? stream.getUnencodedContents() : null;
+}
+
+/**
+ * Splits a content stream into its operations. Numbers and names are kept as
+ * operands, other operands (strings, arrays, dictionaries) are skipped. For
+ * inline images (BI), the operands are the entries of the image dictionary.
+ *
+ * @param {Uint8Array} contents Decoded content stream
+ * @returns {Generator<{operator: string, operands: Array<number|{name: string}>}>} Operations in drawing order
+ */
+function* contentOperations(contents) {
+  const text = Buffer.from(contents).toString('latin1');
+  let operands = [];
+  let inlineImage = false;
+  let i = 0;
+  while (i < text.length) {
+    const char = text[i];
+    if (WHITESPACE.test(char)) {
+      i++;
+    } else if (char === '%') {
+      while (i < text.length && text[i] !== '\n' && text[i] !== '\r') {
+        i++;
+      }
+    } else if (char === '(') {
+      // Literal strings may hold balanced parentheses and escaped ones
+      let depth = 0;
+      do {
+        if (text[i] === '\\') {
+          i++;
+        } else if (text[i] === '(') {
+          depth++;
+        } else if (text[i] === ')') {
+          depth--;
+        }
+        i++;
+      } while (i < text.length && depth > 0);
+    } else if (char === '<') {
+      // Dictionaries start with <<, hexadecimal strings with a single <
+      i = text[i + 1] === '<' ? i + 2 : text.indexOf('>', i) + 1 || text.length;
+    } else if (char === '/') {
+      let end = i + 1;
+      while (end < text.length && !WHITESPACE.test(text[end]) && !DELIMITER.test(text[end])) {
+        end++;
+      }
+      // Names may escape characters as #xx
+      operands.push({ name: text.slice(i + 1, end).replace(/#([0-9a-fA-F]{2})/g, (match, hex) => String.fromCharCode(parseInt(hex, 16))) });
+      i = end;
+    } else if (DELIMITER.test(char)) {
+      i++;
+    } else {
+      let end = i;
+      while (end < text.length && !WHITESPACE.test(text[end]) && !DELIMITER.test(text[end])) {
+        end++;
+      }
+      const token = text.slice(i, end);
+      i = end;
+      if (NUMBER.test(token)) {
+        operands.push(Number(token));
+      } else if (token === 'BI') {
+        inlineImage = true;
+        operands = [];
+      } else if (token === 'ID' && inlineImage) {
+        // The image data follows a single whitespace character and ends with EI
+        const imageEnd = /\sEI(?=[\0\t\n\f\r ]|$)/g;
+        imageEnd.lastIndex = i + 1;
+        const match = imageEnd.exec(text);
+        i = match ? match.index + match[0].length : text.length;
+        inlineImage = false;
+        yield { operator: 'BI', operands };
+        operands = [];
+      } else if (!inlineImage && token !== 'true' && token !== 'false' && token !== 'null') {
+        yield { operator: token, operands };
+        operands = [];
+      }
+    }
+  }
+}
+
+/**
+ * Decodes the content of a page
+ *
+ * @param {PDFPage} page Page
+ * @returns {Buffer|null} Its content streams, decoded and joined, or null if one of them can't be decoded
+ */
+function pageContents(page) {
+  const context = page.doc.context;
+  const contents = page.node.Contents();
+  const streams = contents instanceof PDFArray
+    ? contents.asArray().map(item => context.lookup(item))
+    : (contents ? [contents] : []);
+  // Content streams of a page are drawn as one, so operations may span them
+  const parts = [];
+  for (const stream of streams) {
+    const data = streamContents(stream);
+    if (!data) {
+      return null;
+    }
+    parts.push(Buffer.from(data), Buffer.from('\n'));
+  }
+  return Buffer.concat(parts);
+}
+
+module.exports = {
+  streamContents,
+  pageContents,
+  contentOperations
+};
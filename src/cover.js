@@ -0,0 +1,78 @@
+// Cover pages: a page at the start of each part with the source title, the
+// part number, the source pages it contains and the date, as print rooms
+// put in front of each bundle. The text comes from a template.
+
+const { METADATA_PLACEHOLDERS, findUnknownPlaceholders, formatMetadata, templateValues } = require('./metadata');
+const { embedStampFont, toEncodableText } = require('./stamp');
+const { formatPageRanges } = require('./plan');
+
+// Placeholders of cover templates beyond the metadata ones
+const COVER_EXTRA_PLACEHOLDERS = ['page_range', 'date'];
+const COVER_PLACEHOLDERS = [...METADATA_PLACEHOLDERS, ...COVER_EXTRA_PLACEHOLDERS];
+const DEFAULT_COVER_TEMPLATE = '{source_title}\nPart {part} of {total}\nPages {page_range}\n{date}';
+
+const MARGIN = 56;
+const HEADING_SIZE = 24;
+const FONT_SIZE = 14;
+const LINE_HEIGHT = 28;
+
+/**
+ * Lists the placeholders of a cover template that are not supported
+ *
+ * @param {string} template Cover template
+ * @returns {Array<string>} Unknown placeholder names
+ */
+function findUnknownCoverPlaceholders(template) {
+  return findUnknownPlaceholders(template).filter(name => !COVER_EXTRA_PLACEHOLDERS.includes(name));
+}
+
+/**
+ * Fills in the cover template of one part
+ *
+ * @param {string} template Template with placeholders from COVER_PLACEHOLDERS; lines are separated by newlines or \n
+ * @param {Object} sourceMetadata Metadata read with readMetadata
+ * @param {Object} part Part being built, with index, total and basename
+ * @param {Array<number>} pageNumbers 1-based source page numbers of the part
+ * @param {Date} date Date printed for {date}
+ * @returns {{lines: Array<string>}} Cover of the part; empty lines are left out
+ */
+function partCover(template, sourceMetadata, part, pageNumbers, date) {
+  const filled = template
+    .replace(/\{page_range\}/g, formatPageRanges(pageNumbers))
+    .replace(/\{date\}/g, date.toISOString().slice(0, 10));
+  const text = formatMetadata(filled, templateValues(sourceMetadata, part));
+  return { lines: text.split(/\r?\n|\\n/).map(line => line.trim()).filter(line => line !== '') };
+}
+
+/**
+ * Inserts a cover page at the start of a part
+ *
+ * @param {PDFDocument} pdf Part document
+ * @param {Object} cover Cover created with partCover; the first line is the heading
+ * @returns {Promise<number>} Number of pages inserted (1)
+ */
+async function addCoverPage(pdf, cover) {
+  const font = await embedStampFont(pdf);
+  const firstPage = pdf.getPages()[0];
+  const [width, height] = firstPage ? [firstPage.getWidth(), firstPage.getHeight()] : [612, 792];
+  const page = pdf.insertPage(0, [width, height]);
+
+  let y = height * 0.6;
+  for (const [lineIndex, line] of cover.lines.entries()) {
+    const text = toEncodableText(font, line);
+    // Long lines are made smaller to fit the page
+    const maxSize = lineIndex === 0 ? HEADING_SIZE : FONT_SIZE;
+    const size = Math.min(maxSize, (width - 2 * MARGIN) / Math.max(font.widthOfTextAtSize(text, 1), 1));
+    page.drawText(text, { x: (width - font.widthOfTextAtSize(text, size)) / 2, y, size, font });
+    y -= lineIndex === 0 ? LINE_HEIGHT * 1.5 : LINE_HEIGHT;
+  }
+  return 1;
+}
+
+module.exports = {
+  COVER_PLACEHOLDERS,
+  DEFAULT_COVER_TEMPLATE,
+  findUnknownCoverPlaceholders,
+  partCover,
+  addCoverPage
+};
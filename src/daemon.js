@@ -0,0 +1,307 @@
+// JSON-RPC 2.0 daemon reading one request per line from stdin and writing
+// responses and event notifications to stdout, one JSON document per line.
+// Opened source PDFs are cached, so a GUI wrapper can keep one warm process
+// instead of paying startup and parsing cost for every request.
+//
+// Methods:
+//   pageCount { file, backend }                       -> number
+//   plan      { file, parts, intro, outputDir, ... }  -> { parts }
+//   split     { file, parts, intro, outputDir, ... }  -> { parts }, with
+//             "event" notifications { id, ...event } while it runs;
+//             part thumbnails are base64-encoded PNGs
+//   cancel    { id }                                  -> true if a running split was cancelled
+
+const fs = require('fs');
+const readline = require('readline');
+const { SplitOptions } = require('./options');
+const { Splitter } = require('./splitter');
+const { createCallbackSink } = require('./events');
+
+// Standard JSON-RPC error codes
+const PARSE_ERROR = -32700;
+const INVALID_REQUEST = -32600;
+const METHOD_NOT_FOUND = -32601;
+
+/**
+ * Builds validated split options from request parameters
+ *
+ * @param {Object} params Request parameters
+ * @returns {Object} Options accepted by Splitter#split
+ */
+function buildOptions(params) {
+  const builder = SplitOptions.builder()
+    .input(params.file)
+    .parts(params.parts === undefined ? 1 : params.parts)
+    .dryRun(!!params.dryRun);
+  if (params.intro) {
+    builder.intro(params.intro);
+  }
+  if (params.groupBy) {
+    builder.groupBy(params.groupBy);
+  }
+  if (params.maxParts !== undefined) {
+    builder.maxParts(params.maxParts);
+  }
+  if (params.tooFewPages) {
+    builder.tooFewPages(params.tooFewPages);
+  }
+  if (params.outputDir) {
+    builder.outputDir(params.outputDir);
+  }
+  if (params.outputBasename) {
+    builder.outputBasename(params.outputBasename);
+  }
+  if (params.threads !== undefined) {
+    builder.threads(params.threads);
+  }
+  if (params.backend) {
+    builder.backend(params.backend);
+  }
+  if (params.password !== undefined) {
+    builder.password(params.password);
+  }
+  if (params.spreads) {
+    builder.spreads();
+  }
+  if (params.repair) {
+    builder.repair();
+  }
+  if (params.checkpoint) {
+    builder.checkpoint(params.checkpoint);
+  }
+  if (params.lowMemory) {
+    builder.lowMemory();
+  }
+  if (params.deterministic) {
+    builder.deterministic();
+  }
+  if (params.timeout !== undefined) {
+    builder.timeout(params.timeout);
+  }
+  if (params.partTimeout !== undefined) {
+    builder.partTimeout(params.partTimeout);
+  }
+  if (params.memoryInterval !== undefined) {
+    builder.memoryInterval(params.memoryInterval);
+  }
+  if (params.memoryLimit !== undefined) {
+    builder.memoryLimit(params.memoryLimit, params.memoryLimitAction);
+  }
+  if (params.dropBlankPages) {
+    builder.dropBlankPages();
+  }
+  if (params.blankThreshold !== undefined) {
+    builder.blankThreshold(params.blankThreshold);
+  }
+  if (params.duplicatePages) {
+    builder.duplicatePages(params.duplicatePages);
+  }
+  if (params.bookmarks) {
+    builder.bookmarks(params.bookmarks);
+  }
+  if (params.pageLabels !== undefined) {
+    builder.pageLabels(params.pageLabels);
+  }
+  if (params.crossLinks) {
+    builder.crossLinks(params.crossLinks);
+  }
+  if (params.attachments) {
+    builder.attachments(params.attachments);
+  }
+  if (params.breakSignatures) {
+    builder.breakSignatures();
+  }
+  if (params.keepPdfa) {
+    builder.keepPdfa();
+  }
+  if (params.pdfVersion) {
+    builder.pdfVersion(params.pdfVersion);
+  }
+  if (params.documentIds) {
+    builder.documentIds(params.documentIds);
+  }
+  if (params.bates) {
+    builder.bates(params.bates);
+  }
+  if (params.watermark) {
+    builder.watermark(params.watermark);
+  }
+  if (params.header) {
+    builder.header(params.header);
+  }
+  if (params.footer) {
+    builder.footer(params.footer);
+  }
+  if (params.flatten) {
+    builder.flatten();
+  }
+  if (params.tableOfContents) {
+    builder.tableOfContents();
+  }
+  if (params.cover) {
+    builder.cover(params.cover);
+  }
+  if (params.pageSize) {
+    builder.pageSize(params.pageSize);
+  }
+  if (params.pageFit) {
+    builder.pageFit(params.pageFit);
+  }
+  if (params.nUp) {
+    builder.nUp(params.nUp);
+  }
+  if (params.booklet) {
+    builder.booklet();
+  }
+  if (params.pruneResources !== undefined) {
+    builder.pruneResources(params.pruneResources);
+  }
+  if (params.optimize) {
+    builder.optimize(params.optimize === true ? undefined : params.optimize);
+  }
+  if (params.linearize) {
+    builder.linearize();
+  }
+  if (params.outputFormat) {
+    builder.outputFormat(params.outputFormat);
+  }
+  if (params.renderDpi) {
+    builder.renderDpi(params.renderDpi);
+  }
+  if (params.textSidecar) {
+    builder.textSidecar();
+  }
+  if (params.thumbnails) {
+    builder.thumbnails(params.thumbnails === true ? undefined : params.thumbnails);
+  }
+  if (params.metadata) {
+    builder.metadata(params.metadata);
+  }
+  return builder.build();
+}
+
+/**
+ * Runs the daemon until the input stream ends
+ *
+ * @param {stream.Readable} input Stream of JSON-RPC requests, one per line
+ * @param {stream.Writable} output Stream receiving responses and notifications
+ * @returns {Promise<void>} Resolves once the input has ended and every request has been answered
+ */
+async function runDaemon(input, output) {
+  // Opened sources by backend and path, reopened when the file changes
+  const splitters = new Map();
+  // Abort controllers of running splits by request id
+  const runningSplits = new Map();
+  const pendingRequests = new Set();
+
+  function send(message) {
+    output.write(`${JSON.stringify({ jsonrpc: '2.0', ...message })}\n`);
+  }
+
+  async function getSplitter(options) {
+    // Sources opened with different passwords, spread splitting or low memory mode are cached separately
+    const key = JSON.stringify([options.backend, options.filePath, options.password, !!options.spreads, !!options.lowMemory]);
+    const { mtimeMs } = await fs.promises.stat(options.filePath);
+    const cached = splitters.get(key);
+    if (cached && cached.mtimeMs === mtimeMs) {
+      return cached.splitter;
+    }
+    if (cached) {
+      splitters.delete(key);
+      await cached.splitter.close();
+    }
+    const splitter = await Splitter.open(options.filePath, { backend: options.backend, password: options.password, spreads: options.spreads, lowMemory: options.lowMemory });
+    splitters.set(key, { splitter, mtimeMs });
+    return splitter;
+  }
+
+  async function handleMethod(id, method, params) {
+    if (method === 'cancel') {
+      const abortController = runningSplits.get(params.id);
+      if (abortController) {
+        abortController.abort();
+      }
+      return !!abortController;
+    }
+
+    if (method !== 'pageCount' && method !== 'plan' && method !== 'split') {
+      const error = new Error(`Method not found: ${method}`);
+      error.code = METHOD_NOT_FOUND;
+      throw error;
+    }
+
+    const options = buildOptions(params);
+    const splitter = await getSplitter(options);
+    if (method === 'pageCount') {
+      return splitter.pageCount();
+    }
+    if (method === 'plan') {
+      return { parts: splitter.plan(options) };
+    }
+
+    const abortController = new AbortController();
+    runningSplits.set(id, abortController);
+    try {
+      const result = await splitter.split({
+        ...options,
+        signal: abortController.signal,
+        eventSink: createCallbackSink((event) => {
+          send({ method: 'event', params: { id, ...event } });
+        })
+      });
+      for (const part of result.parts) {
+        if (part.thumbnail) {
+          part.thumbnail = Buffer.from(part.thumbnail).toString('base64');
+        }
+      }
+      return result;
+    } finally {
+      runningSplits.delete(id);
+    }
+  }
+
+  async function handleLine(line) {
+    if (line.trim() === '') {
+      return;
+    }
+
+    let request;
+    try {
+      request = JSON.parse(line);
+    } catch (error) {
+      send({ id: null, error: { code: PARSE_ERROR, message: `Parse error: ${error.message}` } });
+      return;
+    }
+    if (!request || typeof request.method !== 'string') {
+      send({ id: null, error: { code: INVALID_REQUEST, message: 'Invalid request' } });
+      return;
+    }
+
+    const id = request.id === undefined ? null : request.id;
+    try {
+      const result = await handleMethod(id, request.method, request.params || {});
+      send({ id, result });
+    } catch (error) {
+      // Split errors carry the CLI exit code, which callers can rely on as well
+      send({ id, error: { code: typeof error.code === 'number' ? error.code : 1, message: error.message } });
+    }
+  }
+
+  // Requests run concurrently so that cancel can reach a split that is still running
+  const lines = readline.createInterface({ input, crlfDelay: Infinity });
+  for await (const line of lines) {
+    const handling = handleLine(line);
+    pendingRequests.add(handling);
+    handling.finally(() => pendingRequests.delete(handling));
+  }
+
+  await Promise.all(pendingRequests);
+  for (const { splitter } of splitters.values()) {
+    await splitter.close();
+  }
+}
+
+module.exports = {
+  buildOptions,
+  runDaemon
+};
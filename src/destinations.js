@@ -0,0 +1,129 @@
+// Named destinations, which deep links such as document.pdf#nameddest=intro
+// refer to. Each part keeps the names whose target pages it contains, with
+// the targets remapped to the part's pages.
+
+const { PDFArray, PDFDict, PDFHexString, PDFName, PDFString } = require('pdf-lib');
+const { buildDestination, indexPages, readDestinationArray } = require('./outline');
+
+/**
+ * Reads every named destination, from the catalog's Dests dictionary and the Dests name tree
+ *
+ * @param {PDFDocument} pdf Loaded document
+ * @returns {Array<{name: string, pageIndex: number, view: Array|null}>} Destinations with a known target page
+ */
+function readNamedDestinations(pdf) {
+  const pageIndexByRef = indexPages(pdf);
+  const byName = new Map();
+  const add = (name, destination) => {
+    // Destinations may be wrapped in a dictionary with a D entry
+    const array = destination instanceof PDFDict ? destination.lookup(PDFName.of('D')) : destination;
+    const { pageIndex, view } = readDestinationArray(array, pageIndexByRef);
+    if (pageIndex !== null && !byName.has(name)) {
+      byName.set(name, { name, pageIndex, view });
+    }
+  };
+
+  const dests = pdf.catalog.lookupMaybe(PDFName.of('Dests'), PDFDict);
+  if (dests) {
+    for (const [key] of dests.entries()) {
+      add(key.decodeText(), dests.lookup(key));
+    }
+  }
+
+  const names = pdf.catalog.lookupMaybe(PDFName.of('Names'), PDFDict);
+  const tree = names && names.lookupMaybe(PDFName.of('Dests'), PDFDict);
+  // Damaged files can contain loops, so every node is read only once
+  const visited = new Set();
+  const readTree = (node) => {
+    if (!node || visited.has(node)) {
+      return;
+    }
+    visited.add(node);
+    const entries = node.lookupMaybe(PDFName.of('Names'), PDFArray);
+    for (let i = 0; entries && i + 1 < entries.size(); i += 2) {
+      const key = entries.lookup(i);
+      if (key instanceof PDFString || key instanceof PDFHexString) {
+        add(key.decodeText(), entries.lookup(i + 1));
+      }
+    }
+    const kids = node.lookupMaybe(PDFName.of('Kids'), PDFArray);
+    for (let i = 0; kids && i < kids.size(); i++) {
+      readTree(kids.lookupMaybe(i, PDFDict));
+    }
+  };
+  readTree(tree);
+
+  return [...byName.values()];
+}
+
+/**
+ * Encodes a destination name as a name tree key
+ *
+ * @param {string} name Destination name
+ * @returns {PDFHexString} Key with the same bytes as the source key where possible
+ */
+function toKey(name) {
+  // Deep links match the key bytes, so single-byte names are written as they were read
+  if (/^[\u0000-\u00ff]*$/.test(name)) {
+    return PDFHexString.of(Buffer.from(name, 'latin1').toString('hex'));
+  }
+  return PDFHexString.fromText(name);
+}
+
+/**
+ * Keeps the named destinations pointing into a part, remapped to the part's pages
+ *
+ * @param {Array<Object>} destinations Source destinations read with readNamedDestinations
+ * @param {Array<number>} sourcePageIndices 0-based source page indices of the part, in output order
+ * @returns {Array<Object>} Destinations of the part
+ */
+function partDestinations(destinations, sourcePageIndices) {
+  // As with bookmarks, pages copied twice are targeted at their last copy
+  const outputIndexBySourceIndex = new Map();
+  for (const [outputIndex, sourceIndex] of sourcePageIndices.entries()) {
+    outputIndexBySourceIndex.set(sourceIndex, outputIndex);
+  }
+
+  const scoped = [];
+  for (const destination of destinations) {
+    const pageIndex = outputIndexBySourceIndex.get(destination.pageIndex);
+    if (pageIndex !== undefined) {
+      scoped.push({ name: destination.name, pageIndex, view: destination.view });
+    }
+  }
+  return scoped;
+}
+
+/**
+ * Writes named destinations into a new document as a Dests name tree
+ *
+ * @param {PDFDocument} pdf Document to update
+ * @param {Array<Object>} destinations Destinations scoped with partDestinations
+ */
+function writeNamedDestinations(pdf, destinations) {
+  if (destinations.length === 0) {
+    return;
+  }
+  const context = pdf.context;
+
+  // Name tree keys must be sorted
+  const sorted = [...destinations].sort((a, b) => (a.name < b.name ? -1 : a.name > b.name ? 1 : 0));
+  const entries = [];
+  for (const destination of sorted) {
+    entries.push(toKey(destination.name));
+    entries.push(buildDestination(context, pdf.getPage(destination.pageIndex).ref, destination.view));
+  }
+
+  let names = pdf.catalog.lookupMaybe(PDFName.of('Names'), PDFDict);
+  if (!names) {
+    names = context.obj({});
+    pdf.catalog.set(PDFName.of('Names'), context.register(names));
+  }
+  names.set(PDFName.of('Dests'), context.register(context.obj({ Names: entries })));
+}
+
+module.exports = {
+  readNamedDestinations,
+  partDestinations,
+  writeNamedDestinations
+};
@@ -0,0 +1,130 @@
+// Document IDs (the /ID entry of the trailer), which downstream systems use
+// to tell files apart. Each part gets a fresh random ID by default, or an ID
+// derived from the source ID and the part number, which is the same every
+// time the same source is split. Nothing here needs Node.js modules, as parts
+// built in the browser (src/browser.js) get IDs too.
+
+const { PDFArray, PDFHexString, PDFString } = require('pdf-lib');
+
+const DOCUMENT_ID_MODES = ['random', 'derived'];
+const DEFAULT_DOCUMENT_ID_MODE = 'random';
+
+// Shift amounts of the MD5 rounds, four per round (RFC 1321)
+const MD5_SHIFTS = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+// MD5 constants: the integer part of abs(sin(i + 1)) * 2^32
+const MD5_CONSTANTS = Array.from({ length: 64 }, (_, i) => Math.floor(Math.abs(Math.sin(i + 1)) * 2 ** 32) >>> 0);
+
+/**
+ * @param {Uint8Array} bytes Bytes
+ * @returns {string} Bytes as lowercase hex
+ */
+function toHex(bytes) {
+  return Array.from(bytes, byte => byte.toString(16).padStart(2, '0')).join('');
+}
+
+/**
+ * MD5 in plain JavaScript, giving the same derived IDs as Node.js crypto did
+ * without depending on it
+ *
+ * @param {string} text Text to hash, as UTF-8
+ * @returns {string} 16-byte digest as lowercase hex
+ */
+function md5(text) {
+  const bytes = new TextEncoder().encode(text);
+  // Padded with a 1 bit and zeros to 8 bytes short of a 64-byte block, followed by the length in bits
+  const length = Math.ceil((bytes.length + 9) / 64) * 64;
+  const padded = new Uint8Array(length);
+  padded.set(bytes);
+  padded[bytes.length] = 0x80;
+  const view = new DataView(padded.buffer);
+  view.setUint32(length - 8, (bytes.length * 8) >>> 0, true);
+  view.setUint32(length - 4, Math.floor(bytes.length / 2 ** 29), true);
+
+  const state = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+  for (let offset = 0; offset < length; offset += 64) {
+    let [a, b, c, d] = state;
+    for (let i = 0; i < 64; i++) {
+      let f;
+      let word;
+      if (i < 16) {
+        f = (b & c) | (~b & d);
+        word = i;
+      } else if (i < 32) {
+        f = (d & b) | (~d & c);
+        word = (5 * i + 1) % 16;
+      } else if (i < 48) {
+        f = b ^ c ^ d;
+        word = (3 * i + 5) % 16;
+      } else {
+        f = c ^ (b | ~d);
+        word = (7 * i) % 16;
+      }
+      const sum = (a + f + MD5_CONSTANTS[i] + view.getUint32(offset + word * 4, true)) | 0;
+      const shift = MD5_SHIFTS[(i >> 4) * 4 + (i % 4)];
+      a = d;
+      d = c;
+      c = b;
+      b = (b + ((sum << shift) | (sum >>> (32 - shift)))) | 0;
+    }
+    state[0] = (state[0] + a) | 0;
+    state[1] = (state[1] + b) | 0;
+    state[2] = (state[2] + c) | 0;
+    state[3] = (state[3] + d) | 0;
+  }
+
+  const digest = new DataView(new ArrayBuffer(16));
+  state.forEach((word, i) => digest.setUint32(i * 4, word, true));
+  return toHex(new Uint8Array(digest.buffer));
+}
+
+/**
+ * Reads the permanent (first) ID of a document
+ *
+ * @param {PDFDocument} pdf Loaded document
+ * @returns {string|null} ID as lowercase hex, or null if the document has none
+ */
+function readDocumentId(pdf) {
+  const ids = pdf.context.trailerInfo.ID && pdf.context.lookup(pdf.context.trailerInfo.ID);
+  const id = ids instanceof PDFArray ? ids.lookup(0) : null;
+  if (id instanceof PDFHexString) {
+    return id.asString().toLowerCase();
+  }
+  if (id instanceof PDFString) {
+    return toHex(id.asBytes());
+  }
+  return null;
+}
+
+/**
+ * Chooses the ID of a part
+ *
+ * @param {string} mode ID mode (see DOCUMENT_ID_MODES)
+ * @param {string} sourceId Hex ID of the source (see readDocumentId), or a hash of its bytes if it has none
+ * @param {number} index 1-based part number
+ * @returns {string} 16-byte ID as lowercase hex
+ */
+function partDocumentId(mode, sourceId, index) {
+  if (mode === 'derived') {
+    return md5(`${sourceId}:${index}`);
+  }
+  // Web Crypto, available both in Node.js and in browsers
+  return toHex(globalThis.crypto.getRandomValues(new Uint8Array(16)));
+}
+
+/**
+ * Sets the ID of a new document; both entries are the same, as for a newly created file
+ *
+ * @param {PDFDocument} pdf Document to update
+ * @param {string} id ID as hex
+ */
+function writeDocumentId(pdf, id) {
+  pdf.context.trailerInfo.ID = pdf.context.obj([PDFHexString.of(id), PDFHexString.of(id)]);
+}
+
+module.exports = {
+  DOCUMENT_ID_MODES,
+  DEFAULT_DOCUMENT_ID_MODE,
+  readDocumentId,
+  partDocumentId,
+  writeDocumentId
+};
@@ -0,0 +1,119 @@
+// Duplicate pages, such as sheets scanned twice in re-scanned batches. Pages
+// are compared by a hash of everything they draw: their content streams,
+// the resources these use (images, fonts, forms) and their page boxes. Only
+// byte-identical pages match; the same sheet scanned twice gives different
+// images and is not detected.
+
+const crypto = require('crypto');
+const { PDFArray, PDFDict, PDFRawStream, PDFRef, PDFStream } = require('pdf-lib');
+const { dropPages } = require('./plan');
+
+// What to do about duplicate pages: nothing, list them, or also leave them out of the parts
+const DUPLICATE_PAGE_MODES = ['ignore', 'report', 'drop'];
+const DEFAULT_DUPLICATE_PAGE_MODE = 'ignore';
+
+// Page entries that affect how a page looks
+const PAGE_KEYS = ['Contents', 'Resources', 'MediaBox', 'CropBox', 'Rotate'];
+
+/**
+ * Hashes a PDF object and everything it refers to
+ *
+ * @param {PDFContext} context Document context
+ * @param {*} object PDF object
+ * @param {Object} hash Hash updated with the object
+ * @param {Set} visited References hashed so far, against loops
+ */
+function hashObject(context, object, hash, visited) {
+  if (object instanceof PDFRef) {
+    // Shared objects are hashed where they first appear, loops only once
+    if (visited.has(object)) {
+      hash.update('<seen>');
+      return;
+    }
+    visited.add(object);
+    hashObject(context, context.lookup(object), hash, visited);
+  } else if (object instanceof PDFDict) {
+    hash.update('<<');
+    const entries = object.entries().sort(([a], [b]) => a.asString().localeCompare(b.asString()));
+    for (const [key, value] of entries) {
+      hash.update(key.asString());
+      hashObject(context, value, hash, visited);
+    }
+    hash.update('>>');
+  } else if (object instanceof PDFArray) {
+    hash.update('[');
+    for (let i = 0; i < object.size(); i++) {
+      hashObject(context, object.get(i), hash, visited);
+    }
+    hash.update(']');
+  } else if (object instanceof PDFStream) {
+    hashObject(context, object.dict, hash, visited);
+    hash.update(object instanceof PDFRawStream ? object.contents : object.getContents());
+  } else if (object !== undefined) {
+    hash.update(object.toString());
+  }
+}
+
+/**
+ * Hashes what a page draws
+ *
+ * @param {PDFPage} page Page
+ * @returns {string} Hex digest
+ */
+function pageHash(page) {
+  const hash = crypto.createHash('sha256');
+  const visited = new Set();
+  for (const key of PAGE_KEYS) {
+    hash.update(`/${key}`);
+    // Inherited entries count too, as they are part of the page's appearance
+    hashObject(page.doc.context, page.node[key](), hash, visited);
+  }
+  return hash.digest('hex');
+}
+
+/**
+ * Finds pages that repeat an earlier page of a document
+ *
+ * @param {PDFDocument} pdf Loaded document
+ * @returns {Array<{page: number, duplicateOf: number}>} 1-based numbers of each repeat and of the first page it repeats
+ */
+function findDuplicatePages(pdf) {
+  const firstPages = new Map();
+  const duplicates = [];
+  for (const [pageIndex, page] of pdf.getPages().entries()) {
+    const hash = pageHash(page);
+    if (firstPages.has(hash)) {
+      duplicates.push({ page: pageIndex + 1, duplicateOf: firstPages.get(hash) });
+    } else {
+      firstPages.set(hash, pageIndex + 1);
+    }
+  }
+  return duplicates;
+}
+
+/**
+ * Reports duplicate pages and, in drop mode, leaves them out of planned parts (see dropPages in src/plan.js)
+ *
+ * @param {Array<Object>} partInfos Parts planned with Splitter#plan, updated in place in drop mode
+ * @param {Array<Object>} duplicates Duplicates found with findDuplicatePages
+ * @param {string} mode Duplicate page mode (see DUPLICATE_PAGE_MODES)
+ * @returns {Array<{code: string, message: string}>} Warning listing the duplicates, or none
+ */
+function applyDuplicatePageMode(partInfos, duplicates, mode) {
+  if (duplicates.length === 0) {
+    return [];
+  }
+  const list = duplicates.map(duplicate => `${duplicate.page} (of ${duplicate.duplicateOf})`).join(', ');
+  if (mode === 'drop') {
+    dropPages(partInfos, duplicates.map(duplicate => duplicate.page));
+    return [{ code: 'duplicatePagesDropped', message: `Dropped ${duplicates.length} duplicate page(s): ${list}` }];
+  }
+  return [{ code: 'duplicatePages', message: `Found ${duplicates.length} duplicate page(s): ${list}` }];
+}
+
+module.exports = {
+  DUPLICATE_PAGE_MODES,
+  DEFAULT_DUPLICATE_PAGE_MODE,
+  findDuplicatePages,
+  applyDuplicatePageMode
+};
@@ -0,0 +1,209 @@
+// Password handling for encrypted PDFs. pdf-lib can neither decrypt nor
+// encrypt documents, so encrypted sources are decrypted with qpdf before any
+// backend opens them, and parts are encrypted with qpdf after they are built.
+// Parts of an encrypted source keep its encryption unless the caller
+// explicitly asks for unencrypted output (decryptOutput).
+
+const os = require('os');
+const path = require('path');
+const fs = require('fs');
+const crypto = require('crypto');
+const { PDFDict, PDFName, PDFNumber } = require('pdf-lib');
+const { runQpdf } = require('./qpdf');
+
+// Exit code for a missing or wrong password
+const PASSWORD_EXIT_CODE = 6;
+
+// Permissions that can be granted to readers who only know the user password,
+// with the qpdf options (256-bit AES) granting and denying each of them
+const PERMISSIONS = {
+  print: ['--print=full', '--print=none'],
+  modify: ['--modify=all', '--modify=none'],
+  copy: ['--extract=y', '--extract=n'],
+  annotate: ['--annotate=y', '--annotate=n'],
+  form: ['--form=y', '--form=n'],
+  assemble: ['--assemble=y', '--assemble=n'],
+  accessibility: ['--accessibility=y', '--accessibility=n']
+};
+// Bits of the encryption dictionary's P entry granting each permission
+const PERMISSION_BITS = {
+  print: 1 << 2,
+  modify: 1 << 3,
+  copy: 1 << 4,
+  annotate: 1 << 5,
+  form: 1 << 8,
+  accessibility: 1 << 9,
+  assemble: 1 << 10
+};
+
+/**
+ * Runs a function with a temporary directory that is removed afterwards
+ *
+ * @param {Function} callback Async function receiving the directory path
+ * @returns {Promise<*>} Result of the callback
+ */
+async function withTempDir(callback) {
+  const tempDir = await fs.promises.mkdtemp(path.join(os.tmpdir(), 'splitpdf-crypt-'));
+  try {
+    return await callback(tempDir);
+  } finally {
+    await fs.promises.rm(tempDir, { recursive: true, force: true });
+  }
+}
+
+/**
+ * Writes lines holding passwords into a file only the current user can read,
+ * for qpdf to read them from, so that the passwords never appear on a command
+ * line, which other local users can see (e.g. with ps)
+ *
+ * @param {string} filePath Path of the file, in a temporary directory
+ * @param {Array<string>} lines Lines to write, e.g. one password or one qpdf argument each
+ * @returns {Promise<string>} Path of the file
+ */
+async function writeSecretFile(filePath, lines) {
+  if (lines.some(line => /[\r\n]/.test(line))) {
+    const error = new Error('Passwords containing line breaks are not supported.');
+    error.code = 2; // Invalid option
+    throw error;
+  }
+  await fs.promises.writeFile(filePath, lines.join('\n'), { mode: 0o600 });
+  return filePath;
+}
+
+/**
+ * Decrypts an encrypted PDF with its user or owner password
+ *
+ * @param {Uint8Array} sourceBytes Bytes of the encrypted PDF
+ * @param {string} password Password; an empty password opens files that only restrict permissions
+ * @returns {Promise<Uint8Array>} Bytes of the decrypted PDF
+ */
+async function decryptPdf(sourceBytes, password) {
+  return withTempDir(async (tempDir) => {
+    const encryptedPath = path.join(tempDir, 'encrypted.pdf');
+    const decryptedPath = path.join(tempDir, 'decrypted.pdf');
+    await fs.promises.writeFile(encryptedPath, sourceBytes);
+    const passwordPath = await writeSecretFile(path.join(tempDir, 'password'), [password]);
+    try {
+      await runQpdf([`--password-file=${passwordPath}`, '--decrypt', encryptedPath, decryptedPath]);
+    } catch (error) {
+      if (error.message.includes('invalid password')) {
+        const passwordError = new Error('Wrong password for the encrypted PDF.');
+        passwordError.code = PASSWORD_EXIT_CODE;
+        throw passwordError;
+      }
+      throw error;
+    }
+    return fs.promises.readFile(decryptedPath);
+  });
+}
+
+/**
+ * Encrypts a PDF with 256-bit AES
+ *
+ * @param {Uint8Array} pdfBytes Bytes of the PDF to encrypt
+ * @param {Object} encryption Encryption settings
+ * @param {string} encryption.userPassword Password needed to open the PDF (may be empty)
+ * @param {string} encryption.ownerPassword Password granting full access
+ * @param {Array<string>} [encryption.permissions] Permissions granted with the user password (see PERMISSIONS); all are granted when omitted
+ * @param {boolean} [linearize] If true, also linearize the PDF (see src/linearize.js)
+ * @returns {Promise<Uint8Array>} Bytes of the encrypted PDF
+ */
+async function encryptPdf(pdfBytes, encryption, linearize = false) {
+  const restrictions = [];
+  if (encryption.permissions) {
+    for (const [permission, [grant, deny]] of Object.entries(PERMISSIONS)) {
+      restrictions.push(encryption.permissions.includes(permission) ? grant : deny);
+    }
+  }
+
+  return withTempDir(async (tempDir) => {
+    const plainPath = path.join(tempDir, 'plain.pdf');
+    const encryptedPath = path.join(tempDir, 'encrypted.pdf');
+    await fs.promises.writeFile(plainPath, pdfBytes);
+    // qpdf reads the arguments holding the passwords from the file, one per line
+    const argumentsPath = await writeSecretFile(path.join(tempDir, 'encrypt-arguments'), [
+      '--encrypt', encryption.userPassword, encryption.ownerPassword, '256',
+      ...restrictions,
+      '--'
+    ]);
+    await runQpdf([
+      `@${argumentsPath}`,
+      ...(linearize ? ['--linearize'] : []),
+      plainPath,
+      encryptedPath
+    ]);
+    return fs.promises.readFile(encryptedPath);
+  });
+}
+
+/**
+ * Prepares to give documents the same encryption as an encrypted source:
+ * the same passwords, permissions and algorithm
+ *
+ * @param {Uint8Array} encryptedSourceBytes Bytes of the encrypted source PDF
+ * @param {string} password Password of the source
+ * @returns {Promise<{apply: Function, close: Function}>} apply(pdfBytes, linearize) encrypts (and optionally linearizes) a
+ *   document; close() removes temporary files
+ */
+async function createEncryptionCopier(encryptedSourceBytes, password) {
+  // The source is written once and reused for every document
+  const tempDir = await fs.promises.mkdtemp(path.join(os.tmpdir(), 'splitpdf-crypt-'));
+  const sourcePath = path.join(tempDir, 'source.pdf');
+  await fs.promises.writeFile(sourcePath, encryptedSourceBytes);
+  // qpdf has no password file option for the copied encryption, so it reads the whole argument from a file
+  const argumentsPath = await writeSecretFile(path.join(tempDir, 'copy-arguments'), [
+    `--copy-encryption=${sourcePath}`,
+    `--encryption-file-password=${password}`
+  ]);
+
+  return {
+    async apply(pdfBytes, linearize = false) {
+      const id = crypto.randomUUID();
+      const plainPath = path.join(tempDir, `plain-${id}.pdf`);
+      const encryptedPath = path.join(tempDir, `encrypted-${id}.pdf`);
+      try {
+        await fs.promises.writeFile(plainPath, pdfBytes);
+        await runQpdf([
+          `@${argumentsPath}`,
+          ...(linearize ? ['--linearize'] : []),
+          plainPath,
+          encryptedPath
+        ]);
+        return await fs.promises.readFile(encryptedPath);
+      } finally {
+        await fs.promises.rm(plainPath, { force: true });
+        await fs.promises.rm(encryptedPath, { force: true });
+      }
+    },
+    async close() {
+      await fs.promises.rm(tempDir, { recursive: true, force: true });
+    }
+  };
+}
+
+/**
+ * Reads the permissions an encrypted document grants readers who only know the user password
+ *
+ * @param {PDFDocument} pdf Document loaded with ignoreEncryption
+ * @returns {Array<string>|null} Granted permissions (see PERMISSIONS), or null if the document isn't encrypted
+ */
+function readPermissions(pdf) {
+  const encrypt = pdf.context.lookup(pdf.context.trailerInfo.Encrypt);
+  if (!(encrypt instanceof PDFDict)) {
+    return null;
+  }
+  const flags = encrypt.lookup(PDFName.of('P'));
+  const bits = flags instanceof PDFNumber ? flags.asNumber() : 0;
+  return Object.keys(PERMISSION_BITS).filter(permission => (bits & PERMISSION_BITS[permission]) !== 0);
+}
+
+module.exports = {
+  PASSWORD_EXIT_CODE,
+  PERMISSIONS,
+  createEncryptionCopier,
+  decryptPdf,
+  encryptPdf,
+  readPermissions,
+  withTempDir,
+  writeSecretFile
+};
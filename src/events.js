@@ -0,0 +1,183 @@
+// Event sinks receive the structured events emitted while splitting a PDF.
+// The library never writes to the console itself: callers pick a sink (or
+// pass any object with an `emit(event)` method) and decide where events go.
+
+const fs = require('fs');
+
+/**
+ * Creates a sink that writes each event as a JSON line to a writable stream
+ *
+ * @param {NodeJS.WritableStream} stream Destination stream
+ * @returns {{emit: Function}} Event sink
+ */
+function createJsonLinesSink(stream) {
+  return {
+    emit(event) {
+      stream.write(`${JSON.stringify(event)}\n`);
+    }
+  };
+}
+
+/**
+ * Creates a sink that writes each event as a JSON line to a file descriptor
+ *
+ * @param {number} fd File descriptor to write to
+ * @returns {{emit: Function}} Event sink
+ */
+function createFdSink(fd) {
+  // Stdout and stderr are shared with the console output, so events go through
+  // the same streams to stay in order and wait out a full pipe instead of
+  // failing with EAGAIN
+  if (fd === 1) {
+    return createStdoutSink();
+  }
+  if (fd === 2) {
+    return createStderrSink();
+  }
+  return {
+    emit(event) {
+      fs.writeSync(fd, `${JSON.stringify(event)}\n`);
+    }
+  };
+}
+
+/**
+ * Creates a sink that writes each event as a JSON line to stdout
+ *
+ * @returns {{emit: Function}} Event sink
+ */
+function createStdoutSink() {
+  return createJsonLinesSink(process.stdout);
+}
+
+/**
+ * Creates a sink that writes each event as a JSON line to stderr
+ *
+ * @returns {{emit: Function}} Event sink
+ */
+function createStderrSink() {
+  return createJsonLinesSink(process.stderr);
+}
+
+/**
+ * Creates a sink that forwards each event to an EventEmitter as an 'event'
+ * event, so several listeners can consume the same stream of events
+ *
+ * @param {import('events').EventEmitter} emitter Emitter to forward events to
+ * @returns {{emit: Function}} Event sink
+ */
+function createEmitterSink(emitter) {
+  return {
+    emit(event) {
+      emitter.emit('event', event);
+    }
+  };
+}
+
+/**
+ * Creates a sink that calls a function for each event
+ *
+ * @param {Function} callback Function receiving each event
+ * @returns {{emit: Function}} Event sink
+ */
+function createCallbackSink(callback) {
+  return {
+    emit(event) {
+      callback(event);
+    }
+  };
+}
+
+/**
+ * Creates a sink that buffers events and exposes them as an async iterable.
+ * Iteration ends once `close()` is called and the buffered events are drained.
+ *
+ * @returns {{emit: Function, close: Function, [Symbol.asyncIterator]: Function}} Event sink
+ */
+function createQueueSink() {
+  const queue = [];
+  let waiting = null;
+  let closed = false;
+
+  // Wake up a consumer waiting for the next event
+  function wake() {
+    if (waiting) {
+      const resolve = waiting;
+      waiting = null;
+      resolve();
+    }
+  }
+
+  return {
+    emit(event) {
+      queue.push(event);
+      wake();
+    },
+    close() {
+      closed = true;
+      wake();
+    },
+    async *[Symbol.asyncIterator]() {
+      while (true) {
+        if (queue.length > 0) {
+          yield queue.shift();
+        } else if (closed) {
+          return;
+        } else {
+          await new Promise(resolve => {
+            waiting = resolve;
+          });
+        }
+      }
+    }
+  };
+}
+
+/**
+ * Creates a sink that forwards each event to every given sink
+ *
+ * @param {...{emit: Function}} sinks Sinks to forward events to
+ * @returns {{emit: Function}} Event sink
+ */
+function combineSinks(...sinks) {
+  return {
+    emit(event) {
+      for (const sink of sinks) {
+        sink.emit(event);
+      }
+    }
+  };
+}
+
+// Sink that discards every event
+const noopSink = {
+  emit() {}
+};
+
+/**
+ * Picks the sink for a split from its options: an explicit eventSink, or a
+ * sink calling onProgress (or its older name progressCallback) for each event
+ *
+ * @param {Object} options Split options
+ * @returns {{emit: Function}} Event sink
+ */
+function resolveEventSink(options) {
+  if (options.eventSink) {
+    return options.eventSink;
+  }
+  const callback = options.onProgress || options.progressCallback;
+  return callback ? createCallbackSink(callback) : noopSink;
+}
+
+module.exports = {
+  createJsonLinesSink,
+  createFdSink,
+  createStdoutSink,
+  createStderrSink,
+  createEmitterSink,
+  createCallbackSink,
+  createQueueSink,
+  combineSinks,
+  noopSink,
+  resolveEventSink
+};
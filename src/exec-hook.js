@@ -0,0 +1,118 @@
+// Runs a shell command for every part the CLI writes (--exec), e.g. to
+// upload it with existing tooling. Commands run one after another, in the
+// order the parts complete, while the next parts are being built.
+
+const { spawn } = require('child_process');
+
+// What happens when a command fails: abort cancels the split, continue only warns
+const EXEC_FAILURE_MODES = ['abort', 'continue'];
+const DEFAULT_EXEC_FAILURE_MODE = 'abort';
+// Exit code when a command fails
+const EXEC_FAILED_EXIT_CODE = 8;
+
+/**
+ * Quotes a value as one shell word
+ *
+ * @param {string} value Value to quote
+ * @returns {string} Quoted value
+ */
+function quoteShellArgument(value) {
+  if (process.platform === 'win32') {
+    return `"${value.replace(/"/g, '""')}"`;
+  }
+  return `'${value.replace(/'/g, "'\\''")}'`;
+}
+
+/**
+ * Fills in a command template for one part; values are quoted, so the
+ * placeholders must not be quoted in the template
+ *
+ * @param {string} template Command with {path}, {index}, {total} and {range} placeholders
+ * @param {Object} event partComplete event of the part
+ * @returns {string} Shell command
+ */
+function formatExecCommand(template, event) {
+  const values = {
+    path: event.outputPath,
+    index: String(event.part),
+    total: String(event.totalParts),
+    range: event.pageRange
+  };
+  return template.replace(/\{(path|index|total|range)\}/g, (match, name) => quoteShellArgument(values[name]));
+}
+
+/**
+ * Runs a shell command, passing its output on to stderr so that stdout
+ * keeps carrying only the CLI's own result
+ *
+ * @param {string} command Shell command
+ * @returns {Promise<void>} Resolves if the command exits with code 0
+ */
+function runShellCommand(command) {
+  return new Promise((resolve, reject) => {
+    const child = spawn(command, { shell: true, stdio: ['ignore', process.stderr, process.stderr] });
+    child.on('error', reject);
+    child.on('close', (code, signal) => {
+      if (code === 0) {
+        resolve();
+      } else {
+        reject(new Error(signal ? `killed by ${signal}` : `exit code ${code}`));
+      }
+    });
+  });
+}
+
+/**
+ * Creates a sink running a command for each partComplete event
+ *
+ * @param {string} template Command template (see formatExecCommand)
+ * @param {Object} hookOptions Hook options
+ * @param {string} hookOptions.onFailure abort or continue (see EXEC_FAILURE_MODES)
+ * @param {Function} hookOptions.abort Called once when a command fails in abort mode, to cancel the split
+ * @returns {{emit: Function, finish: Function}} Event sink; finish() waits for the commands still running
+ *   and resolves with warnings about failed commands, or rejects with code 8 in abort mode
+ */
+function createExecSink(template, hookOptions) {
+  let queue = Promise.resolve();
+  const failures = [];
+  return {
+    emit(event) {
+      if (event.event !== 'partComplete') {
+        return;
+      }
+      const command = formatExecCommand(template, event);
+      queue = queue.then(async () => {
+        // Parts completing after an abort are removed anyway
+        if (failures.length > 0 && hookOptions.onFailure === 'abort') {
+          return;
+        }
+        try {
+          await runShellCommand(command);
+        } catch (error) {
+          failures.push({ part: event.part, message: error.message });
+          if (hookOptions.onFailure === 'abort') {
+            hookOptions.abort();
+          }
+        }
+      });
+    },
+    async finish() {
+      await queue;
+      const messages = failures.map(failure => `--exec command for part ${failure.part} failed (${failure.message})`);
+      if (failures.length > 0 && hookOptions.onFailure === 'abort') {
+        const error = new Error(messages[0]);
+        error.code = EXEC_FAILED_EXIT_CODE;
+        throw error;
+      }
+      return messages.map(message => ({ code: 'execFailed', message }));
+    }
+  };
+}
+
+module.exports = {
+  EXEC_FAILURE_MODES,
+  DEFAULT_EXEC_FAILURE_MODE,
+  EXEC_FAILED_EXIT_CODE,
+  formatExecCommand,
+  createExecSink
+};
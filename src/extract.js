@@ -0,0 +1,37 @@
+// Copies selected pages of a PDF into a new file.
+
+const fs = require('fs/promises');
+const { parsePageList } = require('./plan');
+const { Splitter } = require('./splitter');
+
+/**
+ * Writes the given pages of a PDF to a new file
+ *
+ * @param {Object} options Extract options
+ * @param {string} options.filePath Path to the source PDF
+ * @param {string|Array<number>} options.pages Page list such as 1-3,7,10- or 1-based page numbers, in output order
+ * @param {string} options.outputPath Path of the file to write
+ * @param {string} [options.backend] Name of the PDF backend (defaults to pdf-lib)
+ * @param {string} [options.password] Password of an encrypted source (requires qpdf)
+ * @param {boolean} [options.flatten] Draw annotations and form field values into the page content
+ * @param {boolean} [options.breakSignatures] Copy pages of a digitally signed source, whose signatures no longer verify
+ * @param {boolean} [options.decryptOutput] Write the pages unencrypted instead of keeping the source's encryption
+ * @returns {Promise<Object>} Output path and the 1-based page numbers it contains
+ */
+async function extractPages(options) {
+  const splitter = await Splitter.open(options.filePath, { backend: options.backend, password: options.password });
+  try {
+    const pages = Array.isArray(options.pages)
+      ? parsePageList(options.pages.join(','), splitter.pageCount())
+      : parsePageList(options.pages, splitter.pageCount());
+    const bytes = await splitter.extract(pages, options);
+    await fs.writeFile(options.outputPath, bytes);
+    return { outputPath: options.outputPath, pages };
+  } finally {
+    await splitter.close();
+  }
+}
+
+module.exports = {
+  extractPages
+};
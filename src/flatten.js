@@ -0,0 +1,163 @@
+// Flattening: draws the appearance of annotations and form fields into the
+// page content and removes the interactive elements, for viewers that
+// mangle them. Links are kept, since they have no appearance of their own.
+
+const {
+  PDFArray,
+  PDFDict,
+  PDFName,
+  PDFNumber,
+  PDFRef,
+  PDFStream,
+  concatTransformationMatrix,
+  drawObject,
+  popGraphicsState,
+  pushGraphicsState
+} = require('pdf-lib');
+
+// Annotation flag marking annotations that are never shown
+const HIDDEN_FLAG = 1 << 1;
+
+/**
+ * Reads an array of numbers
+ *
+ * @param {PDFArray|undefined} array PDF array
+ * @returns {Array<number>|null} Numbers, or null if the array is missing or malformed
+ */
+function toNumbers(array) {
+  if (!(array instanceof PDFArray)) {
+    return null;
+  }
+  const numbers = [];
+  for (let i = 0; i < array.size(); i++) {
+    const value = array.lookup(i);
+    if (!(value instanceof PDFNumber)) {
+      return null;
+    }
+    numbers.push(value.asNumber());
+  }
+  return numbers;
+}
+
+/**
+ * Finds the normal appearance stream shown for an annotation
+ *
+ * @param {PDFDict} annot Annotation
+ * @returns {PDFRef|PDFStream|null} Appearance stream, or null if there is none
+ */
+function findAppearance(annot) {
+  const appearances = annot.lookupMaybe(PDFName.of('AP'), PDFDict);
+  if (!appearances) {
+    return null;
+  }
+  let normal = appearances.get(PDFName.of('N'));
+  // Checkboxes and radio buttons have one appearance per state, chosen by AS
+  if (appearances.lookup(PDFName.of('N')) instanceof PDFDict) {
+    const state = annot.lookup(PDFName.of('AS'));
+    normal = state instanceof PDFName ? appearances.lookup(PDFName.of('N'), PDFDict).get(state) : undefined;
+  }
+  return normal instanceof PDFRef || normal instanceof PDFStream ? normal : null;
+}
+
+/**
+ * Calculates the matrix placing an appearance stream in the annotation rectangle
+ * (the algorithm in section 12.5.5 of the PDF specification)
+ *
+ * @param {Array<number>} rect Annotation rectangle
+ * @param {Array<number>} bbox Bounding box of the appearance stream
+ * @param {Array<number>} matrix Matrix of the appearance stream
+ * @returns {Array<number>} Matrix for the cm operator
+ */
+function placementMatrix(rect, bbox, matrix) {
+  const [a, b, c, d, e, f] = matrix;
+  const xs = [];
+  const ys = [];
+  for (const [x, y] of [[bbox[0], bbox[1]], [bbox[2], bbox[1]], [bbox[0], bbox[3]], [bbox[2], bbox[3]]]) {
+    xs.push(a * x + c * y + e);
+    ys.push(b * x + d * y + f);
+  }
+  const [minX, maxX, minY, maxY] = [Math.min(...xs), Math.max(...xs), Math.min(...ys), Math.max(...ys)];
+  const rectX = Math.min(rect[0], rect[2]);
+  const rectY = Math.min(rect[1], rect[3]);
+  const scaleX = maxX > minX ? Math.abs(rect[2] - rect[0]) / (maxX - minX) : 1;
+  const scaleY = maxY > minY ? Math.abs(rect[3] - rect[1]) / (maxY - minY) : 1;
+  return [scaleX, 0, 0, scaleY, rectX - minX * scaleX, rectY - minY * scaleY];
+}
+
+/**
+ * Draws the annotations and form fields of every page into its content and
+ * removes them, keeping links
+ *
+ * @param {PDFDocument} pdf Document to flatten
+ */
+function flattenPdf(pdf) {
+  const context = pdf.context;
+  for (const page of pdf.getPages()) {
+    const annots = page.node.Annots();
+    if (!annots) {
+      continue;
+    }
+
+    const kept = [];
+    for (let i = 0; i < annots.size(); i++) {
+      const annot = annots.lookupMaybe(i, PDFDict);
+      if (!annot) {
+        continue;
+      }
+      const subtype = annot.lookup(PDFName.of('Subtype'));
+      if (subtype === PDFName.of('Link')) {
+        kept.push(annots.get(i));
+        continue;
+      }
+      // Popups only show the text of their parent annotation
+      if (subtype === PDFName.of('Popup')) {
+        continue;
+      }
+
+      const flags = annot.lookupMaybe(PDFName.of('F'), PDFNumber);
+      if (flags && (flags.asNumber() & HIDDEN_FLAG) !== 0) {
+        continue;
+      }
+      const appearance = findAppearance(annot);
+      const rect = toNumbers(annot.lookup(PDFName.of('Rect')));
+      if (!appearance || !rect) {
+        // Without an appearance there is nothing to draw, so the annotation stays
+        kept.push(annots.get(i));
+        continue;
+      }
+
+      const appearanceRef = appearance instanceof PDFRef ? appearance : context.register(appearance);
+      const stream = context.lookup(appearanceRef);
+      if (!(stream instanceof PDFStream)) {
+        kept.push(annots.get(i));
+        continue;
+      }
+      const bbox = toNumbers(stream.dict.lookup(PDFName.of('BBox'))) || [0, 0, 1, 1];
+      const matrix = toNumbers(stream.dict.lookup(PDFName.of('Matrix'))) || [1, 0, 0, 1, 0, 0];
+      // Appearance streams must be form XObjects to be drawn with Do
+      stream.dict.set(PDFName.of('Type'), PDFName.of('XObject'));
+      stream.dict.set(PDFName.of('Subtype'), PDFName.of('Form'));
+
+      const name = page.node.newXObject('FlatAnnot', appearanceRef);
+      page.pushOperators(
+        pushGraphicsState(),
+        concatTransformationMatrix(...placementMatrix(rect, bbox, matrix)),
+        drawObject(name),
+        popGraphicsState()
+      );
+    }
+
+    if (kept.length > 0) {
+      page.node.set(PDFName.of('Annots'), context.obj(kept));
+    } else {
+      page.node.delete(PDFName.of('Annots'));
+    }
+  }
+
+  // The fields are now part of the page content
+  pdf.catalog.delete(PDFName.of('AcroForm'));
+}
+
+module.exports = {
+  flattenPdf
+};
@@ -0,0 +1,87 @@
+// Form fields and annotations of copied pages. pdf-lib copies the annotations
+// of each page, but the document-level AcroForm dictionary that makes widgets
+// behave as form fields is not part of any page, so it is rebuilt for every
+// part from the fields whose widgets the part contains.
+
+const { PDFDict, PDFName, PDFObjectCopier, PDFRef } = require('pdf-lib');
+
+// Entries of the source AcroForm that apply to the fields of every part
+const SHARED_FORM_ENTRIES = ['DA', 'DR', 'Q', 'NeedAppearances'];
+
+/**
+ * Finds the top-level field a widget belongs to
+ *
+ * @param {PDFRef} widgetRef Reference of the widget annotation
+ * @param {PDFDict} widget Widget annotation
+ * @returns {PDFRef|null} Reference of the root field
+ */
+function findRootField(widgetRef, widget) {
+  let fieldRef = widgetRef;
+  let field = widget;
+  // Damaged files can contain loops, so every field is visited only once
+  const visited = new Set([field]);
+  while (field.get(PDFName.of('Parent')) instanceof PDFRef) {
+    const parentRef = field.get(PDFName.of('Parent'));
+    const parent = field.lookupMaybe(PDFName.of('Parent'), PDFDict);
+    if (!parent || visited.has(parent)) {
+      break;
+    }
+    visited.add(parent);
+    fieldRef = parentRef;
+    field = parent;
+  }
+  return fieldRef instanceof PDFRef ? fieldRef : null;
+}
+
+/**
+ * Links the annotations of a part's pages to their new pages and gives the
+ * part an AcroForm with the fields whose widgets are on its pages
+ *
+ * @param {PDFDocument} sourcePdf Source document the pages were copied from
+ * @param {PDFDocument} partPdf Part document with the copied pages
+ */
+function copyForms(sourcePdf, partPdf) {
+  const fieldRefs = new Map();
+  for (const page of partPdf.getPages()) {
+    const annots = page.node.Annots();
+    if (!annots) {
+      continue;
+    }
+    for (let i = 0; i < annots.size(); i++) {
+      const annot = annots.lookupMaybe(i, PDFDict);
+      if (!annot) {
+        continue;
+      }
+      // Annotations still point at the source page they were copied from
+      if (annot.has(PDFName.of('P'))) {
+        annot.set(PDFName.of('P'), page.ref);
+      }
+      if (annot.lookup(PDFName.of('Subtype')) === PDFName.of('Widget')) {
+        const fieldRef = findRootField(annots.get(i), annot);
+        if (fieldRef) {
+          fieldRefs.set(fieldRef.toString(), fieldRef);
+        }
+      }
+    }
+  }
+
+  const sourceForm = sourcePdf.catalog.lookupMaybe(PDFName.of('AcroForm'), PDFDict);
+  // Parts built by other means (e.g. by qpdf) may already have their own form
+  if (!sourceForm || fieldRefs.size === 0 || partPdf.catalog.has(PDFName.of('AcroForm'))) {
+    return;
+  }
+
+  const copier = PDFObjectCopier.for(sourcePdf.context, partPdf.context);
+  const form = partPdf.context.obj({ Fields: [...fieldRefs.values()] });
+  for (const key of SHARED_FORM_ENTRIES) {
+    const value = sourceForm.get(PDFName.of(key));
+    if (value !== undefined) {
+      form.set(PDFName.of(key), copier.copy(value));
+    }
+  }
+  partPdf.catalog.set(PDFName.of('AcroForm'), partPdf.context.register(form));
+}
+
+module.exports = {
+  copyForms
+};
@@ -0,0 +1,129 @@
+// Synthetic PDFs for tests and bug reports. A document is described by its
+// page count, page sizes, bookmarks and page labels, and built with the same
+// writers the splitter uses for parts, so integration tests can run the real
+// copy and save path on files with exactly the structure they need, and a
+// user can reproduce a problem without sharing a confidential document.
+
+const { PDFDocument, StandardFonts, rgb } = require('pdf-lib');
+const { parsePageSize } = require('./page-size');
+const { writeOutline } = require('./outline');
+const { PAGE_LABEL_STYLES, writePageLabels } = require('./page-labels');
+
+const DEFAULT_PAGE_SIZE = 'Letter';
+
+function invalidSpec(message) {
+  const error = new Error(message);
+  error.code = 2; // Invalid option
+  return error;
+}
+
+/**
+ * Parses a bookmark list such as "1=Intro;3=Chapter 1;>4=Section 1.1": each
+ * entry is a 1-based page and a title, and each leading > nests the entry one
+ * level deeper, under the entry before it
+ *
+ * @param {string} spec Bookmark list
+ * @returns {Array<Object>} Top-level bookmarks with title, page and children
+ */
+function parseBookmarkSpec(spec) {
+  const bookmarks = [];
+  const ancestors = [];
+  for (const entry of spec.split(';').filter(part => part.trim() !== '')) {
+    const match = /^\s*(>*)\s*(\d+)=(.+)$/.exec(entry);
+    if (!match) {
+      throw invalidSpec(`Invalid bookmark ${entry}; expected page=title, with a leading > per nesting level.`);
+    }
+    const level = match[1].length;
+    if (level > ancestors.length) {
+      throw invalidSpec(`Bookmark ${match[3]} is nested deeper than the bookmark before it.`);
+    }
+    const bookmark = { title: match[3].trim(), page: Number(match[2]), children: [] };
+    (level === 0 ? bookmarks : ancestors[level - 1].children).push(bookmark);
+    ancestors.length = level;
+    ancestors.push(bookmark);
+  }
+  return bookmarks;
+}
+
+/**
+ * Parses a page label list such as "1=r;5=D;30=A:Appendix-": each entry is
+ * the 1-based page where a range starts and its numbering style (D, R, r, A,
+ * a, or - for none), optionally followed by a prefix
+ *
+ * @param {string} spec Page label list
+ * @returns {Array<Object>} Label ranges with page, style, prefix and start
+ */
+function parsePageLabelSpec(spec) {
+  return spec.split(';').filter(part => part.trim() !== '').map((entry) => {
+    const match = /^\s*(\d+)=([^:]+)(?::(.*))?$/.exec(entry);
+    if (!match) {
+      throw invalidSpec(`Invalid page label range ${entry}; expected page=style or page=style:prefix.`);
+    }
+    return { page: Number(match[1]), style: match[2].trim() === '-' ? null : match[2].trim(), prefix: match[3] || '', start: 1 };
+  });
+}
+
+/**
+ * Generates a PDF; every page shows its number and size, so parts are easy to
+ * check by eye
+ *
+ * @param {Object} spec Document description
+ * @param {number} spec.pages Number of pages
+ * @param {Array<string>} [spec.pageSizes] Page sizes (names such as A4 or WIDTHxHEIGHT in points), repeated
+ *   over the pages in turn (defaults to Letter)
+ * @param {Array<Object>} [spec.bookmarks] Bookmarks with title, 1-based page and optional children
+ * @param {Array<Object>} [spec.pageLabels] Label ranges with 1-based first page, style (D, R, r, A, a or null),
+ *   optional prefix and start number
+ * @returns {Promise<Uint8Array>} Bytes of the PDF; the same description always gives the same bytes
+ */
+async function generatePdf(spec) {
+  if (!Number.isInteger(spec.pages) || spec.pages < 1) {
+    throw invalidSpec('Page count must be a positive integer.');
+  }
+  const pageSizes = (spec.pageSizes && spec.pageSizes.length > 0 ? spec.pageSizes : [DEFAULT_PAGE_SIZE]).map((size) => {
+    const pageSize = parsePageSize(size);
+    if (!pageSize) {
+      throw invalidSpec(`Unknown page size: ${size}. Use a paper name such as A4 or Letter, or WIDTHxHEIGHT in points.`);
+    }
+    return pageSize;
+  });
+
+  // No producer or dates, so that generated files are reproducible
+  const pdf = await PDFDocument.create({ updateMetadata: false });
+  const font = await pdf.embedFont(StandardFonts.Helvetica);
+  for (let number = 1; number <= spec.pages; number++) {
+    const { width, height } = pageSizes[(number - 1) % pageSizes.length];
+    const page = pdf.addPage([width, height]);
+    const size = Math.min(48, width / 6);
+    page.drawText(`Page ${number}`, { x: size, y: height - 2 * size, size, font });
+    page.drawText(`${width} x ${height} pt`, { x: size, y: height - 3 * size, size: size / 3, font, color: rgb(0.4, 0.4, 0.4) });
+  }
+
+  const toOutline = bookmarks => bookmarks.map((bookmark) => {
+    if (!Number.isInteger(bookmark.page) || bookmark.page < 1 || bookmark.page > spec.pages) {
+      throw invalidSpec(`Bookmark ${bookmark.title} points to page ${bookmark.page}, outside pages 1-${spec.pages}.`);
+    }
+    return { title: bookmark.title, pageIndex: bookmark.page - 1, view: null, open: true, children: toOutline(bookmark.children || []) };
+  });
+  writeOutline(pdf, toOutline(spec.bookmarks || []));
+
+  if (spec.pageLabels && spec.pageLabels.length > 0) {
+    const ranges = [...spec.pageLabels].sort((a, b) => a.page - b.page).map((range) => {
+      if (!Number.isInteger(range.page) || range.page < 1 || range.page > spec.pages) {
+        throw invalidSpec(`Page label range starts at page ${range.page}, outside pages 1-${spec.pages}.`);
+      }
+      if (range.style !== null && range.style !== undefined && !PAGE_LABEL_STYLES.includes(range.style)) {
+        throw invalidSpec(`Unknown page label style: ${range.style}. Available styles: ${PAGE_LABEL_STYLES.join(', ')}`);
+      }
+      return { startIndex: range.page - 1, style: range.style || null, prefix: range.prefix || '', start: range.start || 1 };
+    });
+    writePageLabels(pdf, ranges);
+  }
+  return pdf.save();
+}
+
+module.exports = {
+  parseBookmarkSpec,
+  parsePageLabelSpec,
+  generatePdf
+};
@@ -0,0 +1,142 @@
+// Minimal glob expansion for input patterns such as scans/*.pdf or docs/**/*.pdf,
+// and recursive PDF discovery for --recursive. Patterns support * and ? within a path segment and ** for any number of directories.
+
+const fs = require('fs');
+const path = require('path');
+
+/**
+ * @param {string} text Path or pattern
+ * @returns {boolean} True if the text contains glob characters
+ */
+function hasGlobCharacters(text) {
+  return /[*?]/.test(text);
+}
+
+/**
+ * Converts a single path segment pattern to a regular expression
+ *
+ * @param {string} segment Segment such as *.pdf
+ * @returns {RegExp} Expression matching whole file names
+ */
+function segmentToRegExp(segment) {
+  let source = '';
+  for (const char of segment) {
+    if (char === '*') {
+      source += '.*';
+    } else if (char === '?') {
+      source += '.';
+    } else {
+      source += char.replace(/[.+^${}()|[\]\\]/g, '\\$&');
+    }
+  }
+  return new RegExp(`^${source}$`);
+}
+
+/**
+ * Lists the entries of a directory, treating unreadable directories as empty
+ *
+ * @param {string} dir Directory to read
+ * @returns {Promise<Array<fs.Dirent>>} Directory entries
+ */
+async function readEntries(dir) {
+  try {
+    return await fs.promises.readdir(dir, { withFileTypes: true });
+  } catch (error) {
+    return [];
+  }
+}
+
+/**
+ * Finds the files matching a glob pattern
+ *
+ * @param {string} pattern Pattern such as scans/*.pdf
+ * @returns {Promise<Array<string>>} Matching file paths, sorted
+ */
+async function expandGlob(pattern) {
+  const segments = pattern.split(/[\\/]/);
+  const firstGlobIndex = segments.findIndex(hasGlobCharacters);
+  if (firstGlobIndex === -1) {
+    return fs.existsSync(pattern) ? [pattern] : [];
+  }
+
+  // Start walking from the longest directory prefix without glob characters
+  let baseDir = segments.slice(0, firstGlobIndex).join('/');
+  if (baseDir === '' && firstGlobIndex > 0) {
+    baseDir = '/';
+  }
+
+  // A trailing ** matches every file below its directory
+  const globSegments = segments.slice(firstGlobIndex);
+  if (globSegments[globSegments.length - 1] === '**') {
+    globSegments.push('*');
+  }
+
+  const matches = new Set();
+
+  async function walk(dir, remaining) {
+    const [segment, ...rest] = remaining;
+    const entries = await readEntries(dir || '.');
+
+    if (segment === '**') {
+      // ** matches zero directories...
+      await walk(dir, rest);
+      // ...or any number of them
+      for (const entry of entries) {
+        if (entry.isDirectory() && !entry.name.startsWith('.')) {
+          await walk(path.join(dir, entry.name), remaining);
+        }
+      }
+      return;
+    }
+
+    const matcher = segmentToRegExp(segment);
+    for (const entry of entries) {
+      // Hidden files only match patterns that start with a dot
+      if (entry.name.startsWith('.') && !segment.startsWith('.')) {
+        continue;
+      }
+      if (!matcher.test(entry.name)) {
+        continue;
+      }
+      const entryPath = path.join(dir, entry.name);
+      if (rest.length === 0) {
+        if (entry.isFile()) {
+          matches.add(entryPath);
+        }
+      } else if (entry.isDirectory()) {
+        await walk(entryPath, rest);
+      }
+    }
+  }
+
+  await walk(baseDir, globSegments);
+  return [...matches].sort();
+}
+
+/**
+ * Finds every PDF file below a directory, including its subdirectories
+ *
+ * @param {string} dir Directory to search
+ * @returns {Promise<Array<string>>} PDF file paths, sorted
+ */
+async function findPdfFiles(dir) {
+  const found = [];
+  for (const entry of await readEntries(dir)) {
+    if (entry.name.startsWith('.')) {
+      continue;
+    }
+    const entryPath = path.join(dir, entry.name);
+    if (entry.isDirectory()) {
+      found.push(...await findPdfFiles(entryPath));
+    } else if (entry.isFile() && /\.pdf$/i.test(entry.name)) {
+      found.push(entryPath);
+    }
+  }
+  return found.sort();
+}
+
+module.exports = {
+  expandGlob,
+  findPdfFiles,
+  hasGlobCharacters
+};
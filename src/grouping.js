@@ -0,0 +1,114 @@
+// Parts by page properties instead of by page count: all pages sharing a
+// property, such as their paper size, orientation or use of color, go into
+// one part. Parts follow the
+// order in which their groups first appear, and pages keep their source order
+// within a part.
+
+const { PageSizes } = require('pdf-lib');
+const { shownPageSize } = require('./stamp');
+const { pageHasColor } = require('./color');
+
+// Page properties parts can be grouped by
+const GROUP_KEYS = ['size', 'orientation', 'color'];
+// Sizes within this many points of a paper size count as that paper size
+const SIZE_TOLERANCE = 3;
+
+/**
+ * Names the paper size of a page, whatever its orientation
+ *
+ * @param {PDFPage} page Page
+ * @returns {string} Paper name known to pdf-lib, e.g. A4 or Letter, or WIDTHxHEIGHT in points for other sizes
+ */
+function paperSizeOf(page) {
+  const box = page.getCropBox();
+  const shortSide = Math.min(box.width, box.height);
+  const longSide = Math.max(box.width, box.height);
+  for (const [name, [width, height]] of Object.entries(PageSizes)) {
+    if (Math.abs(width - shortSide) <= SIZE_TOLERANCE && Math.abs(height - longSide) <= SIZE_TOLERANCE) {
+      return name;
+    }
+  }
+  return `${Math.round(shortSide)}x${Math.round(longSide)}`;
+}
+
+/**
+ * Names the orientation of a page as viewers show it, rotation included
+ *
+ * @param {PDFPage} page Page
+ * @returns {string} landscape for pages wider than high, otherwise portrait
+ */
+function orientationOf(page) {
+  const { width, height } = shownPageSize(page);
+  return width > height ? 'landscape' : 'portrait';
+}
+
+/**
+ * Names whether a page is printed in color (see src/color.js)
+ *
+ * @param {PDFPage} page Page
+ * @returns {string} color for pages drawing in color, otherwise mono
+ */
+function colorModeOf(page) {
+  return pageHasColor(page) ? 'color' : 'mono';
+}
+
+/**
+ * Finds the group of every page
+ *
+ * @param {PDFDocument} pdf Loaded document
+ * @param {string} groupBy Page property (see GROUP_KEYS)
+ * @returns {Array<string>} Group name of each page, usable in file names
+ */
+function pageGroups(pdf, groupBy) {
+  const groupOf = {
+    size: paperSizeOf,
+    orientation: orientationOf,
+    color: colorModeOf
+  }[groupBy];
+  return pdf.getPages().map(page => groupOf(page));
+}
+
+/**
+ * Builds one part for each group of content pages
+ *
+ * @param {Array<string>} groups Group name of each page, from pageGroups
+ * @param {Object|null} intro Intro page range (1-based, inclusive), prepended to every part and not grouped
+ * @returns {Array<Object>} Parts with their group name and 1-based intro and content page numbers
+ */
+function rangesFromGroups(groups, intro) {
+  const totalPages = groups.length;
+  const introPages = [];
+  if (intro) {
+    if (intro.start < 1 || intro.end > totalPages) {
+      throw new Error(`Invalid intro range: ${intro.start}:${intro.end}. Valid range is 1:${totalPages}`);
+    }
+    for (let page = intro.start; page <= intro.end; page++) {
+      introPages.push(page);
+    }
+  }
+
+  const partRanges = [];
+  const partOfGroup = new Map();
+  for (const [pageIndex, group] of groups.entries()) {
+    const pageNumber = pageIndex + 1;
+    if (introPages.includes(pageNumber)) {
+      continue;
+    }
+    if (!partOfGroup.has(group)) {
+      const partRange = { index: partRanges.length + 1, group, pages: { intro: [...introPages], content: [] } };
+      partOfGroup.set(group, partRange);
+      partRanges.push(partRange);
+    }
+    partOfGroup.get(group).pages.content.push(pageNumber);
+  }
+  if (partRanges.length === 0) {
+    throw new Error('No pages remain to group after the intro pages');
+  }
+  return partRanges;
+}
+
+module.exports = {
+  GROUP_KEYS,
+  pageGroups,
+  rangesFromGroups
+};
@@ -0,0 +1,198 @@
+// gRPC server running splits, for use as an internal microservice. The
+// service is defined in src/splitpdf.proto: Split streams the events of a
+// split and then its result, Plan calculates the parts without writing files
+// and Info describes a PDF. Split parameters are those of the JSON-RPC daemon
+// (see src/daemon.js). Failed calls carry the CLI exit code in their
+// splitpdf-exit-code metadata, and cancelling a Split call cancels the split.
+// @grpc/grpc-js and @grpc/proto-loader are optional peer dependencies, loaded
+// when the server starts, so installs that don't serve gRPC don't need them.
+
+const path = require('path');
+const { Splitter } = require('./splitter');
+const { buildOptions } = require('./daemon');
+const { getPdfInfo } = require('./info');
+const { createCallbackSink } = require('./events');
+
+const PROTO_PATH = path.join(__dirname, 'splitpdf.proto');
+// Unset proto3 fields read as 0 or '', and the oneof names which field is set
+const LOADER_OPTIONS = { longs: Number, defaults: true, oneofs: true };
+
+// Name of the gRPC status of a failed call by exit code; other codes give INTERNAL
+const EXIT_CODE_STATUSES = {
+  2: 'INVALID_ARGUMENT',
+  3: 'NOT_FOUND',
+  4: 'FAILED_PRECONDITION',
+  5: 'FAILED_PRECONDITION',
+  6: 'UNAUTHENTICATED',
+  7: 'INVALID_ARGUMENT',
+  9: 'DEADLINE_EXCEEDED',
+  10: 'RESOURCE_EXHAUSTED',
+  130: 'CANCELLED'
+};
+
+/**
+ * Loads the gRPC packages
+ *
+ * @returns {{grpc: Object, protoLoader: Object}} @grpc/grpc-js and @grpc/proto-loader
+ */
+function loadGrpc() {
+  try {
+    return { grpc: require('@grpc/grpc-js'), protoLoader: require('@grpc/proto-loader') };
+  } catch (error) {
+    if (error.code !== 'MODULE_NOT_FOUND') {
+      throw error;
+    }
+    const missingError = new Error('The gRPC server needs the @grpc/grpc-js and @grpc/proto-loader packages. Install them with: npm install @grpc/grpc-js @grpc/proto-loader');
+    missingError.code = 2; // Exit code 2: the requested command can't be used
+    throw missingError;
+  }
+}
+
+/**
+ * @param {Object} grpc @grpc/grpc-js
+ * @param {Error} error Error of a failed call
+ * @returns {Object} gRPC error with the status for its exit code and the exit code in the metadata
+ */
+function toServiceError(grpc, error) {
+  const exitCode = typeof error.code === 'number' ? error.code : 1;
+  const metadata = new grpc.Metadata();
+  metadata.set('splitpdf-exit-code', String(exitCode));
+  return { code: grpc.status[EXIT_CODE_STATUSES[exitCode] || 'INTERNAL'], details: error.message, metadata };
+}
+
+/**
+ * Turns SplitArgs into daemon request parameters
+ *
+ * @param {Object} args SplitArgs message
+ * @returns {Object} Parameters accepted by buildOptions in src/daemon.js
+ */
+function toParams(args) {
+  let params = {};
+  if (args.optionsJson) {
+    try {
+      params = JSON.parse(args.optionsJson);
+    } catch (error) {
+      params = null;
+    }
+    if (!params || typeof params !== 'object' || Array.isArray(params)) {
+      const error = new Error('options_json must be a JSON object of split parameters.');
+      error.code = 2; // Invalid option
+      throw error;
+    }
+  }
+  for (const name of ['file', 'parts', 'intro', 'outputDir', 'outputBasename']) {
+    if (args[name]) {
+      params[name] = args[name];
+    }
+  }
+  return params;
+}
+
+/**
+ * @param {Object} result Split result or plan
+ * @returns {Object} SplitResult message; thumbnails are base64-encoded PNGs in its JSON, as in the daemon
+ */
+function toSplitResult(result) {
+  return {
+    parts: result.parts.map(part => ({
+      index: part.index,
+      introPages: part.pages.intro,
+      contentPages: part.pages.content,
+      outputPath: part.outputPath
+    })),
+    resultJson: JSON.stringify(result, (key, value) => (key === 'thumbnail' && value ? Buffer.from(value).toString('base64') : value))
+  };
+}
+
+/**
+ * Runs a function with the source of a request opened, closing it afterwards
+ *
+ * @param {Object} options Split options
+ * @param {Function} callback Async function receiving the splitter
+ * @returns {Promise<*>} Result of the callback
+ */
+async function withSplitter(options, callback) {
+  const splitter = await Splitter.open(options.filePath, { backend: options.backend, password: options.password, spreads: options.spreads, lowMemory: options.lowMemory });
+  try {
+    return await callback(splitter);
+  } finally {
+    await splitter.close();
+  }
+}
+
+/**
+ * @param {Object} grpc @grpc/grpc-js
+ * @returns {Object} Handlers of the Splitter service
+ */
+function createHandlers(grpc) {
+  return {
+    split(call) {
+      const abortController = new AbortController();
+      call.on('cancelled', () => abortController.abort());
+      const run = async () => {
+        const options = buildOptions(toParams(call.request));
+        const result = await withSplitter(options, splitter => splitter.split({
+          ...options,
+          signal: abortController.signal,
+          eventSink: createCallbackSink((event) => {
+            call.write({ event: { event: event.event, eventJson: JSON.stringify(event) } });
+          })
+        }));
+        call.write({ result: toSplitResult(result) });
+        call.end();
+      };
+      run().catch((error) => {
+        // A cancelled call has no one left to tell
+        if (!call.cancelled) {
+          call.emit('error', toServiceError(grpc, error));
+        }
+      });
+    },
+
+    plan(call, callback) {
+      const run = async () => {
+        const options = buildOptions(toParams(call.request));
+        return withSplitter(options, async splitter => toSplitResult({ parts: splitter.plan(options) }));
+      };
+      run().then(result => callback(null, result), error => callback(toServiceError(grpc, error)));
+    },
+
+    info(call, callback) {
+      getPdfInfo(call.request.file).then(
+        info => callback(null, { pageCount: info.pageCount, pdfVersion: info.pdfVersion, encrypted: info.encrypted, infoJson: JSON.stringify(info) }),
+        error => callback(toServiceError(grpc, error))
+      );
+    }
+  };
+}
+
+/**
+ * Starts the gRPC server; it has no transport security, so keep it on a
+ * private network
+ *
+ * @param {Object} serverOptions Server options
+ * @param {string} serverOptions.host Host to listen on
+ * @param {number} serverOptions.port Port to listen on (0 picks a free one)
+ * @returns {Promise<{server: grpc.Server, port: number}>} Running server and the port it listens on
+ */
+async function startGrpcServer(serverOptions) {
+  const { grpc, protoLoader } = loadGrpc();
+  const definition = protoLoader.loadSync(PROTO_PATH, LOADER_OPTIONS);
+  const server = new grpc.Server();
+  server.addService(grpc.loadPackageDefinition(definition).splitpdf.Splitter.service, createHandlers(grpc));
+  return new Promise((resolve, reject) => {
+    server.bindAsync(`${serverOptions.host}:${serverOptions.port}`, grpc.ServerCredentials.createInsecure(), (error, port) => {
+      if (error) {
+        reject(error);
+        return;
+      }
+      resolve({ server, port });
+    });
+  });
+}
+
+module.exports = {
+  PROTO_PATH,
+  LOADER_OPTIONS,
+  startGrpcServer
+};
@@ -0,0 +1,69 @@
+// Headers and footers: a line of text at the top or bottom of every page of
+// a part, e.g. "Part 3 of 7 - report". Templates use the metadata
+// placeholders, filled in for each part before building, plus {page} and
+// {pages}, filled in for each page while building.
+
+const { METADATA_PLACEHOLDERS, findUnknownPlaceholders, formatMetadata, templateValues } = require('./metadata');
+const { embedStampFont, drawStampText } = require('./stamp');
+
+// Placeholders filled in for each page: its number in the part and the part's page count
+const PAGE_PLACEHOLDERS = ['page', 'pages'];
+const HEADER_FOOTER_PLACEHOLDERS = [...METADATA_PLACEHOLDERS, ...PAGE_PLACEHOLDERS];
+const HEADER_FOOTER_FONT_SIZE = 9;
+
+/**
+ * Lists the placeholders of a header or footer template that are not supported
+ *
+ * @param {string} template Template such as "Part {part} of {total} - {basename}"
+ * @returns {Array<string>} Unknown placeholder names
+ */
+function findUnknownPagePlaceholders(template) {
+  return findUnknownPlaceholders(template).filter(name => !PAGE_PLACEHOLDERS.includes(name));
+}
+
+/**
+ * Fills in the part placeholders of the header and footer templates
+ *
+ * @param {Object} templates Header and footer templates with placeholders from HEADER_FOOTER_PLACEHOLDERS
+ * @param {Object} sourceMetadata Metadata read with readMetadata
+ * @param {Object} part Part being built, with index, total and basename
+ * @returns {Object} Header and footer templates with only {page} and {pages} left
+ */
+function partHeaderFooter(templates, sourceMetadata, part) {
+  const values = templateValues(sourceMetadata, part);
+  return {
+    header: templates.header ? formatMetadata(templates.header, values) : null,
+    footer: templates.footer ? formatMetadata(templates.footer, values) : null
+  };
+}
+
+/**
+ * Stamps the header and footer onto every page of a part
+ *
+ * @param {PDFDocument} pdf Part document
+ * @param {Object} headerFooter Templates formatted with partHeaderFooter
+ * @param {string} [headerFooter.header] Header template, centered at the top
+ * @param {string} [headerFooter.footer] Footer template, centered at the bottom
+ */
+async function stampHeaderFooter(pdf, headerFooter) {
+  const font = await embedStampFont(pdf);
+  const pages = pdf.getPages();
+  for (const [pageIndex, page] of pages.entries()) {
+    const format = (template) => template
+      .replace(/\{page\}/g, String(pageIndex + 1))
+      .replace(/\{pages\}/g, String(pages.length));
+    if (headerFooter.header) {
+      drawStampText(page, format(headerFooter.header), { font, size: HEADER_FOOTER_FONT_SIZE, position: 'top-center' });
+    }
+    if (headerFooter.footer) {
+      drawStampText(page, format(headerFooter.footer), { font, size: HEADER_FOOTER_FONT_SIZE, position: 'bottom-center' });
+    }
+  }
+}
+
+module.exports = {
+  HEADER_FOOTER_PLACEHOLDERS,
+  findUnknownPagePlaceholders,
+  partHeaderFooter,
+  stampHeaderFooter
+};
@@ -0,0 +1,221 @@
+// Imposition: several pages of a part printed on each output sheet, in
+// reading order (n-up) or in booklet order. The pages
+// are embedded as form XObjects and drawn scaled onto new sheets that replace
+// them. Bookmarks and destinations move to the sheet showing their page; links
+// and form fields of the pages are not kept (flatten first to keep field values).
+
+const { PDFArray, PDFDict, PDFName, PDFRef, PDFStream, degrees } = require('pdf-lib');
+const { shownPageSize } = require('./stamp');
+
+// Columns and rows of the grid for each number of pages per sheet, for portrait pages
+const N_UP_LAYOUTS = { 2: [2, 1], 4: [2, 2], 6: [3, 2], 8: [4, 2], 9: [3, 3], 16: [4, 4] };
+
+// Second entry of an explicit destination array
+const DESTINATION_TYPES = ['XYZ', 'Fit', 'FitH', 'FitV', 'FitR', 'FitB', 'FitBH', 'FitBV'].map(name => PDFName.of(name));
+
+/**
+ * Draws an embedded page scaled to fit a cell of a sheet, centered and upright as viewers show it
+ *
+ * @param {PDFPage} sheet Sheet to draw on
+ * @param {PDFEmbeddedPage} embeddedPage Page embedded with its crop box
+ * @param {number} rotation Clockwise rotation of the page: 0, 90, 180 or 270
+ * @param {{x: number, y: number, width: number, height: number}} cell Area of the sheet the page goes in
+ */
+function drawPageInCell(sheet, embeddedPage, rotation, cell) {
+  const { width, height } = embeddedPage;
+  const [shownWidth, shownHeight] = rotation % 180 === 0 ? [width, height] : [height, width];
+  const scale = Math.min(cell.width / shownWidth, cell.height / shownHeight);
+  const x = cell.x + (cell.width - shownWidth * scale) / 2;
+  const y = cell.y + (cell.height - shownHeight * scale) / 2;
+
+  // The page is drawn rotated clockwise around its lower left corner, so the corner moves to keep it in the cell
+  const offsets = {
+    0: [0, 0],
+    90: [0, width * scale],
+    180: [width * scale, height * scale],
+    270: [height * scale, 0]
+  };
+  const [offsetX, offsetY] = offsets[rotation] || offsets[0];
+  sheet.drawPage(embeddedPage, {
+    x: x + offsetX,
+    y: y + offsetY,
+    xScale: scale,
+    yScale: scale,
+    rotate: degrees(-rotation)
+  });
+}
+
+/**
+ * Points destinations at the sheets that now show their pages. The position
+ * on the page no longer applies, so they show the whole sheet.
+ *
+ * @param {PDFContext} context Document context
+ * @param {Map<PDFRef, PDFRef>} sheetOfPage Sheet reference for each replaced page reference
+ */
+function moveDestinations(context, sheetOfPage) {
+  const visited = new Set();
+  const visit = (object) => {
+    if (visited.has(object)) {
+      return;
+    }
+    visited.add(object);
+    if (object instanceof PDFArray) {
+      const page = object.get(0);
+      if (page instanceof PDFRef && sheetOfPage.has(page) && DESTINATION_TYPES.includes(object.get(1))) {
+        while (object.size() > 1) {
+          object.remove(1);
+        }
+        object.set(0, sheetOfPage.get(page));
+        object.push(PDFName.of('Fit'));
+        return;
+      }
+      for (let i = 0; i < object.size(); i++) {
+        visit(object.get(i));
+      }
+    } else if (object instanceof PDFDict) {
+      for (const [, value] of object.entries()) {
+        visit(value);
+      }
+    } else if (object instanceof PDFStream) {
+      visit(object.dict);
+    }
+  };
+  for (const [, object] of context.enumerateIndirectObjects()) {
+    visit(object);
+  }
+}
+
+/**
+ * Replaces the pages of a document with sheets showing them
+ *
+ * @param {PDFDocument} pdf Document to impose
+ * @param {Array<Array<number|null>>} sheets 0-based page indices on each sheet, in grid order (left to right,
+ *   top to bottom); null leaves a cell blank
+ * @param {Array<number>} grid Columns and rows of the grid, for portrait pages
+ */
+async function imposePages(pdf, sheets, grid) {
+  const pages = pdf.getPages();
+  if (pages.length === 0) {
+    return;
+  }
+  const embeddedPages = await pdf.embedPages(pages, pages.map((page) => {
+    const box = page.getCropBox();
+    return { left: box.x, bottom: box.y, right: box.x + box.width, top: box.y + box.height };
+  }));
+
+  // The sheet has the size of the first page; grids with more columns than rows turn it sideways
+  const pageSize = shownPageSize(pages[0]);
+  const landscape = pageSize.width > pageSize.height;
+  const [columns, rows] = landscape ? [grid[1], grid[0]] : grid;
+  const [sheetWidth, sheetHeight] = columns === rows
+    ? [pageSize.width, pageSize.height]
+    : [pageSize.height, pageSize.width];
+  const cellWidth = sheetWidth / columns;
+  const cellHeight = sheetHeight / rows;
+
+  const sheetOfPage = new Map();
+  for (const pageIndices of sheets) {
+    const sheet = pdf.addPage([sheetWidth, sheetHeight]);
+    for (const [position, pageIndex] of pageIndices.entries()) {
+      if (pageIndex === null) {
+        continue;
+      }
+      const page = pages[pageIndex];
+      const rotation = ((page.getRotation().angle % 360) + 360) % 360;
+      drawPageInCell(sheet, embeddedPages[pageIndex], rotation, {
+        x: (position % columns) * cellWidth,
+        y: sheetHeight - (Math.floor(position / columns) + 1) * cellHeight,
+        width: cellWidth,
+        height: cellHeight
+      });
+      sheetOfPage.set(page.ref, sheet.ref);
+    }
+  }
+
+  for (let i = pages.length - 1; i >= 0; i--) {
+    pdf.removePage(i);
+  }
+  moveDestinations(pdf.context, sheetOfPage);
+  // The fields' widgets were on the replaced pages, and labels number the pages, not the sheets
+  pdf.catalog.delete(PDFName.of('AcroForm'));
+  pdf.catalog.delete(PDFName.of('PageLabels'));
+}
+
+/**
+ * Groups consecutive pages into sheets, in reading order
+ *
+ * @param {number} pageCount Number of pages
+ * @param {number} pagesPerSheet Pages per sheet (see N_UP_LAYOUTS)
+ * @returns {Array<Array<number>>} 0-based page indices on each sheet
+ */
+function nUpSheets(pageCount, pagesPerSheet) {
+  const sheets = [];
+  for (let i = 0; i < pageCount; i += pagesPerSheet) {
+    const pageIndices = [];
+    for (let j = i; j < Math.min(i + pagesPerSheet, pageCount); j++) {
+      pageIndices.push(j);
+    }
+    sheets.push(pageIndices);
+  }
+  return sheets;
+}
+
+/**
+ * Pairs pages for a saddle-stitched booklet: the last and the first page,
+ * then the second and the second to last, and so on. Folded and stacked in
+ * order, the printed sides read as the document. Blank pages fill the
+ * booklet up to a multiple of four pages.
+ *
+ * @param {number} pageCount Number of pages
+ * @returns {Array<Array<number|null>>} 0-based page indices on the left and right of each printed side, null for blank pages
+ */
+function bookletSheets(pageCount) {
+  const paddedCount = Math.ceil(pageCount / 4) * 4;
+  const orBlank = pageIndex => (pageIndex < pageCount ? pageIndex : null);
+  const sheets = [];
+  for (let side = 0; side < paddedCount / 2; side++) {
+    const outer = paddedCount - 1 - side;
+    // Fronts of the folded sheets have the later page on the left, backs the earlier one
+    sheets.push(side % 2 === 0 ? [orBlank(outer), orBlank(side)] : [orBlank(side), orBlank(outer)]);
+  }
+  return sheets;
+}
+
+/**
+ * Finds the sheet showing a page
+ *
+ * @param {Array<Array<number|null>>} sheets Sheets from nUpSheets or bookletSheets
+ * @param {number} pageIndex 0-based page index
+ * @returns {number} 0-based sheet index
+ */
+function sheetIndexOf(sheets, pageIndex) {
+  return sheets.findIndex(pageIndices => pageIndices.includes(pageIndex));
+}
+
+/**
+ * Prints several consecutive pages on each sheet, in reading order
+ *
+ * @param {PDFDocument} pdf Document to impose
+ * @param {number} pagesPerSheet Pages per sheet (see N_UP_LAYOUTS)
+ */
+async function imposeNUp(pdf, pagesPerSheet) {
+  await imposePages(pdf, nUpSheets(pdf.getPageCount(), pagesPerSheet), N_UP_LAYOUTS[pagesPerSheet]);
+}
+
+/**
+ * Prints the pages two per side in booklet order (see bookletSheets)
+ *
+ * @param {PDFDocument} pdf Document to impose
+ */
+async function imposeBooklet(pdf) {
+  await imposePages(pdf, bookletSheets(pdf.getPageCount()), N_UP_LAYOUTS[2]);
+}
+
+module.exports = {
+  N_UP_LAYOUTS,
+  nUpSheets,
+  bookletSheets,
+  sheetIndexOf,
+  imposeNUp,
+  imposeBooklet
+};
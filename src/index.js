@@ -1,174 +1,156 @@
 // This file is the main entry point for the module (if imported).
 // For the CLI, see src/cli.js.
 
-const path = require('path');
-const fs = require('fs/promises');
-const { PDFDocument } = require('pdf-lib');
+const { createQueueSink, combineSinks, resolveEventSink } = require('./events');
+const { calculateRanges } = require('./plan');
+const { SplitOptions } = require('./options');
+const { Splitter } = require('./splitter');
+const { splitPdfBatch } = require('./batch');
+const { getPdfInfo, getPdfMetadata, getPdfOutline, getPdfPageCount, getPdfPageDimensions, probePdf } = require('./info');
+const { validatePdf } = require('./validate');
+const { extractPages } = require('./extract');
+const { mergePdfs } = require('./merge');
+const { generatePdf } = require('./generate');
 
 /**
  * Splits a PDF into multiple parts, optionally prepending an intro range
  * 
- * @param {Object} options Configuration options
- * @param {string} options.filePath Path to the source PDF
+ * @param {Object} options Configuration options, e.g. built and validated with SplitOptions.builder()
+ * @param {string} options.filePath Path to the source PDF, or its s3://bucket/key URL (see src/s3.js)
  * @param {number} options.parts Number of parts to split into
+ * @param {Array<Object>} options.ranges Optional content page range ({start, end}, 1-based, inclusive) of each part, used instead of dividing the pages equally
+ * @param {string} options.groupBy Optional page property (size: the paper size, orientation: portrait or landscape, color: color or mono) making one part of each group of pages that share it, used instead of parts; each part of the result names its group
+ * @param {number} options.maxParts Optional most parts the split may write (defaults to 1000); planning more fails with code 7
+ * @param {string} options.tooFewPages Optional; with fewer content pages than parts, error (default) fails with code 2, reduce writes one part per content page with a partsReduced warning
  * @param {Object|null} options.intro Intro page range (1-based, inclusive)
  * @param {number} options.intro.start Start page of intro (1-based)
  * @param {number} options.intro.end End page of intro (1-based)
- * @param {string} options.outputDir Directory for output files
+ * @param {string} options.outputDir Directory for output files, or an s3://bucket/prefix URL the parts are uploaded to as they are built
  * @param {string} options.outputBasename Base filename for output parts
+ * @param {string} options.nameTemplate Optional output file name template with {basename}, {index}, {total} and {group} placeholders (defaults to {basename}_part{index}.pdf)
  * @param {boolean} options.dryRun If true, only returns calculated page ranges without writing files
- * @param {Function} options.progressCallback Optional callback for progress updates
- * @returns {Promise<Array<Object>>} Array of parts with page ranges and output paths
+ * @param {Object} options.eventSink Optional sink receiving progress events (see src/events.js)
+ * @param {Function} options.onProgress Optional callback receiving each event, used when no eventSink is given
+ * @param {Function} options.progressCallback Older name of onProgress
+ * @param {AbortSignal} options.signal Optional signal cancelling the split; parts already written are removed
+ * @param {number} options.threads Optional number of worker threads building parts concurrently (defaults to 1)
+ * @param {string} options.backend Optional name of the PDF backend (see src/backends, defaults to pdf-lib)
+ * @param {string} options.password Optional password of an encrypted source; decrypting requires qpdf
+ * @param {boolean} options.repair Optional; leave the pages that can't be copied (e.g. of a damaged source) out of the parts instead of failing, skipping parts with no pages left. The result lists each part's droppedPages and warnings name the damaged pages
+ * @param {string} options.checkpoint Optional path of a state file recording the plan and each written part; a split run again with it checks that the plan still matches and only builds the missing parts. The file is removed once the split completes
+ * @param {boolean} options.deterministic Optional; write byte-identical parts for the same source and options: creation and modification dates (and the cover date) are SOURCE_DATE_EPOCH or the Unix epoch, and document IDs are derived. Can't be combined with encrypted parts
+ * @param {number} options.timeout Optional time limit of the split in milliseconds; when it runs out, the split is cancelled, its files are removed and it fails with code 9
+ * @param {number} options.partTimeout Optional time limit for building one part in milliseconds; parts taking longer are skipped with a partsTimedOut warning and left out of the result
+ * @param {number} options.memoryInterval Optional milliseconds between memory events reporting the memory use of the process (rss, heapUsed, heapTotal, external, arrayBuffers, in bytes)
+ * @param {number} options.memoryLimit Optional soft limit on the resident set size in bytes; when exceeded, memoryLimitAction reduce (default) builds the remaining parts on a single thread with a memoryLimitExceeded warning, abort cancels the split and fails with code 10
+ * @param {string} options.memoryLimitAction Optional; reduce or abort (see memoryLimit)
+ * @param {boolean} options.lowMemory Optional; build each part with qpdf straight from the source file into its output file, holding neither the source nor any part in memory, for sources too large to split otherwise. Parts get their pages only, so options that change or add to them are rejected with code 2. Sources larger than 2 GiB fail with code 10 without it
+ * @param {Object} options.blankPages Optional; leave blank pages out of the parts, as built by SplitOptions: threshold, the share of inked pixels in percent below which a page is blank. Pages are rendered with poppler's pdftoppm to find them; the result lists each part's droppedPages
+ * @param {string} options.duplicatePages Optional; pages that repeat an earlier page exactly (same content streams and resources) are ignored (default), reported or dropped from the parts. When reported or dropped, the result lists them as duplicatePages ({ page, duplicateOf })
+ * @param {boolean} options.spreads Optional; split every landscape page (a scanned two-page spread) into its left and right halves before planning the parts, so page numbers refer to the halves
+ * @param {boolean} options.partTitles Optional; append " (Part N)" to the title each part copies from the source
+ * @param {Object} options.metadata Optional templates for the title, author, subject and keywords of each part, with {source_title}, {source_author}, {source_subject}, {source_keywords}, {part}, {total} and {basename} placeholders
+ * @param {string} options.bookmarks Optional; scoped (default) keeps the bookmarks pointing into each part, nearest points the others at the closest page of the part, none drops all bookmarks
+ * @param {boolean} options.tableOfContents Optional; start each part with a contents page listing its bookmarks (or source page ranges) with links
+ * @param {string} options.cover Optional cover page template put in front of each part, with the metadata placeholders plus {page_range} and {date}; lines are separated by newlines or \n (SplitOptions.builder().cover() fills in a default template)
+ * @param {boolean} options.flatten Optional; draw annotations and form field values into the page content, keeping only links interactive
+ * @param {string} options.crossLinks Optional; what happens to links to pages in another part: drop (default) removes them, keep leaves them without a destination, external links to the sibling part file
+ * @param {boolean} options.pageLabels Optional; false numbers the pages of each part from 1 instead of keeping the source page labels (e.g. i, ii, 1, 2)
+ * @param {string} options.attachments Optional; which parts get the source's file attachments: all (default), first or none. What happened is reported in the result's warnings
+ * @param {boolean} options.breakSignatures Optional; split a digitally signed source, whose signatures no longer verify in the parts, instead of failing with code 5
+ * @param {boolean} options.keepPdfa Optional; keep the PDF/A identification (XMP metadata and output intents) of the source in the parts instead of dropping the conformance claim
+ * @param {string} options.pdfVersion Optional PDF version the parts declare, e.g. 1.4 (defaults to the version of the source); parts older than 1.5 are saved without object streams
+ * @param {string} options.documentIds Optional; random (default) gives each part a fresh document ID, derived computes it from the source ID and the part number. The result lists each part's documentId
+ * @param {Object} options.watermark Optional text or image watermark stamped onto every page ({text} or {imageBytes, imageType}, with position, opacity, rotate and size; see src/watermark.js). SplitOptions.builder().watermark() also accepts an image path
+ * @param {Object} options.headerFooter Optional {header, footer} templates printed at the top and bottom of every page, with the metadata placeholders plus {page} and {pages} (SplitOptions.builder() sets them with header() and footer())
+ * @param {Object} options.bates Optional Bates numbering ({prefix, start, digits}) stamped onto every page, continuing across parts. The result lists each part's batesNumbers ({first, last})
+ * @param {Object} options.pageSize Optional paper every page is put onto, in the orientation of the page, as built by SplitOptions: width and height in points and fit (fit scales the content to fit, fill scales it to fill the paper, cutting off the rest, center keeps its size)
+ * @param {number} options.nUp Optional number of pages printed on each sheet of the parts: 2, 4, 6, 8, 9 or 16. Bookmarks open the sheet showing their page; links and form fields of the pages are not kept
+ * @param {boolean} options.booklet Optional; print the pages of each part two per side in saddle-stitch booklet order (last and first, second and second to last, ...), padded with blank pages to a multiple of four. Cannot be combined with nUp
+ * @param {boolean} options.pruneResources Optional; false keeps the fonts, images and other resources of the source that the pages of a part don't use (by default they are removed, along with objects nothing refers to)
+ * @param {Object} options.optimize Optional; make the parts smaller, as built by SplitOptions: imageDpi, the resolution images shown at a higher one are downsampled to. Streams are also compressed at the highest level and identical streams kept once
+ * @param {boolean} options.linearize Optional; save the parts linearized (fast web view), with qpdf
+ * @param {Object} options.render Optional; render each part to a folder of page images (named like the part file without its extension) instead of writing a PDF, as built by SplitOptions: format (png, jpeg or tiff) and dpi. Requires poppler's pdftoppm; the result lists each part's images
+ * @param {boolean} options.textSidecar Optional; write the text of each part into a .txt file next to it (requires poppler's pdftotext). The result lists each part's textPath
+ * @param {Object} options.thumbnails Optional; render a PNG thumbnail of the first page of each part, as built by SplitOptions: size, the longest side in pixels. Requires poppler's pdftoppm; the result gives each part's PNG bytes as thumbnail
+ * @param {boolean} options.decryptOutput Optional; write the parts of an encrypted source unencrypted instead of keeping its encryption
+ * @param {Object} options.encryption Optional encryption of the parts ({userPassword, ownerPassword, permissions}, see src/encryption.js); requires qpdf
+ * @param {Function} options.createWriter Optional; called with each part's info (index, outputPath, pages), returns a writable stream (or a promise of one) the part is written into instead of its file. Cannot be combined with render or textSidecar
+ * @returns {Promise<Object>} Split result with the parts' page ranges and output paths, a timing summary (durationMs, pages, bytes, pagesPerSecond) and warnings ({code, message}) about what the parts lose or repeat
  */
 async function splitPdf(options) {
+  const splitter = await Splitter.open(options.filePath, { backend: options.backend, password: options.password, spreads: options.spreads, lowMemory: options.lowMemory });
   try {
-    // Load the source PDF
-    const sourceBytes = await fs.readFile(options.filePath);
-    const sourcePdf = await PDFDocument.load(sourceBytes);
-    
-    // Get total page count
-    const totalPages = sourcePdf.getPageCount();
-    
-    // Calculate intro pages
-    const introCount = options.intro 
-      ? (options.intro.end - options.intro.start + 1) 
-      : 0;
-    
-    // Setup intro pages array for copying later
-    let introPages = [];
-    if (introCount > 0) {
-      // Validate intro range
-      if (options.intro.start < 1 || options.intro.end > totalPages) {
-        throw new Error(`Invalid intro range: ${options.intro.start}:${options.intro.end}. Valid range is 1:${totalPages}`);
-      }
-      
-      // Convert from 1-based to 0-based indexing
-      introPages = Array.from(
-        { length: introCount },
-        (_, i) => i + options.intro.start - 1
-      );
-    }
-    
-    // Calculate main content division
-    const remainingPages = totalPages - introCount;
-    const parts = options.parts;
-    
-    // Error if we can't create the requested number of parts
-    if (remainingPages < parts) {
-      throw new Error(`Cannot split ${remainingPages} remaining pages into ${parts} parts`);
-    }
-    
-    // Calculate base pages per part and remainder
-    const basePerPart = Math.floor(remainingPages / parts);
-    const remainder = remainingPages % parts;
-    
-    // Calculate page ranges for each part
-    const partInfos = [];
-    
-    // Track current page (0-based)
-    let currentNonIntroPage = introCount;
-    
-    for (let i = 0; i < parts; i++) {
-      // Calculate pages for this part (add an extra page for the first 'remainder' parts)
-      const partPageCount = basePerPart + (i < remainder ? 1 : 0);
-      
-      // Define page range for this part
-      const partPages = Array.from(
-        { length: partPageCount },
-        (_, j) => currentNonIntroPage + j
-      );
-      
-      // Update current page tracker
-      currentNonIntroPage += partPageCount;
-      
-      // Calculate output filename
-      const outputFile = `${options.outputBasename}_part${i + 1}.pdf`;
-      const outputPath = path.join(options.outputDir, outputFile);
-      
-      // Save part info
-      partInfos.push({
-        index: i + 1,
-        pages: {
-          // Convert to 1-based for output/reporting
-          intro: introPages.map(p => p + 1),
-          content: partPages.map(p => p + 1)
-        },
-        outputPath
-      });
-    }
-    
-    // For dry-run, just return the part info without creating files
-    if (options.dryRun) {
-      return partInfos;
-    }
-    
-    // Process each part and create output PDFs
-    for (let i = 0; i < partInfos.length; i++) {
-      const partInfo = partInfos[i];
-      
-      // Create new PDF for this part
-      const partPdf = await PDFDocument.create();
-      
-      // Copy intro pages
-      if (introPages.length > 0) {
-        const copiedIntroPages = await partPdf.copyPages(
-          sourcePdf, 
-          introPages
-        );
-        
-        // Add copied intro pages
-        for (const page of copiedIntroPages) {
-          partPdf.addPage(page);
-        }
-      }
-      
-      // Convert 1-based content pages back to 0-based for copying
-      const contentPages = partInfo.pages.content.map(p => p - 1);
-      
-      // Copy content pages for this part
-      const copiedContentPages = await partPdf.copyPages(
-        sourcePdf, 
-        contentPages
-      );
-      
-      // Add copied content pages
-      for (const page of copiedContentPages) {
-        partPdf.addPage(page);
-      }
-      
-      // Save the part to a file
-      const partBytes = await partPdf.save();
-      await fs.writeFile(partInfo.outputPath, partBytes);
-      
-      // Report progress
-      if (options.progressCallback) {
-        options.progressCallback({
-          event: 'partComplete',
-          part: i + 1,
-          totalParts: parts,
-          outputPath: partInfo.outputPath
-        });
-      }
-    }
-    
-    return partInfos;
-  } catch (error) {
-    // Add contextual information to the error
-    if (error.message.includes('file does not exist')) {
-      const enhancedError = new Error(`I/O error: ${error.message}`);
-      enhancedError.code = 3; // I/O error
-      throw enhancedError;
-    } else if (error.message.includes('invalid') || error.message.includes('encrypted')) {
-      const enhancedError = new Error(`PDF error: ${error.message}`);
-      enhancedError.code = error.message.includes('encrypted') ? 5 : 4; // 5 for encrypted, 4 for other PDF errors
-      throw enhancedError;
-    }
-    
-    // For other errors, just pass them through
-    throw error;
+    return await splitter.split(options);
+  } finally {
+    await splitter.close();
   }
 }
 
+/**
+ * Splits a PDF that is already in memory, e.g. an upload or a decrypted blob
+ * 
+ * @param {Uint8Array} sourceBytes Bytes of the source PDF
+ * @param {Object} options Same options as splitPdf except filePath; outputDir and outputBasename are required
+ * @returns {Promise<Object>} Split result with the parts' page ranges and output paths
+ */
+async function splitPdfFromBytes(sourceBytes, options) {
+  const splitter = await Splitter.fromBytes(sourceBytes, { backend: options.backend, password: options.password, spreads: options.spreads });
+  try {
+    return await splitter.split(options);
+  } finally {
+    await splitter.close();
+  }
+}
+
+/**
+ * Splits a PDF into streams the caller provides, e.g. sockets or cloud
+ * storage uploads, without writing the parts to disk
+ * 
+ * @param {Object} options Same options as splitPdf; outputDir only resolves the part names
+ * @param {Function} createWriter Called with each part's info (index, outputPath, pages), returns a writable stream (or a promise of one) receiving the part's bytes; the split waits for each stream to finish
+ * @returns {Promise<Object>} Split result with the parts' page ranges and names
+ */
+async function splitPdfToWriters(options, createWriter) {
+  return splitPdf({ ...options, createWriter });
+}
+
+/**
+ * Starts splitting a PDF and exposes its events as an async iterable,
+ * for hosts that prefer `for await` over callbacks
+ * 
+ * @param {Object} options Same options as splitPdf; an eventSink or onProgress callback still receives every event
+ * @returns {{events: AsyncIterable<Object>, result: Promise<Object>}} Event stream and split result
+ */
+function splitPdfStream(options) {
+  const queueSink = createQueueSink();
+  const callerSink = resolveEventSink(options);
+  const eventSink = combineSinks(callerSink, queueSink);
+  const result = splitPdf({ ...options, eventSink });
+  
+  // End the event stream whether the split succeeds or fails
+  result.then(() => queueSink.close(), () => queueSink.close());
+  
+  return { events: queueSink, result };
+}
+
 module.exports = {
-  splitPdf
+  splitPdf,
+  splitPdfFromBytes,
+  splitPdfToWriters,
+  splitPdfStream,
+  splitPdfBatch,
+  getPdfInfo,
+  getPdfMetadata,
+  getPdfOutline,
+  getPdfPageCount,
+  getPdfPageDimensions,
+  probePdf,
+  validatePdf,
+  extractPages,
+  mergePdfs,
+  generatePdf,
+  calculateRanges,
+  SplitOptions,
+  Splitter
 }; 
\ No newline at end of file
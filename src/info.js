@@ -0,0 +1,243 @@
+// Describes a PDF without modifying it, e.g. to fill in a split dialog.
+
+const fs = require('fs/promises');
+const { PDFDocument, PDFDict, PDFName } = require('pdf-lib');
+const { Splitter, toSplitError } = require('./splitter');
+const { readMetadata } = require('./metadata');
+const { readOutline } = require('./outline');
+const { findSignatures } = require('./signatures');
+const { isTagged } = require('./structure');
+const { readPdfaIdentification } = require('./pdfa');
+const { pageHasColor } = require('./color');
+const { PASSWORD_EXIT_CODE, decryptPdf, readPermissions } = require('./encryption');
+
+/**
+ * Reads the PDF version from the file header
+ *
+ * @param {Uint8Array} sourceBytes Bytes of the PDF
+ * @returns {string|null} Version such as 1.7, or null if the header is missing
+ */
+function readPdfVersion(sourceBytes) {
+  // The header must be within the first 1024 bytes
+  const header = Buffer.from(sourceBytes.subarray(0, 1024)).toString('latin1');
+  const match = /%PDF-(\d+\.\d+)/.exec(header);
+  return match ? match[1] : null;
+}
+
+/**
+ * Reads the claimed PDF/A conformance level
+ *
+ * @param {PDFDocument} pdf Loaded document
+ * @returns {string|null} Level such as PDF/A-2b, or null if the document claims no PDF/A conformance
+ */
+function formatPdfaLevel(pdf) {
+  // The metadata stream of encrypted files can't be read without decrypting it
+  const identification = pdf.isEncrypted ? null : readPdfaIdentification(pdf);
+  return identification ? `PDF/A-${identification.part}${identification.conformance}` : null;
+}
+
+/**
+ * Reads the size and rotation of every page
+ *
+ * @param {PDFDocument} pdf Loaded document
+ * @returns {Array<Object>} 1-based page number, width and height in PDF points (1/72 inch) as
+ *   displayed (before rotation), and rotation in degrees, of each page
+ */
+function readPageDimensions(pdf) {
+  return pdf.getPages().map((page, index) => {
+    const { width, height } = page.getSize();
+    return { number: index + 1, width, height, rotation: page.getRotation().angle };
+  });
+}
+
+/**
+ * Reads information about a PDF: page count, sizes and use of color, version, encryption,
+ * outline presence, tagging, PDF/A conformance claim, metadata and signed signature fields
+ *
+ * @param {string} filePath Path to the PDF
+ * @returns {Promise<Object>} Information about the document
+ */
+async function getPdfInfo(filePath) {
+  let pdf;
+  let sourceBytes;
+  try {
+    sourceBytes = await fs.readFile(filePath);
+    // Encrypted files can still be described; updateMetadata: false keeps the producer as it is
+    pdf = await PDFDocument.load(sourceBytes, { ignoreEncryption: true, updateMetadata: false });
+  } catch (error) {
+    throw toSplitError(error);
+  }
+
+  // Whether pages print in color; the content of encrypted files can't be read without decrypting it
+  const pages = readPageDimensions(pdf).map((dimensions, index) => ({
+    ...dimensions,
+    color: pdf.isEncrypted ? null : pageHasColor(pdf.getPage(index))
+  }));
+
+  const outlines = pdf.catalog.lookupMaybe(PDFName.of('Outlines'), PDFDict);
+
+  return {
+    filePath,
+    fileSize: sourceBytes.length,
+    pdfVersion: readPdfVersion(sourceBytes),
+    pageCount: pdf.getPageCount(),
+    pages,
+    encrypted: pdf.isEncrypted,
+    hasOutline: !!outlines && outlines.has(PDFName.of('First')),
+    tagged: isTagged(pdf),
+    pdfa: formatPdfaLevel(pdf),
+    // Strings of encrypted files can't be read without decrypting them
+    metadata: pdf.isEncrypted ? null : readMetadata(pdf),
+    signatures: findSignatures(pdf)
+  };
+}
+
+/**
+ * Loads a PDF for reading its structure, decrypting it first if a password is given
+ *
+ * @param {string} filePath Path to the PDF
+ * @param {string} [password] Password of an encrypted PDF (requires qpdf)
+ * @param {boolean} [allowEncrypted] If true, load encrypted PDFs without a password, for reading
+ *   what isn't encrypted (e.g. the page tree) instead of failing with code 6
+ * @returns {Promise<PDFDocument>} Loaded document, as stored (pdf-lib doesn't update its producer and dates)
+ */
+async function readDocument(filePath, password, allowEncrypted = false) {
+  let pdf;
+  try {
+    const fileBytes = await fs.readFile(filePath);
+    const sourceBytes = password === undefined || password === null ? fileBytes : await decryptPdf(fileBytes, password);
+    pdf = await PDFDocument.load(sourceBytes, { ignoreEncryption: true, updateMetadata: false });
+  } catch (error) {
+    throw toSplitError(error);
+  }
+  if (pdf.isEncrypted && !allowEncrypted) {
+    const error = new Error('Password required: the PDF is encrypted. Provide its password (an empty password opens files that only restrict permissions).');
+    error.code = PASSWORD_EXIT_CODE;
+    throw error;
+  }
+  return pdf;
+}
+
+/**
+ * Reads the document information of a PDF: title, author, subject, keywords,
+ * creator, producer, and creation and modification dates
+ *
+ * @param {string} filePath Path to the PDF
+ * @param {Object} [readOptions] Read options
+ * @param {string} [readOptions.password] Password of an encrypted PDF (requires qpdf)
+ * @returns {Promise<Object>} Metadata values, null where not set; dates as ISO strings
+ */
+async function getPdfMetadata(filePath, readOptions = {}) {
+  const pdf = await readDocument(filePath, readOptions.password);
+  return readMetadata(pdf);
+}
+
+/**
+ * Turns outline items into bookmarks with levels and page numbers
+ *
+ * @param {Array<Object>} items Outline items read with readOutline
+ * @param {number} level Nesting level of the items, 1 for top-level bookmarks
+ * @returns {Array<Object>} Bookmarks with title, level, page and children
+ */
+function toBookmarks(items, level) {
+  return items.map(item => ({
+    title: item.title,
+    level,
+    page: item.pageIndex === null ? null : item.pageIndex + 1,
+    children: toBookmarks(item.children, level + 1)
+  }));
+}
+
+/**
+ * Reads the bookmark tree of a PDF, e.g. to let users pick split points from it
+ *
+ * @param {string} filePath Path to the PDF
+ * @param {Object} [readOptions] Read options
+ * @param {string} [readOptions.password] Password of an encrypted PDF (requires qpdf)
+ * @returns {Promise<Array<Object>>} Top-level bookmarks with title, level (from 1), the 1-based page
+ *   they open (null if they don't open a page of the document) and children
+ */
+async function getPdfOutline(filePath, readOptions = {}) {
+  const pdf = await readDocument(filePath, readOptions.password);
+  return toBookmarks(readOutline(pdf), 1);
+}
+
+/**
+ * Reads the size and rotation of every page of a PDF, e.g. for previews or print preflight
+ *
+ * @param {string} filePath Path to the PDF
+ * @param {Object} [readOptions] Read options
+ * @param {string} [readOptions.password] Password of an encrypted PDF (not needed, as page sizes aren't encrypted)
+ * @returns {Promise<Array<Object>>} 1-based page number, width and height in PDF points as displayed
+ *   (before rotation), and rotation in degrees, of each page
+ */
+async function getPdfPageDimensions(filePath, readOptions = {}) {
+  const pdf = await readDocument(filePath, readOptions.password, true);
+  return readPageDimensions(pdf);
+}
+
+/**
+ * Finds out whether a PDF is encrypted and needs a password to open, so
+ * callers can ask for it before starting a long job
+ *
+ * @param {string} filePath Path to the PDF
+ * @returns {Promise<Object>} encrypted; requiresPassword, true if the empty password doesn't open
+ *   the PDF (null if that can't be told because qpdf isn't installed); and permissions, those
+ *   granted without the owner password (see PERMISSIONS in src/encryption.js), null if unencrypted
+ */
+async function probePdf(filePath) {
+  let sourceBytes;
+  let pdf;
+  try {
+    sourceBytes = await fs.readFile(filePath);
+    pdf = await PDFDocument.load(sourceBytes, { ignoreEncryption: true, updateMetadata: false });
+  } catch (error) {
+    throw toSplitError(error);
+  }
+  if (!pdf.isEncrypted) {
+    return { encrypted: false, requiresPassword: false, permissions: null };
+  }
+
+  // Files that only restrict permissions open with the empty password
+  let requiresPassword;
+  try {
+    await decryptPdf(sourceBytes, '');
+    requiresPassword = false;
+  } catch (error) {
+    if (error.code === PASSWORD_EXIT_CODE) {
+      requiresPassword = true;
+    } else if (error.code === 2) {
+      requiresPassword = null; // qpdf not found
+    } else {
+      throw toSplitError(error);
+    }
+  }
+  return { encrypted: true, requiresPassword, permissions: readPermissions(pdf) };
+}
+
+/**
+ * Counts the pages of a PDF
+ *
+ * @param {string} filePath Path to the PDF
+ * @param {Object} [countOptions] Count options
+ * @param {string} [countOptions.backend] Name of the PDF backend (defaults to pdf-lib)
+ * @param {string} [countOptions.password] Password of an encrypted source (requires qpdf)
+ * @returns {Promise<number>} Number of pages
+ */
+async function getPdfPageCount(filePath, countOptions = {}) {
+  const splitter = await Splitter.open(filePath, { backend: countOptions.backend, password: countOptions.password });
+  try {
+    return splitter.pageCount();
+  } finally {
+    await splitter.close();
+  }
+}
+
+module.exports = {
+  getPdfInfo,
+  getPdfMetadata,
+  getPdfOutline,
+  getPdfPageCount,
+  getPdfPageDimensions,
+  probePdf
+};
@@ -0,0 +1,121 @@
+// Job files: many splits described in one file and run by one process, so a
+// nightly batch of hundreds of documents pays for startup only once. Each job
+// is an object with the parameters of the daemon's split method (file, parts,
+// intro, outputDir, ...), given as a JSON array or one object per line
+// (NDJSON).
+
+const { buildOptions } = require('./daemon');
+const { Splitter } = require('./splitter');
+
+/**
+ * Reads the jobs of a job file
+ *
+ * @param {string} text Contents of the job file: a JSON array of jobs, or one JSON object per line
+ * @returns {Array<{params: Object|null, error: Error|null}>} Parameters of each job, or the error
+ *   that made its line unreadable
+ */
+function parseJobs(text) {
+  const invalidJob = (message) => {
+    const error = new Error(message);
+    error.code = 2; // Invalid option
+    return { params: null, error };
+  };
+  const toJob = (value, label) => (value && typeof value === 'object' && !Array.isArray(value)
+    ? { params: value, error: null }
+    : invalidJob(`${label} is not a job object.`));
+
+  if (text.trimStart().startsWith('[')) {
+    let jobs;
+    try {
+      jobs = JSON.parse(text);
+    } catch (error) {
+      return [invalidJob(`The job file is not valid JSON: ${error.message}`)];
+    }
+    return jobs.map((value, index) => toJob(value, `Job ${index + 1}`));
+  }
+
+  const jobs = [];
+  for (const [index, line] of text.split(/\r?\n/).entries()) {
+    if (line.trim() === '') {
+      continue;
+    }
+    try {
+      jobs.push(toJob(JSON.parse(line), `Line ${index + 1}`));
+    } catch (error) {
+      jobs.push(invalidJob(`Line ${index + 1} is not valid JSON: ${error.message}`));
+    }
+  }
+  return jobs;
+}
+
+/**
+ * Runs the jobs of a job file, each split independently of the others
+ *
+ * @param {Array<{params: Object|null, error: Error|null}>} jobs Jobs read with parseJobs
+ * @param {Object} [runOptions] Run options
+ * @param {number} [runOptions.concurrency] Number of jobs running at the same time (defaults to 1)
+ * @param {Function} [runOptions.onStatus] Called with the status of each job once it has finished:
+ *   job (1-based), file, status (done or failed), and parts, outputFiles and summary, or error ({code, message})
+ * @param {AbortSignal} [runOptions.signal] Signal cancelling the running jobs and skipping the rest
+ * @returns {Promise<Array<Object>>} Status of every job, in job order
+ */
+async function runJobs(jobs, runOptions = {}) {
+  const concurrency = runOptions.concurrency || 1;
+  const onStatus = runOptions.onStatus || (() => {});
+  const statuses = new Array(jobs.length);
+
+  const runJob = async (job, index) => {
+    const file = job.params ? job.params.file : null;
+    let status;
+    try {
+      if (job.error) {
+        throw job.error;
+      }
+      const options = buildOptions(job.params);
+      const splitter = await Splitter.open(options.filePath, { backend: options.backend, password: options.password, spreads: options.spreads, lowMemory: options.lowMemory });
+      let result;
+      try {
+        result = await splitter.split({ ...options, signal: runOptions.signal });
+      } finally {
+        await splitter.close();
+      }
+      status = {
+        job: index + 1,
+        file,
+        status: 'done',
+        parts: result.parts.length,
+        outputFiles: result.parts.map(part => part.outputPath),
+        summary: result.summary,
+        warnings: result.warnings
+      };
+    } catch (error) {
+      status = {
+        job: index + 1,
+        file,
+        status: 'failed',
+        error: { code: typeof error.code === 'number' ? error.code : 1, message: error.message }
+      };
+    }
+    statuses[index] = status;
+    onStatus(status);
+  };
+
+  // Workers take the next job as soon as they are free
+  let next = 0;
+  const workers = [];
+  for (let i = 0; i < Math.min(concurrency, jobs.length); i++) {
+    workers.push((async () => {
+      while (next < jobs.length && !(runOptions.signal && runOptions.signal.aborted)) {
+        const index = next++;
+        await runJob(jobs[index], index);
+      }
+    })());
+  }
+  await Promise.all(workers);
+  return statuses.filter(status => status !== undefined);
+}
+
+module.exports = {
+  parseJobs,
+  runJobs
+};
@@ -0,0 +1,32 @@
+// Linearized ("fast web view") parts, which web viewers can show page by page
+// while the rest of the file is still loading, using byte-range requests.
+// pdf-lib can't write linearized files, so qpdf rewrites the saved parts.
+// Linearization only survives if it is the last rewrite, so encrypted parts
+// are linearized by the qpdf run that encrypts them (see src/encryption.js).
+
+const path = require('path');
+const fs = require('fs');
+const { runQpdf } = require('./qpdf');
+const { withTempDir } = require('./encryption');
+
+/**
+ * Linearizes a PDF
+ *
+ * @param {Uint8Array} pdfBytes Bytes of the unencrypted PDF
+ * @param {boolean} [deterministic] If true, derive the second half of the document ID from the
+ *   contents instead of the time and file name, so the same input gives the same bytes
+ * @returns {Promise<Uint8Array>} Bytes of the linearized PDF
+ */
+async function linearizePdf(pdfBytes, deterministic = false) {
+  return withTempDir(async (tempDir) => {
+    const plainPath = path.join(tempDir, 'plain.pdf');
+    const linearizedPath = path.join(tempDir, 'linearized.pdf');
+    await fs.promises.writeFile(plainPath, pdfBytes);
+    await runQpdf(['--linearize', ...(deterministic ? ['--deterministic-id'] : []), plainPath, linearizedPath]);
+    return fs.promises.readFile(linearizedPath);
+  });
+}
+
+module.exports = {
+  linearizePdf
+};
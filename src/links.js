@@ -0,0 +1,141 @@
+// Internal links (Link annotations jumping to another page of the document).
+// Copied links still point at the source pages, so every link of a part is
+// rewritten: links to pages in the part point at the part's copy, and links
+// to pages that ended up in another part follow the cross-link policy.
+
+const { PDFDict, PDFHexString, PDFName, PDFString } = require('pdf-lib');
+const { buildDestination, indexPages, resolveDestination } = require('./outline');
+
+// How links to pages in other parts are handled: drop removes them, keep
+// leaves them in place without a destination, noting the source page, and
+// external turns them into links to the page in the sibling part file
+const CROSS_LINK_POLICIES = ['drop', 'keep', 'external'];
+const DEFAULT_CROSS_LINK_POLICY = 'drop';
+
+/**
+ * Reads the internal links of every page
+ *
+ * @param {PDFDocument} pdf Loaded document
+ * @returns {Array<Array<Object>>} Links of each page, with the index in the page's Annots array, the 0-based target pageIndex and view
+ */
+function readLinks(pdf) {
+  const pageIndexByRef = indexPages(pdf);
+  const links = [];
+  for (const page of pdf.getPages()) {
+    const pageLinks = [];
+    const annots = page.node.Annots();
+    for (let i = 0; annots && i < annots.size(); i++) {
+      const annot = annots.lookupMaybe(i, PDFDict);
+      if (!annot || annot.lookup(PDFName.of('Subtype')) !== PDFName.of('Link')) {
+        continue;
+      }
+      const { pageIndex, view } = resolveDestination(pdf, annot, pageIndexByRef);
+      if (pageIndex !== null) {
+        pageLinks.push({ annotIndex: i, pageIndex, view });
+      }
+    }
+    links.push(pageLinks);
+  }
+  return links;
+}
+
+/**
+ * Decides how each link of a part is written
+ *
+ * @param {Array<Array<Object>>} links Source links read with readLinks
+ * @param {Array<number>} sourcePageIndices 0-based source page indices of the part, in output order
+ * @param {Object} [linkOptions] Link options
+ * @param {string} [linkOptions.policy] Cross-link policy (see CROSS_LINK_POLICIES)
+ * @param {Function} [linkOptions.locate] Returns { file, pageIndex } of the part holding a 0-based source page, for the external policy
+ * @returns {Array<Array<Object>>} Links of each page of the part
+ */
+function partLinks(links, sourcePageIndices, linkOptions = {}) {
+  const policy = linkOptions.policy || DEFAULT_CROSS_LINK_POLICY;
+
+  // As with bookmarks, pages copied twice are linked to their last copy
+  const outputIndexBySourceIndex = new Map();
+  for (const [outputIndex, sourceIndex] of sourcePageIndices.entries()) {
+    outputIndexBySourceIndex.set(sourceIndex, outputIndex);
+  }
+
+  const result = [];
+  for (const sourceIndex of sourcePageIndices) {
+    const pageLinks = [];
+    for (const link of links[sourceIndex] || []) {
+      const outputIndex = outputIndexBySourceIndex.get(link.pageIndex);
+      const sibling = outputIndex === undefined && policy === 'external' && linkOptions.locate
+        ? linkOptions.locate(link.pageIndex)
+        : null;
+      if (outputIndex !== undefined) {
+        pageLinks.push({ annotIndex: link.annotIndex, pageIndex: outputIndex, view: link.view });
+      } else if (sibling) {
+        pageLinks.push({ annotIndex: link.annotIndex, file: sibling.file, pageIndex: sibling.pageIndex, view: link.view });
+      } else if (policy === 'keep') {
+        pageLinks.push({ annotIndex: link.annotIndex, sourcePageNumber: link.pageIndex + 1 });
+      } else {
+        pageLinks.push({ annotIndex: link.annotIndex, drop: true });
+      }
+    }
+    result.push(pageLinks);
+  }
+  return result;
+}
+
+/**
+ * Rewrites the links of a part's pages
+ *
+ * @param {PDFDocument} pdf Part document, with its pages in the order partLinks was given
+ * @param {Array<Array<Object>>} links Links created with partLinks
+ */
+function writeLinks(pdf, links) {
+  const context = pdf.context;
+  for (const [pageIndex, page] of pdf.getPages().entries()) {
+    const pageLinks = links[pageIndex] || [];
+    const annots = page.node.Annots();
+    if (pageLinks.length === 0 || !annots) {
+      continue;
+    }
+
+    const dropped = new Set();
+    for (const link of pageLinks) {
+      const annot = annots.lookupMaybe(link.annotIndex, PDFDict);
+      // Backends that rebuild pages may reorder annotations; leave those links as they are
+      if (!annot || annot.lookup(PDFName.of('Subtype')) !== PDFName.of('Link')) {
+        continue;
+      }
+      annot.delete(PDFName.of('A'));
+      annot.delete(PDFName.of('Dest'));
+      if (link.drop) {
+        dropped.add(link.annotIndex);
+      } else if (link.file) {
+        annot.set(PDFName.of('A'), context.obj({
+          S: 'GoToR',
+          F: PDFString.of(link.file),
+          D: buildDestination(context, link.pageIndex, link.view)
+        }));
+      } else if (link.sourcePageNumber) {
+        annot.set(PDFName.of('Contents'), PDFHexString.fromText(`Page ${link.sourcePageNumber} of the source document`));
+      } else {
+        annot.set(PDFName.of('Dest'), buildDestination(context, pdf.getPage(link.pageIndex).ref, link.view));
+      }
+    }
+
+    if (dropped.size > 0) {
+      const kept = [];
+      for (let i = 0; i < annots.size(); i++) {
+        if (!dropped.has(i)) {
+          kept.push(annots.get(i));
+        }
+      }
+      page.node.set(PDFName.of('Annots'), context.obj(kept));
+    }
+  }
+}
+
+module.exports = {
+  CROSS_LINK_POLICIES,
+  DEFAULT_CROSS_LINK_POLICY,
+  readLinks,
+  partLinks,
+  writeLinks
+};
@@ -0,0 +1,131 @@
+// Low memory mode for sources too large to split in memory, such as
+// multi-gigabyte scan archives. Neither the source nor the parts are read into
+// the process: qpdf counts the pages of the source file where it lies on disk
+// and writes each part straight into its output file, one part at a time.
+// qpdf holds the cross-reference table of the source and the objects of the
+// pages it copies, but copies stream contents such as scanned images from file
+// to file, so memory use depends on the part size rather than the source size.
+// Parts hold the pages as qpdf copies them: nothing a backend adds to a part
+// (metadata, bookmarks, page labels, stamps, ...) is available.
+
+const path = require('path');
+const fs = require('fs/promises');
+const { runQpdf, toQpdfPageRange } = require('./qpdf');
+const { throwIfCancelled } = require('./part-builder');
+const { PASSWORD_EXIT_CODE, withTempDir, writeSecretFile } = require('./encryption');
+const { MEMORY_LIMIT_EXIT_CODE, toMegabytes } = require('./memory');
+
+// Largest file that can be read into memory in one piece (fs.readFile fails above it)
+const MAX_IN_MEMORY_SOURCE_SIZE = 2 ** 31 - 1;
+
+/**
+ * @param {number} size Size of the source file in bytes
+ * @returns {Error} Error with code 10
+ */
+function sourceTooLargeError(size) {
+  const error = new Error(`The source PDF is ${toMegabytes(size)} MB, too large to split in memory (at most ${toMegabytes(MAX_IN_MEMORY_SOURCE_SIZE)} MB); use the low memory mode, which splits it with qpdf without reading it into memory.`);
+  error.code = MEMORY_LIMIT_EXIT_CODE;
+  return error;
+}
+
+/**
+ * Runs qpdf on the source file; with a password, the arguments are passed in
+ * a private argument file (see writeSecretFile in src/encryption.js) so the
+ * password stays off the command line
+ *
+ * @param {Array<string>} args qpdf arguments, with the password if there is one
+ * @param {boolean} hasPassword Whether the arguments hold a password
+ * @param {AbortSignal} [signal] Signal that kills qpdf
+ * @returns {Promise<{stdout: string, stderr: string}>} Output of a successful run
+ */
+async function runQpdfOnSource(args, hasPassword, signal) {
+  try {
+    if (!hasPassword) {
+      return await runQpdf(args, { signal });
+    }
+    return await withTempDir(async (tempDir) => {
+      const argumentsPath = await writeSecretFile(path.join(tempDir, 'arguments'), args);
+      return runQpdf([`@${argumentsPath}`], { signal });
+    });
+  } catch (error) {
+    // qpdf reports a missing password like a wrong one
+    if (error.message.includes('invalid password')) {
+      const passwordError = new Error('Wrong or missing password for the encrypted PDF.');
+      passwordError.code = PASSWORD_EXIT_CODE;
+      throw passwordError;
+    }
+    throw error;
+  }
+}
+
+/**
+ * @param {string|null} password Password of an encrypted source, if any
+ * @returns {boolean} Whether a password was given
+ */
+function hasPassword(password) {
+  return password !== undefined && password !== null;
+}
+
+/**
+ * Counts the pages of a source PDF without reading it into memory
+ *
+ * @param {string} filePath Path to the source PDF
+ * @param {string} [password] Password of an encrypted source
+ * @returns {Promise<number>} Number of pages
+ */
+async function countPagesOnDisk(filePath, password) {
+  const passwordArgs = hasPassword(password) ? [`--password=${password}`] : [];
+  const { stdout } = await runQpdfOnSource([...passwordArgs, '--show-npages', filePath], hasPassword(password));
+  return Number.parseInt(stdout, 10);
+}
+
+/**
+ * Builds parts one after another with qpdf, each straight from the source
+ * file into its output file
+ *
+ * @param {string} filePath Path to the source PDF
+ * @param {Array<Object>} partInfos Parts to build, with pages and outputPath
+ * @param {Object} buildOptions Build options
+ * @param {string} [buildOptions.password] Password of an encrypted source
+ * @param {boolean} [buildOptions.decryptOutput] If true, parts of an encrypted source are written unencrypted
+ * @param {boolean} [buildOptions.linearize] Linearize the parts
+ * @param {AbortSignal} [buildOptions.signal] Signal cancelling the remaining builds
+ * @returns {AsyncGenerator<{partInfo: Object, bytes: null, byteCount: number, buildMs: number}>} Parts written,
+ *   with their size and how long building each took
+ */
+async function* buildPartsOnDisk(filePath, partInfos, buildOptions) {
+  const password = buildOptions.password;
+  const passwordArgs = hasPassword(password) ? [`--password=${password}`] : [];
+  // Parts of an encrypted source keep its encryption by default, as parts built in memory do
+  const encryptionArgs = hasPassword(password) && !buildOptions.decryptOutput
+    ? [`--copy-encryption=${filePath}`, `--encryption-file-password=${password}`]
+    : [];
+  for (const partInfo of partInfos) {
+    throwIfCancelled(buildOptions.signal);
+    const buildStart = Date.now();
+    const pageRange = toQpdfPageRange([...partInfo.pages.intro, ...partInfo.pages.content]);
+    try {
+      await runQpdfOnSource([
+        '--empty',
+        ...encryptionArgs,
+        ...(buildOptions.linearize ? ['--linearize'] : []),
+        '--pages', filePath, ...passwordArgs, pageRange, '--',
+        partInfo.outputPath
+      ], hasPassword(password), buildOptions.signal);
+    } catch (error) {
+      // Leave no partly written part behind, e.g. when qpdf was killed by a cancellation
+      await fs.rm(partInfo.outputPath, { force: true });
+      throwIfCancelled(buildOptions.signal);
+      throw error;
+    }
+    const { size } = await fs.stat(partInfo.outputPath);
+    yield { partInfo, bytes: null, byteCount: size, buildMs: Date.now() - buildStart };
+  }
+}
+
+module.exports = {
+  MAX_IN_MEMORY_SOURCE_SIZE,
+  sourceTooLargeError,
+  countPagesOnDisk,
+  buildPartsOnDisk
+};
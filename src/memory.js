@@ -0,0 +1,83 @@
+// Memory monitoring for splits. The memory use of the process is sampled
+// periodically and reported as memory events, so a split that gets killed
+// for running out of memory leaves a trail of how its use grew. A soft limit
+// makes the split switch to building one part at a time on a single thread
+// (reduce) or cancel itself cleanly (abort) before the operating system or
+// container kills the process.
+
+// What happens when memory use exceeds the limit
+const MEMORY_LIMIT_ACTIONS = ['reduce', 'abort'];
+const DEFAULT_MEMORY_LIMIT_ACTION = 'reduce';
+// Exit code when a split is aborted for using too much memory
+const MEMORY_LIMIT_EXIT_CODE = 10;
+// How often memory use is checked against a limit when no events are requested, in milliseconds
+const DEFAULT_CHECK_INTERVAL = 1000;
+
+/**
+ * @returns {Object} Memory use of the process in bytes: rss, heapUsed, heapTotal, external and arrayBuffers
+ */
+function memorySample() {
+  const { rss, heapUsed, heapTotal, external, arrayBuffers } = process.memoryUsage();
+  return { rss, heapUsed, heapTotal, external, arrayBuffers };
+}
+
+/**
+ * Starts sampling the memory use of the process
+ *
+ * @param {Object} monitorOptions Monitor options
+ * @param {number} [monitorOptions.interval] Milliseconds between memory events; none are emitted without it
+ * @param {number} [monitorOptions.limit] Soft limit of the resident set size in bytes
+ * @param {Function} monitorOptions.emit Called with each memory event
+ * @param {Function} monitorOptions.onExceeded Called once with the first sample above the limit
+ * @returns {{check: Function, stop: Function, exceeded: Function}} Monitor; check() samples right away,
+ *   e.g. between parts, exceeded() returns the sample that went over the limit (or null), stop() ends sampling
+ */
+function startMemoryMonitor(monitorOptions) {
+  let exceededSample = null;
+  const check = (emitEvent) => {
+    const sample = memorySample();
+    if (emitEvent) {
+      monitorOptions.emit({ event: 'memory', ...sample, limit: monitorOptions.limit || null });
+    }
+    if (monitorOptions.limit && !exceededSample && sample.rss > monitorOptions.limit) {
+      exceededSample = sample;
+      monitorOptions.onExceeded(sample);
+    }
+  };
+  const emitEvents = !!monitorOptions.interval;
+  // A first event right away, so even short splits report their memory use
+  check(emitEvents);
+  const timer = setInterval(() => check(emitEvents), monitorOptions.interval || DEFAULT_CHECK_INTERVAL);
+  // Sampling alone must not keep the process running
+  timer.unref();
+  return {
+    check: () => check(false),
+    stop: () => clearInterval(timer),
+    exceeded: () => exceededSample
+  };
+}
+
+/**
+ * @param {Object} sample Memory sample above the limit
+ * @param {number} limit Limit in bytes
+ * @returns {Error} Error with code 10
+ */
+function memoryLimitError(sample, limit) {
+  const error = new Error(`Memory limit exceeded: the process used ${toMegabytes(sample.rss)} MB (heap ${toMegabytes(sample.heapUsed)} MB), more than ${toMegabytes(limit)} MB.`);
+  error.code = MEMORY_LIMIT_EXIT_CODE;
+  return error;
+}
+
+// Bytes as whole megabytes, for messages
+function toMegabytes(bytes) {
+  return Math.round(bytes / (1024 * 1024));
+}
+
+module.exports = {
+  MEMORY_LIMIT_ACTIONS,
+  DEFAULT_MEMORY_LIMIT_ACTION,
+  MEMORY_LIMIT_EXIT_CODE,
+  startMemoryMonitor,
+  memoryLimitError,
+  toMegabytes
+};
@@ -0,0 +1,40 @@
+// Concatenates several PDFs into one file.
+
+const fs = require('fs/promises');
+const { PDFDocument } = require('pdf-lib');
+const { toSplitError } = require('./splitter');
+
+/**
+ * Writes the pages of every source PDF, in order, to a new file
+ *
+ * @param {Object} options Merge options
+ * @param {Array<string>} options.filePaths Paths to the source PDFs
+ * @param {string} options.outputPath Path of the file to write
+ * @returns {Promise<Object>} Output path and the number of pages taken from each source
+ */
+async function mergePdfs(options) {
+  const mergedPdf = await PDFDocument.create();
+  const sources = [];
+
+  for (const filePath of options.filePaths) {
+    let sourcePdf;
+    try {
+      sourcePdf = await PDFDocument.load(await fs.readFile(filePath));
+    } catch (error) {
+      throw toSplitError(error);
+    }
+
+    const copiedPages = await mergedPdf.copyPages(sourcePdf, sourcePdf.getPageIndices());
+    for (const page of copiedPages) {
+      mergedPdf.addPage(page);
+    }
+    sources.push({ filePath, pages: copiedPages.length });
+  }
+
+  await fs.writeFile(options.outputPath, await mergedPdf.save());
+  return { outputPath: options.outputPath, sources };
+}
+
+module.exports = {
+  mergePdfs
+};
@@ -0,0 +1,186 @@
+// Document information (title, author, ...) of sources and parts.
+// Parts start from the source's values so that they can be indexed like the
+// source; the values travel inside part descriptions, so they must stay
+// structured-cloneable for worker threads.
+
+// Fields that can be set from a template
+const METADATA_FIELDS = ['title', 'author', 'subject', 'keywords'];
+
+// Placeholders available in metadata templates
+const METADATA_PLACEHOLDERS = ['source_title', 'source_author', 'source_subject', 'source_keywords', 'part', 'total', 'basename'];
+
+/**
+ * Lists the placeholders of a template that are not supported
+ *
+ * @param {string} template Template such as "{source_title} - Part {part}/{total}"
+ * @returns {Array<string>} Unknown placeholder names
+ */
+function findUnknownPlaceholders(template) {
+  const unknown = [];
+  for (const match of template.matchAll(/\{([^{}]*)\}/g)) {
+    if (!METADATA_PLACEHOLDERS.includes(match[1])) {
+      unknown.push(match[1]);
+    }
+  }
+  return unknown;
+}
+
+/**
+ * Fills in a metadata template
+ *
+ * @param {string} template Template with placeholders from METADATA_PLACEHOLDERS
+ * @param {Object} values Placeholder values; missing values become empty strings
+ * @returns {string} Formatted value
+ */
+function formatMetadata(template, values) {
+  return template.replace(/\{([^{}]*)\}/g, (placeholder, name) => {
+    if (!METADATA_PLACEHOLDERS.includes(name)) {
+      return placeholder;
+    }
+    const value = values[name];
+    return value === null || value === undefined ? '' : String(value);
+  });
+}
+
+/**
+ * Reads the document information dictionary
+ *
+ * @param {PDFDocument} pdf Loaded document
+ * @returns {Object} Metadata values, null where not set; dates as ISO strings
+ */
+function readMetadata(pdf) {
+  const toIsoString = (date) => (date ? date.toISOString() : null);
+  return {
+    title: pdf.getTitle() || null,
+    author: pdf.getAuthor() || null,
+    subject: pdf.getSubject() || null,
+    keywords: pdf.getKeywords() || null,
+    creator: pdf.getCreator() || null,
+    producer: pdf.getProducer() || null,
+    creationDate: toIsoString(pdf.getCreationDate()),
+    modificationDate: toIsoString(pdf.getModificationDate())
+  };
+}
+
+/**
+ * Collects the placeholder values of one part
+ *
+ * @param {Object} sourceMetadata Metadata read with readMetadata
+ * @param {Object} part Part being built, with index, total and basename
+ * @returns {Object} Values by placeholder name (see METADATA_PLACEHOLDERS)
+ */
+function templateValues(sourceMetadata, part) {
+  return {
+    source_title: sourceMetadata.title,
+    source_author: sourceMetadata.author,
+    source_subject: sourceMetadata.subject,
+    source_keywords: sourceMetadata.keywords,
+    part: part.index,
+    total: part.total,
+    basename: part.basename
+  };
+}
+
+/**
+ * Chooses the metadata of one part from the source's metadata
+ *
+ * @param {Object} sourceMetadata Metadata read with readMetadata
+ * @param {Object} part Part being built
+ * @param {number} part.index 1-based part number
+ * @param {number} [part.total] Number of parts
+ * @param {string} [part.basename] Base name of the output files
+ * @param {Object} [metadataOptions] Metadata options
+ * @param {boolean} [metadataOptions.partTitles] Append " (Part N)" to the title
+ * @param {Object} [metadataOptions.metadata] Templates for title, author, subject and keywords; they override the source values
+ * @returns {Object} Title, author, subject, keywords and creation date of the part
+ */
+function partMetadata(sourceMetadata, part, metadataOptions = {}) {
+  const metadata = {
+    title: sourceMetadata.title,
+    author: sourceMetadata.author,
+    subject: sourceMetadata.subject,
+    keywords: sourceMetadata.keywords,
+    creationDate: sourceMetadata.creationDate
+  };
+  if (metadataOptions.partTitles) {
+    metadata.title = metadata.title ? `${metadata.title} (Part ${part.index})` : `Part ${part.index}`;
+  }
+
+  const templates = metadataOptions.metadata || {};
+  const values = templateValues(sourceMetadata, part);
+  for (const field of METADATA_FIELDS) {
+    if (typeof templates[field] === 'string') {
+      metadata[field] = formatMetadata(templates[field], values);
+    }
+  }
+  return metadata;
+}
+
+/**
+ * Writes metadata into a new document; values that are not set are left out
+ *
+ * @param {PDFDocument} pdf Document to update
+ * @param {Object} metadata Metadata chosen with partMetadata
+ */
+function applyMetadata(pdf, metadata) {
+  if (metadata.title) {
+    pdf.setTitle(metadata.title);
+  }
+  if (metadata.author) {
+    pdf.setAuthor(metadata.author);
+  }
+  if (metadata.subject) {
+    pdf.setSubject(metadata.subject);
+  }
+  if (metadata.keywords) {
+    // pdf-lib joins keywords with spaces, so the source string is kept as one entry
+    pdf.setKeywords([metadata.keywords]);
+  }
+  if (metadata.creationDate) {
+    pdf.setCreationDate(new Date(metadata.creationDate));
+  }
+}
+
+/**
+ * Chooses the date written into deterministic parts in place of the current
+ * time: SOURCE_DATE_EPOCH, as used for reproducible builds, or the Unix epoch
+ *
+ * @param {Object} env Environment variables
+ * @returns {Date} Fixed date
+ */
+function deterministicDate(env = process.env) {
+  if (env.SOURCE_DATE_EPOCH === undefined || env.SOURCE_DATE_EPOCH === '') {
+    return new Date(0);
+  }
+  if (!/^\d+$/.test(env.SOURCE_DATE_EPOCH)) {
+    const error = new Error(`SOURCE_DATE_EPOCH must be a number of seconds, not ${env.SOURCE_DATE_EPOCH}.`);
+    error.code = 2; // Invalid option
+    throw error;
+  }
+  return new Date(Number(env.SOURCE_DATE_EPOCH) * 1000);
+}
+
+/**
+ * Replaces the creation and modification dates of a new document, which
+ * otherwise record when it was built (or copy a date the source may not have)
+ *
+ * @param {PDFDocument} pdf Document to update
+ * @param {string} timestamp Date as an ISO string
+ */
+function applyTimestamp(pdf, timestamp) {
+  pdf.setCreationDate(new Date(timestamp));
+  pdf.setModificationDate(new Date(timestamp));
+}
+
+module.exports = {
+  METADATA_FIELDS,
+  METADATA_PLACEHOLDERS,
+  findUnknownPlaceholders,
+  formatMetadata,
+  readMetadata,
+  templateValues,
+  partMetadata,
+  applyMetadata,
+  deterministicDate,
+  applyTimestamp
+};
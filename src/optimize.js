@@ -0,0 +1,338 @@
+// Smaller parts for sending by email. Three passes run on each built part:
+// images shown at a higher resolution than needed are downsampled, streams
+// are compressed (again) at the highest level, and identical streams, such as
+// an image or font embedded once per page, are kept only once. Only images
+// stored uncompressed or Flate-compressed with 8 bits per component can be
+// downsampled; JPEG, JPEG 2000 and fax images are kept as they are.
+
+const zlib = require('zlib');
+const crypto = require('crypto');
+const { PDFArray, PDFDict, PDFName, PDFNumber, PDFRawStream, PDFRef, PDFStream } = require('pdf-lib');
+const { contentOperations, pageContents, streamContents } = require('./content-stream');
+
+// Resolution images are downsampled to, in dots per inch
+const DEFAULT_OPTIMIZE_DPI = 150;
+// Images are only downsampled above this multiple of the target resolution, as smaller gains aren't worth the quality loss
+const DOWNSAMPLE_THRESHOLD = 1.5;
+
+// Components of each pixel in the color spaces images can be downsampled in
+const IMAGE_COMPONENTS = { DeviceGray: 1, CalGray: 1, DeviceRGB: 3, CalRGB: 3, DeviceCMYK: 4 };
+// Streams kept as they are: metadata must stay readable without decoding (PDF/A)
+const KEPT_STREAM_TYPES = ['Metadata'];
+
+const IDENTITY = [1, 0, 0, 1, 0, 0];
+
+/**
+ * Multiplies two transformation matrices
+ *
+ * @param {Array<number>} m First matrix [a b c d e f]
+ * @param {Array<number>} n Second matrix
+ * @returns {Array<number>} m applied before n
+ */
+function multiply(m, n) {
+  return [
+    m[0] * n[0] + m[1] * n[2],
+    m[0] * n[1] + m[1] * n[3],
+    m[2] * n[0] + m[3] * n[2],
+    m[2] * n[1] + m[3] * n[3],
+    m[4] * n[0] + m[5] * n[2] + n[4],
+    m[4] * n[1] + m[5] * n[3] + n[5]
+  ];
+}
+
+/**
+ * Finds the largest size at which the images of a content stream are shown, following the forms it draws
+ *
+ * @param {PDFContext} context Document context
+ * @param {Uint8Array} contents Decoded content stream
+ * @param {PDFDict|undefined} resources Resources of the content stream
+ * @param {Array<number>} matrix Transformation from the content's space to the page
+ * @param {Map<PDFRef, {width: number, height: number}>} shownSizes Largest shown size of each image in points, added to
+ * @param {Set<PDFRef>} visited Forms inspected so far, against loops
+ */
+function findShownImageSizes(context, contents, resources, matrix, shownSizes, visited) {
+  const xObjects = resources && resources.lookupMaybe(PDFName.of('XObject'), PDFDict);
+  const stack = [];
+  let ctm = matrix;
+  for (const { operator, operands } of contentOperations(contents)) {
+    if (operator === 'q') {
+      stack.push(ctm);
+    } else if (operator === 'Q') {
+      ctm = stack.pop() || matrix;
+    } else if (operator === 'cm' && operands.length >= 6) {
+      ctm = multiply(operands.slice(-6), ctm);
+    } else if (operator === 'Do' && xObjects && operands.length > 0 && operands[operands.length - 1].name !== undefined) {
+      const ref = xObjects.get(PDFName.of(operands[operands.length - 1].name));
+      const xObject = ref instanceof PDFRef ? context.lookup(ref) : null;
+      if (!(xObject instanceof PDFStream)) {
+        continue;
+      }
+      const subtype = xObject.dict.lookup(PDFName.of('Subtype'));
+      if (subtype === PDFName.of('Image')) {
+        // Images fill the unit square, so the matrix gives their shown size
+        const size = { width: Math.hypot(ctm[0], ctm[1]), height: Math.hypot(ctm[2], ctm[3]) };
+        const softMask = xObject.dict.get(PDFName.of('SMask'));
+        for (const imageRef of softMask instanceof PDFRef ? [ref, softMask] : [ref]) {
+          const shown = shownSizes.get(imageRef) || { width: 0, height: 0 };
+          shownSizes.set(imageRef, { width: Math.max(shown.width, size.width), height: Math.max(shown.height, size.height) });
+        }
+      } else if (subtype === PDFName.of('Form') && !visited.has(ref)) {
+        visited.add(ref);
+        const formMatrix = xObject.dict.lookupMaybe(PDFName.of('Matrix'), PDFArray);
+        const formContents = streamContents(xObject);
+        if (formContents) {
+          findShownImageSizes(
+            context,
+            formContents,
+            xObject.dict.lookupMaybe(PDFName.of('Resources'), PDFDict) || resources,
+            multiply(formMatrix ? formMatrix.asArray().map(value => context.lookup(value).asNumber()) : IDENTITY, ctm),
+            shownSizes,
+            visited
+          );
+        }
+        visited.delete(ref);
+      }
+    }
+  }
+}
+
+/**
+ * Averages the pixels of an image down to a smaller size
+ *
+ * @param {Uint8Array} pixels Pixels row by row, 8 bits per component
+ * @param {number} width Width in pixels
+ * @param {number} height Height in pixels
+ * @param {number} components Components per pixel
+ * @param {number} newWidth New width in pixels
+ * @param {number} newHeight New height in pixels
+ * @returns {Uint8Array} Pixels of the smaller image
+ */
+function downsamplePixels(pixels, width, height, components, newWidth, newHeight) {
+  const result = new Uint8Array(newWidth * newHeight * components);
+  for (let y = 0; y < newHeight; y++) {
+    const top = Math.floor(y * height / newHeight);
+    const bottom = Math.max(top + 1, Math.floor((y + 1) * height / newHeight));
+    for (let x = 0; x < newWidth; x++) {
+      const left = Math.floor(x * width / newWidth);
+      const right = Math.max(left + 1, Math.floor((x + 1) * width / newWidth));
+      const count = (bottom - top) * (right - left);
+      for (let component = 0; component < components; component++) {
+        let sum = 0;
+        for (let row = top; row < bottom; row++) {
+          for (let column = left; column < right; column++) {
+            sum += pixels[(row * width + column) * components + component];
+          }
+        }
+        result[(y * newWidth + x) * components + component] = Math.round(sum / count);
+      }
+    }
+  }
+  return result;
+}
+
+/**
+ * Counts the components of an image's pixels
+ *
+ * @param {PDFContext} context Document context
+ * @param {*} colorSpace Color space of the image
+ * @returns {number|null} Components per pixel, or null for color spaces that can't be averaged (e.g. Indexed)
+ */
+function imageComponents(context, colorSpace) {
+  const value = context.lookup(colorSpace);
+  if (value instanceof PDFName) {
+    return IMAGE_COMPONENTS[value.decodeText()] || null;
+  }
+  if (value instanceof PDFArray && value.lookup(0) === PDFName.of('ICCBased')) {
+    const profile = value.lookup(1);
+    const components = profile instanceof PDFStream && profile.dict.lookup(PDFName.of('N'));
+    return components instanceof PDFNumber ? components.asNumber() : null;
+  }
+  if (value instanceof PDFArray && value.lookup(0) instanceof PDFName) {
+    return IMAGE_COMPONENTS[value.lookup(0).decodeText()] || null;
+  }
+  return null;
+}
+
+/**
+ * Tells whether a stream is stored uncompressed or Flate-compressed without a predictor
+ *
+ * @param {PDFStream} stream Stream
+ * @returns {boolean} True if its data can be decoded and compressed again as it is
+ */
+function isPlainOrFlate(stream) {
+  const filter = stream.dict.lookup(PDFName.of('Filter'));
+  const filters = filter instanceof PDFArray ? filter.asArray() : (filter ? [filter] : []);
+  return filters.length === 0
+    || (filters.length === 1 && filters[0] === PDFName.of('FlateDecode') && !stream.dict.has(PDFName.of('DecodeParms')));
+}
+
+/**
+ * Replaces the data of a stream with Flate-compressed data
+ *
+ * @param {PDFContext} context Document context
+ * @param {PDFRef} ref Reference of the stream
+ * @param {PDFDict} dict Dictionary of the stream, updated with the new filter
+ * @param {Uint8Array} data Decoded data
+ */
+function writeFlateStream(context, ref, dict, data) {
+  dict.set(PDFName.of('Filter'), PDFName.of('FlateDecode'));
+  dict.delete(PDFName.of('DecodeParms'));
+  dict.delete(PDFName.of('DL'));
+  context.assign(ref, PDFRawStream.of(dict, zlib.deflateSync(data, { level: 9 })));
+}
+
+/**
+ * Downsamples the images shown at a higher resolution than needed
+ *
+ * @param {PDFDocument} pdf Document to update
+ * @param {number} dpi Target resolution in dots per inch
+ */
+function downsampleImages(pdf, dpi) {
+  const context = pdf.context;
+  const shownSizes = new Map();
+  for (const page of pdf.getPages()) {
+    const contents = pageContents(page);
+    if (contents) {
+      findShownImageSizes(context, contents, page.node.Resources(), IDENTITY, shownSizes, new Set());
+    }
+  }
+
+  for (const [ref, shown] of shownSizes) {
+    const image = context.lookup(ref);
+    if (!(image instanceof PDFStream) || !isPlainOrFlate(image)) {
+      continue;
+    }
+    const dict = image.dict;
+    const width = dict.lookup(PDFName.of('Width'));
+    const height = dict.lookup(PDFName.of('Height'));
+    const bitsPerComponent = dict.lookup(PDFName.of('BitsPerComponent'));
+    const components = imageComponents(context, dict.get(PDFName.of('ColorSpace')));
+    // Averaged pixels would no longer match the colors a color key mask leaves out
+    if (!(width instanceof PDFNumber) || !(height instanceof PDFNumber) || !(bitsPerComponent instanceof PDFNumber)
+      || bitsPerComponent.asNumber() !== 8 || !components || dict.has(PDFName.of('Mask'))) {
+      continue;
+    }
+
+    // Points are 1/72 inch
+    const newWidth = Math.max(1, Math.ceil(shown.width / 72 * dpi));
+    const newHeight = Math.max(1, Math.ceil(shown.height / 72 * dpi));
+    if (width.asNumber() < newWidth * DOWNSAMPLE_THRESHOLD || height.asNumber() < newHeight * DOWNSAMPLE_THRESHOLD) {
+      continue;
+    }
+    const pixels = streamContents(image);
+    if (!pixels || pixels.length < width.asNumber() * height.asNumber() * components) {
+      continue;
+    }
+    const downsampled = downsamplePixels(pixels, width.asNumber(), height.asNumber(), components, newWidth, newHeight);
+    dict.set(PDFName.of('Width'), PDFNumber.of(newWidth));
+    dict.set(PDFName.of('Height'), PDFNumber.of(newHeight));
+    writeFlateStream(context, ref, dict, downsampled);
+  }
+}
+
+/**
+ * Compresses the streams read from the source at the highest level, keeping
+ * their current data where that is not smaller. Streams created by pdf-lib
+ * are compressed when the part is saved.
+ *
+ * @param {PDFDocument} pdf Document to update
+ */
+function recompressStreams(pdf) {
+  const context = pdf.context;
+  for (const [ref, object] of context.enumerateIndirectObjects()) {
+    if (!(object instanceof PDFRawStream) || !isPlainOrFlate(object)) {
+      continue;
+    }
+    const type = object.dict.lookup(PDFName.of('Type'));
+    if (type instanceof PDFName && KEPT_STREAM_TYPES.includes(type.decodeText())) {
+      continue;
+    }
+    const data = streamContents(object);
+    if (data && zlib.deflateSync(data, { level: 9 }).length < object.contents.length) {
+      writeFlateStream(context, ref, object.dict, data);
+    }
+  }
+}
+
+/**
+ * Points references to removed objects at the objects kept in their place
+ *
+ * @param {*} object PDF object whose references are updated
+ * @param {Map<PDFRef, PDFRef>} replacements Kept reference for each removed one
+ */
+function replaceReferences(object, replacements) {
+  if (object instanceof PDFDict) {
+    for (const [key, value] of object.entries()) {
+      if (value instanceof PDFRef && replacements.has(value)) {
+        object.set(key, replacements.get(value));
+      } else {
+        replaceReferences(value, replacements);
+      }
+    }
+  } else if (object instanceof PDFArray) {
+    for (let i = 0; i < object.size(); i++) {
+      const value = object.get(i);
+      if (value instanceof PDFRef && replacements.has(value)) {
+        object.set(i, replacements.get(value));
+      } else {
+        replaceReferences(value, replacements);
+      }
+    }
+  } else if (object instanceof PDFStream) {
+    replaceReferences(object.dict, replacements);
+  }
+}
+
+/**
+ * Keeps only one of each set of identical streams. Streams referring to
+ * merged streams may become identical in turn, so this repeats until no
+ * streams are merged. Other objects are not merged, as identical pages,
+ * annotations or outline items are still distinct.
+ *
+ * @param {PDFDocument} pdf Document to update
+ */
+function removeDuplicateStreams(pdf) {
+  const context = pdf.context;
+  let replacements;
+  do {
+    replacements = new Map();
+    const firstOfHash = new Map();
+    for (const [ref, object] of context.enumerateIndirectObjects()) {
+      if (!(object instanceof PDFStream)) {
+        continue;
+      }
+      const bytes = new Uint8Array(object.sizeInBytes());
+      object.copyBytesInto(bytes, 0);
+      const hash = crypto.createHash('sha256').update(bytes).digest('hex');
+      if (firstOfHash.has(hash)) {
+        replacements.set(ref, firstOfHash.get(hash));
+      } else {
+        firstOfHash.set(hash, ref);
+      }
+    }
+    for (const [, object] of context.enumerateIndirectObjects()) {
+      replaceReferences(object, replacements);
+    }
+    for (const ref of replacements.keys()) {
+      context.delete(ref);
+    }
+  } while (replacements.size > 0);
+}
+
+/**
+ * Makes a part smaller (see the top of this file)
+ *
+ * @param {PDFDocument} pdf Document to update
+ * @param {Object} optimize Optimization settings
+ * @param {number} optimize.imageDpi Resolution images are downsampled to, in dots per inch
+ */
+function optimizePdf(pdf, optimize) {
+  downsampleImages(pdf, optimize.imageDpi);
+  recompressStreams(pdf);
+  removeDuplicateStreams(pdf);
+}
+
+module.exports = {
+  DEFAULT_OPTIMIZE_DPI,
+  optimizePdf
+};
@@ -0,0 +1,1216 @@
+// Builder for split options, validating everything before a split starts.
+// splitPdf still accepts a plain options object (e.g. parsed from JSON);
+// the builder is the checked way to create one.
+
+const path = require('path');
+const fs = require('fs');
+const { DEFAULT_BACKEND, getBackend } = require('./backends');
+const { DEFAULT_NAME_TEMPLATE, DEFAULT_MAX_PARTS, TOO_FEW_PAGES_POLICIES, DEFAULT_TOO_FEW_PAGES_POLICY, sanitizeFileName } = require('./plan');
+const { PERMISSIONS } = require('./encryption');
+const { BOOKMARK_MODES, DEFAULT_BOOKMARK_MODE } = require('./outline');
+const { CROSS_LINK_POLICIES, DEFAULT_CROSS_LINK_POLICY } = require('./links');
+const { PDF_VERSIONS } = require('./version');
+const { DOCUMENT_ID_MODES, DEFAULT_DOCUMENT_ID_MODE } = require('./document-id');
+const { N_UP_LAYOUTS } = require('./imposition');
+const { PAGE_FITS, DEFAULT_PAGE_FIT, parsePageSize } = require('./page-size');
+const { IMAGE_FORMATS, DEFAULT_RENDER_DPI, DEFAULT_THUMBNAIL_SIZE } = require('./render');
+const { DEFAULT_BLANK_THRESHOLD } = require('./blank-pages');
+const { DUPLICATE_PAGE_MODES, DEFAULT_DUPLICATE_PAGE_MODE } = require('./duplicates');
+const { GROUP_KEYS } = require('./grouping');
+const { DEFAULT_OPTIMIZE_DPI } = require('./optimize');
+
+// Parts are written as PDFs or rendered to one image per page
+const OUTPUT_FORMATS = ['pdf', ...Object.keys(IMAGE_FORMATS)];
+const { DEFAULT_BATES_DIGITS } = require('./bates');
+const { DEFAULT_WATERMARK, detectImageType } = require('./watermark');
+const { STAMP_POSITIONS } = require('./stamp');
+const { HEADER_FOOTER_PLACEHOLDERS, findUnknownPagePlaceholders } = require('./header-footer');
+const { COVER_PLACEHOLDERS, DEFAULT_COVER_TEMPLATE, findUnknownCoverPlaceholders } = require('./cover');
+const { ATTACHMENT_POLICIES, DEFAULT_ATTACHMENT_POLICY } = require('./attachments');
+const { METADATA_FIELDS, METADATA_PLACEHOLDERS, findUnknownPlaceholders } = require('./metadata');
+const { isS3Url } = require('./s3');
+const { MEMORY_LIMIT_ACTIONS, DEFAULT_MEMORY_LIMIT_ACTION } = require('./memory');
+
+/**
+ * Creates an error for an invalid option value
+ *
+ * @param {string} message Human-readable description of the problem
+ * @returns {Error} Error with exit code 2 (invalid arguments)
+ */
+function invalidOption(message) {
+  const error = new Error(message);
+  error.code = 2; // Exit code 2 for invalid CLI arguments
+  return error;
+}
+
+/**
+ * Parses an intro page range written as start:end or start-end
+ *
+ * @param {string} text Range such as 1:10 or 1-10 (1-based, inclusive)
+ * @returns {{start: number, end: number}} Parsed range
+ */
+function parseIntroRange(text) {
+  const introRange = [];
+  for (const part of String(text).split(/[:-]/)) {
+    introRange.push(Number(part));
+  }
+
+  let hasInvalidNumber = false;
+  for (const num of introRange) {
+    if (!Number.isInteger(num)) {
+      hasInvalidNumber = true;
+      break;
+    }
+  }
+
+  if (introRange.length !== 2 || hasInvalidNumber || introRange[0] < 1 || introRange[1] < introRange[0]) {
+    throw invalidOption('Invalid intro range. Must be in format start:end or start-end, e.g., 1:10, with start >= 1 and end >= start.');
+  }
+  return { start: introRange[0], end: introRange[1] };
+}
+
+/**
+ * Validates encryption settings and fills in the owner password
+ *
+ * @param {Object} encryption Encryption settings given to the builder
+ * @returns {Object} Settings accepted by encryptPdf
+ */
+function buildEncryption(encryption) {
+  const userPassword = encryption.userPassword || '';
+  const ownerPassword = encryption.ownerPassword || userPassword;
+  if (ownerPassword === '') {
+    throw invalidOption('Encrypting parts needs a user or owner password.');
+  }
+
+  let permissions = encryption.permissions;
+  if (typeof permissions === 'string') {
+    permissions = permissions.split(',').map(permission => permission.trim()).filter(permission => permission !== '');
+  }
+  for (const permission of permissions || []) {
+    if (!(permission in PERMISSIONS)) {
+      throw invalidOption(`Unknown permission: ${permission}. Available permissions: ${Object.keys(PERMISSIONS).join(', ')}`);
+    }
+  }
+
+  return { userPassword, ownerPassword, permissions: permissions || null };
+}
+
+/**
+ * Validates Bates numbering settings and fills in the defaults
+ *
+ * @param {Object} bates Bates settings given to the builder
+ * @returns {Object} Settings with prefix, start and digits
+ */
+function buildBates(bates) {
+  const prefix = bates.prefix || '';
+  const start = bates.start === undefined ? 1 : Number(bates.start);
+  const digits = bates.digits === undefined ? DEFAULT_BATES_DIGITS : Number(bates.digits);
+  if (typeof prefix !== 'string') {
+    throw invalidOption('The Bates prefix must be a string.');
+  }
+  if (!Number.isInteger(start) || start < 0) {
+    throw invalidOption('The first Bates number must be a non-negative integer.');
+  }
+  if (!Number.isInteger(digits) || digits < 1 || digits > 15) {
+    throw invalidOption('The number of Bates digits must be an integer from 1 to 15.');
+  }
+  return { prefix, start, digits };
+}
+
+/**
+ * Validates watermark settings, fills in the defaults and reads the image
+ *
+ * @param {Object} watermark Watermark settings given to the builder
+ * @returns {Object} Settings accepted by stampWatermark
+ */
+function buildWatermark(watermark) {
+  const settings = { ...DEFAULT_WATERMARK };
+  for (const key of ['position', 'opacity', 'rotate', 'size']) {
+    if (watermark[key] !== undefined && watermark[key] !== null) {
+      settings[key] = watermark[key];
+    }
+  }
+  if (!watermark.text === !watermark.image) {
+    throw invalidOption('A watermark needs either a text or an image.');
+  }
+  if (!STAMP_POSITIONS.includes(settings.position)) {
+    throw invalidOption(`Unknown watermark position: ${settings.position}. Available positions: ${STAMP_POSITIONS.join(', ')}`);
+  }
+  if (typeof settings.opacity !== 'number' || !(settings.opacity >= 0 && settings.opacity <= 1)) {
+    throw invalidOption('The watermark opacity must be a number from 0 to 1.');
+  }
+  if (typeof settings.rotate !== 'number' || !Number.isFinite(settings.rotate)) {
+    throw invalidOption('The watermark rotation must be a number of degrees.');
+  }
+  if (typeof settings.size !== 'number' || !(settings.size > 0)) {
+    throw invalidOption('The watermark font size must be a positive number.');
+  }
+
+  if (watermark.text) {
+    return { ...settings, text: String(watermark.text) };
+  }
+  let imageBytes;
+  try {
+    imageBytes = fs.readFileSync(watermark.image);
+  } catch (error) {
+    const readError = new Error(`I/O error: Cannot read the watermark image ${watermark.image}: ${error.message}`);
+    readError.code = 3; // Exit code 3 for I/O error
+    throw readError;
+  }
+  const imageType = detectImageType(imageBytes);
+  if (!imageType) {
+    throw invalidOption('The watermark image must be a PNG or JPEG file.');
+  }
+  return { ...settings, imageBytes, imageType };
+}
+
+/**
+ * Validates the header and footer templates
+ *
+ * @param {Object} templates Header and footer templates given to the builder
+ * @returns {Object|null} Templates accepted by partHeaderFooter, or null if neither is set
+ */
+function buildHeaderFooter(templates) {
+  for (const [name, template] of Object.entries(templates)) {
+    if (template === null || template === undefined) {
+      continue;
+    }
+    if (typeof template !== 'string') {
+      throw invalidOption(`The ${name} template must be a string.`);
+    }
+    const unknown = findUnknownPagePlaceholders(template);
+    if (unknown.length > 0) {
+      throw invalidOption(`Unknown placeholder {${unknown[0]}} in the ${name} template. Available placeholders: ${HEADER_FOOTER_PLACEHOLDERS.map(placeholder => `{${placeholder}}`).join(', ')}`);
+    }
+  }
+  return templates.header || templates.footer ? { header: templates.header || null, footer: templates.footer || null } : null;
+}
+
+/**
+ * Validates the cover template
+ *
+ * @param {string|boolean} cover Cover template, or true for the default template
+ * @returns {string} Template accepted by partCover
+ */
+function buildCover(cover) {
+  const template = cover === true ? DEFAULT_COVER_TEMPLATE : cover;
+  if (typeof template !== 'string') {
+    throw invalidOption('The cover template must be a string.');
+  }
+  const unknown = findUnknownCoverPlaceholders(template);
+  if (unknown.length > 0) {
+    throw invalidOption(`Unknown placeholder {${unknown[0]}} in the cover template. Available placeholders: ${COVER_PLACEHOLDERS.map(placeholder => `{${placeholder}}`).join(', ')}`);
+  }
+  return template;
+}
+
+/**
+ * Validates the metadata templates of the parts
+ *
+ * @param {Object} metadata Templates by field
+ * @returns {Object} Templates accepted by partMetadata
+ */
+function buildMetadata(metadata) {
+  const templates = {};
+  for (const [field, template] of Object.entries(metadata)) {
+    if (template === undefined || template === null) {
+      continue;
+    }
+    if (!METADATA_FIELDS.includes(field)) {
+      throw invalidOption(`Unknown metadata field: ${field}. Available fields: ${METADATA_FIELDS.join(', ')}`);
+    }
+    if (typeof template !== 'string') {
+      throw invalidOption(`Metadata template for ${field} must be a string.`);
+    }
+    const unknown = findUnknownPlaceholders(template);
+    if (unknown.length > 0) {
+      throw invalidOption(`Unknown placeholder {${unknown[0]}} in the ${field} template. Available placeholders: ${METADATA_PLACEHOLDERS.map(name => `{${name}}`).join(', ')}`);
+    }
+    templates[field] = template;
+  }
+  return templates;
+}
+
+class SplitOptionsBuilder {
+  constructor() {
+    this.options = {
+      filePath: null,
+      parts: null,
+      ranges: null,
+      groupBy: null,
+      intro: null,
+      maxParts: DEFAULT_MAX_PARTS,
+      tooFewPages: DEFAULT_TOO_FEW_PAGES_POLICY,
+      outputDir: null,
+      outputBasename: null,
+      nameTemplate: DEFAULT_NAME_TEMPLATE,
+      dryRun: false,
+      threads: 1,
+      backend: DEFAULT_BACKEND,
+      password: null,
+      encryption: null,
+      spreads: false,
+      repair: false,
+      dropBlankPages: false,
+      blankThreshold: DEFAULT_BLANK_THRESHOLD,
+      duplicatePages: DEFAULT_DUPLICATE_PAGE_MODE,
+      decryptOutput: false,
+      partTitles: false,
+      metadata: null,
+      bookmarks: DEFAULT_BOOKMARK_MODE,
+      tableOfContents: false,
+      flatten: false,
+      crossLinks: DEFAULT_CROSS_LINK_POLICY,
+      pageLabels: true,
+      attachments: DEFAULT_ATTACHMENT_POLICY,
+      breakSignatures: false,
+      keepPdfa: false,
+      pdfVersion: null,
+      documentIds: DEFAULT_DOCUMENT_ID_MODE,
+      bates: null,
+      watermark: null,
+      header: null,
+      footer: null,
+      cover: null,
+      pageSize: null,
+      pageFit: DEFAULT_PAGE_FIT,
+      nUp: null,
+      booklet: false,
+      pruneResources: true,
+      optimize: null,
+      linearize: false,
+      outputFormat: 'pdf',
+      renderDpi: DEFAULT_RENDER_DPI,
+      textSidecar: false,
+      thumbnails: null,
+      createWriter: null,
+      checkpoint: null,
+      timeout: null,
+      partTimeout: null,
+      deterministic: false,
+      memoryInterval: null,
+      memoryLimit: null,
+      memoryLimitAction: DEFAULT_MEMORY_LIMIT_ACTION,
+      lowMemory: false,
+      eventSink: null,
+      signal: null
+    };
+  }
+
+  /**
+   * @param {string} filePath Path to the source PDF
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  input(filePath) {
+    this.options.filePath = filePath;
+    return this;
+  }
+
+  /**
+   * @param {number} parts Number of parts to split into
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  parts(parts) {
+    this.options.parts = parts;
+    return this;
+  }
+
+  /**
+   * Sets the content page range of each part instead of dividing the pages equally
+   *
+   * @param {Array<{start: number, end: number}>} ranges Content page ranges (1-based, inclusive)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  ranges(ranges) {
+    this.options.ranges = ranges;
+    return this;
+  }
+
+  /**
+   * Makes one part of each group of pages sharing a property, instead of dividing the pages equally.
+   * The {group} placeholder of the name template gives the group, e.g. A4
+   *
+   * @param {string} groupBy Page property (see GROUP_KEYS in src/grouping.js)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  groupBy(groupBy) {
+    this.options.groupBy = groupBy;
+    return this;
+  }
+
+  /**
+   * Sets the intro page range, either as two page numbers or as a start:end (or start-end) string
+   *
+   * @param {number|string} start Start page (1-based) or the whole range
+   * @param {number} [end] End page (1-based, inclusive)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  intro(start, end) {
+    this.options.intro = end === undefined ? String(start) : `${start}:${end}`;
+    return this;
+  }
+
+  /**
+   * Sets the most parts a split may write; planning more fails with exit code 7
+   *
+   * @param {number} maxParts Most parts allowed (defaults to 1000)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  maxParts(maxParts) {
+    this.options.maxParts = maxParts;
+    return this;
+  }
+
+  /**
+   * Chooses what happens when there are fewer content pages than parts:
+   * error fails with exit code 2, reduce writes one part per content page
+   *
+   * @param {string} tooFewPages Policy (see TOO_FEW_PAGES_POLICIES in src/plan.js)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  tooFewPages(tooFewPages) {
+    this.options.tooFewPages = tooFewPages;
+    return this;
+  }
+
+  /**
+   * @param {string} outputDir Directory for output files
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  outputDir(outputDir) {
+    this.options.outputDir = outputDir;
+    return this;
+  }
+
+  /**
+   * @param {string} outputBasename Base filename for output parts, without directories or characters reserved in file names
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  outputBasename(outputBasename) {
+    this.options.outputBasename = outputBasename;
+    return this;
+  }
+
+  /**
+   * @param {string} nameTemplate Output file name template with {basename}, {index} and {total} placeholders
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  nameTemplate(nameTemplate) {
+    this.options.nameTemplate = nameTemplate;
+    return this;
+  }
+
+  /**
+   * @param {boolean} dryRun If true, only calculate page ranges without writing files
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  dryRun(dryRun = true) {
+    this.options.dryRun = dryRun;
+    return this;
+  }
+
+  /**
+   * @param {number} threads Number of worker threads building parts concurrently
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  threads(threads) {
+    this.options.threads = threads;
+    return this;
+  }
+
+  /**
+   * @param {string} backend Name of the PDF backend (see src/backends)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  backend(backend) {
+    this.options.backend = backend;
+    return this;
+  }
+
+  /**
+   * @param {string} password Password of an encrypted source; decrypting requires qpdf
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  password(password) {
+    this.options.password = password;
+    return this;
+  }
+
+  /**
+   * Encrypts the parts. The owner password defaults to the user password; an
+   * empty user password lets anyone open the parts with the given permissions.
+   *
+   * @param {Object} encryption Encryption settings
+   * @param {string} [encryption.userPassword] Password needed to open the parts
+   * @param {string} [encryption.ownerPassword] Password granting full access
+   * @param {Array<string>|string} [encryption.permissions] Permissions granted with the user password, e.g. print,copy (all when omitted)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  encryption(encryption) {
+    this.options.encryption = encryption;
+    return this;
+  }
+
+  /**
+   * Confirms that the parts of an encrypted source are written unencrypted.
+   * Without it they keep the source's encryption.
+   *
+   * @param {boolean} decryptOutput If true, write unencrypted parts
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  decryptOutput(decryptOutput = true) {
+    this.options.decryptOutput = decryptOutput;
+    return this;
+  }
+
+  /**
+   * Leaves blank pages (such as the empty backs of duplex scans) out of the
+   * parts; pages are rendered to find them, which requires poppler's pdftoppm
+   *
+   * @param {boolean} dropBlankPages If true, drop blank pages
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  dropBlankPages(dropBlankPages = true) {
+    this.options.dropBlankPages = dropBlankPages;
+    return this;
+  }
+
+  /**
+   * @param {number} blankThreshold Share of inked pixels, in percent, below which a page is blank (defaults to 0.5)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  blankThreshold(blankThreshold) {
+    this.options.blankThreshold = blankThreshold;
+    return this;
+  }
+
+  /**
+   * Looks for pages that repeat an earlier page exactly: ignore skips the
+   * check, report lists them in the result, drop also leaves them out of the parts
+   *
+   * @param {string} duplicatePages Duplicate page mode (see DUPLICATE_PAGE_MODES in src/duplicates.js)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  duplicatePages(duplicatePages) {
+    this.options.duplicatePages = duplicatePages;
+    return this;
+  }
+
+  /**
+   * @param {boolean} partTitles If true, append " (Part N)" to the source title in each part
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  partTitles(partTitles = true) {
+    this.options.partTitles = partTitles;
+    return this;
+  }
+
+  /**
+   * Sets the title, author, subject or keywords of each part from templates.
+   * Templates may use {source_title}, {source_author}, {source_subject},
+   * {source_keywords}, {part}, {total} and {basename}.
+   *
+   * @param {Object} metadata Templates by field, e.g. { title: '{source_title} - Part {part}/{total}' }
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  metadata(metadata) {
+    this.options.metadata = metadata;
+    return this;
+  }
+
+  /**
+   * Chooses how bookmarks are carried over: scoped keeps those pointing into
+   * the part, nearest also points the others at the closest page of the part,
+   * none drops all bookmarks
+   *
+   * @param {string} bookmarks Bookmark mode (see BOOKMARK_MODES in src/outline.js)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  bookmarks(bookmarks) {
+    this.options.bookmarks = bookmarks;
+    return this;
+  }
+
+  /**
+   * @param {boolean} tableOfContents If true, start each part with a page listing its sections, linked to their pages
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  tableOfContents(tableOfContents = true) {
+    this.options.tableOfContents = tableOfContents;
+    return this;
+  }
+
+  /**
+   * @param {boolean} spreads If true, split every landscape page (a scanned two-page spread) into its left and right halves before planning the parts
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  spreads(spreads = true) {
+    this.options.spreads = spreads;
+    return this;
+  }
+
+  /**
+   * Leaves the pages that can't be copied, e.g. of a damaged source, out of
+   * the parts instead of failing; parts with no pages left are skipped
+   *
+   * @param {boolean} repair If true, skip damaged pages
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  repair(repair = true) {
+    this.options.repair = repair;
+    return this;
+  }
+
+  /**
+   * @param {boolean} flatten If true, draw annotations and form fields into the page content of the parts
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  flatten(flatten = true) {
+    this.options.flatten = flatten;
+    return this;
+  }
+
+  /**
+   * Chooses what happens to links pointing at pages in another part: drop
+   * removes them, keep leaves them without a destination, noting the source
+   * page, external links them to the page in the sibling part file
+   *
+   * @param {string} crossLinks Cross-link policy (see CROSS_LINK_POLICIES in src/links.js)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  crossLinks(crossLinks) {
+    this.options.crossLinks = crossLinks;
+    return this;
+  }
+
+  /**
+   * @param {boolean} pageLabels If false, parts are numbered from 1 instead of keeping the source page labels
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  pageLabels(pageLabels = true) {
+    this.options.pageLabels = pageLabels;
+    return this;
+  }
+
+  /**
+   * Chooses which parts get the source's file attachments: all parts, only
+   * the first part, or none
+   *
+   * @param {string} attachments Attachment policy (see ATTACHMENT_POLICIES in src/attachments.js)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  attachments(attachments) {
+    this.options.attachments = attachments;
+    return this;
+  }
+
+  /**
+   * @param {boolean} breakSignatures If true, digitally signed sources are split although their signatures no longer verify in the parts
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  breakSignatures(breakSignatures = true) {
+    this.options.breakSignatures = breakSignatures;
+    return this;
+  }
+
+  /**
+   * @param {boolean} keepPdfa If true, parts keep the PDF/A identification (XMP metadata and output intents) of the source
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  keepPdfa(keepPdfa = true) {
+    this.options.keepPdfa = keepPdfa;
+    return this;
+  }
+
+  /**
+   * @param {string} pdfVersion Version the parts declare, e.g. 1.4, instead of the source's version
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  pdfVersion(pdfVersion) {
+    this.options.pdfVersion = pdfVersion;
+    return this;
+  }
+
+  /**
+   * Chooses the document IDs of the parts: random gives each part a fresh
+   * ID, derived computes it from the source ID and the part number
+   *
+   * @param {string} documentIds ID mode (see DOCUMENT_ID_MODES in src/document-id.js)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  documentIds(documentIds) {
+    this.options.documentIds = documentIds;
+    return this;
+  }
+
+  /**
+   * Stamps Bates numbers onto every page, numbering the pages of all parts in one sequence
+   *
+   * @param {Object} bates Bates settings
+   * @param {string} [bates.prefix] Text before the counter, e.g. ABC
+   * @param {number} [bates.start] First counter value (defaults to 1)
+   * @param {number} [bates.digits] Minimum number of counter digits, padded with zeros (defaults to 6)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  bates(bates) {
+    this.options.bates = bates;
+    return this;
+  }
+
+  /**
+   * Stamps a text or image watermark onto every page of the parts
+   *
+   * @param {Object} watermark Watermark settings
+   * @param {string} [watermark.text] Text to stamp, e.g. "COPY - distributed to X"
+   * @param {string} [watermark.image] Path to a PNG or JPEG image to stamp instead of text
+   * @param {string} [watermark.position] Where the watermark goes (see STAMP_POSITIONS in src/stamp.js, defaults to center)
+   * @param {number} [watermark.opacity] Opacity from 0 to 1 (defaults to 0.3)
+   * @param {number} [watermark.rotate] Counterclockwise rotation in degrees (defaults to 45)
+   * @param {number} [watermark.size] Font size of text watermarks in points (defaults to 48)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  watermark(watermark) {
+    this.options.watermark = watermark;
+    return this;
+  }
+
+  /**
+   * Prints a line of text at the top of every page. Templates may use the
+   * metadata placeholders and {page} and {pages}, the page number in the part
+   * and the part's page count.
+   *
+   * @param {string} header Template such as "{source_title}"
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  header(header) {
+    this.options.header = header;
+    return this;
+  }
+
+  /**
+   * Prints a line of text at the bottom of every page, with the same placeholders as header()
+   *
+   * @param {string} footer Template such as "Part {part} of {total} - {basename}"
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  footer(footer) {
+    this.options.footer = footer;
+    return this;
+  }
+
+  /**
+   * Starts each part with a cover page. Templates may use the metadata
+   * placeholders, {page_range} (the source pages of the part) and {date};
+   * lines are separated by newlines or \n, and the first line is the heading.
+   *
+   * @param {string|boolean} [cover] Cover template, or true for the default template (title, part, page range and date)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  cover(cover = true) {
+    this.options.cover = cover;
+    return this;
+  }
+
+  /**
+   * Puts every page onto the same paper size, in the orientation of the page
+   *
+   * @param {string} pageSize Paper name known to pdf-lib (A4, Letter, Legal, ...) or WIDTHxHEIGHT in points
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  pageSize(pageSize) {
+    this.options.pageSize = pageSize;
+    return this;
+  }
+
+  /**
+   * @param {string} pageFit How pages are put onto the paper size: fit, fill or center (see PAGE_FITS in src/page-size.js)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  pageFit(pageFit) {
+    this.options.pageFit = pageFit;
+    return this;
+  }
+
+  /**
+   * Prints several pages on each sheet of the parts, scaled down in reading order (2-up handouts)
+   *
+   * @param {number} nUp Pages per sheet (see N_UP_LAYOUTS in src/imposition.js)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  nUp(nUp) {
+    this.options.nUp = nUp;
+    return this;
+  }
+
+  /**
+   * Prints the pages of each part two per side in saddle-stitch booklet order
+   * (last and first, second and second to last, ...), adding blank pages up to
+   * a multiple of four
+   *
+   * @param {boolean} booklet If true, impose the parts as booklets
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  booklet(booklet = true) {
+    this.options.booklet = booklet;
+    return this;
+  }
+
+  /**
+   * @param {boolean} pruneResources If false, parts keep fonts, images and other resources their pages don't use
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  pruneResources(pruneResources = true) {
+    this.options.pruneResources = pruneResources;
+    return this;
+  }
+
+  /**
+   * Makes the parts smaller: downsamples images shown at a higher resolution,
+   * compresses streams at the highest level and keeps identical streams once
+   *
+   * @param {number} [imageDpi] Resolution images are downsampled to in dots per inch (defaults to 150)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  optimize(imageDpi = DEFAULT_OPTIMIZE_DPI) {
+    this.options.optimize = imageDpi;
+    return this;
+  }
+
+  /**
+   * Saves the parts linearized for fast web view (requires qpdf)
+   *
+   * @param {boolean} linearize If true, linearize the parts
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  linearize(linearize = true) {
+    this.options.linearize = linearize;
+    return this;
+  }
+
+  /**
+   * Writes each part as a PDF (the default) or renders it to a folder with
+   * one image per page, named like the part file without its extension
+   *
+   * @param {string} outputFormat pdf, png, jpeg or tiff (see IMAGE_FORMATS in src/render.js)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  outputFormat(outputFormat) {
+    this.options.outputFormat = outputFormat;
+    return this;
+  }
+
+  /**
+   * @param {number} renderDpi Resolution of rendered page images in dots per inch
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  renderDpi(renderDpi) {
+    this.options.renderDpi = renderDpi;
+    return this;
+  }
+
+  /**
+   * Writes the text of each part into a .txt file next to it (requires poppler's pdftotext)
+   *
+   * @param {boolean} textSidecar If true, write text sidecars
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  textSidecar(textSidecar = true) {
+    this.options.textSidecar = textSidecar;
+    return this;
+  }
+
+  /**
+   * Renders a PNG thumbnail of the first page of each part, returned as the
+   * part's thumbnail bytes (requires poppler's pdftoppm)
+   *
+   * @param {number} [size] Longest side of the thumbnails in pixels (defaults to 200)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  thumbnails(size = DEFAULT_THUMBNAIL_SIZE) {
+    this.options.thumbnails = size;
+    return this;
+  }
+
+  /**
+   * Writes each part into a stream the caller provides instead of a file,
+   * e.g. a socket or a cloud storage upload. The parts are still named, but
+   * nothing is written to the output directory
+   *
+   * @param {Function} createWriter Called with each part's info (index, outputPath, pages) and
+   *   returning a writable stream, or a promise of one, that receives the part's bytes
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  createWriter(createWriter) {
+    this.options.createWriter = createWriter;
+    return this;
+  }
+
+  /**
+   * Saves the plan and the parts written so far to a state file after every
+   * part, so an interrupted split run again with the same file only builds
+   * the missing parts; the file is removed once the split completes
+   *
+   * @param {string} checkpoint Path of the checkpoint file
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  checkpoint(checkpoint) {
+    this.options.checkpoint = checkpoint;
+    return this;
+  }
+
+  /**
+   * Limits how long the whole split may take; when time runs out, the split
+   * is cancelled, its files are removed and it fails with code 9
+   *
+   * @param {number} timeout Time limit in milliseconds
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  timeout(timeout) {
+    this.options.timeout = timeout;
+    return this;
+  }
+
+  /**
+   * Limits how long building one part may take; a part that takes longer is
+   * skipped with a warning and the split goes on with the next part
+   *
+   * @param {number} partTimeout Time limit in milliseconds
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  partTimeout(partTimeout) {
+    this.options.partTimeout = partTimeout;
+    return this;
+  }
+
+  /**
+   * Makes the same source and options give byte-identical parts: dates are
+   * fixed (SOURCE_DATE_EPOCH or the Unix epoch) and document IDs derived
+   *
+   * @param {boolean} deterministic If true, write reproducible parts
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  deterministic(deterministic = true) {
+    this.options.deterministic = deterministic;
+    return this;
+  }
+
+  /**
+   * @param {number} memoryInterval Milliseconds between memory events reporting the memory use of the process
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  memoryInterval(memoryInterval) {
+    this.options.memoryInterval = memoryInterval;
+    return this;
+  }
+
+  /**
+   * Sets a soft limit on the memory use (resident set size) of the process;
+   * what happens when it is exceeded depends on the memory limit action
+   *
+   * @param {number} memoryLimit Limit in bytes
+   * @param {string} [action] reduce (build the remaining parts on a single thread, default) or abort
+   *   (cancel the split, failing with code 10); see MEMORY_LIMIT_ACTIONS in src/memory.js
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  memoryLimit(memoryLimit, action = DEFAULT_MEMORY_LIMIT_ACTION) {
+    this.options.memoryLimit = memoryLimit;
+    this.options.memoryLimitAction = action;
+    return this;
+  }
+
+  /**
+   * Splits in low memory mode: qpdf writes each part straight from the source
+   * file into its output file, so neither the source nor any part is held in
+   * memory, at the cost of everything parts get besides their pages (see
+   * src/low-memory.js)
+   *
+   * @param {boolean} lowMemory If true, split in low memory mode
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  lowMemory(lowMemory = true) {
+    this.options.lowMemory = lowMemory;
+    return this;
+  }
+
+  /**
+   * @param {Object} eventSink Sink receiving progress events (see src/events.js)
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  eventSink(eventSink) {
+    this.options.eventSink = eventSink;
+    return this;
+  }
+
+  /**
+   * @param {AbortSignal} signal Signal cancelling the split
+   * @returns {SplitOptionsBuilder} This builder
+   */
+  signal(signal) {
+    this.options.signal = signal;
+    return this;
+  }
+
+  /**
+   * Validates the collected values and fills in defaults
+   *
+   * @returns {Object} Options accepted by splitPdf
+   */
+  build() {
+    const options = this.options;
+
+    if (!options.filePath) {
+      throw invalidOption('Source PDF file is required.');
+    }
+    if (!isS3Url(options.filePath) && !fs.existsSync(options.filePath)) {
+      const error = new Error(`File not found at ${options.filePath}`);
+      error.code = 3; // Exit code 3 for I/O error (file not found)
+      throw error;
+    }
+
+    if (options.groupBy) {
+      if (!GROUP_KEYS.includes(options.groupBy)) {
+        throw invalidOption(`Unknown page grouping: ${options.groupBy}. Available groupings: ${GROUP_KEYS.join(', ')}`);
+      }
+      if (options.ranges) {
+        throw invalidOption('Parts are either grouped by a page property or given as page ranges; drop one of the two.');
+      }
+    } else if (options.ranges) {
+      if (!Array.isArray(options.ranges) || options.ranges.length === 0) {
+        throw invalidOption('Page ranges must be a non-empty list.');
+      }
+    } else if (!Number.isInteger(options.parts) || options.parts <= 0) {
+      throw invalidOption('Number of parts must be a positive integer.');
+    }
+    if (!Number.isInteger(options.maxParts) || options.maxParts <= 0) {
+      throw invalidOption('Maximum number of parts must be a positive integer.');
+    }
+    if (!TOO_FEW_PAGES_POLICIES.includes(options.tooFewPages)) {
+      throw invalidOption(`Unknown policy for too few pages: ${options.tooFewPages}. Available policies: ${TOO_FEW_PAGES_POLICIES.join(', ')}`);
+    }
+
+    // The basename goes into file names as it is, so it must not lead out of the output directory
+    if (options.outputBasename && sanitizeFileName(options.outputBasename) !== options.outputBasename) {
+      throw invalidOption(`Output basename "${options.outputBasename}" is not a valid file name; leave out directories and the characters < > : " / \\ | ? *, e.g. "${sanitizeFileName(options.outputBasename)}".`);
+    }
+
+    // Without the part number (or, for grouped parts, the group) every part would be written to the same file
+    const hasPartPlaceholder = typeof options.nameTemplate === 'string'
+      && (options.nameTemplate.includes('{index}') || (!!options.groupBy && options.nameTemplate.includes('{group}')));
+    if (!hasPartPlaceholder) {
+      throw invalidOption('Name template must contain {index}, e.g., {basename}_part{index}.pdf.');
+    }
+
+    if (!Number.isInteger(options.threads) || options.threads < 1) {
+      throw invalidOption('Number of threads must be a positive integer.');
+    }
+
+    // Throws for unknown backend names
+    getBackend(options.backend);
+
+    if (!BOOKMARK_MODES.includes(options.bookmarks)) {
+      throw invalidOption(`Unknown bookmark mode: ${options.bookmarks}. Available modes: ${BOOKMARK_MODES.join(', ')}`);
+    }
+
+    if (!CROSS_LINK_POLICIES.includes(options.crossLinks)) {
+      throw invalidOption(`Unknown cross-link policy: ${options.crossLinks}. Available policies: ${CROSS_LINK_POLICIES.join(', ')}`);
+    }
+    if (options.pdfVersion && !PDF_VERSIONS.includes(options.pdfVersion)) {
+      throw invalidOption(`Unknown PDF version: ${options.pdfVersion}. Available versions: ${PDF_VERSIONS.join(', ')}`);
+    }
+    if (!DOCUMENT_ID_MODES.includes(options.documentIds)) {
+      throw invalidOption(`Unknown document ID mode: ${options.documentIds}. Available modes: ${DOCUMENT_ID_MODES.join(', ')}`);
+    }
+    const pageSize = options.pageSize ? parsePageSize(options.pageSize) : null;
+    if (options.pageSize && !pageSize) {
+      throw invalidOption(`Unknown page size: ${options.pageSize}. Use a paper name such as A4 or Letter, or WIDTHxHEIGHT in points.`);
+    }
+    if (!PAGE_FITS.includes(options.pageFit)) {
+      throw invalidOption(`Unknown page fit: ${options.pageFit}. Available fits: ${PAGE_FITS.join(', ')}`);
+    }
+    if (options.nUp !== null && !Object.keys(N_UP_LAYOUTS).includes(String(options.nUp))) {
+      throw invalidOption(`Unsupported pages per sheet: ${options.nUp}. Available values: ${Object.keys(N_UP_LAYOUTS).join(', ')}`);
+    }
+    if (options.nUp !== null && options.booklet) {
+      throw invalidOption('Parts are imposed either n-up or as booklets; drop one of the two options.');
+    }
+    if (options.optimize !== null && (!Number.isInteger(options.optimize) || options.optimize <= 0)) {
+      throw invalidOption('Optimized image resolution must be a positive integer.');
+    }
+    if (!ATTACHMENT_POLICIES.includes(options.attachments)) {
+      throw invalidOption(`Unknown attachment policy: ${options.attachments}. Available policies: ${ATTACHMENT_POLICIES.join(', ')}`);
+    }
+
+    if (!OUTPUT_FORMATS.includes(options.outputFormat)) {
+      throw invalidOption(`Unknown output format: ${options.outputFormat}. Available formats: ${OUTPUT_FORMATS.join(', ')}`);
+    }
+    if (!Number.isInteger(options.renderDpi) || options.renderDpi <= 0) {
+      throw invalidOption('Render resolution must be a positive integer.');
+    }
+    if (typeof options.blankThreshold !== 'number' || !(options.blankThreshold >= 0 && options.blankThreshold <= 100)) {
+      throw invalidOption('Blank page threshold must be a percentage from 0 to 100.');
+    }
+    if (!DUPLICATE_PAGE_MODES.includes(options.duplicatePages)) {
+      throw invalidOption(`Unknown duplicate page mode: ${options.duplicatePages}. Available modes: ${DUPLICATE_PAGE_MODES.join(', ')}`);
+    }
+    if (options.thumbnails !== null && (!Number.isInteger(options.thumbnails) || options.thumbnails <= 0)) {
+      throw invalidOption('Thumbnail size must be a positive integer.');
+    }
+    const render = options.outputFormat === 'pdf' ? null : { format: options.outputFormat, dpi: options.renderDpi };
+
+    const encryption = options.encryption ? buildEncryption(options.encryption) : null;
+    if (encryption && render) {
+      throw invalidOption('Parts rendered to images cannot be encrypted; drop the output format or the new passwords.');
+    }
+    if (options.linearize && render) {
+      throw invalidOption('Parts rendered to images cannot be linearized; drop the output format or the linearize option.');
+    }
+    if (options.createWriter !== null && typeof options.createWriter !== 'function') {
+      throw invalidOption('The writer factory must be a function returning a writable stream.');
+    }
+    if (options.createWriter && (render || options.textSidecar)) {
+      throw invalidOption('Parts written to streams cannot be rendered to images or get text sidecars; drop one of the options.');
+    }
+    if (isS3Url(options.outputDir) && (render || options.textSidecar || options.thumbnails)) {
+      throw invalidOption('Parts uploaded to S3 cannot be rendered to images or get text sidecars or thumbnails; drop one of the options.');
+    }
+    for (const [name, value] of [['Timeout', options.timeout], ['Part timeout', options.partTimeout], ['Memory interval', options.memoryInterval]]) {
+      if (value !== null && !(typeof value === 'number' && value > 0 && Number.isFinite(value))) {
+        throw invalidOption(`${name} must be a positive number of milliseconds.`);
+      }
+    }
+    if (options.memoryLimit !== null && !(Number.isInteger(options.memoryLimit) && options.memoryLimit > 0)) {
+      throw invalidOption('Memory limit must be a positive number of bytes.');
+    }
+    if (!MEMORY_LIMIT_ACTIONS.includes(options.memoryLimitAction)) {
+      throw invalidOption(`Unknown memory limit action: ${options.memoryLimitAction}. Available actions: ${MEMORY_LIMIT_ACTIONS.join(', ')}`);
+    }
+    if (options.checkpoint !== null && (typeof options.checkpoint !== 'string' || options.checkpoint === '')) {
+      throw invalidOption('The checkpoint must be a file path.');
+    }
+    if (options.checkpoint && (options.createWriter || isS3Url(options.outputDir) || options.thumbnails)) {
+      throw invalidOption('Checkpoints need parts written to local files and cannot be combined with writer streams, S3 output or thumbnails; drop one of the options.');
+    }
+    if (options.deterministic && encryption) {
+      throw invalidOption('Encrypted parts get random encryption keys and cannot be deterministic; drop the new passwords or the deterministic option.');
+    }
+    if (encryption && options.decryptOutput) {
+      throw invalidOption('Parts cannot be both encrypted and decrypted; drop the decrypt output option or the new passwords.');
+    }
+    if (options.lowMemory) {
+      // Everything that needs the parsed source or the bytes of the parts
+      const unsupported = [
+        ['S3', isS3Url(options.filePath) || isS3Url(options.outputDir)],
+        ['page grouping', !!options.groupBy],
+        ['threads', options.threads > 1],
+        ['spreads', !!options.spreads],
+        ['repair', !!options.repair],
+        ['blank page removal', !!options.dropBlankPages],
+        ['duplicate page handling', options.duplicatePages !== DEFAULT_DUPLICATE_PAGE_MODE],
+        ['part titles or metadata', !!options.partTitles || !!options.metadata],
+        ['bookmark modes', options.bookmarks !== DEFAULT_BOOKMARK_MODE],
+        ['contents pages', !!options.tableOfContents],
+        ['flattening', !!options.flatten],
+        ['cross-link policies', options.crossLinks !== DEFAULT_CROSS_LINK_POLICY],
+        ['dropping page labels', options.pageLabels === false],
+        ['attachment policies', options.attachments !== DEFAULT_ATTACHMENT_POLICY],
+        ['PDF/A identification', !!options.keepPdfa],
+        ['PDF versions', !!options.pdfVersion],
+        ['document ID modes', options.documentIds !== DEFAULT_DOCUMENT_ID_MODE],
+        ['deterministic output', !!options.deterministic],
+        ['Bates numbers', !!options.bates],
+        ['watermarks, headers or footers', !!options.watermark || !!options.header || !!options.footer],
+        ['cover pages', !!options.cover],
+        ['page sizes', !!options.pageSize],
+        ['n-up or booklets', options.nUp !== null || !!options.booklet],
+        ['keeping unused resources', options.pruneResources === false],
+        ['optimization', options.optimize !== null],
+        ['image output', !!render],
+        ['text sidecars or thumbnails', !!options.textSidecar || options.thumbnails !== null],
+        ['writer streams', !!options.createWriter],
+        ['checkpoints', !!options.checkpoint],
+        ['part time limits', options.partTimeout !== null],
+        ['new passwords', !!encryption]
+      ].filter(([, used]) => used).map(([name]) => name);
+      if (unsupported.length > 0) {
+        throw invalidOption(`Low memory mode only copies pages with qpdf and cannot be combined with ${unsupported.join(', ')}; drop them or the low memory option.`);
+      }
+    }
+
+    const filePath = isS3Url(options.filePath) ? options.filePath : path.resolve(options.filePath);
+    return {
+      filePath,
+      parts: options.groupBy ? null : (options.ranges ? options.ranges.length : options.parts),
+      ranges: options.ranges,
+      groupBy: options.groupBy,
+      intro: options.intro ? parseIntroRange(options.intro) : null,
+      maxParts: options.maxParts,
+      tooFewPages: options.tooFewPages,
+      // Default to the source file's directory and name
+      outputDir: options.outputDir || path.dirname(filePath),
+      outputBasename: options.outputBasename || sanitizeFileName(path.basename(filePath, path.extname(filePath))),
+      nameTemplate: options.nameTemplate,
+      dryRun: !!options.dryRun,
+      threads: options.threads,
+      backend: options.backend,
+      password: options.password,
+      encryption,
+      spreads: !!options.spreads,
+      repair: !!options.repair,
+      blankPages: options.dropBlankPages ? { threshold: options.blankThreshold } : null,
+      duplicatePages: options.duplicatePages,
+      decryptOutput: !!options.decryptOutput,
+      partTitles: !!options.partTitles,
+      metadata: options.metadata ? buildMetadata(options.metadata) : null,
+      bookmarks: options.bookmarks,
+      tableOfContents: !!options.tableOfContents,
+      flatten: !!options.flatten,
+      crossLinks: options.crossLinks,
+      pageLabels: options.pageLabels !== false,
+      attachments: options.attachments,
+      breakSignatures: !!options.breakSignatures,
+      keepPdfa: !!options.keepPdfa,
+      pdfVersion: options.pdfVersion || null,
+      // Random IDs would make every run differ
+      documentIds: options.deterministic ? 'derived' : options.documentIds,
+      bates: options.bates ? buildBates(options.bates) : null,
+      watermark: options.watermark ? buildWatermark(options.watermark) : null,
+      headerFooter: buildHeaderFooter({ header: options.header, footer: options.footer }),
+      cover: options.cover ? buildCover(options.cover) : null,
+      pageSize: pageSize ? { ...pageSize, fit: options.pageFit } : null,
+      nUp: options.nUp,
+      booklet: !!options.booklet,
+      pruneResources: options.pruneResources !== false,
+      optimize: options.optimize ? { imageDpi: options.optimize } : null,
+      linearize: !!options.linearize,
+      render,
+      textSidecar: !!options.textSidecar,
+      thumbnails: options.thumbnails ? { size: options.thumbnails } : null,
+      createWriter: options.createWriter,
+      checkpoint: options.checkpoint ? path.resolve(options.checkpoint) : null,
+      timeout: options.timeout,
+      partTimeout: options.partTimeout,
+      deterministic: !!options.deterministic,
+      memoryInterval: options.memoryInterval,
+      memoryLimit: options.memoryLimit,
+      memoryLimitAction: options.memoryLimitAction,
+      lowMemory: !!options.lowMemory,
+      eventSink: options.eventSink,
+      signal: options.signal
+    };
+  }
+}
+
+const SplitOptions = {
+  /**
+   * @returns {SplitOptionsBuilder} New options builder
+   */
+  builder() {
+    return new SplitOptionsBuilder();
+  }
+};
+
+module.exports = {
+  SplitOptions,
+  SplitOptionsBuilder,
+  parseIntroRange
+};
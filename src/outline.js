@@ -0,0 +1,324 @@
+// Bookmarks (the document outline) of sources and parts.
+// The source outline is read once into plain objects with 0-based page
+// indices, scoped to the pages of each part and written into the part with
+// the destinations remapped to the part's own pages.
+
+const {
+  PDFArray,
+  PDFDict,
+  PDFHexString,
+  PDFName,
+  PDFNull,
+  PDFNumber,
+  PDFRef,
+  PDFString
+} = require('pdf-lib');
+
+// How bookmarks pointing outside a part are handled
+const BOOKMARK_MODES = ['scoped', 'nearest', 'none'];
+const DEFAULT_BOOKMARK_MODE = 'scoped';
+
+/**
+ * @param {PDFDocument} pdf Loaded document
+ * @returns {Map<string, number>} 0-based page indices by page reference
+ */
+function indexPages(pdf) {
+  const pageIndexByRef = new Map();
+  for (const [index, page] of pdf.getPages().entries()) {
+    pageIndexByRef.set(page.ref.toString(), index);
+  }
+  return pageIndexByRef;
+}
+
+/**
+ * Looks up a named destination in the catalog's Dests dictionary or Names tree
+ *
+ * @param {PDFDocument} pdf Loaded document
+ * @param {string} name Destination name
+ * @returns {PDFArray|PDFDict|undefined} Destination, if found
+ */
+function lookupNamedDestination(pdf, name) {
+  const dests = pdf.catalog.lookupMaybe(PDFName.of('Dests'), PDFDict);
+  if (dests) {
+    const destination = dests.lookup(PDFName.of(name));
+    if (destination) {
+      return destination;
+    }
+  }
+
+  const names = pdf.catalog.lookupMaybe(PDFName.of('Names'), PDFDict);
+  const tree = names && names.lookupMaybe(PDFName.of('Dests'), PDFDict);
+  const visited = new Set();
+  const searchTree = (node) => {
+    if (!node || visited.has(node)) {
+      return undefined;
+    }
+    visited.add(node);
+    const entries = node.lookupMaybe(PDFName.of('Names'), PDFArray);
+    if (entries) {
+      for (let i = 0; i + 1 < entries.size(); i += 2) {
+        const key = entries.lookup(i);
+        if ((key instanceof PDFString || key instanceof PDFHexString) && key.decodeText() === name) {
+          return entries.lookup(i + 1);
+        }
+      }
+    }
+    const kids = node.lookupMaybe(PDFName.of('Kids'), PDFArray);
+    if (kids) {
+      for (let i = 0; i < kids.size(); i++) {
+        const found = searchTree(kids.lookupMaybe(i, PDFDict));
+        if (found) {
+          return found;
+        }
+      }
+    }
+    return undefined;
+  };
+  return searchTree(tree);
+}
+
+/**
+ * Resolves the destination of an outline item to a page and view
+ *
+ * @param {PDFDocument} pdf Loaded document
+ * @param {PDFDict} item Outline item
+ * @param {Map<string, number>} pageIndexByRef 0-based page indices by page reference
+ * @returns {{pageIndex: number|null, view: Array|null}} Target page and view, null where unknown
+ */
+function resolveDestination(pdf, item, pageIndexByRef) {
+  let destination = item.lookup(PDFName.of('Dest'));
+  if (!destination) {
+    const action = item.lookupMaybe(PDFName.of('A'), PDFDict);
+    if (action && action.lookup(PDFName.of('S')) === PDFName.of('GoTo')) {
+      destination = action.lookup(PDFName.of('D'));
+    }
+  }
+  if (destination instanceof PDFName) {
+    destination = lookupNamedDestination(pdf, destination.decodeText());
+  } else if (destination instanceof PDFString || destination instanceof PDFHexString) {
+    destination = lookupNamedDestination(pdf, destination.decodeText());
+  }
+  // Named destinations may be wrapped in a dictionary with a D entry
+  if (destination instanceof PDFDict) {
+    destination = destination.lookup(PDFName.of('D'));
+  }
+  return readDestinationArray(destination, pageIndexByRef);
+}
+
+/**
+ * Reads the page and view of an explicit destination
+ *
+ * @param {PDFArray|*} destination Destination array; anything else has no target
+ * @param {Map<string, number>} pageIndexByRef 0-based page indices by page reference
+ * @returns {{pageIndex: number|null, view: Array|null}} Target page and view, null where unknown
+ */
+function readDestinationArray(destination, pageIndexByRef) {
+  if (!(destination instanceof PDFArray) || destination.size() === 0) {
+    return { pageIndex: null, view: null };
+  }
+
+  const pageRef = destination.get(0);
+  const pageIndex = pageRef instanceof PDFRef ? pageIndexByRef.get(pageRef.toString()) : undefined;
+  if (pageIndex === undefined) {
+    return { pageIndex: null, view: null };
+  }
+
+  // The view is kept as a fit type followed by numbers (null for unchanged values)
+  const fitType = destination.lookup(1);
+  if (!(fitType instanceof PDFName)) {
+    return { pageIndex, view: null };
+  }
+  const view = [fitType.decodeText()];
+  for (let i = 2; i < destination.size(); i++) {
+    const value = destination.lookup(i);
+    view.push(value instanceof PDFNumber ? value.asNumber() : null);
+  }
+  return { pageIndex, view };
+}
+
+/**
+ * Creates a destination array
+ *
+ * @param {PDFContext} context Context of the document the destination is written to
+ * @param {PDFRef|number} target Page reference, or a 0-based page number for a page in another file
+ * @param {Array|null} view Fit type followed by numbers (null for unchanged values), as read by resolveDestination; Fit when null
+ * @returns {PDFArray} Destination
+ */
+function buildDestination(context, target, view) {
+  const fitView = view || ['Fit'];
+  return context.obj([
+    typeof target === 'number' ? PDFNumber.of(target) : target,
+    PDFName.of(fitView[0]),
+    ...fitView.slice(1).map(value => (value === null ? PDFNull : PDFNumber.of(value)))
+  ]);
+}
+
+/**
+ * Reads the outline of a document
+ *
+ * @param {PDFDocument} pdf Loaded document
+ * @returns {Array<Object>} Top-level items with title, 0-based pageIndex (null if unknown), view, open and children
+ */
+function readOutline(pdf) {
+  const outlines = pdf.catalog.lookupMaybe(PDFName.of('Outlines'), PDFDict);
+  if (!outlines) {
+    return [];
+  }
+
+  const pageIndexByRef = indexPages(pdf);
+
+  // Damaged files can contain loops, so every item is read only once
+  const visited = new Set();
+  const readItems = (parent) => {
+    const items = [];
+    let item = parent.lookupMaybe(PDFName.of('First'), PDFDict);
+    while (item && !visited.has(item)) {
+      visited.add(item);
+      const title = item.lookup(PDFName.of('Title'));
+      const count = item.lookupMaybe(PDFName.of('Count'), PDFNumber);
+      items.push({
+        title: title instanceof PDFString || title instanceof PDFHexString ? title.decodeText() : '',
+        ...resolveDestination(pdf, item, pageIndexByRef),
+        open: !!count && count.asNumber() > 0,
+        children: readItems(item)
+      });
+      item = item.lookupMaybe(PDFName.of('Next'), PDFDict);
+    }
+    return items;
+  };
+  return readItems(outlines);
+}
+
+/**
+ * Keeps the bookmarks that point into a part, remapped to the part's pages
+ *
+ * @param {Array<Object>} outline Source outline read with readOutline
+ * @param {Array<number>} sourcePageIndices 0-based source page indices of the part, in output order
+ * @param {string} [mode] scoped drops bookmarks pointing outside the part, nearest points them at the
+ *   closest page of the part, none drops all bookmarks
+ * @returns {Array<Object>} Outline of the part
+ */
+function partOutline(outline, sourcePageIndices, mode = DEFAULT_BOOKMARK_MODE) {
+  if (mode === 'none') {
+    return [];
+  }
+
+  // A page may appear twice (as an intro and a content page); bookmarks go to its last copy,
+  // which is the one in the part's own content
+  const outputIndexBySourceIndex = new Map();
+  for (const [outputIndex, sourceIndex] of sourcePageIndices.entries()) {
+    outputIndexBySourceIndex.set(sourceIndex, outputIndex);
+  }
+
+  const findNearest = (sourceIndex) => {
+    let nearest;
+    let nearestDistance = Infinity;
+    for (const [includedIndex, outputIndex] of outputIndexBySourceIndex) {
+      const distance = Math.abs(includedIndex - sourceIndex);
+      if (distance < nearestDistance) {
+        nearest = outputIndex;
+        nearestDistance = distance;
+      }
+    }
+    return nearest;
+  };
+
+  const scopeItems = (items) => {
+    const scoped = [];
+    for (const item of items) {
+      const children = scopeItems(item.children);
+      let pageIndex = item.pageIndex === null ? undefined : outputIndexBySourceIndex.get(item.pageIndex);
+      let view = item.view;
+      if (pageIndex === undefined && mode === 'nearest' && item.pageIndex !== null) {
+        pageIndex = findNearest(item.pageIndex);
+        view = null;
+      }
+      if (pageIndex === undefined) {
+        if (children.length === 0) {
+          continue;
+        }
+        // Keep a heading whose children are in the part, pointing at its first child
+        pageIndex = children[0].pageIndex;
+        view = null;
+      }
+      scoped.push({ title: item.title, pageIndex, view, open: item.open, children });
+    }
+    return scoped;
+  };
+  return scopeItems(outline);
+}
+
+/**
+ * @param {Array<Object>} items Outline items
+ * @returns {number} Number of items shown when the outline is first displayed
+ */
+function countVisible(items) {
+  let count = 0;
+  for (const item of items) {
+    count += 1 + (item.open ? countVisible(item.children) : 0);
+  }
+  return count;
+}
+
+/**
+ * Writes an outline into a new document
+ *
+ * @param {PDFDocument} pdf Document to update
+ * @param {Array<Object>} outline Outline scoped with partOutline
+ */
+function writeOutline(pdf, outline) {
+  if (outline.length === 0) {
+    return;
+  }
+  const context = pdf.context;
+  const pages = pdf.getPages();
+
+  const writeItems = (items, parentRef) => {
+    const refs = items.map(() => context.nextRef());
+    for (const [i, item] of items.entries()) {
+      const dict = context.obj({
+        Title: PDFHexString.fromText(item.title),
+        Parent: parentRef,
+        Dest: buildDestination(context, pages[item.pageIndex].ref, item.view)
+      });
+      if (i > 0) {
+        dict.set(PDFName.of('Prev'), refs[i - 1]);
+      }
+      if (i < items.length - 1) {
+        dict.set(PDFName.of('Next'), refs[i + 1]);
+      }
+      if (item.children.length > 0) {
+        const childRefs = writeItems(item.children, refs[i]);
+        dict.set(PDFName.of('First'), childRefs[0]);
+        dict.set(PDFName.of('Last'), childRefs[childRefs.length - 1]);
+        // A negative count marks a closed item
+        const count = item.open ? countVisible(item.children) : -item.children.length;
+        dict.set(PDFName.of('Count'), PDFNumber.of(count));
+      }
+      context.assign(refs[i], dict);
+    }
+    return refs;
+  };
+
+  const outlinesRef = context.nextRef();
+  const topRefs = writeItems(outline, outlinesRef);
+  context.assign(outlinesRef, context.obj({
+    Type: 'Outlines',
+    First: topRefs[0],
+    Last: topRefs[topRefs.length - 1],
+    Count: countVisible(outline)
+  }));
+  pdf.catalog.set(PDFName.of('Outlines'), outlinesRef);
+}
+
+module.exports = {
+  BOOKMARK_MODES,
+  DEFAULT_BOOKMARK_MODE,
+  buildDestination,
+  indexPages,
+  readDestinationArray,
+  resolveDestination,
+  readOutline,
+  partOutline,
+  writeOutline
+};
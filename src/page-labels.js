@@ -0,0 +1,136 @@
+// Page labels (the page numbers viewers display, such as i, ii, iii, 1, 2).
+// Each page of a part keeps the label it had in the source, so the label
+// ranges of the source are recalculated for the part's pages.
+
+const { PDFArray, PDFDict, PDFHexString, PDFName, PDFNumber, PDFString } = require('pdf-lib');
+
+// Numbering styles: D decimal, R and r upper- and lowercase roman, A and a letters
+const STYLES = ['D', 'R', 'r', 'A', 'a'];
+
+/**
+ * Reads the page label ranges of a document
+ *
+ * @param {PDFDocument} pdf Loaded document
+ * @returns {Array<{startIndex: number, style: string|null, prefix: string, start: number}>|null} Ranges by 0-based first page, or null without page labels
+ */
+function readPageLabels(pdf) {
+  const root = pdf.catalog.lookupMaybe(PDFName.of('PageLabels'), PDFDict);
+  if (!root) {
+    return null;
+  }
+
+  const ranges = [];
+  // Damaged files can contain loops, so every node is read only once
+  const visited = new Set();
+  const readTree = (node) => {
+    if (!node || visited.has(node)) {
+      return;
+    }
+    visited.add(node);
+    const nums = node.lookupMaybe(PDFName.of('Nums'), PDFArray);
+    for (let i = 0; nums && i + 1 < nums.size(); i += 2) {
+      const startIndex = nums.lookup(i);
+      const label = nums.lookup(i + 1);
+      if (!(startIndex instanceof PDFNumber) || !(label instanceof PDFDict)) {
+        continue;
+      }
+      const style = label.lookup(PDFName.of('S'));
+      const prefix = label.lookup(PDFName.of('P'));
+      const start = label.lookup(PDFName.of('St'));
+      ranges.push({
+        startIndex: startIndex.asNumber(),
+        style: style instanceof PDFName && STYLES.includes(style.decodeText()) ? style.decodeText() : null,
+        prefix: prefix instanceof PDFString || prefix instanceof PDFHexString ? prefix.decodeText() : '',
+        start: start instanceof PDFNumber ? start.asNumber() : 1
+      });
+    }
+    const kids = node.lookupMaybe(PDFName.of('Kids'), PDFArray);
+    for (let i = 0; kids && i < kids.size(); i++) {
+      readTree(kids.lookupMaybe(i, PDFDict));
+    }
+  };
+  readTree(root);
+
+  ranges.sort((a, b) => a.startIndex - b.startIndex);
+  return ranges.length > 0 ? ranges : null;
+}
+
+/**
+ * Recalculates label ranges so every page of a part keeps its source label
+ *
+ * @param {Array<Object>|null} ranges Source ranges read with readPageLabels
+ * @param {Array<number>} sourcePageIndices 0-based source page indices of the part, in output order
+ * @returns {Array<Object>|null} Ranges of the part by 0-based first page, or null without page labels
+ */
+function partPageLabels(ranges, sourcePageIndices) {
+  if (!ranges) {
+    return null;
+  }
+
+  const partRanges = [];
+  let previous = null;
+  for (const [outputIndex, sourceIndex] of sourcePageIndices.entries()) {
+    // The last range starting at or before the page applies to it
+    let range = null;
+    for (const candidate of ranges) {
+      if (candidate.startIndex > sourceIndex) {
+        break;
+      }
+      range = candidate;
+    }
+    const label = range
+      ? { style: range.style, prefix: range.prefix, number: range.start + sourceIndex - range.startIndex }
+      // Pages before the first range have no label; viewers show their page number
+      : { style: 'D', prefix: '', number: outputIndex + 1 };
+
+    const continues = previous
+      && previous.style === label.style
+      && previous.prefix === label.prefix
+      && (label.style === null || previous.number + 1 === label.number);
+    if (!continues) {
+      partRanges.push({ startIndex: outputIndex, style: label.style, prefix: label.prefix, start: label.number });
+    }
+    previous = label;
+  }
+  return partRanges;
+}
+
+/**
+ * Writes page label ranges into a new document
+ *
+ * @param {PDFDocument} pdf Document to update
+ * @param {Array<Object>} ranges Ranges created with partPageLabels
+ * @param {number} [offset] Number of contents pages inserted before the labelled pages, labelled "Contents 1", ...
+ * @param {number} [coverPageCount] Number of cover pages inserted before the contents pages, labelled "Cover"
+ */
+function writePageLabels(pdf, ranges, offset = 0, coverPageCount = 0) {
+  const context = pdf.context;
+  const nums = [];
+  if (coverPageCount > 0) {
+    nums.push(0, context.obj({ P: PDFHexString.fromText('Cover') }));
+  }
+  if (offset > 0) {
+    nums.push(coverPageCount, context.obj({ S: 'D', P: PDFHexString.fromText('Contents ') }));
+  }
+  for (const range of ranges) {
+    const label = context.obj({});
+    if (range.style) {
+      label.set(PDFName.of('S'), PDFName.of(range.style));
+      if (range.start !== 1) {
+        label.set(PDFName.of('St'), PDFNumber.of(range.start));
+      }
+    }
+    if (range.prefix) {
+      label.set(PDFName.of('P'), PDFHexString.fromText(range.prefix));
+    }
+    nums.push(range.startIndex + coverPageCount + offset, label);
+  }
+  pdf.catalog.set(PDFName.of('PageLabels'), context.register(context.obj({ Nums: nums })));
+}
+
+module.exports = {
+  PAGE_LABEL_STYLES: STYLES,
+  readPageLabels,
+  partPageLabels,
+  writePageLabels
+};
@@ -0,0 +1,110 @@
+// Uniform page sizes. Sources mixing page sizes give parts that printers
+// handle badly, so every page can be put onto the same paper size: its
+// content is scaled and moved with a transformation around the existing
+// content streams, and its annotations move along, so links and form fields
+// keep working.
+
+const { PDFArray, PDFDict, PDFName, PDFNumber, PageSizes } = require('pdf-lib');
+
+// How the content of a page is put onto the new page: scaled to fit inside it,
+// scaled to fill it (cutting off what sticks out), or centered at its own size
+const PAGE_FITS = ['fit', 'fill', 'center'];
+const DEFAULT_PAGE_FIT = 'fit';
+
+// Page boundaries that no longer apply once the page has the new size
+const OTHER_BOXES = ['CropBox', 'BleedBox', 'TrimBox', 'ArtBox'];
+
+/**
+ * Reads a paper size
+ *
+ * @param {string} size Paper name known to pdf-lib (A4, Letter, Legal, ...) in any case, or WIDTHxHEIGHT in points
+ * @returns {{width: number, height: number}|null} Size in points, or null if it can't be read
+ */
+function parsePageSize(size) {
+  const name = Object.keys(PageSizes).find(key => key.toLowerCase() === String(size).toLowerCase());
+  if (name) {
+    const [width, height] = PageSizes[name];
+    return { width, height };
+  }
+  const match = /^(\d+(?:\.\d+)?)x(\d+(?:\.\d+)?)$/i.exec(String(size));
+  if (!match || Number(match[1]) <= 0 || Number(match[2]) <= 0) {
+    return null;
+  }
+  return { width: Number(match[1]), height: Number(match[2]) };
+}
+
+/**
+ * Moves the corners of an annotation rectangle or quadrilateral
+ *
+ * @param {PDFContext} context Document context
+ * @param {PDFDict} annot Annotation
+ * @param {string} key Rect or QuadPoints
+ * @param {Function} transform Maps [x, y] to the new coordinates
+ */
+function transformPoints(context, annot, key, transform) {
+  const points = annot.lookupMaybe(PDFName.of(key), PDFArray);
+  if (!points) {
+    return;
+  }
+  const values = points.asArray().map(value => (value instanceof PDFNumber ? value.asNumber() : 0));
+  const transformed = [];
+  for (let i = 0; i + 1 < values.length; i += 2) {
+    transformed.push(...transform([values[i], values[i + 1]]));
+  }
+  annot.set(PDFName.of(key), context.obj(transformed));
+}
+
+/**
+ * Puts every page onto a page of the given size, in the orientation of the page
+ *
+ * @param {PDFDocument} pdf Document to update
+ * @param {Object} pageSize New page size
+ * @param {number} pageSize.width Width of the paper in points (in portrait orientation)
+ * @param {number} pageSize.height Height of the paper in points
+ * @param {string} pageSize.fit How the content is scaled (see PAGE_FITS)
+ */
+function normalizePageSizes(pdf, pageSize) {
+  const context = pdf.context;
+  const shortSide = Math.min(pageSize.width, pageSize.height);
+  const longSide = Math.max(pageSize.width, pageSize.height);
+
+  for (const page of pdf.getPages()) {
+    // The page's own coordinates are used throughout, so rotated pages keep their rotation
+    const box = page.getCropBox();
+    const [width, height] = box.width > box.height ? [longSide, shortSide] : [shortSide, longSide];
+    const scale = {
+      fit: Math.min(width / box.width, height / box.height),
+      fill: Math.max(width / box.width, height / box.height),
+      center: 1
+    }[pageSize.fit];
+    const offsetX = (width - box.width * scale) / 2 - box.x * scale;
+    const offsetY = (height - box.height * scale) / 2 - box.y * scale;
+
+    // Fixed notation, as PDF numbers have no exponents
+    const matrix = [scale, 0, 0, scale, offsetX, offsetY].map(value => value.toFixed(4)).join(' ');
+    const start = context.register(context.stream(`q ${matrix} cm\n`));
+    const end = context.register(context.stream('\nQ'));
+    page.node.wrapContentStreams(start, end);
+    page.setMediaBox(0, 0, width, height);
+    for (const name of OTHER_BOXES) {
+      page.node.delete(PDFName.of(name));
+    }
+
+    const transform = ([x, y]) => [x * scale + offsetX, y * scale + offsetY];
+    const annots = page.node.Annots();
+    for (let i = 0; annots && i < annots.size(); i++) {
+      const annot = annots.lookupMaybe(i, PDFDict);
+      if (annot) {
+        transformPoints(context, annot, 'Rect', transform);
+        transformPoints(context, annot, 'QuadPoints', transform);
+      }
+    }
+  }
+}
+
+module.exports = {
+  PAGE_FITS,
+  DEFAULT_PAGE_FIT,
+  parsePageSize,
+  normalizePageSizes
+};
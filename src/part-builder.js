@@ -0,0 +1,141 @@
+// Builds the bytes of a single output part from an already loaded source PDF.
+// Used both in-process and from worker threads (see src/part-worker.js).
+
+const { applyMetadata, applyTimestamp } = require('./metadata');
+const { writeOutline } = require('./outline');
+const { addTocPages } = require('./toc');
+const { flattenPdf } = require('./flatten');
+const { writeLinks } = require('./links');
+const { writeNamedDestinations } = require('./destinations');
+const { writePageLabels } = require('./page-labels');
+const { writeAttachments } = require('./attachments');
+const { writePdfaIdentification } = require('./pdfa');
+const { writePdfVersion } = require('./version');
+const { writeDocumentId } = require('./document-id');
+const { stampBatesNumbers } = require('./bates');
+const { stampWatermark } = require('./watermark');
+const { stampHeaderFooter } = require('./header-footer');
+const { addCoverPage } = require('./cover');
+const { imposeNUp, imposeBooklet } = require('./imposition');
+const { normalizePageSizes } = require('./page-size');
+const { pruneUnusedObjects } = require('./prune');
+
+// Exit code used when a split is cancelled, matching the shell convention for SIGINT
+const CANCELLED_EXIT_CODE = 130;
+
+/**
+ * Throws a cancellation error if the abort signal has fired
+ *
+ * @param {AbortSignal|undefined} signal Optional abort signal
+ */
+function throwIfCancelled(signal) {
+  if (signal && signal.aborted) {
+    const cancelError = new Error('Split cancelled');
+    cancelError.code = CANCELLED_EXIT_CODE;
+    throw cancelError;
+  }
+}
+
+/**
+ * Creates the PDF for one part: its intro pages followed by its content pages
+ *
+ * @param {Object} backend PDF backend (see src/backends)
+ * @param {Object} source Source opened by the backend
+ * @param {Object} partInfo Part description with 1-based intro and content page numbers, flatten flag and optional links, named destinations, metadata, outline, cover page, table of contents, page labels, attachments, PDF/A identification, PDF version, document ID, fixed timestamp, watermark, header and footer, Bates numbering, page size, imposition, resource pruning and optimization (see src/links.js, src/destinations.js, src/metadata.js, src/outline.js, src/cover.js, src/toc.js, src/page-labels.js, src/attachments.js, src/pdfa.js, src/version.js, src/document-id.js, src/watermark.js, src/header-footer.js, src/bates.js, src/page-size.js, src/imposition.js, src/prune.js and src/optimize.js)
+ * @param {Object} options Build options
+ * @param {AbortSignal} options.signal Optional signal cancelling the build between page copies or before saving
+ * @param {Function} options.onPageCopied Optional; called with the number of pages copied so far, for page progress
+ * @returns {Promise<Uint8Array>} Bytes of the saved part
+ */
+async function buildPart(backend, source, partInfo, options = {}) {
+  // Convert 1-based intro and content pages back to 0-based indices, in output order
+  const pageIndices = [];
+  for (const pageNumber of partInfo.pages.intro) {
+    pageIndices.push(pageNumber - 1);
+  }
+  for (const pageNumber of partInfo.pages.content) {
+    pageIndices.push(pageNumber - 1);
+  }
+
+  throwIfCancelled(options.signal);
+  const partPdf = await backend.copyPages(source, pageIndices, { onPageCopied: options.onPageCopied, signal: options.signal });
+  if (partInfo.links) {
+    writeLinks(partPdf, partInfo.links);
+  }
+  if (partInfo.destinations) {
+    writeNamedDestinations(partPdf, partInfo.destinations);
+  }
+  if (partInfo.flatten) {
+    flattenPdf(partPdf);
+  }
+  if (partInfo.metadata) {
+    applyMetadata(partPdf, partInfo.metadata);
+  }
+  if (partInfo.timestamp) {
+    applyTimestamp(partPdf, partInfo.timestamp);
+  }
+  if (partInfo.pdfa) {
+    writePdfaIdentification(partPdf, partInfo.pdfa);
+  }
+  if (partInfo.outline) {
+    writeOutline(partPdf, partInfo.outline);
+  }
+  let tocPageCount = 0;
+  if (partInfo.toc) {
+    tocPageCount = await addTocPages(partPdf, partInfo.toc);
+  }
+  // The cover goes in front of the contents pages
+  let coverPageCount = 0;
+  if (partInfo.cover) {
+    coverPageCount = await addCoverPage(partPdf, partInfo.cover);
+  }
+  if (partInfo.pageLabels) {
+    writePageLabels(partPdf, partInfo.pageLabels, tocPageCount, coverPageCount);
+  }
+  // Stamps are placed on the pages at their new size
+  if (partInfo.pageSize) {
+    normalizePageSizes(partPdf, partInfo.pageSize);
+  }
+  // Bates numbers are drawn last, so watermarks don't cover them
+  if (partInfo.watermark) {
+    await stampWatermark(partPdf, partInfo.watermark);
+  }
+  if (partInfo.headerFooter) {
+    await stampHeaderFooter(partPdf, partInfo.headerFooter);
+  }
+  if (partInfo.bates) {
+    await stampBatesNumbers(partPdf, partInfo.bates);
+  }
+  // Sheets show the pages with everything stamped onto them
+  if (partInfo.nUp) {
+    await imposeNUp(partPdf, partInfo.nUp);
+  } else if (partInfo.booklet) {
+    await imposeBooklet(partPdf);
+  }
+  if (partInfo.attachments) {
+    await writeAttachments(partPdf, partInfo.attachments, partInfo.timestamp);
+  }
+  // Unused images are removed before optimizing, so they aren't downsampled for nothing
+  if (partInfo.pruneResources) {
+    await pruneUnusedObjects(partPdf);
+  }
+  // Loaded only for parts that are optimized: it needs zlib and crypto, which
+  // the browser build (src/browser.js) must do without
+  if (partInfo.optimize) {
+    require('./optimize').optimizePdf(partPdf, partInfo.optimize);
+  }
+
+  if (partInfo.documentId) {
+    writeDocumentId(partPdf, partInfo.documentId);
+  }
+  const saveOptions = partInfo.pdfVersion ? writePdfVersion(partPdf, partInfo.pdfVersion) : {};
+
+  throwIfCancelled(options.signal);
+  return backend.save(partPdf, saveOptions);
+}
+
+module.exports = {
+  CANCELLED_EXIT_CODE,
+  throwIfCancelled,
+  buildPart
+};
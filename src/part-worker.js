@@ -0,0 +1,32 @@
+// Worker thread entry point building parts in parallel.
+// The source bytes (or, for backends that support it, the already opened
+// source) are shared with every worker; each worker opens the source once and
+// then builds whichever parts the main thread sends it.
+
+const { parentPort, workerData } = require('worker_threads');
+const { getBackend } = require('./backends');
+const { buildPart } = require('./part-builder');
+
+const backend = getBackend(workerData.backendName);
+const sourceOpening = workerData.sharedSource
+  ? Promise.resolve(backend.fromShared(workerData.sharedSource))
+  : backend.open(new Uint8Array(workerData.sourceBytes));
+// Open failures are reported for each requested part instead of crashing the worker
+sourceOpening.catch(() => {});
+
+parentPort.on('message', async (partInfo) => {
+  try {
+    const source = await sourceOpening;
+    const buildStart = Date.now();
+    // Page progress messages carry no part bytes, which tells them apart from replies
+    const bytes = await buildPart(backend, source, partInfo, {
+      onPageCopied: pagesCopied => parentPort.postMessage({ pageProgress: { part: partInfo.index, pagesCopied } })
+    });
+    parentPort.postMessage({ partInfo, bytes, buildMs: Date.now() - buildStart });
+  } catch (error) {
+    parentPort.postMessage({
+      partInfo,
+      error: { message: error.message, code: error.code }
+    });
+  }
+});
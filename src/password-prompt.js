@@ -0,0 +1,49 @@
+// Reads a password from the terminal without echoing it.
+
+const { CANCELLED_EXIT_CODE } = require('./part-builder');
+
+/**
+ * Asks for a password on stderr and reads it from stdin in raw mode
+ *
+ * @param {string} promptText Text shown before the input
+ * @returns {Promise<string>} Entered password
+ */
+function promptPassword(promptText) {
+  const input = process.stdin;
+  process.stderr.write(promptText);
+
+  return new Promise((resolve) => {
+    let password = '';
+    input.setRawMode(true);
+    input.resume();
+    input.setEncoding('utf8');
+
+    const onData = (text) => {
+      for (const char of text) {
+        if (char === '\r' || char === '\n') {
+          input.off('data', onData);
+          input.setRawMode(false);
+          input.pause();
+          process.stderr.write('\n');
+          resolve(password);
+          return;
+        }
+        if (char === '\u0003') {
+          // Ctrl+C: raw mode swallows SIGINT, so exit like it would
+          process.stderr.write('\n');
+          process.exit(CANCELLED_EXIT_CODE);
+        }
+        if (char === '\u007f' || char === '\b') {
+          password = password.slice(0, -1);
+        } else {
+          password += char;
+        }
+      }
+    };
+    input.on('data', onData);
+  });
+}
+
+module.exports = {
+  promptPassword
+};
@@ -0,0 +1,131 @@
+// PDF/A identification. A PDF/A file declares its conformance in the XMP
+// metadata of the catalog and describes its colors with output intents.
+// Parts are not checked for conformance, so by default they drop the claim;
+// on request they keep the source's XMP metadata and output intents.
+
+const { PDFArray, PDFDict, PDFHexString, PDFName, PDFNumber, PDFRawStream, PDFString, decodePDFRawStream } = require('pdf-lib');
+
+// Text entries of an output intent dictionary
+const OUTPUT_INTENT_STRINGS = ['OutputCondition', 'OutputConditionIdentifier', 'RegistryName', 'Info'];
+
+/**
+ * Decodes a stream of the source, or returns null if its filters are not supported
+ *
+ * @param {*} stream PDF object
+ * @returns {Uint8Array|null} Decoded data
+ */
+function decodeStream(stream) {
+  if (!(stream instanceof PDFRawStream)) {
+    return null;
+  }
+  try {
+    return decodePDFRawStream(stream).decode();
+  } catch (error) {
+    return null;
+  }
+}
+
+/**
+ * Reads a property of the pdfaid schema, written as an attribute or an element
+ *
+ * @param {string} xmp XMP packet
+ * @param {string} property Property name, e.g. part
+ * @returns {string|null} Property value
+ */
+function readPdfaidProperty(xmp, property) {
+  const match = new RegExp(`pdfaid:${property}\\s*=\\s*["']([^"']*)["']|<pdfaid:${property}>([^<]*)</pdfaid:${property}>`).exec(xmp);
+  return match ? (match[1] || match[2]).trim() : null;
+}
+
+/**
+ * Reads the PDF/A identification of a document
+ *
+ * @param {PDFDocument} pdf Loaded document
+ * @returns {Object|null} Part, conformance level, XMP packet and output intents, or null if the document claims no PDF/A conformance
+ */
+function readPdfaIdentification(pdf) {
+  const xmp = decodeStream(pdf.catalog.lookup(PDFName.of('Metadata')));
+  const part = xmp && readPdfaidProperty(Buffer.from(xmp).toString('utf8'), 'part');
+  if (!part) {
+    return null;
+  }
+
+  const outputIntents = [];
+  const intents = pdf.catalog.lookupMaybe(PDFName.of('OutputIntents'), PDFArray);
+  for (let i = 0; intents && i < intents.size(); i++) {
+    const intent = intents.lookupMaybe(i, PDFDict);
+    const subtype = intent && intent.lookup(PDFName.of('S'));
+    if (!(subtype instanceof PDFName)) {
+      continue;
+    }
+    const outputIntent = { subtype: subtype.decodeText(), strings: {}, profile: null };
+    for (const key of OUTPUT_INTENT_STRINGS) {
+      const value = intent.lookup(PDFName.of(key));
+      if (value instanceof PDFString || value instanceof PDFHexString) {
+        outputIntent.strings[key] = value.decodeText();
+      }
+    }
+    const profile = intent.lookup(PDFName.of('DestOutputProfile'));
+    const profileData = decodeStream(profile);
+    if (profileData) {
+      const components = profile.dict.lookup(PDFName.of('N'));
+      outputIntent.profile = { data: profileData, components: components instanceof PDFNumber ? components.asNumber() : 3 };
+    }
+    outputIntents.push(outputIntent);
+  }
+
+  const conformance = readPdfaidProperty(Buffer.from(xmp).toString('utf8'), 'conformance');
+  return { part, conformance: conformance ? conformance.toLowerCase() : '', xmp, outputIntents };
+}
+
+/**
+ * Describes what happened to the PDF/A claim, for the split warnings
+ *
+ * @param {Object|null} identification Identification read with readPdfaIdentification
+ * @param {boolean} keep True if the parts keep the identification
+ * @returns {Array<{code: string, message: string}>} Warnings, empty if the source claims no PDF/A conformance
+ */
+function pdfaWarnings(identification, keep) {
+  if (!identification) {
+    return [];
+  }
+  const level = `PDF/A-${identification.part}${identification.conformance}`;
+  return [keep
+    ? { code: 'pdfaUnverified', message: `The parts keep the ${level} identification of the source, but their conformance is not verified` }
+    : { code: 'pdfaDropped', message: `The PDF claims ${level} conformance; the parts do not keep the claim` }];
+}
+
+/**
+ * Writes the source's XMP metadata and output intents into a new document
+ *
+ * @param {PDFDocument} pdf Document to update
+ * @param {Object} identification Identification read with readPdfaIdentification
+ */
+function writePdfaIdentification(pdf, identification) {
+  const context = pdf.context;
+  const metadata = context.stream(identification.xmp, { Type: 'Metadata', Subtype: 'XML' });
+  pdf.catalog.set(PDFName.of('Metadata'), context.register(metadata));
+
+  if (identification.outputIntents.length === 0) {
+    return;
+  }
+  const intents = [];
+  for (const outputIntent of identification.outputIntents) {
+    const intent = context.obj({ Type: 'OutputIntent', S: outputIntent.subtype });
+    for (const [key, value] of Object.entries(outputIntent.strings)) {
+      intent.set(PDFName.of(key), PDFHexString.fromText(value));
+    }
+    if (outputIntent.profile) {
+      const profile = context.flateStream(outputIntent.profile.data, { N: outputIntent.profile.components });
+      intent.set(PDFName.of('DestOutputProfile'), context.register(profile));
+    }
+    intents.push(intent);
+  }
+  pdf.catalog.set(PDFName.of('OutputIntents'), context.obj(intents));
+}
+
+module.exports = {
+  readPdfaIdentification,
+  pdfaWarnings,
+  writePdfaIdentification
+};
@@ -0,0 +1,307 @@
+// Calculates which source pages go into each part and how the parts are named.
+// Pure computation with no file system access, so it can be reused anywhere.
+
+// Characters not allowed in file names on Windows (or, for / and \, anywhere), and control characters
+const RESERVED_FILE_NAME_CHARACTERS = /[<>:"/\\|?*\u0000-\u001f]/g;
+// Device names Windows reserves, with or without an extension
+const RESERVED_FILE_NAMES = /^(con|prn|aux|nul|com\d|lpt\d)(\..*)?$/i;
+
+// Most parts a split may write unless the caller allows more, so a mistaken
+// part count or a hostile request can't flood the file system
+const DEFAULT_MAX_PARTS = 1000;
+// Exit code for splits that would write more parts than allowed
+const TOO_MANY_PARTS_EXIT_CODE = 7;
+// What happens when there are fewer content pages than parts: fail, or write one part per page
+const TOO_FEW_PAGES_POLICIES = ['error', 'reduce'];
+const DEFAULT_TOO_FEW_PAGES_POLICY = 'error';
+
+/**
+ * @param {string} message Why the pages can't be divided as asked
+ * @returns {Error} Error with code 2
+ */
+function invalidPlan(message) {
+  const error = new Error(message);
+  error.code = 2; // Exit code 2 for invalid CLI arguments
+  return error;
+}
+
+/**
+ * Calculates the page ranges of each part
+ *
+ * @param {number} totalPages Number of pages in the source document
+ * @param {number} parts Number of parts to split into
+ * @param {Object|null} intro Intro page range (1-based, inclusive)
+ * @param {number} intro.start Start page of intro (1-based)
+ * @param {number} intro.end End page of intro (1-based)
+ * @param {string} [tooFewPages] With fewer content pages than parts, error (default) throws and reduce
+ *   writes one part per content page (see TOO_FEW_PAGES_POLICIES)
+ * @returns {Array<Object>} Parts with 1-based intro and content page numbers; every part has at least one content page
+ * @throws {Error} With code 2 if the parts or intro are invalid, or the pages can't be divided into the parts
+ */
+function calculateRanges(totalPages, parts, intro, tooFewPages = DEFAULT_TOO_FEW_PAGES_POLICY) {
+  if (!Number.isInteger(parts) || parts < 1) {
+    throw invalidPlan(`Number of parts must be a positive integer, not ${parts}.`);
+  }
+
+  // Calculate intro pages
+  const introCount = intro
+    ? (intro.end - intro.start + 1)
+    : 0;
+
+  // Setup intro pages array for copying later
+  let introPages = [];
+  if (introCount > 0) {
+    // Validate intro range
+    if (intro.start < 1 || intro.end > totalPages) {
+      throw invalidPlan(`Invalid intro range: ${intro.start}:${intro.end}. Valid range is 1:${totalPages}`);
+    }
+
+    // Convert from 1-based to 0-based indexing
+    introPages = Array.from(
+      { length: introCount },
+      (_, i) => i + intro.start - 1
+    );
+  }
+
+  // Calculate main content division
+  const remainingPages = totalPages - introCount;
+
+  // Every part needs a content page; with too few, fail or write fewer parts as the policy says
+  if (remainingPages === 0) {
+    throw invalidPlan(`Cannot split: the intro covers all ${totalPages} pages, leaving no content pages for the parts.`);
+  }
+  if (remainingPages < parts) {
+    if (tooFewPages !== 'reduce') {
+      throw invalidPlan(`Cannot split ${remainingPages} content pages into ${parts} parts. Use at most ${remainingPages} parts, or --too-few-pages reduce to write one part per page.`);
+    }
+    parts = remainingPages;
+  }
+
+  // Calculate base pages per part and remainder
+  const basePerPart = Math.floor(remainingPages / parts);
+  const remainder = remainingPages % parts;
+
+  // Calculate page ranges for each part
+  const partRanges = [];
+
+  // Track current page (0-based)
+  let currentNonIntroPage = introCount;
+
+  for (let i = 0; i < parts; i++) {
+    // Calculate pages for this part (add an extra page for the first 'remainder' parts)
+    const partPageCount = basePerPart + (i < remainder ? 1 : 0);
+
+    // Define page range for this part
+    const partPages = Array.from(
+      { length: partPageCount },
+      (_, j) => currentNonIntroPage + j
+    );
+
+    // Update current page tracker
+    currentNonIntroPage += partPageCount;
+
+    partRanges.push({
+      index: i + 1,
+      pages: {
+        // Convert to 1-based for output/reporting
+        intro: introPages.map(p => p + 1),
+        content: partPages.map(p => p + 1)
+      }
+    });
+  }
+
+  return partRanges;
+}
+
+/**
+ * Builds the parts for explicitly chosen content page ranges, e.g. from the
+ * interactive planner, instead of dividing the pages equally
+ *
+ * @param {number} totalPages Number of pages in the source document
+ * @param {Array<Object>} contentRanges Content page range of each part (1-based, inclusive)
+ * @param {Object|null} intro Intro page range (1-based, inclusive)
+ * @returns {Array<Object>} Parts with 1-based intro and content page numbers
+ */
+function rangesFromContentRanges(totalPages, contentRanges, intro) {
+  const introPages = [];
+  if (intro) {
+    if (intro.start < 1 || intro.end > totalPages) {
+      throw invalidPlan(`Invalid intro range: ${intro.start}:${intro.end}. Valid range is 1:${totalPages}`);
+    }
+    for (let page = intro.start; page <= intro.end; page++) {
+      introPages.push(page);
+    }
+  }
+
+  const partRanges = [];
+  for (const [i, range] of contentRanges.entries()) {
+    if (!Number.isInteger(range.start) || !Number.isInteger(range.end) || range.start < 1 || range.end > totalPages || range.end < range.start) {
+      throw invalidPlan(`Invalid page range for part ${i + 1}: ${range.start}:${range.end}. Valid range is 1:${totalPages}`);
+    }
+    const contentPages = [];
+    for (let page = range.start; page <= range.end; page++) {
+      contentPages.push(page);
+    }
+    partRanges.push({
+      index: i + 1,
+      pages: {
+        intro: [...introPages],
+        content: contentPages
+      }
+    });
+  }
+  return partRanges;
+}
+
+/**
+ * Parses a list of pages such as 1-3,7,10- (1-based; an open range runs to the last page)
+ *
+ * @param {string} text Comma-separated page numbers and ranges
+ * @param {number} totalPages Number of pages in the source document
+ * @returns {Array<number>} 1-based page numbers in the order given
+ */
+function parsePageList(text, totalPages) {
+  const invalidPageList = (detail) => {
+    const error = new Error(`Invalid page list ${text}: ${detail}. Use e.g. 1-3,7,10- with pages between 1 and ${totalPages}.`);
+    error.code = 2; // Exit code 2 for invalid CLI arguments
+    return error;
+  };
+
+  const pageNumbers = [];
+  for (const item of String(text).split(',')) {
+    const match = /^\s*(\d+)\s*(?:(-)\s*(\d*)\s*)?$/.exec(item);
+    if (!match) {
+      throw invalidPageList(`cannot read "${item}"`);
+    }
+    const start = Number(match[1]);
+    let end = start;
+    if (match[2]) {
+      end = match[3] === '' ? totalPages : Number(match[3]);
+    }
+    if (start < 1 || end > totalPages || end < start) {
+      throw invalidPageList(`"${item.trim()}" is out of range`);
+    }
+    for (let page = start; page <= end; page++) {
+      pageNumbers.push(page);
+    }
+  }
+  return pageNumbers;
+}
+
+/**
+ * Leaves pages out of planned parts, which keep the source page numbers of
+ * the remaining pages. A part that would lose all its pages keeps them, so
+ * that no part is empty.
+ *
+ * @param {Array<Object>} partInfos Planned parts, updated in place; the pages left out of each are added to its droppedPages
+ * @param {Array<number>} pageNumbers 1-based numbers of the pages to leave out
+ * @returns {Array<number>} 1-based numbers of the pages left out of at least one part, in ascending order
+ */
+function dropPages(partInfos, pageNumbers) {
+  const toDrop = new Set(pageNumbers);
+  const dropped = new Set();
+  for (const partInfo of partInfos) {
+    const intro = partInfo.pages.intro.filter(pageNumber => !toDrop.has(pageNumber));
+    const content = partInfo.pages.content.filter(pageNumber => !toDrop.has(pageNumber));
+    partInfo.droppedPages = partInfo.droppedPages || [];
+    if (intro.length + content.length === 0) {
+      continue;
+    }
+    for (const pageNumber of [...partInfo.pages.intro, ...partInfo.pages.content]) {
+      if (toDrop.has(pageNumber)) {
+        partInfo.droppedPages.push(pageNumber);
+        dropped.add(pageNumber);
+      }
+    }
+    partInfo.pages = { ...partInfo.pages, intro, content };
+  }
+  return [...dropped].sort((a, b) => a - b);
+}
+
+/**
+ * Describes page numbers as ranges
+ *
+ * @param {Array<number>} pageNumbers 1-based page numbers, in output order
+ * @param {string} [separator] Text between ranges
+ * @returns {string} Ranges such as "1-4, 9-12"
+ */
+function formatPageRanges(pageNumbers, separator = ', ') {
+  const ranges = [];
+  for (const pageNumber of pageNumbers) {
+    const last = ranges[ranges.length - 1];
+    if (last && pageNumber === last.end + 1) {
+      last.end = pageNumber;
+    } else {
+      ranges.push({ start: pageNumber, end: pageNumber });
+    }
+  }
+  return ranges.map(range => (range.start === range.end ? `${range.start}` : `${range.start}-${range.end}`)).join(separator);
+}
+
+// Output file name used when no template is given
+const DEFAULT_NAME_TEMPLATE = '{basename}_part{index}.pdf';
+
+/**
+ * Fills in an output file name template
+ *
+ * @param {string} template Template with {basename}, {index}, {total} and {group} placeholders
+ * @param {Object} values Placeholder values
+ * @param {string} values.basename Base name of the output files
+ * @param {number} values.index Part number (1-based)
+ * @param {number} values.total Number of parts
+ * @param {string} [values.group] Group of the part's pages when parts are grouped by a page property
+ * @returns {string} Output file name
+ */
+function formatPartName(template, values) {
+  return template
+    .replace(/\{basename\}/g, values.basename)
+    .replace(/\{index\}/g, String(values.index))
+    .replace(/\{total\}/g, String(values.total))
+    .replace(/\{group\}/g, values.group || '');
+}
+
+/**
+ * Makes a name safe to use as a file name on every platform: reserved and
+ * control characters become underscores, trailing dots and spaces (dropped by
+ * Windows) are removed, and reserved device names get an underscore in front
+ *
+ * @param {string} name File name without directories
+ * @returns {string} Safe file name, an underscore if nothing remains
+ */
+function sanitizeFileName(name) {
+  const sanitized = String(name).replace(RESERVED_FILE_NAME_CHARACTERS, '_').replace(/[. ]+$/, '');
+  if (sanitized === '') {
+    return '_';
+  }
+  return RESERVED_FILE_NAMES.test(sanitized) ? `_${sanitized}` : sanitized;
+}
+
+/**
+ * Refuses to write more parts than allowed
+ *
+ * @param {number} partCount Number of planned parts
+ * @param {number} maxParts Most parts allowed
+ */
+function checkPartCount(partCount, maxParts) {
+  if (partCount > maxParts) {
+    const error = new Error(`The split would write ${partCount} parts, more than the maximum of ${maxParts}. Raise the maximum with --max-parts if this is intended.`);
+    error.code = TOO_MANY_PARTS_EXIT_CODE;
+    throw error;
+  }
+}
+
+module.exports = {
+  calculateRanges,
+  rangesFromContentRanges,
+  parsePageList,
+  formatPartName,
+  dropPages,
+  formatPageRanges,
+  checkPartCount,
+  TOO_FEW_PAGES_POLICIES,
+  DEFAULT_TOO_FEW_PAGES_POLICY,
+  sanitizeFileName,
+  DEFAULT_NAME_TEMPLATE,
+  DEFAULT_MAX_PARTS,
+  TOO_MANY_PARTS_EXIT_CODE
+};
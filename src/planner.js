@@ -0,0 +1,259 @@
+// Interactive terminal planner for choosing where each part starts before splitting.
+// Draws with plain ANSI escape sequences and reads keys through readline, so it
+// needs no dependencies beyond Node.js.
+
+const readline = require('readline');
+
+const HELP_LINE = '↑/↓ select part  ←/→ move start page (PgUp/PgDn by 10)  + split part  - merge with previous  Enter split  q quit';
+const OUTLINE_HELP_LINE = '↑/↓ select bookmark (PgUp/PgDn by 10)  Enter start a part at the bookmark  Tab back to parts  q quit';
+
+/**
+ * Formats a byte count for display
+ *
+ * @param {number} bytes Number of bytes
+ * @returns {string} Size such as 1.4 MB
+ */
+function formatSize(bytes) {
+  if (bytes < 1024) return `${bytes} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  return `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+}
+
+/**
+ * Moves the start page of a part, taking the pages from or giving them to the previous part
+ *
+ * @param {Array<Object>} ranges Content page ranges, updated in place
+ * @param {number} index Index of the part whose start moves (must be > 0)
+ * @param {number} delta Number of pages to move by (negative moves towards the start)
+ */
+function moveStart(ranges, index, delta) {
+  const previous = ranges[index - 1];
+  const current = ranges[index];
+  // Both parts keep at least one page
+  const start = Math.min(Math.max(current.start + delta, previous.start + 1), current.end);
+  previous.end = start - 1;
+  current.start = start;
+}
+
+/**
+ * Flattens a bookmark tree into the bookmarks that open a page, in outline order
+ *
+ * @param {Array<Object>} bookmarks Bookmarks as returned by getPdfOutline in src/info.js
+ * @returns {Array<Object>} Bookmarks with title, level and page
+ */
+function flattenOutline(bookmarks) {
+  const entries = [];
+  for (const bookmark of bookmarks) {
+    if (bookmark.page !== null) {
+      entries.push({ title: bookmark.title, level: bookmark.level, page: bookmark.page });
+    }
+    entries.push(...flattenOutline(bookmark.children));
+  }
+  return entries;
+}
+
+/**
+ * Starts a new part at a page by splitting the part containing it
+ *
+ * @param {Array<Object>} ranges Content page ranges, updated in place
+ * @param {number} page 1-based page the new part starts at
+ * @returns {string|null} Why no part could be started there, or null if one was
+ */
+function splitAt(ranges, page) {
+  const index = ranges.findIndex(range => range.start <= page && page <= range.end);
+  if (index === -1) {
+    return `Page ${page} is not a content page.`;
+  }
+  if (ranges[index].start === page) {
+    return `Part ${index + 1} already starts at page ${page}.`;
+  }
+  ranges.splice(index + 1, 0, { start: page, end: ranges[index].end });
+  ranges[index].end = page - 1;
+  return null;
+}
+
+/**
+ * @param {number} selected Index of the selected row
+ * @param {number} count Number of rows
+ * @param {number} visibleRows Number of rows that fit on screen
+ * @returns {number} Index of the first row to show so the selected one is in view
+ */
+function firstVisibleRow(selected, count, visibleRows) {
+  return Math.min(Math.max(selected - Math.floor(visibleRows / 2), 0), Math.max(count - visibleRows, 0));
+}
+
+/**
+ * Renders the planner screen
+ *
+ * @param {Object} state Planner state
+ * @returns {string} Screen contents
+ */
+function render(state) {
+  const lines = [];
+  const intro = state.intro ? `, intro pages ${state.intro.start}-${state.intro.end}` : '';
+  lines.push(`splitpdf planner: ${state.fileName} (${state.totalPages} pages${intro})`);
+  lines.push('');
+
+  // Show a window of parts around the selection when they don't fit on screen,
+  // sharing the screen with the outline pane when the PDF has bookmarks
+  const availableRows = state.outline.length > 0 ? Math.floor((state.rows - 8) / 2) : state.rows - 6;
+  const visibleRows = Math.max(availableRows, 3);
+  const first = firstVisibleRow(state.selected, state.ranges.length, visibleRows);
+  const introCount = state.intro ? state.intro.end - state.intro.start + 1 : 0;
+  for (let i = first; i < Math.min(first + visibleRows, state.ranges.length); i++) {
+    const range = state.ranges[i];
+    const pageCount = range.end - range.start + 1;
+    // Estimated from the share of the source pages each part contains
+    const estimatedSize = Math.round(state.fileSize * (pageCount + introCount) / state.totalPages);
+    const marker = i === state.selected && state.focus === 'parts' ? '>' : ' ';
+    lines.push(`${marker} Part ${String(i + 1).padStart(3)}  pages ${`${range.start}-${range.end}`.padEnd(11)} ${String(pageCount).padStart(5)} pages  ~${formatSize(estimatedSize)}`);
+  }
+
+  if (state.outline.length > 0) {
+    lines.push('');
+    lines.push(state.focus === 'outline' ? 'Outline (Tab to switch panes, * marks bookmarks where a part starts):' : 'Outline (Tab to pick split points from bookmarks):');
+    const partStarts = new Set(state.ranges.map(range => range.start));
+    const outlineRows = Math.max(state.rows - 8 - visibleRows, 3);
+    const firstBookmark = firstVisibleRow(state.outlineSelected, state.outline.length, outlineRows);
+    for (let i = firstBookmark; i < Math.min(firstBookmark + outlineRows, state.outline.length); i++) {
+      const bookmark = state.outline[i];
+      const marker = i === state.outlineSelected && state.focus === 'outline' ? '>' : ' ';
+      const start = partStarts.has(bookmark.page) ? '*' : ' ';
+      lines.push(`${marker}${start}${'  '.repeat(bookmark.level - 1)}${bookmark.title} (page ${bookmark.page})`);
+    }
+  }
+
+  lines.push('');
+  lines.push(state.message || (state.focus === 'outline' ? OUTLINE_HELP_LINE : HELP_LINE));
+  return lines.join('\n');
+}
+
+/**
+ * Lets the user adjust the content page range of each part in the terminal
+ *
+ * @param {Object} options Planner options
+ * @param {string} options.fileName Name of the source PDF, for display
+ * @param {number} options.totalPages Number of pages in the source PDF
+ * @param {number} options.fileSize Size of the source PDF in bytes, for size estimates
+ * @param {Object|null} options.intro Intro page range (1-based, inclusive)
+ * @param {Array<Object>} options.ranges Initial content page range of each part (1-based, inclusive)
+ * @param {Array<Object>} [options.outline] Bookmarks of the source PDF as returned by getPdfOutline in
+ *   src/info.js, shown in a pane to start parts at
+ * @param {tty.ReadStream} options.input Terminal input
+ * @param {tty.WriteStream} options.output Terminal output
+ * @returns {Promise<Array<Object>|null>} Chosen content page ranges, or null if the user quit
+ */
+function runPlanner(options) {
+  const { input, output } = options;
+  const state = {
+    fileName: options.fileName,
+    totalPages: options.totalPages,
+    fileSize: options.fileSize,
+    intro: options.intro,
+    ranges: options.ranges.map(range => ({ ...range })),
+    selected: 0,
+    outline: flattenOutline(options.outline || []),
+    outlineSelected: 0,
+    // Pane the arrow keys and Enter act on: parts or outline
+    focus: 'parts',
+    rows: output.rows || 24,
+    message: null
+  };
+
+  const draw = () => {
+    // Clear the screen and move the cursor to the top left corner
+    output.write(`\x1b[2J\x1b[H${render(state)}\n`);
+  };
+
+  return new Promise((resolve) => {
+    readline.emitKeypressEvents(input);
+    input.setRawMode(true);
+    input.resume();
+
+    const finish = (result) => {
+      input.off('keypress', onKeypress);
+      input.setRawMode(false);
+      input.pause();
+      output.write('\x1b[2J\x1b[H');
+      resolve(result);
+    };
+
+    function onKeypress(text, key = {}) {
+      state.message = null;
+      const ranges = state.ranges;
+      const selected = ranges[state.selected];
+
+      if (key.name === 'q' || key.name === 'escape' || (key.ctrl && key.name === 'c')) {
+        finish(null);
+        return;
+      }
+      if (key.name === 'tab') {
+        if (state.outline.length === 0) {
+          state.message = 'The PDF has no bookmarks.';
+        } else {
+          state.focus = state.focus === 'parts' ? 'outline' : 'parts';
+        }
+        draw();
+        return;
+      }
+      if (state.focus === 'outline') {
+        if (key.name === 'return') {
+          const page = state.outline[state.outlineSelected].page;
+          state.message = splitAt(ranges, page);
+          if (!state.message) {
+            state.selected = ranges.findIndex(range => range.start === page);
+          }
+        } else if (['up', 'down', 'pageup', 'pagedown'].includes(key.name)) {
+          const deltas = { up: -1, down: 1, pageup: -10, pagedown: 10 };
+          state.outlineSelected = Math.min(Math.max(state.outlineSelected + deltas[key.name], 0), state.outline.length - 1);
+        }
+        draw();
+        return;
+      }
+      if (key.name === 'return') {
+        finish(ranges);
+        return;
+      }
+
+      if (key.name === 'up') {
+        state.selected = Math.max(state.selected - 1, 0);
+      } else if (key.name === 'down') {
+        state.selected = Math.min(state.selected + 1, ranges.length - 1);
+      } else if (['left', 'right', 'pageup', 'pagedown'].includes(key.name)) {
+        if (state.selected === 0) {
+          state.message = 'The first part always starts at the first content page.';
+        } else {
+          const deltas = { left: -1, right: 1, pageup: -10, pagedown: 10 };
+          moveStart(ranges, state.selected, deltas[key.name]);
+        }
+      } else if (text === '+') {
+        if (selected.end === selected.start) {
+          state.message = 'A part with one page cannot be split.';
+        } else {
+          const middle = Math.floor((selected.start + selected.end) / 2);
+          ranges.splice(state.selected + 1, 0, { start: middle + 1, end: selected.end });
+          selected.end = middle;
+        }
+      } else if (text === '-') {
+        if (ranges.length === 1) {
+          state.message = 'There is only one part.';
+        } else {
+          // Merge into the previous part, or into the next one for the first part
+          const target = state.selected === 0 ? 1 : state.selected - 1;
+          ranges[target].start = Math.min(ranges[target].start, selected.start);
+          ranges[target].end = Math.max(ranges[target].end, selected.end);
+          ranges.splice(state.selected, 1);
+          state.selected = state.selected === 0 ? 0 : target;
+        }
+      }
+      draw();
+    }
+
+    input.on('keypress', onKeypress);
+    draw();
+  });
+}
+
+module.exports = {
+  runPlanner
+};
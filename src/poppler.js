@@ -0,0 +1,67 @@
+// Runs the command-line tools of poppler (https://poppler.freedesktop.org),
+// such as pdftoppm for rendering pages to images. The tools are looked up on
+// PATH unless SPLITPDF_POPPLER points at the directory holding them.
+
+const os = require('os');
+const path = require('path');
+const fs = require('fs/promises');
+const { execFile } = require('child_process');
+
+/**
+ * @param {string} tool Tool name, e.g. pdftoppm
+ * @returns {string} Path or name of the tool's executable
+ */
+function getPopplerPath(tool) {
+  return process.env.SPLITPDF_POPPLER ? path.join(process.env.SPLITPDF_POPPLER, tool) : tool;
+}
+
+/**
+ * Runs a poppler tool with the given arguments
+ *
+ * @param {string} tool Tool name, e.g. pdftoppm
+ * @param {Array<string>} args Command-line arguments
+ * @returns {Promise<{stdout: string, stderr: string}>} Output of a successful run
+ */
+function runPoppler(tool, args) {
+  return new Promise((resolve, reject) => {
+    execFile(getPopplerPath(tool), args, { maxBuffer: 64 * 1024 * 1024 }, (error, stdout, stderr) => {
+      if (error && error.code === 'ENOENT') {
+        const notFoundError = new Error(`${tool} not found at ${getPopplerPath(tool)}. Install poppler (poppler-utils) or set SPLITPDF_POPPLER to the directory holding its tools.`);
+        notFoundError.code = 2; // Exit code 2: the requested option can't be used
+        reject(notFoundError);
+        return;
+      }
+      if (error) {
+        const popplerError = new Error(`${tool} failed: ${stderr.trim() || error.message}`);
+        popplerError.popplerExitCode = error.code;
+        reject(popplerError);
+        return;
+      }
+      resolve({ stdout, stderr });
+    });
+  });
+}
+
+/**
+ * Writes a PDF to a temporary file for the duration of a callback, as the tools read their input from files
+ *
+ * @param {Uint8Array} pdfBytes Bytes of an unencrypted PDF
+ * @param {Function} callback Receives the path of the temporary file
+ * @returns {Promise<*>} Result of the callback
+ */
+async function withTemporaryPdf(pdfBytes, callback) {
+  const tempDir = await fs.mkdtemp(path.join(os.tmpdir(), 'splitpdf-poppler-'));
+  try {
+    const pdfPath = path.join(tempDir, 'part.pdf');
+    await fs.writeFile(pdfPath, pdfBytes);
+    return await callback(pdfPath);
+  } finally {
+    await fs.rm(tempDir, { recursive: true, force: true });
+  }
+}
+
+module.exports = {
+  getPopplerPath,
+  runPoppler,
+  withTemporaryPdf
+};
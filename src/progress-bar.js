@@ -0,0 +1,77 @@
+// Progress bar sink for people watching a split in a terminal.
+// Completed parts are listed above a bar for each part being built, driven by
+// partProgress events, and an overall bar; the bars are redrawn in place.
+
+const path = require('path');
+
+const BAR_WIDTH = 30;
+
+/**
+ * @param {number} fraction Completed fraction between 0 and 1
+ * @returns {string} Bar such as [#########.....]
+ */
+function drawBar(fraction) {
+  const filled = Math.round(Math.min(Math.max(fraction, 0), 1) * BAR_WIDTH);
+  return `[${'#'.repeat(filled)}${'.'.repeat(BAR_WIDTH - filled)}]`;
+}
+
+/**
+ * Creates a sink drawing split progress on a terminal stream
+ *
+ * @param {tty.WriteStream} stream Terminal stream to draw on, usually stderr
+ * @returns {{emit: Function}} Event sink
+ */
+function createProgressBarSink(stream) {
+  // Bars of the parts being built, by file and part
+  const partLines = new Map();
+  let barLine = '';
+  // Number of lines the bars take up, the cursor being at the end of the last one
+  let drawnLineCount = 0;
+
+  // Replace the bars with a completed line, if any, and the current bars
+  const redraw = (completedLine) => {
+    let output = drawnLineCount > 1 ? `\x1b[${drawnLineCount - 1}A` : '';
+    output += '\r\x1b[0J';
+    if (completedLine) {
+      output += `${completedLine}\n`;
+    }
+    const lines = [...partLines.values()];
+    if (barLine) {
+      lines.push(barLine);
+    }
+    output += lines.join('\n');
+    drawnLineCount = lines.length;
+    stream.write(output);
+  };
+
+  return {
+    emit(event) {
+      const file = event.file ? `${path.basename(event.file)}: ` : '';
+      const partKey = `${event.file || ''}\n${event.part}`;
+
+      if (event.event === 'partProgress') {
+        partLines.set(partKey, `${file}part ${event.part}/${event.totalParts} ${drawBar(event.pagesCopied / event.pages)} ${event.pagesCopied}/${event.pages} pages`);
+        redraw();
+      } else if (event.event === 'partComplete') {
+        partLines.delete(partKey);
+        redraw(`${file}part ${event.part}/${event.totalParts} ${drawBar(1)} ${event.outputPath}`);
+      } else if (event.event === 'partTimedOut') {
+        partLines.delete(partKey);
+        redraw(`${file}part ${event.part}/${event.totalParts} timed out`);
+      } else if (event.event === 'progress') {
+        const eta = event.etaMs > 0 ? `, ETA ${Math.ceil(event.etaMs / 1000)}s` : '';
+        barLine = `${file}${drawBar(event.percent / 100)} ${event.percent}% (${event.pagesWritten}/${event.totalPages} pages${eta})`;
+        redraw();
+      } else if (event.event === 'complete' || event.event === 'cancelled' || event.event === 'fileFailed') {
+        // Leave the terminal on a clean line for the summary
+        partLines.clear();
+        barLine = '';
+        redraw();
+      }
+    }
+  };
+}
+
+module.exports = {
+  createProgressBarSink
+};
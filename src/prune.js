@@ -0,0 +1,188 @@
+// Removing what a part doesn't use. Sources often share one resource
+// dictionary between all pages, listing every font and image of the document,
+// so copying a few pages would copy all of them. Resources that no content
+// stream of the part names are removed, and then every object that can no
+// longer be reached from the document catalog or info dictionary, e.g. the
+// pages replaced by imposition.
+
+const { PDFArray, PDFDict, PDFName, PDFRef, PDFStream } = require('pdf-lib');
+const { contentOperations, pageContents, streamContents } = require('./content-stream');
+
+// Resource categories whose entries content streams refer to by name
+const RESOURCE_CATEGORIES = ['Font', 'XObject', 'ExtGState', 'ColorSpace', 'Pattern', 'Shading', 'Properties'];
+// Appearance streams of annotations, for the normal, rollover and down states
+const APPEARANCE_KEYS = ['N', 'R', 'D'];
+
+/**
+ * Collects the resource names a content stream uses, following the forms it draws
+ *
+ * @param {PDFContext} context Document context
+ * @param {Uint8Array|null} contents Decoded content stream, or null if it can't be decoded
+ * @param {PDFDict|undefined} resources Resources of the content stream
+ * @param {Map<PDFDict, Set<string>|null>} usedNames Names used from each resource dictionary, added to;
+ *   null for dictionaries used by content that can't be read
+ * @param {Set<PDFStream>} visited Forms inspected so far, against loops
+ */
+function collectUsedNames(context, contents, resources, usedNames, visited) {
+  if (!resources) {
+    return;
+  }
+  if (!contents) {
+    usedNames.set(resources, null);
+    return;
+  }
+  if (!usedNames.has(resources)) {
+    usedNames.set(resources, new Set());
+  }
+  const names = usedNames.get(resources);
+  const xObjects = resources.lookupMaybe(PDFName.of('XObject'), PDFDict);
+  for (const { operator, operands } of contentOperations(contents)) {
+    // Any name may refer to a resource; keeping a few unused ones is harmless
+    for (const operand of operands) {
+      if (names && operand.name !== undefined) {
+        names.add(operand.name);
+      }
+    }
+    const name = operands.length > 0 ? operands[operands.length - 1].name : undefined;
+    if (operator !== 'Do' || !xObjects || name === undefined) {
+      continue;
+    }
+    const form = xObjects.lookup(PDFName.of(name));
+    if (form instanceof PDFStream && form.dict.lookup(PDFName.of('Subtype')) === PDFName.of('Form') && !visited.has(form)) {
+      visited.add(form);
+      // Forms without resources of their own use those of the content drawing them
+      const formResources = form.dict.lookupMaybe(PDFName.of('Resources'), PDFDict) || resources;
+      collectUsedNames(context, streamContents(form), formResources, usedNames, visited);
+    }
+  }
+}
+
+/**
+ * Collects the resource names used by the pages of a document and their annotations' appearances
+ *
+ * @param {PDFDocument} pdf Document
+ * @returns {Map<PDFDict, Set<string>|null>} Names used from each resource dictionary, null for those that must be kept
+ */
+function findUsedNames(pdf) {
+  const context = pdf.context;
+  const usedNames = new Map();
+  const visited = new Set();
+  for (const page of pdf.getPages()) {
+    collectUsedNames(context, pageContents(page), page.node.Resources(), usedNames, visited);
+
+    const annots = page.node.Annots();
+    for (let i = 0; annots && i < annots.size(); i++) {
+      const annot = annots.lookupMaybe(i, PDFDict);
+      const appearances = annot && annot.lookupMaybe(PDFName.of('AP'), PDFDict);
+      for (const key of APPEARANCE_KEYS) {
+        const appearance = appearances && appearances.lookup(PDFName.of(key));
+        // An appearance is a form, or a dictionary of forms by state (e.g. on and off for checkboxes)
+        const forms = appearance instanceof PDFDict
+          ? appearance.values().map(value => context.lookup(value))
+          : [appearance];
+        for (const form of forms) {
+          if (form instanceof PDFStream && !visited.has(form)) {
+            visited.add(form);
+            const formResources = form.dict.lookupMaybe(PDFName.of('Resources'), PDFDict);
+            collectUsedNames(context, streamContents(form), formResources, usedNames, visited);
+          }
+        }
+      }
+    }
+  }
+
+  // Default resources of form fields are used to draw field values later
+  const acroForm = pdf.catalog.lookupMaybe(PDFName.of('AcroForm'), PDFDict);
+  const defaultResources = acroForm && acroForm.lookupMaybe(PDFName.of('DR'), PDFDict);
+  if (defaultResources) {
+    usedNames.set(defaultResources, null);
+  }
+  return usedNames;
+}
+
+/**
+ * Removes the entries of resource dictionaries that no content uses. A
+ * category dictionary (e.g. the fonts) shared by several resource dictionaries
+ * keeps the names any of them uses.
+ *
+ * @param {PDFDocument} pdf Document to update
+ */
+function removeUnusedResources(pdf) {
+  const usedNames = findUsedNames(pdf);
+  const keptNames = new Map();
+  for (const [resources, names] of usedNames) {
+    for (const category of RESOURCE_CATEGORIES) {
+      const entries = resources.lookupMaybe(PDFName.of(category), PDFDict);
+      if (!entries) {
+        continue;
+      }
+      if (names === null || keptNames.get(entries) === null) {
+        keptNames.set(entries, null);
+      } else {
+        keptNames.set(entries, new Set([...(keptNames.get(entries) || []), ...names]));
+      }
+    }
+  }
+
+  for (const [entries, names] of keptNames) {
+    if (names === null) {
+      continue;
+    }
+    for (const key of entries.keys()) {
+      if (!names.has(key.decodeText())) {
+        entries.delete(key);
+      }
+    }
+  }
+}
+
+/**
+ * Deletes the objects that can't be reached from the trailer
+ *
+ * @param {PDFDocument} pdf Document to update
+ */
+function removeUnreachableObjects(pdf) {
+  const context = pdf.context;
+  const reachable = new Set();
+  // Walked without recursion, as outlines and page trees can be long chains of objects
+  const pending = [context.trailerInfo.Root, context.trailerInfo.Info];
+  while (pending.length > 0) {
+    const object = pending.pop();
+    if (object instanceof PDFRef) {
+      if (!reachable.has(object)) {
+        reachable.add(object);
+        pending.push(context.lookup(object));
+      }
+    } else if (object instanceof PDFDict) {
+      pending.push(...object.values());
+    } else if (object instanceof PDFArray) {
+      pending.push(...object.asArray());
+    } else if (object instanceof PDFStream) {
+      pending.push(object.dict);
+    }
+  }
+
+  for (const [ref] of context.enumerateIndirectObjects()) {
+    if (!reachable.has(ref)) {
+      context.delete(ref);
+    }
+  }
+}
+
+/**
+ * Removes unused resources and unreachable objects from a part
+ *
+ * @param {PDFDocument} pdf Document to update
+ * @returns {Promise<void>} Resolves once the document is cleaned up
+ */
+async function pruneUnusedObjects(pdf) {
+  // Fonts, images and pages embedded by pdf-lib are only written when saving; writing them now
+  // keeps the objects they refer to reachable
+  await pdf.flush();
+  removeUnusedResources(pdf);
+  removeUnreachableObjects(pdf);
+}
+
+module.exports = {
+  pruneUnusedObjects
+};
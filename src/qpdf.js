@@ -0,0 +1,59 @@
+// Runs the qpdf command-line tool (https://qpdf.readthedocs.io).
+// The executable is looked up on PATH unless SPLITPDF_QPDF points at it.
+
+const { execFile } = require('child_process');
+const { formatPageRanges } = require('./plan');
+
+/**
+ * @returns {string} Path or name of the qpdf executable
+ */
+function getQpdfPath() {
+  return process.env.SPLITPDF_QPDF || 'qpdf';
+}
+
+/**
+ * Runs qpdf with the given arguments
+ *
+ * @param {Array<string>} args Command-line arguments
+ * @param {Object} [runOptions] Run options
+ * @param {AbortSignal} [runOptions.signal] Signal that kills qpdf, e.g. when a split is cancelled
+ * @returns {Promise<{stdout: string, stderr: string}>} Output of a successful run
+ */
+function runQpdf(args, runOptions = {}) {
+  return new Promise((resolve, reject) => {
+    execFile(getQpdfPath(), args, { maxBuffer: 64 * 1024 * 1024, signal: runOptions.signal || undefined }, (error, stdout, stderr) => {
+      if (error && error.code === 'ENOENT') {
+        const notFoundError = new Error(`qpdf not found at ${getQpdfPath()}. Install qpdf or set SPLITPDF_QPDF to its path.`);
+        notFoundError.code = 2; // Exit code 2: the requested backend can't be used
+        reject(notFoundError);
+        return;
+      }
+
+      // qpdf exits with 3 when it succeeded with warnings, e.g. after repairing a damaged file
+      if (error && error.code !== 3) {
+        const qpdfError = new Error(`qpdf failed: ${stderr.trim() || error.message}`);
+        qpdfError.qpdfExitCode = error.code;
+        reject(qpdfError);
+        return;
+      }
+
+      resolve({ stdout, stderr });
+    });
+  });
+}
+
+/**
+ * Formats 1-based page numbers as a qpdf page range, e.g. 1-3,7
+ *
+ * @param {Array<number>} pageNumbers 1-based page numbers, in output order
+ * @returns {string} qpdf page range
+ */
+function toQpdfPageRange(pageNumbers) {
+  return formatPageRanges(pageNumbers, ',');
+}
+
+module.exports = {
+  getQpdfPath,
+  runQpdf,
+  toQpdfPageRange
+};
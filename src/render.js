@@ -0,0 +1,79 @@
+// Parts rendered to images instead of written as PDFs, for pipelines (such as
+// OCR) that want page images, and thumbnails of the parts for previews. Each
+// rendered part becomes a folder with one image per page. Pages are rendered
+// by poppler's pdftoppm (see src/poppler.js).
+
+const path = require('path');
+const fs = require('fs/promises');
+const { runPoppler, withTemporaryPdf } = require('./poppler');
+
+// Image formats, with the pdftoppm flag and the extension of the files it writes
+const IMAGE_FORMATS = {
+  png: { flag: '-png', extension: 'png' },
+  jpeg: { flag: '-jpeg', extension: 'jpg' },
+  tiff: { flag: '-tiff', extension: 'tif' }
+};
+const DEFAULT_RENDER_DPI = 150;
+// Longest side of thumbnails, in pixels
+const DEFAULT_THUMBNAIL_SIZE = 200;
+
+// Page images are named page-1.png, or page-01.png and so on for longer parts
+const PAGE_IMAGE_PATTERN = /^page-\d+\.(png|jpg|tif)$/;
+
+/**
+ * Renders the pages of a part to images in a folder
+ *
+ * @param {Uint8Array} pdfBytes Bytes of the unencrypted part
+ * @param {string} outputDir Folder receiving the images, created if needed
+ * @param {Object} render Render settings
+ * @param {string} render.format Image format (see IMAGE_FORMATS)
+ * @param {number} render.dpi Resolution in dots per inch
+ * @returns {Promise<{images: Array<string>, bytes: number}>} Paths of the page images, in page order, and their total size
+ */
+async function renderImages(pdfBytes, outputDir, render) {
+  await fs.mkdir(outputDir, { recursive: true });
+  // Images of an earlier, longer run would otherwise be mistaken for pages of this one
+  for (const name of await fs.readdir(outputDir)) {
+    if (PAGE_IMAGE_PATTERN.test(name)) {
+      await fs.rm(path.join(outputDir, name), { force: true });
+    }
+  }
+
+  const { flag } = IMAGE_FORMATS[render.format];
+  await withTemporaryPdf(pdfBytes, pdfPath => runPoppler('pdftoppm', [flag, '-r', String(render.dpi), pdfPath, path.join(outputDir, 'page')]));
+
+  // The numbers are padded to the same width, so names sort in page order
+  const images = [];
+  let bytes = 0;
+  for (const name of (await fs.readdir(outputDir)).sort()) {
+    if (PAGE_IMAGE_PATTERN.test(name)) {
+      const imagePath = path.join(outputDir, name);
+      images.push(imagePath);
+      bytes += (await fs.stat(imagePath)).size;
+    }
+  }
+  return { images, bytes };
+}
+
+/**
+ * Renders the first page of a part as a small PNG image
+ *
+ * @param {Uint8Array} pdfBytes Bytes of the unencrypted part
+ * @param {number} size Longest side of the image in pixels
+ * @returns {Promise<Uint8Array>} PNG bytes
+ */
+async function renderThumbnail(pdfBytes, size) {
+  return withTemporaryPdf(pdfBytes, async (pdfPath) => {
+    const imageRoot = path.join(path.dirname(pdfPath), 'thumbnail');
+    await runPoppler('pdftoppm', ['-png', '-f', '1', '-l', '1', '-singlefile', '-scale-to', String(size), pdfPath, imageRoot]);
+    return new Uint8Array(await fs.readFile(`${imageRoot}.png`));
+  });
+}
+
+module.exports = {
+  IMAGE_FORMATS,
+  DEFAULT_RENDER_DPI,
+  DEFAULT_THUMBNAIL_SIZE,
+  renderImages,
+  renderThumbnail
+};
@@ -0,0 +1,75 @@
+// Best-effort splitting of damaged sources. Some files open fine but have
+// pages that can't be copied, e.g. because an object they use is missing or
+// malformed, which would abort the whole split. With repair requested, each
+// page is first copied on its own; the pages that fail are left out of the
+// parts, and parts that have no pages left are skipped, so the rest of the
+// job still produces its parts.
+
+const { dropPages } = require('./plan');
+
+/**
+ * Finds the pages of a source that can't be copied into a new document
+ *
+ * @param {Object} backend PDF backend (see src/backends)
+ * @param {Object} source Source opened by the backend
+ * @param {Array<number>} pageNumbers 1-based numbers of the pages to try
+ * @returns {Promise<Array<{page: number, reason: string}>>} Pages that failed, with the error each gave, in ascending order
+ */
+async function findBrokenPages(backend, source, pageNumbers) {
+  const brokenPages = [];
+  for (const pageNumber of [...new Set(pageNumbers)].sort((a, b) => a - b)) {
+    try {
+      // Saving too, as broken streams and references may only fail when written
+      const pagePdf = await backend.copyPages(source, [pageNumber - 1]);
+      await backend.save(pagePdf);
+    } catch (error) {
+      brokenPages.push({ page: pageNumber, reason: error.message });
+    }
+  }
+  return brokenPages;
+}
+
+/**
+ * Leaves broken pages out of planned parts and removes the parts that only
+ * had broken pages
+ *
+ * @param {Array<Object>} partInfos Parts planned with Splitter#plan, updated in place (see dropPages in src/plan.js)
+ * @param {Array<{page: number, reason: string}>} brokenPages Pages found with findBrokenPages
+ * @returns {Array<{code: string, message: string}>} Warnings listing the skipped pages and parts, or none
+ * @throws {Error} With code 4 if no part has pages left
+ */
+function dropBrokenPages(partInfos, brokenPages) {
+  if (brokenPages.length === 0) {
+    return [];
+  }
+  const broken = new Set(brokenPages.map(brokenPage => brokenPage.page));
+  const skippedParts = [];
+  for (let i = partInfos.length - 1; i >= 0; i--) {
+    const pageNumbers = [...partInfos[i].pages.intro, ...partInfos[i].pages.content];
+    if (pageNumbers.every(pageNumber => broken.has(pageNumber))) {
+      skippedParts.unshift(partInfos[i].index);
+      partInfos.splice(i, 1);
+    }
+  }
+  if (partInfos.length === 0) {
+    const error = new Error('PDF error: no page of the document can be copied.');
+    error.code = 4; // PDF processing error
+    throw error;
+  }
+  dropPages(partInfos, [...broken]);
+
+  const list = brokenPages.map(brokenPage => `${brokenPage.page} (${brokenPage.reason})`).join('; ');
+  const warnings = [{ code: 'damagedPagesSkipped', message: `Skipped ${brokenPages.length} damaged page(s): ${list}` }];
+  if (skippedParts.length > 0) {
+    warnings.push({
+      code: 'damagedPartsSkipped',
+      message: `Skipped part(s) ${skippedParts.join(', ')}, whose pages are all damaged`
+    });
+  }
+  return warnings;
+}
+
+module.exports = {
+  findBrokenPages,
+  dropBrokenPages
+};
@@ -0,0 +1,245 @@
+// Amazon S3 (and S3-compatible) object storage for sources and parts, for
+// hosts such as AWS Lambda where local disk is scarce. A source at
+// s3://bucket/key is read into memory, and parts for an output directory
+// s3://bucket/prefix are uploaded one by one as they are built, so nothing
+// touches the disk. Requests are signed with AWS Signature Version 4 using
+// the standard AWS_* environment variables; SPLITPDF_S3_ENDPOINT points at
+// another S3-compatible service (e.g. MinIO, or Google Cloud Storage with
+// HMAC keys) using path-style URLs.
+
+const http = require('http');
+const https = require('https');
+const crypto = require('crypto');
+const path = require('path');
+const { Writable } = require('stream');
+
+const S3_URL_PREFIX = 's3://';
+const DEFAULT_REGION = 'us-east-1';
+
+/**
+ * @param {*} location File path or URL
+ * @returns {boolean} True for s3://bucket/key URLs
+ */
+function isS3Url(location) {
+  return typeof location === 'string' && location.startsWith(S3_URL_PREFIX);
+}
+
+/**
+ * Splits an s3:// URL into its bucket and key
+ *
+ * @param {string} url URL such as s3://bucket/reports/2024.pdf
+ * @returns {{bucket: string, key: string}} Bucket and key (without a leading slash, may be empty)
+ */
+function parseS3Url(url) {
+  const rest = url.slice(S3_URL_PREFIX.length);
+  const slash = rest.indexOf('/');
+  const bucket = slash === -1 ? rest : rest.slice(0, slash);
+  if (bucket === '') {
+    const error = new Error(`Invalid S3 URL ${url}; expected s3://bucket/key.`);
+    error.code = 2; // Invalid option
+    throw error;
+  }
+  return { bucket, key: slash === -1 ? '' : rest.slice(slash + 1) };
+}
+
+/**
+ * Joins a file name to an s3:// URL naming a bucket or key prefix
+ *
+ * @param {string} dirUrl URL of the "directory", e.g. s3://bucket/parts
+ * @param {string} fileName Name to append; . and .. segments are resolved
+ * @returns {string} URL of the object
+ */
+function joinS3Url(dirUrl, fileName) {
+  const { bucket, key } = parseS3Url(dirUrl);
+  // Resolved as a path below the bucket, so .. can't leave it
+  const objectKey = path.posix.join('/', key, fileName).slice(1);
+  return `${S3_URL_PREFIX}${bucket}/${objectKey}`;
+}
+
+/**
+ * Reads the credentials and location of the S3 service from the environment
+ *
+ * @param {Object} env Environment variables
+ * @returns {Object} accessKeyId, secretAccessKey, sessionToken, region and endpoint (null for AWS)
+ */
+function s3Config(env = process.env) {
+  if (!env.AWS_ACCESS_KEY_ID || !env.AWS_SECRET_ACCESS_KEY) {
+    const error = new Error('S3 access needs credentials in AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY.');
+    error.code = 2; // Invalid option
+    throw error;
+  }
+  return {
+    accessKeyId: env.AWS_ACCESS_KEY_ID,
+    secretAccessKey: env.AWS_SECRET_ACCESS_KEY,
+    sessionToken: env.AWS_SESSION_TOKEN || null,
+    region: env.AWS_REGION || env.AWS_DEFAULT_REGION || DEFAULT_REGION,
+    endpoint: env.SPLITPDF_S3_ENDPOINT || null
+  };
+}
+
+/**
+ * Percent-encodes a string the way Signature Version 4 expects
+ *
+ * @param {string} value String to encode
+ * @returns {string} Encoded string; only letters, digits and - . _ ~ stay as they are
+ */
+function encodeUriPart(value) {
+  return encodeURIComponent(value).replace(/[!'()*]/g, char => `%${char.charCodeAt(0).toString(16).toUpperCase()}`);
+}
+
+/**
+ * @param {string|Buffer} data Data to hash
+ * @returns {string} Hex SHA-256 of the data
+ */
+function sha256(data) {
+  return crypto.createHash('sha256').update(data).digest('hex');
+}
+
+/**
+ * @param {string|Buffer} key HMAC key
+ * @param {string} data Data to sign
+ * @returns {Buffer} HMAC-SHA256 of the data
+ */
+function hmac(key, data) {
+  return crypto.createHmac('sha256', key).update(data).digest();
+}
+
+/**
+ * Builds a signed request for an object
+ *
+ * @param {string} method HTTP method
+ * @param {string} url s3:// URL of the object
+ * @param {Buffer} body Request body (empty for GET)
+ * @param {Object} config Service settings read with s3Config
+ * @param {Date} [date] Time of the request
+ * @returns {{url: URL, headers: Object}} HTTP(S) URL and headers, including the Authorization header
+ */
+function signRequest(method, url, body, config, date = new Date()) {
+  const { bucket, key } = parseS3Url(url);
+  const encodedKey = key.split('/').map(encodeUriPart).join('/');
+  const requestUrl = config.endpoint
+    ? new URL(`${config.endpoint.replace(/\/$/, '')}/${encodeUriPart(bucket)}/${encodedKey}`)
+    : new URL(`https://${bucket}.s3.${config.region}.amazonaws.com/${encodedKey}`);
+
+  const amzDate = date.toISOString().replace(/[-:]/g, '').replace(/\.\d{3}/, '');
+  const day = amzDate.slice(0, 8);
+  const payloadHash = sha256(body);
+  const headers = {
+    host: requestUrl.host,
+    'x-amz-content-sha256': payloadHash,
+    'x-amz-date': amzDate
+  };
+  if (config.sessionToken) {
+    headers['x-amz-security-token'] = config.sessionToken;
+  }
+
+  const headerNames = Object.keys(headers).sort();
+  const canonicalRequest = [
+    method,
+    requestUrl.pathname,
+    '',
+    ...headerNames.map(name => `${name}:${headers[name]}`),
+    '',
+    headerNames.join(';'),
+    payloadHash
+  ].join('\n');
+  const scope = `${day}/${config.region}/s3/aws4_request`;
+  const stringToSign = ['AWS4-HMAC-SHA256', amzDate, scope, sha256(canonicalRequest)].join('\n');
+  const signingKey = hmac(hmac(hmac(hmac(`AWS4${config.secretAccessKey}`, day), config.region), 's3'), 'aws4_request');
+  const signature = crypto.createHmac('sha256', signingKey).update(stringToSign).digest('hex');
+
+  headers.authorization = `AWS4-HMAC-SHA256 Credential=${config.accessKeyId}/${scope}, SignedHeaders=${headerNames.join(';')}, Signature=${signature}`;
+  return { url: requestUrl, headers };
+}
+
+/**
+ * Sends a signed request for an object
+ *
+ * @param {string} method HTTP method
+ * @param {string} url s3:// URL of the object
+ * @param {Buffer} body Request body
+ * @returns {Promise<Buffer>} Response body of a successful request
+ */
+function requestObject(method, url, body) {
+  const signed = signRequest(method, url, body, s3Config());
+  const transport = signed.url.protocol === 'http:' ? http : https;
+  return new Promise((resolve, reject) => {
+    const ioError = (message) => {
+      const error = new Error(`I/O error: ${method} ${url} failed: ${message}`);
+      error.code = 3; // I/O error
+      return error;
+    };
+    const request = transport.request(signed.url, {
+      method,
+      headers: { ...signed.headers, 'content-length': body.length }
+    }, (response) => {
+      const chunks = [];
+      response.on('data', chunk => chunks.push(chunk));
+      response.on('error', error => reject(ioError(error.message)));
+      response.on('end', () => {
+        const responseBody = Buffer.concat(chunks);
+        if (response.statusCode >= 200 && response.statusCode < 300) {
+          resolve(responseBody);
+        } else {
+          // S3 explains errors in an XML document with a Code element
+          const code = /<Code>([^<]*)<\/Code>/.exec(responseBody.toString('utf8'));
+          reject(ioError(`HTTP ${response.statusCode}${code ? ` (${code[1]})` : ''}`));
+        }
+      });
+    });
+    request.on('error', error => reject(ioError(error.message)));
+    request.end(body);
+  });
+}
+
+/**
+ * Downloads an object into memory
+ *
+ * @param {string} url s3:// URL of the object
+ * @returns {Promise<Buffer>} Bytes of the object
+ */
+async function getObject(url) {
+  return requestObject('GET', url, Buffer.alloc(0));
+}
+
+/**
+ * Uploads an object
+ *
+ * @param {string} url s3:// URL of the object
+ * @param {Uint8Array} bytes Bytes to store
+ * @returns {Promise<void>} Resolves once the object is stored
+ */
+async function putObject(url, bytes) {
+  await requestObject('PUT', url, Buffer.from(bytes));
+}
+
+/**
+ * Creates a stream that uploads what is written to it as one object once it
+ * ends, for use as a split's createWriter
+ *
+ * @param {string} url s3:// URL of the object
+ * @returns {Writable} Stream finishing once the object is stored
+ */
+function createUploadStream(url) {
+  const chunks = [];
+  return new Writable({
+    write(chunk, encoding, callback) {
+      chunks.push(chunk);
+      callback();
+    },
+    final(callback) {
+      putObject(url, Buffer.concat(chunks)).then(() => callback(), callback);
+    }
+  });
+}
+
+module.exports = {
+  isS3Url,
+  parseS3Url,
+  joinS3Url,
+  s3Config,
+  signRequest,
+  getObject,
+  putObject,
+  createUploadStream
+};
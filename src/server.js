@@ -0,0 +1,323 @@
+// HTTP server running split jobs, for use as an internal service.
+//
+// POST   /jobs?parts=3&intro=1:4   Upload a PDF (request body) and start splitting it
+// GET    /jobs/:id                 Job status, latest progress event and finished parts
+// GET    /jobs/:id/parts/:index    Download a finished part
+// DELETE /jobs/:id                 Cancel a job and delete its files
+//
+// Uploads larger than the upload limit are refused with 413, and uploads
+// beyond the number of jobs that may run at once with 429. Finished jobs and
+// their files are deleted once they have been kept for the job lifetime.
+
+const http = require('http');
+const os = require('os');
+const path = require('path');
+const fs = require('fs');
+const crypto = require('crypto');
+const { pipeline } = require('stream');
+const { splitPdf } = require('./index');
+const { SplitOptions } = require('./options');
+const { createCallbackSink } = require('./events');
+
+// Largest upload accepted, in bytes
+const DEFAULT_MAX_UPLOAD_SIZE = 512 * 1024 * 1024;
+// Jobs that may run at the same time
+const DEFAULT_MAX_JOBS = 4;
+// How long a finished job and its files are kept, in milliseconds
+const DEFAULT_JOB_TTL = 60 * 60 * 1000;
+
+/**
+ * Sends a JSON response
+ *
+ * @param {http.ServerResponse} response Response to write
+ * @param {number} statusCode HTTP status code
+ * @param {Object} body Response body
+ */
+function sendJson(response, statusCode, body) {
+  response.writeHead(statusCode, { 'Content-Type': 'application/json' });
+  response.end(JSON.stringify(body));
+}
+
+/**
+ * Describes a job for API responses
+ *
+ * @param {Object} job Job state
+ * @returns {Object} Public view of the job
+ */
+function describeJob(job) {
+  const parts = [];
+  for (const part of job.parts) {
+    parts.push({
+      index: part.index,
+      pages: part.pages,
+      url: `/jobs/${job.id}/parts/${part.index}`
+    });
+  }
+  return {
+    id: job.id,
+    status: job.status,
+    progress: job.progress,
+    parts,
+    error: job.error
+  };
+}
+
+/**
+ * Reads the whole request body
+ *
+ * @param {http.IncomingMessage} request Incoming request
+ * @param {number} maxSize Largest body accepted, in bytes
+ * @returns {Promise<Buffer>} Request body; rejects with an error whose statusCode is 413 if it is larger
+ */
+function readBody(request, maxSize) {
+  return new Promise((resolve, reject) => {
+    const tooLargeError = new Error(`Uploads may be at most ${maxSize} bytes.`);
+    tooLargeError.statusCode = 413;
+    if (Number(request.headers['content-length']) > maxSize) {
+      reject(tooLargeError);
+      return;
+    }
+    const chunks = [];
+    let size = 0;
+    request.on('data', (chunk) => {
+      size += chunk.length;
+      // The rest is read and dropped, so the client gets the response instead of a reset connection
+      if (size > maxSize) {
+        chunks.length = 0;
+        reject(tooLargeError);
+        return;
+      }
+      chunks.push(chunk);
+    });
+    request.on('end', () => resolve(Buffer.concat(chunks)));
+    request.on('error', reject);
+  });
+}
+
+/**
+ * Creates an HTTP server handling split jobs. Uploaded files and parts are
+ * kept in a temporary directory that is removed when the process exits.
+ *
+ * @param {Object} [serverOptions] Server options
+ * @param {number} [serverOptions.threads] Worker threads per job (defaults to 1)
+ * @param {string} [serverOptions.backend] PDF backend used for every job
+ * @param {number} [serverOptions.maxUploadSize] Largest upload accepted in bytes (defaults to 512 MiB)
+ * @param {number} [serverOptions.maxJobs] Jobs that may run at the same time (defaults to 4)
+ * @param {number} [serverOptions.jobTtl] Milliseconds a finished job and its files are kept (defaults to an hour)
+ * @returns {http.Server} Server that is not listening yet
+ */
+function createServer(serverOptions = {}) {
+  const workDir = fs.mkdtempSync(path.join(os.tmpdir(), 'splitpdf-server-'));
+  process.once('exit', () => {
+    fs.rmSync(workDir, { recursive: true, force: true });
+  });
+
+  const jobs = new Map();
+  const maxUploadSize = serverOptions.maxUploadSize || DEFAULT_MAX_UPLOAD_SIZE;
+  const maxJobs = serverOptions.maxJobs || DEFAULT_MAX_JOBS;
+  const jobTtl = serverOptions.jobTtl || DEFAULT_JOB_TTL;
+  // Jobs being uploaded or split
+  let activeJobs = 0;
+
+  async function deleteJob(job) {
+    clearTimeout(job.evictionTimer);
+    jobs.delete(job.id);
+    await fs.promises.rm(job.dir, { recursive: true, force: true });
+  }
+
+  async function createJob(request, response, searchParams) {
+    if (activeJobs >= maxJobs) {
+      response.setHeader('Connection', 'close');
+      sendJson(response, 429, { error: `${maxJobs} job(s) are already running; try again later.` });
+      return;
+    }
+    activeJobs++;
+    try {
+      await uploadAndRunJob(request, response, searchParams);
+    } finally {
+      activeJobs--;
+    }
+  }
+
+  async function uploadAndRunJob(request, response, searchParams) {
+    let body;
+    try {
+      body = await readBody(request, maxUploadSize);
+    } catch (error) {
+      if (error.statusCode !== 413) {
+        throw error;
+      }
+      response.setHeader('Connection', 'close');
+      sendJson(response, 413, { error: error.message });
+      return;
+    }
+    const id = crypto.randomUUID();
+    const jobDir = path.join(workDir, id);
+    await fs.promises.mkdir(jobDir);
+    const sourcePath = path.join(jobDir, 'source.pdf');
+    await fs.promises.writeFile(sourcePath, body);
+
+    const job = {
+      id,
+      dir: jobDir,
+      status: 'running',
+      progress: null,
+      parts: [],
+      error: null,
+      abortController: new AbortController(),
+      // Resolves once the split has finished, whatever its outcome
+      settled: null,
+      evictionTimer: null
+    };
+
+    // Validate the request the same way the CLI validates its arguments
+    let splitOptions;
+    try {
+      const builder = SplitOptions.builder()
+        .input(sourcePath)
+        .parts(Number(searchParams.get('parts')))
+        .outputDir(jobDir)
+        .outputBasename('part')
+        .threads(serverOptions.threads || 1)
+        .backend(serverOptions.backend || 'pdf-lib')
+        .signal(job.abortController.signal)
+        .eventSink(createCallbackSink((event) => {
+          if (event.event === 'progress') {
+            job.progress = event;
+          }
+        }));
+      if (searchParams.get('intro')) {
+        builder.intro(searchParams.get('intro'));
+      }
+      splitOptions = builder.build();
+    } catch (error) {
+      await fs.promises.rm(jobDir, { recursive: true, force: true });
+      sendJson(response, 400, { error: error.message });
+      return;
+    }
+
+    jobs.set(id, job);
+    sendJson(response, 202, describeJob(job));
+
+    job.settled = runJob(job, splitOptions);
+    await job.settled;
+    // Finished jobs are kept for a while for their parts to be downloaded, unless they were deleted
+    // already; the timer alone must not keep the process running
+    if (jobs.has(job.id)) {
+      job.evictionTimer = setTimeout(() => {
+        deleteJob(job).catch(() => {});
+      }, jobTtl);
+      job.evictionTimer.unref();
+    }
+  }
+
+  async function runJob(job, splitOptions) {
+    try {
+      const result = await splitPdf(splitOptions);
+      job.parts = result.parts;
+      job.status = 'done';
+    } catch (error) {
+      job.status = job.abortController.signal.aborted ? 'cancelled' : 'failed';
+      job.error = { message: error.message, code: typeof error.code === 'number' ? error.code : 1 };
+    }
+  }
+
+  async function handleRequest(request, response) {
+    const url = new URL(request.url, 'http://localhost');
+    const segments = [];
+    for (const segment of url.pathname.split('/')) {
+      if (segment !== '') {
+        segments.push(segment);
+      }
+    }
+
+    if (segments.length === 1 && segments[0] === 'jobs' && request.method === 'POST') {
+      await createJob(request, response, url.searchParams);
+      return;
+    }
+
+    const job = segments[0] === 'jobs' ? jobs.get(segments[1]) : undefined;
+    if (!job) {
+      sendJson(response, 404, { error: 'Not found' });
+      return;
+    }
+
+    if (segments.length === 2 && request.method === 'GET') {
+      sendJson(response, 200, describeJob(job));
+    } else if (segments.length === 2 && request.method === 'DELETE') {
+      job.abortController.abort();
+      // The cancelled split may still be writing a part
+      await job.settled;
+      await deleteJob(job);
+      response.writeHead(204);
+      response.end();
+    } else if (segments.length === 4 && segments[2] === 'parts' && request.method === 'GET') {
+      let part = null;
+      for (const candidate of job.parts) {
+        if (String(candidate.index) === segments[3]) {
+          part = candidate;
+        }
+      }
+      if (!part) {
+        sendJson(response, 404, { error: 'Part not found' });
+        return;
+      }
+      // The file may be gone already, e.g. deleted with its job; once open, it can be sent whatever happens to it
+      let fileHandle;
+      try {
+        fileHandle = await fs.promises.open(part.outputPath, 'r');
+      } catch (error) {
+        if (error.code !== 'ENOENT') {
+          throw error;
+        }
+        sendJson(response, 404, { error: 'Part not found' });
+        return;
+      }
+      response.writeHead(200, {
+        'Content-Type': 'application/pdf',
+        'Content-Disposition': `attachment; filename="part${part.index}.pdf"`
+      });
+      pipeline(fileHandle.createReadStream(), response, (error) => {
+        if (error) {
+          response.destroy(error);
+        }
+      });
+    } else {
+      sendJson(response, 405, { error: 'Method not allowed' });
+    }
+  }
+
+  return http.createServer((request, response) => {
+    handleRequest(request, response).catch((error) => {
+      if (!response.headersSent) {
+        sendJson(response, 500, { error: error.message });
+      } else {
+        response.destroy(error);
+      }
+    });
+  });
+}
+
+/**
+ * Creates a server and starts listening
+ *
+ * @param {Object} serverOptions Server options (see createServer) plus host and port
+ * @param {string} serverOptions.host Host to listen on
+ * @param {number} serverOptions.port Port to listen on (0 picks a free port)
+ * @returns {Promise<http.Server>} Listening server
+ */
+function startServer(serverOptions) {
+  const server = createServer(serverOptions);
+  return new Promise((resolve, reject) => {
+    server.once('error', reject);
+    server.listen(serverOptions.port, serverOptions.host, () => {
+      server.off('error', reject);
+      resolve(server);
+    });
+  });
+}
+
+module.exports = {
+  createServer,
+  startServer
+};
@@ -0,0 +1,74 @@
+// Digital signatures. A signature covers the exact bytes of the signed file,
+// so any part built from a signed document carries signatures that no longer
+// verify. Signed sources are refused unless the caller acknowledges that.
+
+const { PDFArray, PDFDict, PDFName } = require('pdf-lib');
+
+// Exit code for PDF features that splitting cannot preserve
+const UNSUPPORTED_EXIT_CODE = 5;
+
+/**
+ * Lists the names of the signed signature fields of a document
+ *
+ * @param {PDFDocument} pdf Loaded document
+ * @returns {Array<string>} Fully qualified names of the fields holding a signature
+ */
+function findSignatures(pdf) {
+  const acroForm = pdf.catalog.lookupMaybe(PDFName.of('AcroForm'), PDFDict);
+  const fields = acroForm && acroForm.lookupMaybe(PDFName.of('Fields'), PDFArray);
+  const signatures = [];
+
+  // Damaged files can contain loops, so every field is visited only once
+  const visited = new Set();
+  const visit = (field, parentName, inheritedType) => {
+    if (!field || visited.has(field)) {
+      return;
+    }
+    visited.add(field);
+    const partialName = field.lookup(PDFName.of('T'));
+    const name = partialName && partialName.decodeText
+      ? (parentName ? `${parentName}.${partialName.decodeText()}` : partialName.decodeText())
+      : parentName;
+    // The field type is inherited from the parent field
+    const type = field.lookup(PDFName.of('FT')) || inheritedType;
+    if (type === PDFName.of('Sig') && field.lookupMaybe(PDFName.of('V'), PDFDict)) {
+      signatures.push(name || '(unnamed)');
+    }
+    const kids = field.lookupMaybe(PDFName.of('Kids'), PDFArray);
+    for (let i = 0; kids && i < kids.size(); i++) {
+      visit(kids.lookupMaybe(i, PDFDict), name, type);
+    }
+  };
+  for (let i = 0; fields && i < fields.size(); i++) {
+    visit(fields.lookupMaybe(i, PDFDict), '', null);
+  }
+  return signatures;
+}
+
+/**
+ * Refuses to split a signed document unless breaking its signatures was acknowledged
+ *
+ * @param {PDFDocument} pdf Source document
+ * @param {boolean} breakSignatures If true, signed documents are split with a warning
+ * @returns {Array<{code: string, message: string}>} Warnings, empty for unsigned documents
+ */
+function signatureWarnings(pdf, breakSignatures) {
+  const signatures = findSignatures(pdf);
+  if (signatures.length === 0) {
+    return [];
+  }
+  if (!breakSignatures) {
+    const error = new Error(`The PDF is digitally signed (${signatures.join(', ')}) and splitting invalidates its signatures. Use --break-signatures to split it anyway.`);
+    error.code = UNSUPPORTED_EXIT_CODE;
+    throw error;
+  }
+  return [{
+    code: 'signaturesBroken',
+    message: `The signatures of ${signatures.length} field(s) no longer verify in the parts: ${signatures.join(', ')}`
+  }];
+}
+
+module.exports = {
+  findSignatures,
+  signatureWarnings
+};
@@ -0,0 +1,880 @@
+// Splitter keeps a parsed source PDF so that page counting, planning, and
+// splitting don't each have to read and parse the file again.
+
+const path = require('path');
+const crypto = require('crypto');
+const fs = require('fs/promises');
+const { finished } = require('stream/promises');
+const { getBackend } = require('./backends');
+const { resolveEventSink } = require('./events');
+const { calculateRanges, rangesFromContentRanges, formatPartName, formatPageRanges, checkPartCount, DEFAULT_NAME_TEMPLATE, DEFAULT_MAX_PARTS } = require('./plan');
+const { CANCELLED_EXIT_CODE, throwIfCancelled, buildPart } = require('./part-builder');
+const { buildPartsInWorkers } = require('./worker-pool');
+const { readMetadata, partMetadata, deterministicDate } = require('./metadata');
+const { readOutline, partOutline } = require('./outline');
+const { countTocPages, tocEntries } = require('./toc');
+const { readLinks, partLinks } = require('./links');
+const { readNamedDestinations, partDestinations } = require('./destinations');
+const { readPageLabels, partPageLabels } = require('./page-labels');
+const { readAttachments, partAttachments, attachmentWarnings } = require('./attachments');
+const { signatureWarnings } = require('./signatures');
+const { structureWarnings } = require('./structure');
+const { readPdfaIdentification, pdfaWarnings } = require('./pdfa');
+const { readDocumentVersion } = require('./version');
+const { readDocumentId, partDocumentId } = require('./document-id');
+const { formatBatesNumber } = require('./bates');
+const { partHeaderFooter } = require('./header-footer');
+const { partCover } = require('./cover');
+const { splitSpreads } = require('./spreads');
+const { nUpSheets, bookletSheets, sheetIndexOf } = require('./imposition');
+const { pageGroups, rangesFromGroups } = require('./grouping');
+const { renderImages, renderThumbnail } = require('./render');
+const { textSidecarPath, writeTextSidecar } = require('./text');
+const { findBlankPages, dropBlankPages } = require('./blank-pages');
+const { findDuplicatePages, applyDuplicatePageMode } = require('./duplicates');
+const { findBrokenPages, dropBrokenPages } = require('./repair');
+const { checkpointPlan, readCheckpoint, writeCheckpoint } = require('./checkpoint');
+const { TIMEOUT_EXIT_CODE, createTimeoutSignal, splitTimeoutError } = require('./timeout');
+const { startMemoryMonitor, memoryLimitError, toMegabytes } = require('./memory');
+const { PASSWORD_EXIT_CODE, createEncryptionCopier, decryptPdf, encryptPdf } = require('./encryption');
+const { MAX_IN_MEMORY_SOURCE_SIZE, sourceTooLargeError, countPagesOnDisk, buildPartsOnDisk } = require('./low-memory');
+const { linearizePdf } = require('./linearize');
+const { isS3Url, joinS3Url, getObject, createUploadStream } = require('./s3');
+
+/**
+ * Refuses output paths outside the output directory, e.g. from a name template such as ../{index}.pdf
+ *
+ * @param {string} outputDir Output directory
+ * @param {string} outputPath Path of an output file
+ */
+function checkOutputPath(outputDir, outputPath) {
+  const relativePath = path.relative(path.resolve(outputDir), path.resolve(outputPath));
+  if (relativePath === '' || relativePath.split(path.sep)[0] === '..' || path.isAbsolute(relativePath)) {
+    const error = new Error(`Output file ${outputPath} would be outside the output directory ${outputDir}.`);
+    error.code = 2; // Exit code 2 for invalid CLI arguments
+    throw error;
+  }
+}
+
+/**
+ * Adds contextual information and an exit code to a loading or splitting error
+ *
+ * @param {Error} error Original error
+ * @returns {Error} Error with a numeric code where one applies
+ */
+function toSplitError(error) {
+  // Errors that already carry an exit code need no further context
+  if (typeof error.code === 'number') {
+    return error;
+  }
+  
+  if (error.qpdfExitCode !== undefined) {
+    const enhancedError = new Error(`PDF error: ${error.message}`);
+    enhancedError.code = 4; // PDF processing error reported by qpdf
+    return enhancedError;
+  }
+  
+  if (error.message.includes('file does not exist') || error.code === 'ENOENT') {
+    const enhancedError = new Error(`I/O error: ${error.message}`);
+    enhancedError.code = 3; // I/O error
+    return enhancedError;
+  }
+  if (error.message.includes('encrypted')) {
+    const enhancedError = new Error('Password required: the PDF is encrypted. Provide its password (an empty password opens files that only restrict permissions).');
+    enhancedError.code = PASSWORD_EXIT_CODE;
+    return enhancedError;
+  }
+  if (error.message.includes('invalid')) {
+    const enhancedError = new Error(`PDF error: ${error.message}`);
+    enhancedError.code = 4; // PDF processing error
+    return enhancedError;
+  }
+
+  // For other errors, just pass them through
+  return error;
+}
+
+/**
+ * Writes a part into a caller's stream and ends it
+ *
+ * @param {Writable} stream Stream receiving the part
+ * @param {Uint8Array} bytes Bytes of the part
+ * @returns {Promise<void>} Resolves once the stream has flushed the part, rejects if it fails
+ */
+async function writeToStream(stream, bytes) {
+  if (!stream || typeof stream.end !== 'function') {
+    const error = new Error('The writer factory must return a writable stream.');
+    error.code = 2; // Invalid option
+    throw error;
+  }
+  try {
+    stream.end(Buffer.from(bytes));
+    await finished(stream);
+  } catch (error) {
+    const enhancedError = new Error(`I/O error: ${error.message}`);
+    enhancedError.code = 3; // I/O error
+    throw enhancedError;
+  }
+}
+
+/**
+ * @param {number} pages Number of pages written
+ * @param {number} durationMs Time it took in milliseconds
+ * @returns {number} Throughput rounded to one decimal, or 0 when too fast to measure
+ */
+function toPagesPerSecond(pages, durationMs) {
+  return durationMs > 0 ? Math.round(pages / durationMs * 10000) / 10 : 0;
+}
+
+// Least time between two partProgress events of a part, in milliseconds
+const PART_PROGRESS_INTERVAL = 100;
+
+/**
+ * Builds parts one after another in the current thread
+ *
+ * @param {Object} backend PDF backend (see src/backends)
+ * @param {Object} source Source opened by the backend
+ * @param {Array<Object>} partInfos Parts to build
+ * @param {AbortSignal} signal Optional signal cancelling the remaining builds
+ * @param {Function} [onPageCopied] Called with the part being built and the number of its pages copied so far
+ * @returns {AsyncGenerator<{partInfo: Object, bytes: Uint8Array, buildMs: number}>} Built parts and how long building each took
+ */
+async function* buildPartsInProcess(backend, source, partInfos, signal, onPageCopied) {
+  for (const partInfo of partInfos) {
+    throwIfCancelled(signal);
+    const buildStart = Date.now();
+    const bytes = await buildPart(backend, source, partInfo, {
+      signal,
+      onPageCopied: onPageCopied && (pagesCopied => onPageCopied(partInfo, pagesCopied))
+    });
+    yield { partInfo, bytes, buildMs: Date.now() - buildStart };
+  }
+}
+
+class Splitter {
+  /**
+   * @param {Uint8Array} sourceBytes Bytes of the source PDF
+   * @param {Object} source Source opened by the backend
+   * @param {Object} backend PDF backend (see src/backends)
+   */
+  constructor(sourceBytes, source, backend) {
+    this.sourceBytes = sourceBytes;
+    this.source = source;
+    this.backend = backend;
+    // Set for sources opened with a password, whose encryption outputs keep by default
+    this.encryptedSource = null;
+    this.encryptionCopier = null;
+    // Set instead of the parsed source for sources split in low memory mode, which stay on disk
+    this.onDiskSource = null;
+  }
+
+  /**
+   * Reads and parses a source PDF once for any number of later operations
+   *
+   * @param {string} filePath Path to the source PDF, or its s3://bucket/key URL (see src/s3.js)
+   * @param {Object} [openOptions] Open options
+   * @param {string} [openOptions.backend] Name of the PDF backend (defaults to pdf-lib)
+   * @param {string} [openOptions.password] Password of an encrypted source (requires qpdf)
+   * @param {boolean} [openOptions.spreads] Split landscape pages (scanned two-page spreads) into their left and right halves
+   * @param {boolean} [openOptions.lowMemory] Leave the source on disk, for splits in low memory mode (see src/low-memory.js)
+   * @returns {Promise<Splitter>} Splitter bound to the source PDF
+   */
+  static async open(filePath, openOptions = {}) {
+    if (openOptions.lowMemory) {
+      return Splitter.openOnDisk(filePath, openOptions);
+    }
+    let sourceBytes;
+    try {
+      if (isS3Url(filePath)) {
+        sourceBytes = await getObject(filePath);
+      } else {
+        // Refuse files that can't be read into memory with an explanation instead of a raw read error
+        const { size } = await fs.stat(filePath);
+        if (size > MAX_IN_MEMORY_SOURCE_SIZE) {
+          throw sourceTooLargeError(size);
+        }
+        sourceBytes = await fs.readFile(filePath);
+      }
+    } catch (error) {
+      throw toSplitError(error);
+    }
+    return Splitter.fromBytes(sourceBytes, openOptions);
+  }
+
+  /**
+   * Parses a source PDF that is already in memory
+   *
+   * @param {Uint8Array} sourceBytes Bytes of the source PDF
+   * @param {Object} [openOptions] Open options
+   * @param {string} [openOptions.backend] Name of the PDF backend (defaults to pdf-lib)
+   * @param {string} [openOptions.password] Password of an encrypted source (requires qpdf)
+   * @param {boolean} [openOptions.spreads] Split landscape pages (scanned two-page spreads) into their left and right halves
+   * @returns {Promise<Splitter>} Splitter bound to the source PDF
+   */
+  static async fromBytes(sourceBytes, openOptions = {}) {
+    const backend = getBackend(openOptions.backend);
+    try {
+      // Backends only see the decrypted document
+      const decryptedBytes = openOptions.password === undefined || openOptions.password === null
+        ? sourceBytes
+        : await decryptPdf(sourceBytes, openOptions.password);
+      // Spreads are split before anything else, so page numbers refer to the single pages
+      const openedBytes = openOptions.spreads ? await splitSpreads(decryptedBytes) : decryptedBytes;
+      const source = await backend.open(openedBytes);
+      const splitter = new Splitter(openedBytes, source, backend);
+      if (decryptedBytes !== sourceBytes) {
+        splitter.encryptedSource = { bytes: sourceBytes, password: openOptions.password };
+      }
+      return splitter;
+    } catch (error) {
+      throw toSplitError(error);
+    }
+  }
+
+  /**
+   * Counts the pages of a source PDF for a split in low memory mode, which
+   * builds the parts from the file with qpdf instead of parsing it
+   *
+   * @param {string} filePath Path to the source PDF
+   * @param {Object} [openOptions] Open options
+   * @param {string} [openOptions.password] Password of an encrypted source
+   * @returns {Promise<Splitter>} Splitter bound to the source file
+   */
+  static async openOnDisk(filePath, openOptions = {}) {
+    try {
+      const pageCount = await countPagesOnDisk(filePath, openOptions.password);
+      const splitter = new Splitter(null, null, null);
+      splitter.onDiskSource = { filePath, password: openOptions.password, pageCount };
+      return splitter;
+    } catch (error) {
+      throw toSplitError(error);
+    }
+  }
+
+  /**
+   * Releases resources the backend holds for the source PDF
+   */
+  async close() {
+    if (this.backend && this.backend.close) {
+      await this.backend.close(this.source);
+    }
+    if (this.encryptionCopier) {
+      await (await this.encryptionCopier).close();
+    }
+  }
+
+  /**
+   * Encrypts an output document as the options ask: with new passwords, with
+   * the source's encryption (the default for encrypted sources), or not at
+   * all, and linearizes it if requested
+   *
+   * @param {Uint8Array} bytes Bytes of the unencrypted output
+   * @param {Object} options Options with optional encryption, decryptOutput and linearize
+   * @returns {Promise<Uint8Array>} Bytes to write
+   */
+  async encryptOutput(bytes, options) {
+    const linearize = !!options.linearize;
+    if (options.encryption) {
+      return encryptPdf(bytes, options.encryption, linearize);
+    }
+    if (this.encryptedSource && !options.decryptOutput) {
+      if (!this.encryptionCopier) {
+        this.encryptionCopier = createEncryptionCopier(this.encryptedSource.bytes, this.encryptedSource.password);
+      }
+      return (await this.encryptionCopier).apply(bytes, linearize);
+    }
+    return linearize ? linearizePdf(bytes, !!options.deterministic) : bytes;
+  }
+
+  /**
+   * @returns {number} Number of pages in the source PDF
+   */
+  pageCount() {
+    return this.onDiskSource ? this.onDiskSource.pageCount : this.backend.pageCount(this.source);
+  }
+
+  /**
+   * Refuses operations that need the parsed source when it was left on disk
+   *
+   * @param {string} operation What was asked for, for the message
+   */
+  checkInMemory(operation) {
+    if (this.onDiskSource) {
+      const error = new Error(`${operation} needs the source PDF in memory and is not available in low memory mode.`);
+      error.code = 2; // Invalid option
+      throw error;
+    }
+  }
+
+  /**
+   * Calculates the page ranges and output paths of each part without writing anything
+   *
+   * @param {Object} options Same options as splitPdf; only parts, ranges, groupBy, intro, maxParts, outputDir, outputBasename, nameTemplate and render are used
+   * @returns {Array<Object>} Parts with page ranges and output paths
+   */
+  plan(options) {
+    // Page groups or explicit content ranges replace the equal division into parts
+    let partRanges;
+    if (options.groupBy) {
+      partRanges = rangesFromGroups(pageGroups(this.source.pdf, options.groupBy), options.intro);
+    } else if (options.ranges) {
+      partRanges = rangesFromContentRanges(this.pageCount(), options.ranges, options.intro);
+    } else {
+      partRanges = calculateRanges(this.pageCount(), options.parts, options.intro, options.tooFewPages);
+    }
+    checkPartCount(partRanges.length, options.maxParts || DEFAULT_MAX_PARTS);
+
+    const partInfos = [];
+    for (const partRange of partRanges) {
+      // Calculate output filename
+      const outputFile = formatPartName(options.nameTemplate || DEFAULT_NAME_TEMPLATE, {
+        basename: options.outputBasename,
+        index: partRange.index,
+        total: partRanges.length,
+        group: partRange.group
+      });
+      // Rendered parts are folders of page images, named like the PDF without its extension
+      const outputName = options.render ? path.basename(outputFile, path.extname(outputFile)) : outputFile;
+      const outputPath = isS3Url(options.outputDir) ? joinS3Url(options.outputDir, outputName) : path.join(options.outputDir, outputName);
+      checkOutputPath(options.outputDir, outputPath);
+      partInfos.push({ ...partRange, outputPath });
+    }
+    return partInfos;
+  }
+
+  /**
+   * Plans the parts and leaves out the damaged, blank and duplicate pages the options drop
+   *
+   * @param {Object} options Split options
+   * @returns {Promise<Object>} Planned parts (partInfos), warnings about what the parts lose or
+   *   repeat, and the duplicate pages found, if they were looked for (duplicates)
+   */
+  async planPages(options) {
+    const partInfos = this.plan(options);
+    // Things the parts lose or repeat compared to the source, such as attachments
+    const warnings = [];
+    if (!options.groupBy && !options.ranges && partInfos.length < options.parts) {
+      warnings.push({
+        code: 'partsReduced',
+        message: `Only ${partInfos.length} content page(s) to split, so ${partInfos.length} part(s) were written instead of ${options.parts}`
+      });
+    }
+    if (options.repair) {
+      const pageNumbers = partInfos.flatMap(partInfo => [...partInfo.pages.intro, ...partInfo.pages.content]);
+      warnings.push(...dropBrokenPages(partInfos, await findBrokenPages(this.backend, this.source, pageNumbers)));
+    }
+    if (options.blankPages) {
+      const blankPages = await findBlankPages(this.sourceBytes, options.blankPages.threshold);
+      warnings.push(...dropBlankPages(partInfos, blankPages));
+    }
+    // Listed in the result whenever they were looked for
+    const duplicates = {};
+    if (options.duplicatePages && options.duplicatePages !== 'ignore') {
+      duplicates.duplicatePages = findDuplicatePages(this.source.pdf);
+      warnings.push(...applyDuplicatePageMode(partInfos, duplicates.duplicatePages, options.duplicatePages));
+    }
+    return { partInfos, warnings, duplicates };
+  }
+
+  /**
+   * Builds the parts one at a time, each only when the consumer asks for the
+   * next one, e.g. to upload a part before the following one is built.
+   * Nothing is written to the output directory, and stopping the iteration
+   * stops building
+   *
+   * @param {Object} options Same options as splitPdf; render, textSidecar, thumbnails, threads and dryRun are not used
+   * @returns {AsyncGenerator<{part: Object, bytes: Uint8Array}>} Each planned part (index, pages, outputPath)
+   *   with its bytes, encrypted and linearized as requested
+   */
+  async *parts(options) {
+    this.checkInMemory('Building parts one at a time');
+    const eventSink = resolveEventSink(options);
+    let partInfos;
+    let partsToBuild;
+    try {
+      const planned = await this.planPages(options);
+      partInfos = planned.partInfos;
+      partsToBuild = this.describeParts(partInfos, options, planned.warnings);
+      for (const warning of planned.warnings) {
+        eventSink.emit({ event: 'warning', ...warning });
+      }
+    } catch (error) {
+      throw toSplitError(error);
+    }
+
+    for (const [position, partToBuild] of partsToBuild.entries()) {
+      let bytes;
+      try {
+        throwIfCancelled(options.signal);
+        const builtBytes = await buildPart(this.backend, this.source, partToBuild, { signal: options.signal });
+        bytes = await this.encryptOutput(builtBytes, options);
+      } catch (error) {
+        throw toSplitError(error);
+      }
+      yield { part: partInfos[position], bytes };
+    }
+  }
+
+  /**
+   * Builds a new document from pages of the source PDF
+   *
+   * @param {Array<number>} pageNumbers 1-based page numbers, in output order
+   * @param {Object} [options] Output options (flatten, crossLinks, metadata, bookmarks, pageLabels, attachments, breakSignatures, keepPdfa, pdfVersion, documentIds, pruneResources, encryption and decryptOutput, as for split)
+   * @returns {Promise<Uint8Array>} Bytes of the new document
+   */
+  async extract(pageNumbers, options = {}) {
+    this.checkInMemory('Extracting pages');
+    try {
+      const sourcePdf = this.source.pdf;
+      const sourcePageIndices = pageNumbers.map(pageNumber => pageNumber - 1);
+      signatureWarnings(sourcePdf, options.breakSignatures);
+      const bytes = await buildPart(this.backend, this.source, {
+        pages: { intro: [], content: pageNumbers },
+        flatten: !!options.flatten,
+        links: partLinks(readLinks(sourcePdf), sourcePageIndices, { policy: options.crossLinks }),
+        metadata: partMetadata(readMetadata(sourcePdf), { index: 1, total: 1 }, options),
+        outline: partOutline(readOutline(sourcePdf), sourcePageIndices, options.bookmarks),
+        destinations: partDestinations(readNamedDestinations(sourcePdf), sourcePageIndices),
+        pageLabels: partPageLabels(options.pageLabels === false ? null : readPageLabels(sourcePdf), sourcePageIndices),
+        attachments: partAttachments(readAttachments(sourcePdf).attachments, 1, options.attachments),
+        pdfa: options.keepPdfa ? readPdfaIdentification(sourcePdf) : null,
+        pdfVersion: options.pdfVersion || readDocumentVersion(sourcePdf),
+        documentId: partDocumentId(options.documentIds, this.sourceDocumentId(), 1),
+        pruneResources: options.pruneResources !== false
+      });
+      return await this.encryptOutput(bytes, options);
+    } catch (error) {
+      throw toSplitError(error);
+    }
+  }
+
+  /**
+   * @returns {string} Hex ID of the source, or a hash of its bytes if it has none, for deriving part IDs
+   */
+  sourceDocumentId() {
+    return readDocumentId(this.source.pdf) || crypto.createHash('md5').update(this.sourceBytes).digest('hex');
+  }
+
+  /**
+   * Adds what each part carries over from the source to the part descriptions:
+   * the title, author, subject, keywords and creation date, the bookmarks,
+   * links and named destinations pointing into its pages, its page labels,
+   * its cover and contents pages, the attachments the attachment policy gives it and,
+   * if requested, the PDF/A identification, and the PDF version and document
+   * ID to declare, the watermark, header, footer and Bates numbers to stamp, the page size and the imposition. The document IDs and Bates
+   * numbers are also added to the planned parts, so the split result lists them
+   *
+   * @param {Array<Object>} partInfos Parts planned with plan()
+   * @param {Object} options Split options
+   * @param {Array<Object>} warnings Warnings about what the parts lose or repeat, added to
+   * @returns {Array<Object>} Part descriptions for buildPart
+   */
+  describeParts(partInfos, options, warnings) {
+    const sourcePdf = this.source.pdf;
+    warnings.push(...signatureWarnings(sourcePdf, options.breakSignatures));
+    warnings.push(...structureWarnings(sourcePdf));
+    const sourcePdfa = readPdfaIdentification(sourcePdf);
+    const pdfVersion = options.pdfVersion || readDocumentVersion(sourcePdf);
+    const sourceId = this.sourceDocumentId();
+    warnings.push(...pdfaWarnings(sourcePdfa, !!options.keepPdfa));
+    const sourceMetadata = readMetadata(sourcePdf);
+    const sourceOutline = readOutline(sourcePdf);
+    const sourceLinks = readLinks(sourcePdf);
+    const sourceDestinations = readNamedDestinations(sourcePdf);
+    const sourcePageLabels = options.pageLabels === false ? null : readPageLabels(sourcePdf);
+    const sourceAttachments = readAttachments(sourcePdf);
+    warnings.push(...attachmentWarnings(sourceAttachments, options.attachments));
+
+    const partsToBuild = [];
+    // Deterministic parts carry a fixed date instead of the time they were built
+    const timestamp = options.deterministic ? deterministicDate() : null;
+    // Every cover shows the same date
+    const coverDate = timestamp || new Date();
+    // Bates numbers continue from part to part, counting every page written
+    let nextBatesNumber = options.bates ? options.bates.start : 0;
+    // Where each content page ends up, for links from other parts
+    const locations = new Map();
+    for (const partInfo of partInfos) {
+      const part = { index: partInfo.index, total: partInfos.length, basename: options.outputBasename };
+      partInfo.documentId = partDocumentId(options.documentIds, sourceId, partInfo.index);
+      const sourcePageIndices = [];
+      for (const pageNumber of [...partInfo.pages.intro, ...partInfo.pages.content]) {
+        sourcePageIndices.push(pageNumber - 1);
+      }
+      const outline = partOutline(sourceOutline, sourcePageIndices, options.bookmarks);
+      const cover = options.cover
+        ? partCover(options.cover, sourceMetadata, part, [...partInfo.pages.intro, ...partInfo.pages.content], coverDate)
+        : null;
+      const coverPageCount = cover ? 1 : 0;
+      const toc = options.tableOfContents
+        ? {
+          heading: `${sourceMetadata.title || options.outputBasename}: part ${partInfo.index} of ${partInfos.length}`,
+          entries: tocEntries(outline, partInfo.pages),
+          coverPageCount
+        }
+        : null;
+
+      const tocPageCount = toc ? countTocPages(toc.entries.length, sourcePdf.getPage(sourcePageIndices[0]).getHeight()) : 0;
+      const partPageCount = coverPageCount + tocPageCount + sourcePageIndices.length;
+      // Imposed parts have a sheet for several pages
+      const sheets = options.nUp ? nUpSheets(partPageCount, options.nUp)
+        : options.booklet ? bookletSheets(partPageCount)
+          : null;
+      for (const [position, pageNumber] of partInfo.pages.content.entries()) {
+        const pageIndex = coverPageCount + tocPageCount + partInfo.pages.intro.length + position;
+        locations.set(pageNumber - 1, {
+          file: path.basename(partInfo.outputPath),
+          pageIndex: sheets ? sheetIndexOf(sheets, pageIndex) : pageIndex
+        });
+      }
+
+      let bates = null;
+      if (options.bates) {
+        bates = { prefix: options.bates.prefix, digits: options.bates.digits, first: nextBatesNumber };
+        nextBatesNumber += partPageCount;
+        partInfo.batesNumbers = {
+          first: formatBatesNumber(bates, bates.first),
+          last: formatBatesNumber(bates, nextBatesNumber - 1)
+        };
+      }
+
+      partsToBuild.push({
+        ...partInfo,
+        sourcePageIndices,
+        metadata: partMetadata(sourceMetadata, part, options),
+        outline,
+        destinations: partDestinations(sourceDestinations, sourcePageIndices),
+        pageLabels: partPageLabels(sourcePageLabels, sourcePageIndices),
+        flatten: !!options.flatten,
+        toc,
+        cover,
+        attachments: partAttachments(sourceAttachments.attachments, partInfo.index, options.attachments),
+        pdfa: options.keepPdfa ? sourcePdfa : null,
+        pdfVersion,
+        watermark: options.watermark || null,
+        headerFooter: options.headerFooter ? partHeaderFooter(options.headerFooter, sourceMetadata, part) : null,
+        bates,
+        pageSize: options.pageSize || null,
+        nUp: options.nUp || null,
+        booklet: !!options.booklet,
+        pruneResources: options.pruneResources !== false,
+        optimize: options.optimize || null,
+        timestamp: timestamp ? timestamp.toISOString() : null
+      });
+    }
+
+    for (const partToBuild of partsToBuild) {
+      partToBuild.links = partLinks(sourceLinks, partToBuild.sourcePageIndices, {
+        policy: options.crossLinks,
+        locate: (sourceIndex) => locations.get(sourceIndex)
+      });
+      delete partToBuild.sourcePageIndices;
+    }
+    return partsToBuild;
+  }
+
+  /**
+   * Splits the source PDF into parts (see splitPdf for the options)
+   *
+   * @param {Object} options Configuration options
+   * @returns {Promise<Object>} Split result with the parts' page ranges and output paths, a timing summary and warnings
+   */
+  async split(options) {
+    // Route every event through a single sink so the library never writes to the console
+    const eventSink = resolveEventSink(options);
+    // A time limit cancels the split, which is then reported as timed out
+    const timeout = options.timeout ? createTimeoutSignal(options.signal, options.timeout) : null;
+    if (timeout) {
+      options = { ...options, signal: timeout.signal };
+    }
+    // Memory use is sampled for memory events and checked against the soft limit
+    let memory = null;
+    let lowMemory = false;
+    const memoryWarnings = [];
+    if (options.memoryInterval || options.memoryLimit) {
+      const memoryAbortController = new AbortController();
+      if (options.signal) {
+        if (options.signal.aborted) {
+          memoryAbortController.abort();
+        }
+        options.signal.addEventListener('abort', () => memoryAbortController.abort(), { once: true });
+      }
+      memory = startMemoryMonitor({
+        interval: options.memoryInterval,
+        limit: options.memoryLimit,
+        emit: event => eventSink.emit(event),
+        onExceeded: (sample) => {
+          if (options.memoryLimitAction === 'abort') {
+            memoryAbortController.abort();
+            return;
+          }
+          lowMemory = true;
+          const warning = {
+            code: 'memoryLimitExceeded',
+            message: `Memory use of ${toMegabytes(sample.rss)} MB exceeded the limit of ${toMegabytes(options.memoryLimit)} MB; ${options.threads > 1 ? 'building the remaining parts on a single thread' : 'parts are already built one at a time'}`
+          };
+          memoryWarnings.push(warning);
+          eventSink.emit({ event: 'warning', ...warning });
+        }
+      });
+      options = { ...options, signal: memoryAbortController.signal };
+    }
+
+    try {
+      if (options.deterministic && this.encryptedSource && !options.decryptOutput) {
+        const error = new Error('Parts keeping the encryption of the source get random encryption keys and cannot be deterministic; add the decrypt output option.');
+        error.code = 2; // Invalid option
+        throw error;
+      }
+      const { partInfos, warnings, duplicates } = await this.planPages(options);
+
+      // For dry-run, just return the part info without creating files
+      if (options.dryRun) {
+        return { parts: partInfos, ...duplicates };
+      }
+
+      // Parts built on disk carry nothing over from the source but their pages
+      let partsToBuild = this.onDiskSource ? partInfos : this.describeParts(partInfos, options, warnings);
+      for (const warning of warnings) {
+        eventSink.emit({ event: 'warning', ...warning });
+      }
+
+      // Resume from a checkpoint: parts it records whose files are still there aren't built again
+      const plan = options.checkpoint ? checkpointPlan(this.sourceBytes, partInfos) : null;
+      const completedParts = [];
+      if (plan) {
+        for (const index of await readCheckpoint(options.checkpoint, plan)) {
+          const partInfo = partInfos.find(candidate => candidate.index === index);
+          if (await fs.access(partInfo.outputPath).then(() => true, () => false)) {
+            completedParts.push(index);
+          }
+        }
+        if (completedParts.length > 0) {
+          partsToBuild = partsToBuild.filter(partToBuild => !completedParts.includes(partToBuild.index));
+          eventSink.emit({ event: 'resumed', checkpoint: options.checkpoint, completedParts: [...completedParts] });
+        }
+        await writeCheckpoint(options.checkpoint, plan, completedParts);
+      }
+
+      // Total number of pages to write across all parts, used for overall progress
+      let totalPagesToWrite = 0;
+      for (const partToBuild of partsToBuild) {
+        totalPagesToWrite += partToBuild.pages.intro.length + partToBuild.pages.content.length;
+      }
+      let pagesWritten = 0;
+      let bytesWritten = 0;
+      const startTime = Date.now();
+
+      // Files written so far, removed again if the split is cancelled (except parts recorded in the checkpoint)
+      const writtenPaths = [];
+      // Parts skipped because building them took longer than the part time limit
+      const timedOutParts = [];
+      // Parts for object storage are uploaded as they are built
+      const createWriter = options.createWriter
+        || (isS3Url(options.outputDir) ? partInfo => createUploadStream(partInfo.outputPath) : null);
+      // Pages copied into the parts being built, reported at most every PART_PROGRESS_INTERVAL ms per part
+      const partProgressTimes = new Map();
+      const onPageCopied = (partInfo, pagesCopied) => {
+        const partPages = partInfo.pages.intro.length + partInfo.pages.content.length;
+        const now = Date.now();
+        if (pagesCopied < partPages && now - (partProgressTimes.get(partInfo.index) || 0) < PART_PROGRESS_INTERVAL) {
+          return;
+        }
+        partProgressTimes.set(partInfo.index, now);
+        eventSink.emit({ event: 'partProgress', part: partInfo.index, totalParts: partInfos.length, pagesCopied, pages: partPages });
+      };
+
+      try {
+        // Build the parts with qpdf from the source file in low memory mode, or
+        // concurrently on worker threads if requested; with time limits on
+        // workers too, as a hanging build can only be stopped there
+        const builtParts = this.onDiskSource
+          ? buildPartsOnDisk(this.onDiskSource.filePath, partsToBuild, {
+            password: this.onDiskSource.password,
+            decryptOutput: options.decryptOutput,
+            linearize: options.linearize,
+            signal: options.signal
+          })
+          : options.threads > 1 || options.partTimeout || options.timeout
+            ? buildPartsInWorkers(this.sourceBytes, partsToBuild, {
+              // Library callers may leave threads out when only a time limit asks for workers
+              threads: Math.max(1, options.threads || 1),
+              backendName: this.backend.name,
+              sharedSource: this.backend.share ? this.backend.share(this.source) : null,
+              signal: options.signal,
+              partTimeout: options.partTimeout,
+              lowMemory: () => lowMemory,
+              onPageCopied
+            })
+            : buildPartsInProcess(this.backend, this.source, partsToBuild, options.signal, onPageCopied);
+
+        for await (const { partInfo, bytes: builtBytes, byteCount: builtByteCount, buildMs, timedOut } of builtParts) {
+          // A part that takes too long to build is skipped, and the split moves on
+          if (timedOut) {
+            timedOutParts.push(partInfo.index);
+            eventSink.emit({ event: 'partTimedOut', part: partInfo.index, totalParts: partInfos.length, timeoutMs: options.partTimeout });
+            continue;
+          }
+          // Parts built on disk are already in their files
+          if (this.onDiskSource) {
+            writtenPaths.push(partInfo.outputPath);
+          }
+          // Save the part to a file or the caller's stream, encrypted if requested, or render it to images
+          throwIfCancelled(options.signal);
+          const writeStart = Date.now();
+          let byteCount;
+          if (this.onDiskSource) {
+            byteCount = builtByteCount;
+          } else if (createWriter) {
+            const bytes = await this.encryptOutput(builtBytes, options);
+            await writeToStream(await createWriter(partInfo), bytes);
+            byteCount = bytes.length;
+          } else if (options.render) {
+            writtenPaths.push(partInfo.outputPath);
+            const rendered = await renderImages(builtBytes, partInfo.outputPath, options.render);
+            partInfo.images = rendered.images;
+            byteCount = rendered.bytes;
+          } else {
+            writtenPaths.push(partInfo.outputPath);
+            const bytes = await this.encryptOutput(builtBytes, options);
+            await fs.writeFile(partInfo.outputPath, bytes);
+            byteCount = bytes.length;
+          }
+          if (options.textSidecar) {
+            partInfo.textPath = textSidecarPath(partInfo.outputPath);
+            writtenPaths.push(partInfo.textPath);
+            await writeTextSidecar(builtBytes, partInfo.textPath);
+          }
+          if (options.thumbnails) {
+            partInfo.thumbnail = await renderThumbnail(builtBytes, options.thumbnails.size);
+          }
+          // Memory use peaks between parts, when the interval timer may not have had a chance to run
+          if (memory) {
+            memory.check();
+          }
+          if (plan) {
+            completedParts.push(partInfo.index);
+            await writeCheckpoint(options.checkpoint, plan, completedParts);
+            writtenPaths.length = 0;
+          }
+
+          // Report progress, with timing and size metrics for capacity planning
+          const partPages = partInfo.pages.intro.length + partInfo.pages.content.length;
+          const durationMs = buildMs + (Date.now() - writeStart);
+          bytesWritten += byteCount;
+          eventSink.emit({
+            event: 'partComplete',
+            part: partInfo.index,
+            totalParts: partInfos.length,
+            outputPath: partInfo.outputPath,
+            pages: partPages,
+            pageRange: formatPageRanges([...partInfo.pages.intro, ...partInfo.pages.content]),
+            bytes: byteCount,
+            durationMs,
+            pagesPerSecond: toPagesPerSecond(partPages, durationMs)
+          });
+
+          // Report overall progress with an ETA extrapolated from the pages written so far
+          pagesWritten += partPages;
+          const elapsedMs = Date.now() - startTime;
+          const etaMs = Math.round(elapsedMs / pagesWritten * (totalPagesToWrite - pagesWritten));
+          eventSink.emit({
+            event: 'progress',
+            part: partInfo.index,
+            totalParts: partInfos.length,
+            pagesWritten,
+            totalPages: totalPagesToWrite,
+            percent: Math.round(pagesWritten / totalPagesToWrite * 1000) / 10,
+            elapsedMs,
+            etaMs
+          });
+        }
+      } catch (error) {
+        if (error.code === CANCELLED_EXIT_CODE) {
+          // Clean up the partial output of a cancelled split
+          for (const writtenPath of writtenPaths) {
+            await fs.rm(writtenPath, { force: true, recursive: true });
+          }
+          if (memory && memory.exceeded() && options.memoryLimitAction === 'abort') {
+            eventSink.emit({ event: 'memoryLimitExceeded', ...memory.exceeded(), limit: options.memoryLimit, removedFiles: writtenPaths });
+            throw memoryLimitError(memory.exceeded(), options.memoryLimit);
+          }
+          if (timeout && timeout.timedOut()) {
+            eventSink.emit({ event: 'timedOut', timeoutMs: options.timeout, removedFiles: writtenPaths });
+            throw splitTimeoutError(options.timeout);
+          }
+          eventSink.emit({
+            event: 'cancelled',
+            removedFiles: writtenPaths
+          });
+        }
+        throw error;
+      }
+
+      warnings.push(...memoryWarnings);
+      if (timedOutParts.length > 0) {
+        if (timedOutParts.length === partsToBuild.length && completedParts.length === 0) {
+          const error = new Error(`Every part timed out after ${options.partTimeout} ms.`);
+          error.code = TIMEOUT_EXIT_CODE;
+          throw error;
+        }
+        const warning = {
+          code: 'partsTimedOut',
+          message: `Skipped part(s) ${timedOutParts.sort((a, b) => a - b).join(', ')}, which took longer than ${options.partTimeout} ms to build`
+        };
+        warnings.push(warning);
+        eventSink.emit({ event: 'warning', ...warning });
+        for (let i = partInfos.length - 1; i >= 0; i--) {
+          if (timedOutParts.includes(partInfos[i].index)) {
+            partInfos.splice(i, 1);
+          }
+        }
+      }
+
+      const outputFiles = [];
+      for (const partInfo of partInfos) {
+        outputFiles.push(partInfo.outputPath);
+      }
+      const durationMs = Date.now() - startTime;
+      const summary = {
+        durationMs,
+        pages: pagesWritten,
+        bytes: bytesWritten,
+        pagesPerSecond: toPagesPerSecond(pagesWritten, durationMs)
+      };
+      // A finished split has nothing to resume, unless parts timed out
+      if (plan && timedOutParts.length === 0) {
+        await fs.rm(options.checkpoint, { force: true });
+      }
+      eventSink.emit({
+        event: 'complete',
+        parts: partInfos.length,
+        outputFiles,
+        summary
+      });
+
+      return { parts: partInfos, summary, warnings, ...duplicates };
+    } catch (error) {
+      // Memory or time running out while the parts are planned
+      if (error.code === CANCELLED_EXIT_CODE && memory && memory.exceeded() && options.memoryLimitAction === 'abort') {
+        throw memoryLimitError(memory.exceeded(), options.memoryLimit);
+      }
+      if (error.code === CANCELLED_EXIT_CODE && timeout && timeout.timedOut()) {
+        throw splitTimeoutError(options.timeout);
+      }
+      throw toSplitError(error);
+    } finally {
+      if (timeout) {
+        timeout.clear();
+      }
+      if (memory) {
+        memory.stop();
+      }
+    }
+  }
+}
+
+module.exports = {
+  Splitter,
+  toSplitError
+};
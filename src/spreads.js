@@ -0,0 +1,122 @@
+// Scanned two-page spreads. Book scans often hold two book pages side by side
+// on one landscape page; splitting the spreads turns each of them into a left
+// and a right page before the parts are planned, so page numbers, ranges and
+// parts all refer to the book pages.
+
+const { PDFDict, PDFDocument, PDFName, PDFPage } = require('pdf-lib');
+
+// Page boundaries replaced by the half of the page that is kept
+const OTHER_BOXES = ['BleedBox', 'TrimBox', 'ArtBox'];
+
+/**
+ * Computes the left and right halves of a page as shown, in the page's own coordinates
+ *
+ * @param {{x: number, y: number, width: number, height: number}} box Crop box of the page
+ * @param {number} rotation Clockwise page rotation: 0, 90, 180 or 270
+ * @returns {Array<Object>} Boxes of the left and the right half
+ */
+function halves(box, rotation) {
+  const { x, y, width, height } = box;
+  const lowerX = { x, y, width: width / 2, height };
+  const upperX = { x: x + width / 2, y, width: width / 2, height };
+  const lowerY = { x, y, width, height: height / 2 };
+  const upperY = { x, y: y + height / 2, width, height: height / 2 };
+  // Rotated pages show another side of the page on the left
+  return {
+    0: [lowerX, upperX],
+    90: [lowerY, upperY],
+    180: [upperX, lowerX],
+    270: [upperY, lowerY]
+  }[rotation];
+}
+
+/**
+ * Sets the visible area of a page
+ *
+ * @param {PDFPage} page Page to crop
+ * @param {Object} box New media and crop box
+ */
+function cropPage(page, box) {
+  page.setMediaBox(box.x, box.y, box.width, box.height);
+  page.setCropBox(box.x, box.y, box.width, box.height);
+  for (const name of OTHER_BOXES) {
+    page.node.delete(PDFName.of(name));
+  }
+}
+
+/**
+ * Creates a second page showing the same content as a page. The content
+ * and resources are shared rather than copied, so scanned images are not
+ * stored twice.
+ *
+ * @param {PDFDocument} pdf Document holding the page
+ * @param {PDFPage} page Page to duplicate
+ * @returns {PDFPage} New page, not yet inserted into the page tree
+ */
+function duplicatePage(pdf, page) {
+  const context = pdf.context;
+  const node = page.node.clone(context);
+  // Inherited entries may differ where the new page is inserted, so they are set on the page itself
+  const resources = page.node.Resources();
+  if (resources) {
+    node.set(PDFName.of('Resources'), resources);
+  }
+  node.set(PDFName.of('MediaBox'), page.node.MediaBox());
+  node.set(PDFName.of('Rotate'), context.obj(page.getRotation().angle));
+  const ref = context.register(node);
+
+  // Annotations belong to a single page, so the new page gets its own copies;
+  // form field widgets stay on the original page only
+  const annots = page.node.Annots();
+  if (annots) {
+    const copies = [];
+    for (let i = 0; i < annots.size(); i++) {
+      const annot = annots.lookupMaybe(i, PDFDict);
+      if (!annot || annot.lookup(PDFName.of('Subtype')) === PDFName.of('Widget')) {
+        continue;
+      }
+      const copy = annot.clone(context);
+      if (copy.has(PDFName.of('P'))) {
+        copy.set(PDFName.of('P'), ref);
+      }
+      copies.push(context.register(copy));
+    }
+    node.set(PDFName.of('Annots'), context.obj(copies));
+  }
+  node.delete(PDFName.of('Parent'));
+  return PDFPage.of(node, ref, pdf);
+}
+
+/**
+ * Splits every landscape page (as shown) into its left and right halves
+ *
+ * @param {Uint8Array} sourceBytes Bytes of an unencrypted PDF
+ * @returns {Promise<Uint8Array>} Bytes of the PDF with two pages per spread, or the original bytes without spreads
+ */
+async function splitSpreads(sourceBytes) {
+  const pdf = await PDFDocument.load(sourceBytes, { updateMetadata: false });
+  let spreadCount = 0;
+  // Walk backwards so inserted pages don't shift the pages still to visit
+  for (let pageIndex = pdf.getPageCount() - 1; pageIndex >= 0; pageIndex--) {
+    const page = pdf.getPage(pageIndex);
+    const rotation = ((page.getRotation().angle % 360) + 360) % 360;
+    const box = page.getCropBox();
+    const [shownWidth, shownHeight] = rotation % 180 === 0 ? [box.width, box.height] : [box.height, box.width];
+    if (shownWidth <= shownHeight || !halves(box, rotation)) {
+      continue;
+    }
+
+    const [left, right] = halves(box, rotation);
+    // The original page becomes the left half, so bookmarks and links to the spread open its left page
+    const rightPage = duplicatePage(pdf, page);
+    cropPage(page, left);
+    cropPage(rightPage, right);
+    pdf.insertPage(pageIndex + 1, rightPage);
+    spreadCount += 1;
+  }
+  return spreadCount > 0 ? pdf.save() : sourceBytes;
+}
+
+module.exports = {
+  splitSpreads
+};
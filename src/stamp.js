@@ -0,0 +1,148 @@
+// Text and images stamped onto output pages (Bates numbers, watermarks,
+// footers). Stamps are placed relative to the page as viewers show it, so
+// they stay upright and inside the visible area on rotated and cropped pages.
+
+const { StandardFonts, degrees, rgb } = require('pdf-lib');
+
+const STAMP_POSITIONS = ['top-left', 'top-center', 'top-right', 'center', 'bottom-left', 'bottom-center', 'bottom-right'];
+// Distance of stamps from the edges of the page, in points
+const MARGIN = 24;
+
+// Each document embeds the stamp font once, however many stamps it gets
+const fonts = new WeakMap();
+
+/**
+ * Embeds the font stamps are drawn with
+ *
+ * @param {PDFDocument} pdf Document to stamp
+ * @returns {Promise<PDFFont>} Helvetica, embedded once per document
+ */
+async function embedStampFont(pdf) {
+  if (!fonts.has(pdf)) {
+    fonts.set(pdf, await pdf.embedFont(StandardFonts.Helvetica));
+  }
+  return fonts.get(pdf);
+}
+
+/**
+ * Replaces characters the standard font can't encode
+ *
+ * @param {PDFFont} font Font the text is drawn with
+ * @param {string} text Text to draw
+ * @returns {string} Text that can be drawn
+ */
+function toEncodableText(font, text) {
+  const characterSet = new Set(font.getCharacterSet());
+  let encodable = '';
+  for (const character of text) {
+    encodable += characterSet.has(character.codePointAt(0)) ? character : '?';
+  }
+  return encodable;
+}
+
+/**
+ * @param {PDFPage} page Page
+ * @returns {number} Clockwise page rotation: 0, 90, 180 or 270
+ */
+function pageRotationOf(page) {
+  return ((page.getRotation().angle % 360) + 360) % 360;
+}
+
+/**
+ * Measures a page as viewers show it, after cropping and rotation
+ *
+ * @param {PDFPage} page Page
+ * @returns {{width: number, height: number}} Size in points
+ */
+function shownPageSize(page) {
+  const box = page.getCropBox();
+  return pageRotationOf(page) % 180 === 0
+    ? { width: box.width, height: box.height }
+    : { width: box.height, height: box.width };
+}
+
+/**
+ * Places a stamp box on a page
+ *
+ * @param {PDFPage} page Page to stamp
+ * @param {number} width Width of the stamp in points
+ * @param {number} height Height of the stamp in points
+ * @param {string} position Where the stamp goes on the page as shown (see STAMP_POSITIONS)
+ * @param {number} rotate Counterclockwise rotation of the stamp in degrees
+ * @returns {{x: number, y: number, rotate: Object}} Lower left corner of the stamp and its rotation in page coordinates
+ */
+function placeStamp(page, width, height, position, rotate) {
+  const box = page.getCropBox();
+  const pageRotation = pageRotationOf(page);
+  const { width: shownWidth, height: shownHeight } = shownPageSize(page);
+
+  const [vertical, horizontal] = position === 'center' ? ['center', 'center'] : position.split('-');
+  const centerX = horizontal === 'left' ? MARGIN + width / 2
+    : horizontal === 'right' ? shownWidth - MARGIN - width / 2
+      : shownWidth / 2;
+  const centerY = vertical === 'top' ? shownHeight - MARGIN - height / 2
+    : vertical === 'bottom' ? MARGIN + height / 2
+      : shownHeight / 2;
+
+  // Lower left corner, so that the (possibly rotated) stamp is centered on the point
+  const angle = rotate * Math.PI / 180;
+  const u = centerX - width / 2 * Math.cos(angle) + height / 2 * Math.sin(angle);
+  const v = centerY - width / 2 * Math.sin(angle) - height / 2 * Math.cos(angle);
+
+  // Map the point from the page as shown back to the page's own coordinates
+  const toPage = {
+    0: () => [box.x + u, box.y + v],
+    90: () => [box.x + box.width - v, box.y + u],
+    180: () => [box.x + box.width - u, box.y + box.height - v],
+    270: () => [box.x + v, box.y + box.height - u]
+  };
+  const [x, y] = (toPage[pageRotation] || toPage[0])();
+  return { x, y, rotate: degrees(pageRotation + rotate) };
+}
+
+/**
+ * Draws a line of text onto a page
+ *
+ * @param {PDFPage} page Page to stamp
+ * @param {string} text Text to draw
+ * @param {Object} stampOptions Stamp options
+ * @param {PDFFont} stampOptions.font Font, e.g. from embedStampFont
+ * @param {number} stampOptions.size Font size in points
+ * @param {string} stampOptions.position Where the text goes on the page as shown (see STAMP_POSITIONS)
+ * @param {number} [stampOptions.opacity] Opacity from 0 to 1 (defaults to 1)
+ * @param {number} [stampOptions.rotate] Counterclockwise rotation of the text in degrees (defaults to 0)
+ * @param {Object} [stampOptions.color] pdf-lib color (defaults to black)
+ */
+function drawStampText(page, text, stampOptions) {
+  const { font, size, position, opacity = 1, rotate = 0, color = rgb(0, 0, 0) } = stampOptions;
+  const encodable = toEncodableText(font, text);
+  const width = font.widthOfTextAtSize(encodable, size);
+  const height = font.heightAtSize(size, { descender: false });
+  page.drawText(encodable, { ...placeStamp(page, width, height, position, rotate), size, font, color, opacity });
+}
+
+/**
+ * Draws an image onto a page
+ *
+ * @param {PDFPage} page Page to stamp
+ * @param {PDFImage} image Embedded image
+ * @param {Object} stampOptions Stamp options
+ * @param {number} stampOptions.width Width of the image in points; the height keeps its aspect ratio
+ * @param {string} stampOptions.position Where the image goes on the page as shown (see STAMP_POSITIONS)
+ * @param {number} [stampOptions.opacity] Opacity from 0 to 1 (defaults to 1)
+ * @param {number} [stampOptions.rotate] Counterclockwise rotation of the image in degrees (defaults to 0)
+ */
+function drawStampImage(page, image, stampOptions) {
+  const { width, position, opacity = 1, rotate = 0 } = stampOptions;
+  const height = width * image.height / image.width;
+  page.drawImage(image, { ...placeStamp(page, width, height, position, rotate), width, height, opacity });
+}
+
+module.exports = {
+  STAMP_POSITIONS,
+  embedStampFont,
+  toEncodableText,
+  shownPageSize,
+  drawStampText,
+  drawStampImage
+};
@@ -0,0 +1,46 @@
+// Reads a source PDF piped into the process, e.g. `curl ... | splitpdf --file -`.
+// The bytes are spooled to a temporary file rather than held in memory, so
+// large downloads work like any other source file.
+
+const os = require('os');
+const path = require('path');
+const fs = require('fs');
+const { pipeline } = require('stream/promises');
+
+// File name standing for standard input
+const STDIN_PATH = '-';
+// Base name of the parts of a source read from standard input
+const STDIN_BASENAME = 'stdin';
+
+/**
+ * Copies a stream, normally standard input, into a temporary file
+ *
+ * @param {Readable} input Stream to read
+ * @returns {Promise<{filePath: string, remove: Function}>} Path of the file, and remove() deleting it again
+ */
+async function spoolToTempFile(input) {
+  const tempDir = await fs.promises.mkdtemp(path.join(os.tmpdir(), 'splitpdf-stdin-'));
+  const filePath = path.join(tempDir, `${STDIN_BASENAME}.pdf`);
+  const remove = () => fs.rmSync(tempDir, { recursive: true, force: true });
+  try {
+    await pipeline(input, fs.createWriteStream(filePath));
+  } catch (error) {
+    remove();
+    const ioError = new Error(`I/O error: can't read the PDF from standard input: ${error.message}`);
+    ioError.code = 3; // I/O error
+    throw ioError;
+  }
+  if (fs.statSync(filePath).size === 0) {
+    remove();
+    const emptyError = new Error('I/O error: no PDF was piped to standard input.');
+    emptyError.code = 3; // I/O error
+    throw emptyError;
+  }
+  return { filePath, remove };
+}
+
+module.exports = {
+  STDIN_PATH,
+  STDIN_BASENAME,
+  spoolToTempFile
+};
@@ -0,0 +1,38 @@
+// Tagged (accessible) PDFs. The structure tree that screen readers follow is
+// a document-level tree pointing into the page content, and it is not copied
+// into the parts, so splitting a tagged source is reported as a warning.
+
+const { PDFBool, PDFDict, PDFName } = require('pdf-lib');
+
+/**
+ * Tells whether a document is tagged
+ *
+ * @param {PDFDocument} pdf Loaded document
+ * @returns {boolean} True if the document has a structure tree or is marked as tagged
+ */
+function isTagged(pdf) {
+  const markInfo = pdf.catalog.lookupMaybe(PDFName.of('MarkInfo'), PDFDict);
+  const marked = markInfo && markInfo.lookup(PDFName.of('Marked'));
+  return pdf.catalog.has(PDFName.of('StructTreeRoot')) || (marked instanceof PDFBool && marked.asBoolean());
+}
+
+/**
+ * Describes the structure lost by splitting, for the split warnings
+ *
+ * @param {PDFDocument} pdf Source document
+ * @returns {Array<{code: string, message: string}>} Warnings, empty for untagged documents
+ */
+function structureWarnings(pdf) {
+  if (!isTagged(pdf)) {
+    return [];
+  }
+  return [{
+    code: 'structureLost',
+    message: 'The PDF is tagged, but the parts do not keep its structure tree, so screen readers lose headings, reading order and alternate text'
+  }];
+}
+
+module.exports = {
+  isTagged,
+  structureWarnings
+};
@@ -0,0 +1,30 @@
+// Plain-text sidecars: the text of each part written next to it, for search
+// indexers that would otherwise open every part again. The text is extracted
+// by poppler's pdftotext (see src/poppler.js); pages are separated by form feeds.
+
+const path = require('path');
+const { runPoppler, withTemporaryPdf } = require('./poppler');
+
+/**
+ * @param {string} outputPath Path of the part file (or image folder)
+ * @returns {string} Path of its text sidecar: the same name with a .txt extension
+ */
+function textSidecarPath(outputPath) {
+  const extension = path.extname(outputPath);
+  return `${extension.toLowerCase() === '.pdf' ? outputPath.slice(0, -extension.length) : outputPath}.txt`;
+}
+
+/**
+ * Extracts the text of a part into a sidecar file
+ *
+ * @param {Uint8Array} pdfBytes Bytes of the unencrypted part
+ * @param {string} textPath Path of the text file to write
+ */
+async function writeTextSidecar(pdfBytes, textPath) {
+  await withTemporaryPdf(pdfBytes, pdfPath => runPoppler('pdftotext', ['-enc', 'UTF-8', pdfPath, textPath]));
+}
+
+module.exports = {
+  textSidecarPath,
+  writeTextSidecar
+};
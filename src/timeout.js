@@ -0,0 +1,59 @@
+// Time limits for splits and for building single parts, so a pathological
+// PDF that makes building hang can't stall a whole batch, job file or daemon.
+// Parts are built on worker threads while a limit applies, since a worker
+// stuck in a build can be terminated where the main thread could not be
+// interrupted.
+
+// Exit code when a split runs out of time
+const TIMEOUT_EXIT_CODE = 9;
+
+/**
+ * Creates a signal that fires when the caller's signal does or when the time
+ * limit is reached
+ *
+ * @param {AbortSignal|null} signal Caller's signal, if any
+ * @param {number} timeoutMs Time limit in milliseconds
+ * @returns {{signal: AbortSignal, timedOut: Function, clear: Function}} Combined signal; timedOut() tells
+ *   whether the limit was reached, clear() stops the timer
+ */
+function createTimeoutSignal(signal, timeoutMs) {
+  const abortController = new AbortController();
+  let timedOut = false;
+  const timer = setTimeout(() => {
+    timedOut = true;
+    abortController.abort();
+  }, timeoutMs);
+  const onAbort = () => abortController.abort();
+  if (signal) {
+    if (signal.aborted) {
+      abortController.abort();
+    }
+    signal.addEventListener('abort', onAbort);
+  }
+  return {
+    signal: abortController.signal,
+    timedOut: () => timedOut,
+    clear() {
+      clearTimeout(timer);
+      if (signal) {
+        signal.removeEventListener('abort', onAbort);
+      }
+    }
+  };
+}
+
+/**
+ * @param {number} timeoutMs Time limit of the split in milliseconds
+ * @returns {Error} Error with code 9
+ */
+function splitTimeoutError(timeoutMs) {
+  const error = new Error(`Split timed out after ${timeoutMs} ms.`);
+  error.code = TIMEOUT_EXIT_CODE;
+  return error;
+}
+
+module.exports = {
+  TIMEOUT_EXIT_CODE,
+  createTimeoutSignal,
+  splitTimeoutError
+};
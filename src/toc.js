@@ -0,0 +1,166 @@
+// Table-of-contents pages listing what a part contains, with links to the
+// listed pages. Entries come from the part's bookmarks or, without bookmarks,
+// from the source page ranges the part was built from.
+
+const { StandardFonts, PDFName } = require('pdf-lib');
+
+const MARGIN = 56;
+const TITLE_SIZE = 16;
+const FONT_SIZE = 11;
+const LINE_HEIGHT = 18;
+const INDENT = 14;
+// Letter size, for parts without pages to take the size from
+const DEFAULT_PAGE_SIZE = [612, 792];
+
+/**
+ * Lists the entries of a part's table of contents
+ *
+ * @param {Array<Object>} outline Outline of the part (see src/outline.js)
+ * @param {Object} pages 1-based intro and content page numbers of the part
+ * @returns {Array<{title: string, pageIndex: number, level: number}>} Entries with 0-based page indices in the part
+ */
+function tocEntries(outline, pages) {
+  const entries = [];
+  const addItems = (items, level) => {
+    for (const item of items) {
+      entries.push({ title: item.title, pageIndex: item.pageIndex, level });
+      addItems(item.children, level + 1);
+    }
+  };
+  addItems(outline, 0);
+  if (entries.length > 0) {
+    return entries;
+  }
+
+  // Without bookmarks, list the source pages
+  const describe = (label, pageNumbers) => {
+    const first = pageNumbers[0];
+    const last = pageNumbers[pageNumbers.length - 1];
+    return first === last ? `${label}: page ${first}` : `${label}: pages ${first}-${last}`;
+  };
+  if (pages.intro.length > 0) {
+    entries.push({ title: describe('Intro', pages.intro), pageIndex: 0, level: 0 });
+  }
+  if (pages.content.length > 0) {
+    entries.push({ title: describe('Content', pages.content), pageIndex: pages.intro.length, level: 0 });
+  }
+  return entries;
+}
+
+/**
+ * Counts the contents pages needed for a number of entries
+ *
+ * @param {number} entryCount Number of entries
+ * @param {number} pageHeight Height of the contents pages in points
+ * @returns {number} Number of contents pages
+ */
+function countTocPages(entryCount, pageHeight) {
+  // The heading takes two lines on the first page
+  return Math.max(Math.ceil((entryCount + 2) / linesPerPage(pageHeight)), 1);
+}
+
+/**
+ * @param {number} pageHeight Height of the contents pages in points
+ * @returns {number} Number of lines that fit on a contents page
+ */
+function linesPerPage(pageHeight) {
+  return Math.max(Math.floor((pageHeight - 2 * MARGIN) / LINE_HEIGHT), 3);
+}
+
+/**
+ * Shortens text to a width, replacing characters the standard font can't encode
+ *
+ * @param {PDFFont} font Font the text is drawn with
+ * @param {Set<number>} characterSet Code points the font can encode
+ * @param {string} text Text to fit
+ * @param {number} maxWidth Available width in points
+ * @returns {string} Text that can be drawn
+ */
+function fitText(font, characterSet, text, maxWidth) {
+  let fitted = '';
+  for (const character of text) {
+    fitted += characterSet.has(character.codePointAt(0)) ? character : '?';
+  }
+  if (font.widthOfTextAtSize(fitted, FONT_SIZE) <= maxWidth) {
+    return fitted;
+  }
+  while (fitted.length > 0 && font.widthOfTextAtSize(`${fitted}...`, FONT_SIZE) > maxWidth) {
+    fitted = fitted.slice(0, -1);
+  }
+  return `${fitted}...`;
+}
+
+/**
+ * Inserts table-of-contents pages at the start of a part.
+ * Call it after the outline is written: bookmarks refer to page objects, so
+ * they keep pointing at the right pages when the contents pages are inserted.
+ *
+ * @param {PDFDocument} pdf Part document
+ * @param {Object} toc Table of contents
+ * @param {string} toc.heading Heading of the first contents page, e.g. "Contents of part 2 of 5"
+ * @param {Array<Object>} toc.entries Entries created with tocEntries
+ * @param {number} [toc.coverPageCount] Number of cover pages that will be inserted before the contents pages
+ * @returns {Promise<number>} Number of contents pages inserted
+ */
+async function addTocPages(pdf, toc) {
+  const font = await pdf.embedFont(StandardFonts.Helvetica);
+  const characterSet = new Set(font.getCharacterSet());
+  const pages = pdf.getPages();
+  const [width, height] = pages.length > 0
+    ? [pages[0].getWidth(), pages[0].getHeight()]
+    : DEFAULT_PAGE_SIZE;
+
+  const lineCount = linesPerPage(height);
+  const tocPageCount = countTocPages(toc.entries.length, height);
+
+  let tocPage = null;
+  let tocPageIndex = -1;
+  let line = 0;
+  const nextPage = () => {
+    tocPageIndex += 1;
+    tocPage = pdf.insertPage(tocPageIndex, [width, height]);
+    line = 0;
+  };
+
+  nextPage();
+  tocPage.drawText(fitText(font, characterSet, toc.heading, width - 2 * MARGIN), {
+    x: MARGIN,
+    y: height - MARGIN - TITLE_SIZE,
+    size: TITLE_SIZE,
+    font
+  });
+  line = 2;
+
+  for (const entry of toc.entries) {
+    if (line >= lineCount) {
+      nextPage();
+    }
+    const y = height - MARGIN - FONT_SIZE - line * LINE_HEIGHT;
+    const x = MARGIN + entry.level * INDENT;
+    // Page numbers as shown by viewers, counting the cover and contents pages
+    const pageLabel = String(entry.pageIndex + 1 + tocPageCount + (toc.coverPageCount || 0));
+    const pageLabelWidth = font.widthOfTextAtSize(pageLabel, FONT_SIZE);
+    const title = fitText(font, characterSet, entry.title, width - MARGIN - x - pageLabelWidth - INDENT);
+
+    tocPage.drawText(title, { x, y, size: FONT_SIZE, font });
+    tocPage.drawText(pageLabel, { x: width - MARGIN - pageLabelWidth, y, size: FONT_SIZE, font });
+
+    // The whole line links to the entry's page
+    const link = pdf.context.register(pdf.context.obj({
+      Type: 'Annot',
+      Subtype: 'Link',
+      Rect: [x, y - 4, width - MARGIN, y + FONT_SIZE],
+      Border: [0, 0, 0],
+      Dest: [pages[entry.pageIndex].ref, PDFName.of('Fit')]
+    }));
+    tocPage.node.addAnnot(link);
+    line += 1;
+  }
+  return tocPageIndex + 1;
+}
+
+module.exports = {
+  countTocPages,
+  tocEntries,
+  addTocPages
+};
@@ -0,0 +1,135 @@
+// Structural check of a PDF before it enters a pipeline. The document is
+// loaded and every page walked, and each problem found is reported instead
+// of thrown, so batch jobs can quarantine bad files up front rather than
+// fail in the middle of a split.
+
+const fs = require('fs/promises');
+const { PDFArray, PDFDict, PDFDocument, PDFName, PDFNumber, PDFStream } = require('pdf-lib');
+const { toSplitError } = require('./splitter');
+const { PASSWORD_EXIT_CODE, decryptPdf } = require('./encryption');
+
+// More pages than this are taken to be a damaged page tree
+const DEFAULT_MAX_PAGES = 100000;
+// Largest page side PDF readers accept, in points (200 inches)
+const MAX_PAGE_SIDE = 14400;
+
+/**
+ * Checks the page tree entries and content streams of a page
+ *
+ * @param {PDFContext} context Document context
+ * @param {PDFPage} page Page to check
+ * @param {number} number 1-based page number
+ * @returns {Array<Object>} Problems found ({code, message, page})
+ */
+function pageProblems(context, page, number) {
+  const problems = [];
+  const report = (code, message) => problems.push({ code, message: `Page ${number}: ${message}`, page: number });
+
+  let width;
+  let height;
+  try {
+    ({ width, height } = page.getMediaBox());
+  } catch (error) {
+    report('invalid-media-box', `the media box can't be read (${error.message}).`);
+  }
+  if (width !== undefined && !(width > 0 && height > 0)) {
+    report('invalid-media-box', `the media box is ${width} by ${height} points.`);
+  } else if (width > MAX_PAGE_SIDE || height > MAX_PAGE_SIDE) {
+    report('page-too-large', `the page is ${width} by ${height} points, more than ${MAX_PAGE_SIDE} on a side.`);
+  }
+
+  // Contents is a stream or an array of streams; pages without it are blank
+  const contents = page.node.get(PDFName.of('Contents'));
+  if (contents) {
+    const resolved = context.lookup(contents);
+    const streams = resolved instanceof PDFArray ? resolved.asArray().map(item => context.lookup(item)) : [resolved];
+    if (!streams.every(stream => stream instanceof PDFStream)) {
+      report('missing-contents', 'a content stream is missing or not a stream.');
+    }
+  }
+
+  const rotate = page.node.lookup(PDFName.of('Rotate'));
+  if (rotate !== undefined && !(rotate instanceof PDFNumber && rotate.asNumber() % 90 === 0)) {
+    report('invalid-rotation', 'the rotation is not a multiple of 90 degrees.');
+  }
+  return problems;
+}
+
+/**
+ * Checks that a PDF can be opened and that its page tree is sound
+ *
+ * @param {string} filePath Path to the PDF
+ * @param {Object} [validateOptions] Validation options
+ * @param {string} [validateOptions.password] Password of an encrypted PDF (requires qpdf); without it,
+ *   encrypted files are reported as such
+ * @param {number} [validateOptions.maxPages] Page count above which the page tree is taken to be damaged (defaults to 100000)
+ * @returns {Promise<Object>} Report with valid, pageCount (null if the file can't be opened) and
+ *   problems ({code, message} and the 1-based page for page problems); only a file that can't be read throws
+ */
+async function validatePdf(filePath, validateOptions = {}) {
+  const maxPages = validateOptions.maxPages || DEFAULT_MAX_PAGES;
+  let fileBytes;
+  try {
+    fileBytes = await fs.readFile(filePath);
+  } catch (error) {
+    throw toSplitError(error);
+  }
+
+  const problems = [];
+  const report = (pageCount) => ({ filePath, valid: problems.length === 0, pageCount, problems });
+  let pdf;
+  try {
+    const password = validateOptions.password;
+    const sourceBytes = password === undefined || password === null ? fileBytes : await decryptPdf(fileBytes, password);
+    pdf = await PDFDocument.load(sourceBytes, { ignoreEncryption: true, updateMetadata: false });
+  } catch (error) {
+    if (error.code === 2) {
+      throw error; // qpdf not found
+    }
+    problems.push(error.code === PASSWORD_EXIT_CODE
+      ? { code: 'wrong-password', message: error.message }
+      : { code: 'unreadable', message: `The document can't be opened: ${error.message}` });
+    return report(null);
+  }
+  if (pdf.isEncrypted) {
+    problems.push({ code: 'encrypted', message: 'The document is encrypted; give its password to check it.' });
+  }
+
+  // The page count the page tree declares, checked before walking a possibly huge tree
+  const pagesDict = pdf.catalog.lookupMaybe(PDFName.of('Pages'), PDFDict);
+  const declaredCount = pagesDict && pagesDict.lookup(PDFName.of('Count'));
+  if (!pagesDict) {
+    problems.push({ code: 'unreadable', message: 'The document has no page tree.' });
+    return report(null);
+  }
+  if (declaredCount instanceof PDFNumber && declaredCount.asNumber() > maxPages) {
+    problems.push({ code: 'too-many-pages', message: `The page tree declares ${declaredCount.asNumber()} pages, more than ${maxPages}.` });
+    return report(null);
+  }
+
+  let pages;
+  try {
+    pages = pdf.getPages();
+  } catch (error) {
+    problems.push({ code: 'invalid-page-tree', message: `The pages can't be listed: ${error.message}` });
+    return report(null);
+  }
+  if (pages.length === 0) {
+    problems.push({ code: 'no-pages', message: 'The document has no pages.' });
+  }
+  if (declaredCount instanceof PDFNumber && declaredCount.asNumber() !== pages.length) {
+    problems.push({
+      code: 'page-count-mismatch',
+      message: `The page tree declares ${declaredCount.asNumber()} pages but has ${pages.length}.`
+    });
+  }
+  for (const [index, page] of pages.entries()) {
+    problems.push(...pageProblems(pdf.context, page, index + 1));
+  }
+  return report(pages.length);
+}
+
+module.exports = {
+  DEFAULT_MAX_PAGES,
+  validatePdf
+};
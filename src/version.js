@@ -0,0 +1,59 @@
+// PDF version of the parts. New documents default to the version pdf-lib
+// writes (1.7); parts declare the version of their source instead, or the
+// version the caller asks for.
+
+const { PDFHeader, PDFName } = require('pdf-lib');
+
+const PDF_VERSIONS = ['1.0', '1.1', '1.2', '1.3', '1.4', '1.5', '1.6', '1.7', '2.0'];
+// Object streams, which make files smaller, were introduced in PDF 1.5
+const OBJECT_STREAMS_VERSION = '1.5';
+
+/**
+ * Compares two PDF versions
+ *
+ * @param {string} a Version such as 1.4
+ * @param {string} b Version such as 1.7
+ * @returns {number} Negative if a is older than b, 0 if they are equal, positive if a is newer
+ */
+function compareVersions(a, b) {
+  const [aMajor, aMinor] = a.split('.').map(Number);
+  const [bMajor, bMinor] = b.split('.').map(Number);
+  return aMajor !== bMajor ? aMajor - bMajor : aMinor - bMinor;
+}
+
+/**
+ * Reads the version of a document: the header version, or the catalog's
+ * Version entry where an incremental update raised it
+ *
+ * @param {PDFDocument} pdf Loaded document
+ * @returns {string} Version such as 1.7
+ */
+function readDocumentVersion(pdf) {
+  const match = /^%PDF-(\d+\.\d+)/.exec(pdf.context.header.toString());
+  let version = match && PDF_VERSIONS.includes(match[1]) ? match[1] : '1.7';
+  const catalogVersion = pdf.catalog.lookup(PDFName.of('Version'));
+  if (catalogVersion instanceof PDFName && PDF_VERSIONS.includes(catalogVersion.decodeText())
+    && compareVersions(catalogVersion.decodeText(), version) > 0) {
+    version = catalogVersion.decodeText();
+  }
+  return version;
+}
+
+/**
+ * Sets the version a new document declares
+ *
+ * @param {PDFDocument} pdf Document to update
+ * @param {string} version Version such as 1.4
+ * @returns {Object} Save options for the version: documents older than 1.5 are saved without object streams
+ */
+function writePdfVersion(pdf, version) {
+  const [major, minor] = version.split('.').map(Number);
+  pdf.context.header = PDFHeader.forVersion(major, minor);
+  return { useObjectStreams: compareVersions(version, OBJECT_STREAMS_VERSION) >= 0 };
+}
+
+module.exports = {
+  PDF_VERSIONS,
+  readDocumentVersion,
+  writePdfVersion
+};
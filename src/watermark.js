@@ -0,0 +1,76 @@
+// Watermarks: a text or an image stamped onto every page of the parts, e.g.
+// "COPY - distributed to X", with a position, opacity and rotation.
+
+const { embedStampFont, shownPageSize, drawStampText, drawStampImage } = require('./stamp');
+
+const DEFAULT_WATERMARK = {
+  position: 'center',
+  opacity: 0.3,
+  rotate: 45,
+  size: 48
+};
+// Width of image watermarks as a share of the page width
+const IMAGE_WIDTH_SHARE = 0.5;
+
+/**
+ * Tells the format of an image from its first bytes
+ *
+ * @param {Uint8Array} bytes Image file contents
+ * @returns {string|null} png, jpg, or null for other formats
+ */
+function detectImageType(bytes) {
+  if (bytes[0] === 0x89 && bytes[1] === 0x50 && bytes[2] === 0x4e && bytes[3] === 0x47) {
+    return 'png';
+  }
+  if (bytes[0] === 0xff && bytes[1] === 0xd8) {
+    return 'jpg';
+  }
+  return null;
+}
+
+/**
+ * Stamps a watermark onto every page of a part
+ *
+ * @param {PDFDocument} pdf Part document
+ * @param {Object} watermark Watermark settings
+ * @param {string} [watermark.text] Text to stamp
+ * @param {Uint8Array} [watermark.imageBytes] PNG or JPEG image to stamp instead of text
+ * @param {string} [watermark.imageType] png or jpg
+ * @param {string} watermark.position Where the watermark goes (see STAMP_POSITIONS in src/stamp.js)
+ * @param {number} watermark.opacity Opacity from 0 to 1
+ * @param {number} watermark.rotate Counterclockwise rotation in degrees
+ * @param {number} watermark.size Font size of text watermarks in points
+ */
+async function stampWatermark(pdf, watermark) {
+  if (watermark.imageBytes) {
+    const image = watermark.imageType === 'png'
+      ? await pdf.embedPng(watermark.imageBytes)
+      : await pdf.embedJpg(watermark.imageBytes);
+    for (const page of pdf.getPages()) {
+      drawStampImage(page, image, {
+        width: shownPageSize(page).width * IMAGE_WIDTH_SHARE,
+        position: watermark.position,
+        opacity: watermark.opacity,
+        rotate: watermark.rotate
+      });
+    }
+    return;
+  }
+
+  const font = await embedStampFont(pdf);
+  for (const page of pdf.getPages()) {
+    drawStampText(page, watermark.text, {
+      font,
+      size: watermark.size,
+      position: watermark.position,
+      opacity: watermark.opacity,
+      rotate: watermark.rotate
+    });
+  }
+}
+
+module.exports = {
+  DEFAULT_WATERMARK,
+  detectImageType,
+  stampWatermark
+};
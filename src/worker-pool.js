@@ -0,0 +1,171 @@
+// Builds parts concurrently on a pool of worker threads.
+
+const path = require('path');
+const { Worker } = require('worker_threads');
+const { createQueueSink } = require('./events');
+const { CANCELLED_EXIT_CODE } = require('./part-builder');
+
+const WORKER_PATH = path.join(__dirname, 'part-worker.js');
+
+/**
+ * Builds parts on worker threads, yielding each part as soon as it is ready.
+ * Parts may complete out of order.
+ *
+ * @param {Uint8Array} sourceBytes Bytes of the source PDF, shared with every worker
+ * @param {Array<Object>} partInfos Parts to build
+ * @param {Object} options Pool options
+ * @param {number} options.threads Maximum number of worker threads
+ * @param {string} options.backendName Name of the PDF backend the workers use
+ * @param {Object} options.sharedSource Optional already opened source shared by the backend
+ * @param {AbortSignal} options.signal Optional signal cancelling the remaining builds
+ * @param {Function} options.lowMemory Optional; while it returns true, idle workers are shut down until one is left
+ * @param {Function} options.onPageCopied Optional; called with a part being built and the number of its pages copied so far
+ * @param {number} options.partTimeout Optional time limit for building one part in milliseconds; the worker
+ *   building a part for longer is terminated and replaced, and the part is yielded with timedOut set
+ * @returns {AsyncGenerator<{partInfo: Object, bytes: Uint8Array, buildMs: number, timedOut: boolean}>} Built parts and how long building each took
+ */
+async function* buildPartsInWorkers(sourceBytes, partInfos, options) {
+  // Nothing to build, e.g. when a checkpoint records every part: no worker
+  // would ever reply, so waiting for replies would never end
+  if (partInfos.length === 0) {
+    return;
+  }
+
+  // Copy the source once into shared memory instead of once per worker,
+  // unless the workers can use the source opened by the main thread
+  let sharedBytes = null;
+  if (!options.sharedSource) {
+    sharedBytes = new SharedArrayBuffer(sourceBytes.length);
+    new Uint8Array(sharedBytes).set(sourceBytes);
+  }
+
+  const partInfosByIndex = new Map();
+  const pendingParts = [];
+  for (const partInfo of partInfos) {
+    partInfosByIndex.set(partInfo.index, partInfo);
+    pendingParts.push(partInfo);
+  }
+
+  // Worker replies and failures all arrive through one queue
+  const messages = createQueueSink();
+  const onAbort = () => messages.emit({ cancelled: true });
+  if (options.signal) {
+    options.signal.addEventListener('abort', onAbort);
+  }
+
+  // Time limits of the parts being built, by worker
+  const partTimers = new Map();
+
+  // Hand the next pending part to a worker that has become idle
+  function startNextPart(worker) {
+    // Under memory pressure, workers beyond the first stop once they are idle
+    if (options.lowMemory && options.lowMemory() && workers.length > 1) {
+      workers.splice(workers.indexOf(worker), 1);
+      worker.removeAllListeners();
+      worker.terminate();
+      return;
+    }
+    const partInfo = pendingParts.shift();
+    if (partInfo) {
+      worker.postMessage(partInfo);
+      if (options.partTimeout) {
+        partTimers.set(worker, setTimeout(() => timeOutPart(worker, partInfo), options.partTimeout));
+      }
+    }
+  }
+
+  // A worker stuck in a build can only be stopped by terminating it
+  function timeOutPart(worker, partInfo) {
+    partTimers.delete(worker);
+    workers.splice(workers.indexOf(worker), 1);
+    worker.removeAllListeners();
+    worker.terminate();
+    messages.emit({ partInfo, timedOut: true, buildMs: options.partTimeout });
+    if (pendingParts.length > 0) {
+      startWorker();
+    }
+  }
+
+  const workers = [];
+  function startWorker() {
+    const worker = new Worker(WORKER_PATH, {
+      workerData: {
+        sourceBytes: sharedBytes,
+        sharedSource: options.sharedSource,
+        backendName: options.backendName
+      }
+    });
+    worker.on('message', (message) => {
+      if (message.pageProgress) {
+        if (options.onPageCopied) {
+          options.onPageCopied(partInfosByIndex.get(message.pageProgress.part), message.pageProgress.pagesCopied);
+        }
+        return;
+      }
+      clearTimeout(partTimers.get(worker));
+      partTimers.delete(worker);
+      messages.emit(message);
+      startNextPart(worker);
+    });
+    worker.on('error', (error) => {
+      messages.emit({ error: { message: error.message, code: error.code } });
+    });
+    // A worker that dies without an error (e.g. killed for running out of
+    // memory) would otherwise leave its part unanswered forever
+    worker.on('exit', (exitCode) => {
+      if (exitCode !== 0) {
+        messages.emit({ error: { message: `A worker thread building parts stopped with exit code ${exitCode}` } });
+      }
+    });
+    workers.push(worker);
+    startNextPart(worker);
+  }
+
+  const workerCount = Math.min(options.threads, partInfos.length);
+  for (let i = 0; i < workerCount; i++) {
+    startWorker();
+  }
+
+  try {
+    let remainingParts = partInfos.length;
+    for await (const message of messages) {
+      if (message.cancelled) {
+        const cancelError = new Error('Split cancelled');
+        cancelError.code = CANCELLED_EXIT_CODE;
+        throw cancelError;
+      }
+      if (message.error) {
+        const workerError = new Error(message.error.message);
+        workerError.code = message.error.code;
+        throw workerError;
+      }
+
+      yield {
+        partInfo: partInfosByIndex.get(message.partInfo.index),
+        bytes: message.bytes,
+        buildMs: message.buildMs,
+        timedOut: !!message.timedOut
+      };
+
+      remainingParts -= 1;
+      if (remainingParts === 0) {
+        break;
+      }
+    }
+  } finally {
+    if (options.signal) {
+      options.signal.removeEventListener('abort', onAbort);
+    }
+    for (const timer of partTimers.values()) {
+      clearTimeout(timer);
+    }
+    for (const worker of workers) {
+      worker.removeAllListeners();
+      await worker.terminate();
+    }
+  }
+}
+
+module.exports = {
+  buildPartsInWorkers
+};
@@ -2,12 +2,15 @@ const { describe, it, before, afterEach } = require('node:test');
 const assert = require('node:assert');
 const path = require('node:path');
 const fs = require('node:fs');
-const { spawn } = require('node:child_process');
+const { spawn, spawnSync } = require('node:child_process');
+const crypto = require('node:crypto');
 const { promisify } = require('node:util');
-const { PDFDocument } = require('pdf-lib');
+const { PDFArray, PDFDocument, PDFHeader, PDFName, PDFString, concatTransformationMatrix, decodePDFRawStream, degrees, drawObject, popGraphicsState, pushGraphicsState, rgb } = require('pdf-lib');
 
 const CLI_PATH = path.join(__dirname, '../src/cli.js');
 const TEST_PDF_PATH = path.join(__dirname, 'fixtures/test.pdf');
+const FORMS_PDF_PATH = path.join(__dirname, 'fixtures/forms.pdf');
+const STRUCTURED_PDF_PATH = path.join(__dirname, 'fixtures/structured.pdf');
 const TEST_OUTPUT_DIR = path.join(__dirname, 'temp');
 
 // Helper function to run the CLI
@@ -59,6 +62,39 @@ async function getPdfPageCount(filePath) {
   }
 }
 
+// Check whether the qpdf executable is available for the qpdf backend
+function isQpdfInstalled() {
+  const result = spawnSync(process.env.SPLITPDF_QPDF || 'qpdf', ['--version']);
+  return !result.error && result.status === 0;
+}
+
+// Check whether poppler's pdftoppm is available for rendering parts to images
+function isPdftoppmInstalled() {
+  const pdftoppm = process.env.SPLITPDF_POPPLER ? path.join(process.env.SPLITPDF_POPPLER, 'pdftoppm') : 'pdftoppm';
+  const result = spawnSync(pdftoppm, ['-v']);
+  return !result.error && result.status === 0;
+}
+
+// Check whether poppler's pdftotext is available for text sidecars
+function isPdftotextInstalled() {
+  const pdftotext = process.env.SPLITPDF_POPPLER ? path.join(process.env.SPLITPDF_POPPLER, 'pdftotext') : 'pdftotext';
+  const result = spawnSync(pdftotext, ['-v']);
+  return !result.error && result.status === 0;
+}
+
+// Parse the JSON lines printed by the CLI, ignoring any non-JSON output
+function parseJsonLines(output) {
+  const events = [];
+  for (const line of output.split('\n')) {
+    try {
+      events.push(JSON.parse(line));
+    } catch (err) {
+      // Not a JSON line
+    }
+  }
+  return events;
+}
+
 describe('PDF Splitter CLI', () => {
   before(async () => {
     // Create test output directory
@@ -104,6 +140,19 @@ describe('PDF Splitter CLI', () => {
     assert.strictEqual(code, 2, 'Exits with code 2 for invalid intro parameter format');
   });
   
+  it('prints the package version with --version', async () => {
+    const { code, stdout } = await runCLI(['--version']);
+    const { version } = JSON.parse(await fs.promises.readFile(path.join(__dirname, '..', 'package.json'), 'utf8'));
+    assert.strictEqual(code, 0);
+    assert.strictEqual(stdout.trim(), version);
+  });
+  
+  it('returns error code 2 for unknown options', async () => {
+    const { code, stderr } = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--no-such-option']);
+    assert.strictEqual(code, 2);
+    assert.ok(stderr.includes('--no-such-option'));
+  });
+  
   it('returns error code 3 when file not found', async () => {
     const { code } = await runCLI(['--file', 'nonexistent.pdf', '--parts', '2']);
     assert.strictEqual(code, 3);
@@ -282,4 +331,2971 @@ describe('PDF Splitter CLI', () => {
       `Content pages should be evenly distributed: ${part1ContentPages}, ${part2ContentPages}, ${part3ContentPages}`
     );
   });
-}); 
\ No newline at end of file
+  
+  it('emits part completion events as JSON lines with --json-events', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { code, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_events',
+      '--json-events'
+    ]);
+    
+    assert.strictEqual(code, 0);
+    
+    const events = parseJsonLines(stderr);
+    const partEvents = [];
+    for (const event of events) {
+      if (event.event === 'partComplete') {
+        partEvents.push(event);
+      }
+    }
+    
+    assert.strictEqual(partEvents.length, 2, 'One partComplete event is emitted per part');
+    for (const event of partEvents) {
+      assert.ok(event.pages > 0 && event.bytes > 0, 'Reports the pages and bytes of each part');
+      assert.ok(event.durationMs >= 0 && event.pagesPerSecond >= 0, 'Reports timing metrics for each part');
+    }
+    assert.strictEqual(events[events.length - 1].event, 'complete', 'The last event is complete');
+    assert.strictEqual(events[events.length - 1].parts, 2);
+  });
+  
+  it('emits overall progress with percentage and ETA with --json-events', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { code, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '3',
+      '--intro', '1:2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_progress',
+      '--json-events'
+    ]);
+    
+    assert.strictEqual(code, 0);
+    
+    const progressEvents = [];
+    for (const event of parseJsonLines(stderr)) {
+      if (event.event === 'progress') {
+        progressEvents.push(event);
+      }
+    }
+    
+    assert.strictEqual(progressEvents.length, 3, 'One progress event is emitted per part');
+    const sourcePdfPageCount = await getPdfPageCount(TEST_PDF_PATH);
+    const lastProgress = progressEvents[progressEvents.length - 1];
+    // Intro pages are written once per part
+    assert.strictEqual(lastProgress.totalPages, sourcePdfPageCount + 2 * (3 - 1));
+    assert.strictEqual(lastProgress.pagesWritten, lastProgress.totalPages);
+    assert.strictEqual(lastProgress.percent, 100);
+    assert.strictEqual(lastProgress.etaMs, 0);
+    assert.ok(progressEvents[0].percent < progressEvents[1].percent, 'Percentage grows with each part');
+    assert.ok(typeof progressEvents[0].elapsedMs === 'number');
+  });
+  
+  it('prints human-readable progress without JSON events in verbose mode', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { code, stdout } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_human',
+      '--verbose'
+    ]);
+    
+    assert.strictEqual(code, 0);
+    assert.ok(stdout.includes('Wrote part 1 of 2'), 'Verbose output describes each written part');
+    assert.strictEqual(parseJsonLines(stdout).length, 0, 'Verbose output contains no JSON events');
+  });
+  
+  it('draws a progress bar per part being built and an overall bar on a terminal', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    // Progress bars are only drawn when stdout and stderr are terminals
+    const preloadPath = path.join(TEST_OUTPUT_DIR, 'fake-tty.js');
+    await fs.promises.writeFile(preloadPath, 'process.stdout.isTTY = true;\nprocess.stderr.isTTY = true;\n');
+    const { code, stderr } = await new Promise((resolve, reject) => {
+      const child = spawn('node', ['--require', preloadPath, CLI_PATH,
+        '--file', TEST_PDF_PATH,
+        '--parts', '2',
+        '--threads', '2',
+        '--output-dir', TEST_OUTPUT_DIR,
+        '--output-basename', 'test_progress_bars'
+      ]);
+      let output = '';
+      child.stderr.on('data', (data) => {
+        output += data.toString();
+      });
+      child.on('close', exitCode => resolve({ code: exitCode, stderr: output }));
+      child.on('error', reject);
+    });
+    await fs.promises.rm(preloadPath, { force: true });
+    
+    assert.strictEqual(code, 0);
+    assert.match(stderr, /part [12]\/2 \[[#.]+\] \d+\/\d+ pages/, 'Draws a bar for each part being built');
+    assert.match(stderr, /part [12]\/2 \[#+\] \S*test_progress_bars_part[12]\.pdf/, 'Lists each part once written');
+    assert.match(stderr, /\[[#.]+\] 100% \(\d+\/\d+ pages\)/, 'Draws the overall bar');
+  });
+  
+  it('prints nothing but errors with --quiet and every event with -vv', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const quiet = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_quiet',
+      '-q'
+    ]);
+    assert.strictEqual(quiet.code, 0);
+    assert.strictEqual(quiet.stdout, '');
+    assert.strictEqual(quiet.stderr, '');
+    
+    const chatty = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_chatty',
+      '-vv'
+    ]);
+    assert.strictEqual(chatty.code, 0);
+    assert.ok(chatty.stdout.includes('Wrote part 1 of 2'));
+    assert.ok(chatty.stdout.includes('complete: '), 'Prints the complete event at -vv');
+    
+    const conflicting = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '-q', '-v']);
+    assert.strictEqual(conflicting.code, 2);
+  });
+  
+  it('keeps stdout for the final result when JSON events are enabled', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { code, stdout, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_result',
+      '--json-events'
+    ]);
+    
+    assert.strictEqual(code, 0);
+    
+    // The whole of stdout is a single JSON document
+    const result = JSON.parse(stdout);
+    assert.strictEqual(result.parts.length, 2);
+    assert.strictEqual(result.parts[0].outputPath, path.join(TEST_OUTPUT_DIR, 'test_result_part1.pdf'));
+    assert.ok(result.summary.pages > 0 && result.summary.bytes > 0, 'The result summarizes the split');
+    assert.ok(parseJsonLines(stderr).length > 0, 'Events are written to stderr');
+  });
+  
+  it('splits a PDF on multiple worker threads', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const outputBasename = 'test_threads';
+    const { code, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '4',
+      '--intro', '1:2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', outputBasename,
+      '--threads', '3'
+    ]);
+    
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const sourcePdfPageCount = await getPdfPageCount(TEST_PDF_PATH);
+    let totalPageCount = 0;
+    for (let i = 1; i <= 4; i++) {
+      totalPageCount += await getPdfPageCount(path.join(TEST_OUTPUT_DIR, `${outputBasename}_part${i}.pdf`));
+    }
+    assert.strictEqual(totalPageCount, sourcePdfPageCount + 2 * (4 - 1));
+  });
+  
+  it('returns error code 2 for a non-positive thread count', async () => {
+    const { code } = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--threads', '0']);
+    assert.strictEqual(code, 2);
+  });
+  
+  it('splits a PDF with the qpdf backend', async function() {
+    if (!await fileExists(TEST_PDF_PATH) || !isQpdfInstalled()) {
+      this.skip('Test PDF or qpdf not found.');
+      return;
+    }
+    
+    const outputBasename = 'test_qpdf';
+    const { code, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--intro', '1:3',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', outputBasename,
+      '--backend', 'qpdf'
+    ]);
+    
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const sourcePdfPageCount = await getPdfPageCount(TEST_PDF_PATH);
+    const part1PageCount = await getPdfPageCount(path.join(TEST_OUTPUT_DIR, `${outputBasename}_part1.pdf`));
+    const part2PageCount = await getPdfPageCount(path.join(TEST_OUTPUT_DIR, `${outputBasename}_part2.pdf`));
+    assert.strictEqual(part1PageCount + part2PageCount, sourcePdfPageCount + 3);
+  });
+  
+  it('splits an encrypted PDF with --password and returns error code 6 without it', async function() {
+    // Skip if test PDF doesn't exist or qpdf is missing
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    if (!isQpdfInstalled()) {
+      this.skip('qpdf is not installed.');
+      return;
+    }
+    
+    const encryptedPath = path.join(TEST_OUTPUT_DIR, 'test_encrypted_source.pdf');
+    spawnSync(process.env.SPLITPDF_QPDF || 'qpdf', ['--encrypt', 'secret', 'owner', '256', '--', TEST_PDF_PATH, encryptedPath]);
+    
+    const missing = await runCLI(['--file', encryptedPath, '--parts', '2', '--output-dir', TEST_OUTPUT_DIR]);
+    assert.strictEqual(missing.code, 6, 'Exits with code 6 without a password');
+    
+    const wrong = await runCLI(['--file', encryptedPath, '--parts', '2', '--output-dir', TEST_OUTPUT_DIR, '--password', 'wrong']);
+    assert.strictEqual(wrong.code, 6, 'Exits with code 6 for a wrong password');
+    
+    const { code, stderr } = await runCLI([
+      '--file', encryptedPath,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_decrypted',
+      '--password', 'secret',
+      '--decrypt-output'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    const partPdf = await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, 'test_decrypted_part1.pdf')));
+    assert.ok(partPdf.getPageCount() > 0);
+  });
+  
+  it('keeps the encryption of an encrypted source unless --decrypt-output is given', async function() {
+    // Skip if test PDF doesn't exist or qpdf is missing
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    if (!isQpdfInstalled()) {
+      this.skip('qpdf is not installed.');
+      return;
+    }
+    
+    const encryptedPath = path.join(TEST_OUTPUT_DIR, 'test_protected_source.pdf');
+    spawnSync(process.env.SPLITPDF_QPDF || 'qpdf', ['--encrypt', 'secret', 'owner', '256', '--', TEST_PDF_PATH, encryptedPath]);
+    
+    const { code, stderr } = await runCLI([
+      '--file', encryptedPath,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_protected',
+      '--password', 'secret'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const partPdf = await PDFDocument.load(
+      await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, 'test_protected_part1.pdf')),
+      { ignoreEncryption: true }
+    );
+    assert.strictEqual(partPdf.isEncrypted, true, 'Parts keep the source encryption by default');
+    
+    const conflicting = await runCLI([
+      '--file', encryptedPath,
+      '--parts', '2',
+      '--password', 'secret',
+      '--decrypt-output',
+      '--user-password', 'other'
+    ]);
+    assert.strictEqual(conflicting.code, 2, '--decrypt-output cannot be combined with new passwords');
+  });
+  
+  it('encrypts the parts with --user-password', async function() {
+    // Skip if test PDF doesn't exist or qpdf is missing
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    if (!isQpdfInstalled()) {
+      this.skip('qpdf is not installed.');
+      return;
+    }
+    
+    const { code, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_encrypted',
+      '--user-password', 'secret',
+      '--permissions', 'print'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const partPdf = await PDFDocument.load(
+      await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, 'test_encrypted_part1.pdf')),
+      { ignoreEncryption: true }
+    );
+    assert.strictEqual(partPdf.isEncrypted, true);
+  });
+  
+  it('returns error code 2 for unknown permissions', async () => {
+    const { code } = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--user-password', 'secret', '--permissions', 'fly']);
+    assert.strictEqual(code, 2);
+  });
+  
+  it('keeps passwords off the qpdf command line', async function() {
+    if (process.platform === 'win32') {
+      this.skip('The stand-in for qpdf is a Node.js script run through its shebang line.');
+      return;
+    }
+    
+    // A stand-in for qpdf recording its arguments and the files they name, which are gone once it returns
+    const fakeQpdfPath = path.join(TEST_OUTPUT_DIR, 'fake-qpdf.js');
+    const logPath = path.join(TEST_OUTPUT_DIR, 'fake-qpdf.json');
+    await fs.promises.writeFile(fakeQpdfPath, `#!/usr/bin/env node
+const fs = require('fs');
+const files = [];
+for (const arg of process.argv.slice(2)) {
+  const filePath = arg.startsWith('@') ? arg.slice(1) : arg.startsWith('--password-file=') ? arg.slice(16) : null;
+  if (filePath) {
+    files.push({ mode: fs.statSync(filePath).mode & 0o777, lines: fs.readFileSync(filePath, 'utf8').split('\\n') });
+  }
+}
+fs.writeFileSync(${JSON.stringify(logPath)}, JSON.stringify({ args: process.argv.slice(2), files }));
+process.exit(2);
+`, { mode: 0o755 });
+    const runFakeQpdf = async (run) => {
+      await assert.rejects(run);
+      return JSON.parse(await fs.promises.readFile(logPath, 'utf8'));
+    };
+    const assertPasswordsHidden = (qpdfRun, passwords) => {
+      for (const password of passwords) {
+        assert.ok(!qpdfRun.args.some(arg => arg.includes(password)), `${password} should not be on the command line`);
+        assert.ok(qpdfRun.files.some(file => file.mode === 0o600 && file.lines.some(line => line.endsWith(password))), `${password} should be in a file only its owner can read`);
+      }
+    };
+    
+    const { createEncryptionCopier, decryptPdf, encryptPdf } = require('../src/encryption');
+    const previousQpdf = process.env.SPLITPDF_QPDF;
+    process.env.SPLITPDF_QPDF = fakeQpdfPath;
+    try {
+      assertPasswordsHidden(await runFakeQpdf(decryptPdf(Buffer.from('%PDF-1.7'), 'source secret')), ['source secret']);
+      const encryption = { userPassword: 'user secret', ownerPassword: 'owner secret', permissions: ['print'] };
+      assertPasswordsHidden(await runFakeQpdf(encryptPdf(Buffer.from('%PDF-1.7'), encryption)), ['user secret', 'owner secret']);
+      const copier = await createEncryptionCopier(Buffer.from('%PDF-1.7'), 'source secret');
+      try {
+        assertPasswordsHidden(await runFakeQpdf(copier.apply(Buffer.from('%PDF-1.7'))), ['source secret']);
+      } finally {
+        await copier.close();
+      }
+    } finally {
+      if (previousQpdf === undefined) {
+        delete process.env.SPLITPDF_QPDF;
+      } else {
+        process.env.SPLITPDF_QPDF = previousQpdf;
+      }
+      await fs.promises.rm(fakeQpdfPath, { force: true });
+      await fs.promises.rm(logPath, { force: true });
+    }
+  });
+  
+  it('copies the source metadata into each part, with --part-titles numbering the titles', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    sourcePdf.setTitle('Annual Report');
+    sourcePdf.setAuthor('Finance Team');
+    sourcePdf.setSubject('Results');
+    sourcePdf.setKeywords(['finance', 'annual']);
+    sourcePdf.setCreationDate(new Date('2020-01-02T03:04:05Z'));
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_metadata_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const { code, stderr } = await runCLI([
+      '--file', sourcePath,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_metadata',
+      '--part-titles'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const partPdf = await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, 'test_metadata_part2.pdf')));
+    assert.strictEqual(partPdf.getTitle(), 'Annual Report (Part 2)');
+    assert.strictEqual(partPdf.getAuthor(), 'Finance Team');
+    assert.strictEqual(partPdf.getSubject(), 'Results');
+    assert.strictEqual(partPdf.getKeywords(), 'finance annual');
+    assert.strictEqual(partPdf.getCreationDate().toISOString(), '2020-01-02T03:04:05.000Z');
+  });
+  
+  it('sets part metadata from --title and --author templates', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    sourcePdf.setTitle('Handbook');
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_template_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const { code, stderr } = await runCLI([
+      '--file', sourcePath,
+      '--parts', '3',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_template',
+      '--title', '{source_title} - Part {part}/{total}',
+      '--author', 'Docs Team'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const partPdf = await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, 'test_template_part3.pdf')));
+    assert.strictEqual(partPdf.getTitle(), 'Handbook - Part 3/3');
+    assert.strictEqual(partPdf.getAuthor(), 'Docs Team');
+  });
+  
+  it('returns error code 2 for an unknown placeholder in a metadata template', async () => {
+    const { code } = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--title', '{chapter}']);
+    assert.strictEqual(code, 2);
+  });
+  
+  it('keeps the bookmarks pointing into each part, remapped to its pages', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { readOutline, writeOutline } = require('../src/outline');
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    const lastPage = sourcePdf.getPageCount() - 1;
+    writeOutline(sourcePdf, [
+      { title: 'Start', pageIndex: 0, view: null, open: true, children: [] },
+      { title: 'End', pageIndex: lastPage, view: ['XYZ', 0, 100, null], open: true, children: [] }
+    ]);
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_bookmarks_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const { code, stderr } = await runCLI([
+      '--file', sourcePath,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_bookmarks'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const part1Pdf = await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, 'test_bookmarks_part1.pdf')));
+    const part2Pdf = await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, 'test_bookmarks_part2.pdf')));
+    assert.deepStrictEqual(readOutline(part1Pdf).map(item => [item.title, item.pageIndex]), [['Start', 0]]);
+    const part2Outline = readOutline(part2Pdf);
+    assert.deepStrictEqual(part2Outline.map(item => [item.title, item.pageIndex]), [['End', part2Pdf.getPageCount() - 1]]);
+    assert.deepStrictEqual(part2Outline[0].view, ['XYZ', 0, 100, null]);
+    
+    const nearest = await runCLI([
+      '--file', sourcePath,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_bookmarks_nearest',
+      '--bookmarks', 'nearest'
+    ]);
+    assert.strictEqual(nearest.code, 0, `CLI exits with code 0, but got ${nearest.code}. Stderr: ${nearest.stderr}`);
+    const nearestPdf = await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, 'test_bookmarks_nearest_part1.pdf')));
+    assert.deepStrictEqual(
+      readOutline(nearestPdf).map(item => [item.title, item.pageIndex]),
+      [['Start', 0], ['End', nearestPdf.getPageCount() - 1]]
+    );
+  });
+  
+  it('returns error code 2 for an unknown bookmark mode', async () => {
+    const { code } = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--bookmarks', 'all']);
+    assert.strictEqual(code, 2);
+  });
+  
+  it('starts each part with a linked contents page with --toc', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { code, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--intro', '1:2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_toc',
+      '--toc'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const sourcePdfPageCount = await getPdfPageCount(TEST_PDF_PATH);
+    const part1Pdf = await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, 'test_toc_part1.pdf')));
+    const part2Pdf = await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, 'test_toc_part2.pdf')));
+    // One contents page per part on top of the intro pages
+    assert.strictEqual(part1Pdf.getPageCount() + part2Pdf.getPageCount(), sourcePdfPageCount + 2 + 2);
+    
+    // Intro and content entries link to their pages
+    const annots = part1Pdf.getPage(0).node.Annots();
+    assert.ok(annots, 'Contents page has link annotations');
+    assert.strictEqual(annots.size(), 2);
+  });
+  
+  it('keeps the form fields and annotations of the pages in each part', async function() {
+    // Skip if the forms fixture doesn't exist
+    if (!await fileExists(FORMS_PDF_PATH)) {
+      this.skip('Forms PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { code, stderr } = await runCLI([
+      '--file', FORMS_PDF_PATH,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_forms'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const part1Pdf = await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, 'test_forms_part1.pdf')));
+    const part2Pdf = await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, 'test_forms_part2.pdf')));
+    
+    // Pages 1-2: the name field, a comment and a highlight
+    assert.deepStrictEqual(part1Pdf.getForm().getFields().map(field => field.getName()), ['name']);
+    assert.strictEqual(part1Pdf.getForm().getTextField('name').getText(), 'Ada');
+    assert.strictEqual(part1Pdf.getPage(1).node.Annots().size(), 2);
+    
+    // Pages 3-4: the city field and the checkbox
+    const part2Fields = part2Pdf.getForm().getFields().map(field => field.getName()).sort();
+    assert.deepStrictEqual(part2Fields, ['agree', 'city']);
+    assert.strictEqual(part2Pdf.getForm().getCheckBox('agree').isChecked(), true);
+  });
+  
+  it('draws annotations and form fields into the page content with --flatten', async function() {
+    // Skip if the forms fixture doesn't exist
+    if (!await fileExists(FORMS_PDF_PATH)) {
+      this.skip('Forms PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { code, stderr } = await runCLI([
+      '--file', FORMS_PDF_PATH,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_flatten',
+      '--flatten'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const partPdf = await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, 'test_flatten_part1.pdf')));
+    assert.strictEqual(partPdf.getForm().getFields().length, 0, 'Form fields are removed');
+    // The text field has an appearance and is drawn; the comment has none and stays
+    assert.strictEqual(partPdf.getPage(0).node.Annots(), undefined);
+    assert.ok(partPdf.getPage(0).node.Resources().lookup(PDFName.of('XObject')), 'Field appearance is drawn as an XObject');
+  });
+  
+  it('remaps internal links and applies the --cross-links policy to links into other parts', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    // Page 1 links to page 2 (same part) and to the last page (second part)
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    const pages = sourcePdf.getPages();
+    const addLink = (targetPage, y) => sourcePdf.context.register(sourcePdf.context.obj({
+      Type: 'Annot',
+      Subtype: 'Link',
+      Rect: [100, y, 300, y + 20],
+      Dest: [targetPage.ref, PDFName.of('Fit')]
+    }));
+    pages[0].node.set(PDFName.of('Annots'), sourcePdf.context.obj([addLink(pages[1], 500), addLink(pages[pages.length - 1], 450)]));
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_links_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const splitLinks = async (basename, policy) => {
+      const { code, stderr } = await runCLI([
+        '--file', sourcePath,
+        '--parts', '2',
+        '--output-dir', TEST_OUTPUT_DIR,
+        '--output-basename', basename,
+        '--cross-links', policy
+      ]);
+      assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+      const partPdf = await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, `${basename}_part1.pdf`)));
+      return { partPdf, annots: partPdf.getPage(0).node.Annots() };
+    };
+    
+    const dropped = await splitLinks('test_links_drop', 'drop');
+    assert.strictEqual(dropped.annots.size(), 1, 'The link into the second part is dropped');
+    const destination = dropped.annots.lookup(0).lookup(PDFName.of('Dest'));
+    assert.strictEqual(destination.get(0), dropped.partPdf.getPage(1).ref, 'The link points at the copy of page 2');
+    
+    const external = await splitLinks('test_links_external', 'external');
+    assert.strictEqual(external.annots.size(), 2);
+    const action = external.annots.lookup(1).lookup(PDFName.of('A'));
+    assert.strictEqual(action.lookup(PDFName.of('S')), PDFName.of('GoToR'));
+    assert.strictEqual(action.lookup(PDFName.of('F')).decodeText(), 'test_links_external_part2.pdf');
+  });
+  
+  it('returns error code 2 for an unknown cross-link policy', async () => {
+    const { code } = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--cross-links', 'follow']);
+    assert.strictEqual(code, 2);
+  });
+  
+  it('keeps the named destinations whose pages are in each part', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    const pages = sourcePdf.getPages();
+    sourcePdf.catalog.set(PDFName.of('Dests'), sourcePdf.context.obj({
+      intro: [pages[0].ref, PDFName.of('Fit')],
+      summary: [pages[pages.length - 1].ref, PDFName.of('Fit')]
+    }));
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_dests_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const { code, stderr } = await runCLI([
+      '--file', sourcePath,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_dests'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const readDestinations = async (partPath) => {
+      const partPdf = await PDFDocument.load(await fs.promises.readFile(partPath));
+      const entries = partPdf.catalog.lookup(PDFName.of('Names')).lookup(PDFName.of('Dests')).lookup(PDFName.of('Names'));
+      const destinations = {};
+      for (let i = 0; i < entries.size(); i += 2) {
+        const pageRef = entries.lookup(i + 1).get(0);
+        destinations[entries.lookup(i).decodeText()] = partPdf.getPages().findIndex(page => page.ref === pageRef);
+      }
+      return { partPdf, destinations };
+    };
+    
+    const part1 = await readDestinations(path.join(TEST_OUTPUT_DIR, 'test_dests_part1.pdf'));
+    assert.deepStrictEqual(part1.destinations, { intro: 0 });
+    const part2 = await readDestinations(path.join(TEST_OUTPUT_DIR, 'test_dests_part2.pdf'));
+    assert.deepStrictEqual(part2.destinations, { summary: part2.partPdf.getPageCount() - 1 });
+  });
+  
+  it('keeps the source page labels unless --no-page-labels is given', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    // Pages i-iv, then 1, 2, 3...
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    sourcePdf.catalog.set(PDFName.of('PageLabels'), sourcePdf.context.obj({
+      Nums: [0, { S: 'r' }, 4, { S: 'D' }]
+    }));
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_labels_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const splitLabels = async (basename, extraArgs) => {
+      const { code, stderr } = await runCLI([
+        '--file', sourcePath,
+        '--parts', '2',
+        '--output-dir', TEST_OUTPUT_DIR,
+        '--output-basename', basename,
+        ...extraArgs
+      ]);
+      assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+      const part1Pdf = await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, `${basename}_part1.pdf`)));
+      const part2Pdf = await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, `${basename}_part2.pdf`)));
+      return { part1Pdf, part2Pdf };
+    };
+    
+    const labelled = await splitLabels('test_labels', []);
+    const part1Nums = labelled.part1Pdf.catalog.lookup(PDFName.of('PageLabels')).lookup(PDFName.of('Nums'));
+    assert.strictEqual(part1Nums.size(), 4, 'Part 1 has the roman and the decimal range');
+    assert.strictEqual(part1Nums.lookup(1).lookup(PDFName.of('S')), PDFName.of('r'));
+    
+    // Part 2 continues the decimal numbering where part 1 stopped
+    const part2Nums = labelled.part2Pdf.catalog.lookup(PDFName.of('PageLabels')).lookup(PDFName.of('Nums'));
+    assert.strictEqual(part2Nums.size(), 2);
+    assert.strictEqual(part2Nums.lookup(1).lookup(PDFName.of('S')), PDFName.of('D'));
+    assert.strictEqual(part2Nums.lookup(1).lookup(PDFName.of('St')).asNumber(), labelled.part1Pdf.getPageCount() - 4 + 1);
+    
+    const plain = await splitLabels('test_labels_plain', ['--no-page-labels']);
+    assert.strictEqual(plain.part2Pdf.catalog.lookup(PDFName.of('PageLabels')), undefined);
+  });
+  
+  it('copies the file attachments into the parts chosen by --attachments', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    await sourcePdf.attach(Buffer.from('a,b\n1,2\n'), 'data.csv', { mimeType: 'text/csv', description: 'Raw data' });
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_attachments_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const splitAttachments = async (basename, policy) => {
+      const { code, stdout, stderr } = await runCLI([
+        '--file', sourcePath,
+        '--parts', '2',
+        '--output-dir', TEST_OUTPUT_DIR,
+        '--output-basename', basename,
+        '--attachments', policy,
+        '--json-events'
+      ]);
+      assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+      const attachmentNames = [];
+      for (const index of [1, 2]) {
+        const partPdf = await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, `${basename}_part${index}.pdf`)));
+        const names = partPdf.catalog.lookup(PDFName.of('Names'));
+        const tree = names && names.lookup(PDFName.of('EmbeddedFiles'));
+        attachmentNames.push(tree ? tree.lookup(PDFName.of('Names')).lookup(0).decodeText() : null);
+      }
+      return { attachmentNames, warnings: JSON.parse(stdout).warnings };
+    };
+    
+    const all = await splitAttachments('test_attachments_all', 'all');
+    assert.deepStrictEqual(all.attachmentNames, ['data.csv', 'data.csv']);
+    assert.strictEqual(all.warnings[0].code, 'attachments');
+    assert.match(all.warnings[0].message, /every part: data\.csv/);
+    
+    const first = await splitAttachments('test_attachments_first', 'first');
+    assert.deepStrictEqual(first.attachmentNames, ['data.csv', null]);
+    
+    const none = await splitAttachments('test_attachments_none', 'none');
+    assert.deepStrictEqual(none.attachmentNames, [null, null]);
+    assert.match(none.warnings[0].message, /Dropped 1 attachment/);
+  });
+  
+  it('returns error code 2 for an unknown attachment policy', async () => {
+    const { code } = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--attachments', 'last']);
+    assert.strictEqual(code, 2);
+  });
+  
+  it('refuses signed PDFs unless --break-signatures is given', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    // A signature field with a (dummy) signature value is enough to mark the file as signed
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    const context = sourcePdf.context;
+    const signature = context.obj({ Type: 'Sig', Filter: 'Adobe.PPKLite', SubFilter: 'adbe.pkcs7.detached' });
+    const field = context.register(context.obj({ FT: 'Sig', T: PDFString.of('Approval'), V: signature }));
+    sourcePdf.catalog.set(PDFName.of('AcroForm'), context.obj({ Fields: [field], SigFlags: 3 }));
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_signed_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const args = ['--file', sourcePath, '--parts', '2', '--output-dir', TEST_OUTPUT_DIR, '--output-basename', 'test_signed'];
+    const refused = await runCLI(args);
+    assert.strictEqual(refused.code, 5);
+    assert.match(refused.stderr, /digitally signed \(Approval\)/);
+    assert.strictEqual(await fileExists(path.join(TEST_OUTPUT_DIR, 'test_signed_part1.pdf')), false, 'Nothing is written');
+    
+    const { code, stdout, stderr } = await runCLI([...args, '--break-signatures', '--json-events']);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    const { warnings } = JSON.parse(stdout);
+    assert.strictEqual(warnings[0].code, 'signaturesBroken');
+    assert.match(stderr, /"event":"warning","code":"signaturesBroken"/);
+  });
+  
+  it('warns that the structure of a tagged PDF is not kept', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    sourcePdf.catalog.set(PDFName.of('MarkInfo'), sourcePdf.context.obj({ Marked: true }));
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_tagged_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const { code, stdout, stderr } = await runCLI([
+      '--file', sourcePath,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_tagged',
+      '--json-events'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    const { warnings } = JSON.parse(stdout);
+    assert.deepStrictEqual(warnings.map(warning => warning.code), ['structureLost']);
+  });
+  
+  it('drops the PDF/A claim of the source unless --keep-pdfa is given', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    const xmp = '<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">'
+      + '<rdf:Description rdf:about="" xmlns:pdfaid="http://www.aiim.org/pdfa/ns/id/" pdfaid:part="2" pdfaid:conformance="B"/>'
+      + '</rdf:RDF></x:xmpmeta>';
+    const metadata = sourcePdf.context.stream(xmp, { Type: 'Metadata', Subtype: 'XML' });
+    sourcePdf.catalog.set(PDFName.of('Metadata'), sourcePdf.context.register(metadata));
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_pdfa_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const splitPdfa = async (basename, extraArgs) => {
+      const { code, stdout, stderr } = await runCLI([
+        '--file', sourcePath,
+        '--parts', '2',
+        '--output-dir', TEST_OUTPUT_DIR,
+        '--output-basename', basename,
+        '--json-events',
+        ...extraArgs
+      ]);
+      assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+      const info = await runCLI(['info', path.join(TEST_OUTPUT_DIR, `${basename}_part1.pdf`)]);
+      return { warnings: JSON.parse(stdout).warnings, pdfa: JSON.parse(info.stdout).pdfa };
+    };
+    
+    const dropped = await splitPdfa('test_pdfa_dropped', []);
+    assert.deepStrictEqual(dropped.warnings.map(warning => warning.code), ['pdfaDropped']);
+    assert.match(dropped.warnings[0].message, /PDF\/A-2b/);
+    assert.strictEqual(dropped.pdfa, null);
+    
+    const kept = await splitPdfa('test_pdfa_kept', ['--keep-pdfa']);
+    assert.deepStrictEqual(kept.warnings.map(warning => warning.code), ['pdfaUnverified']);
+    assert.strictEqual(kept.pdfa, 'PDF/A-2b');
+  });
+  
+  it('keeps the PDF version of the source unless --pdf-version is given', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    sourcePdf.context.header = PDFHeader.forVersion(1, 4);
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_version_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save({ useObjectStreams: false }));
+    
+    const splitVersion = async (basename, extraArgs) => {
+      const { code, stderr } = await runCLI([
+        '--file', sourcePath,
+        '--parts', '2',
+        '--output-dir', TEST_OUTPUT_DIR,
+        '--output-basename', basename,
+        ...extraArgs
+      ]);
+      assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+      const partBytes = await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, `${basename}_part1.pdf`));
+      return partBytes.subarray(0, 8).toString('latin1');
+    };
+    
+    assert.strictEqual(await splitVersion('test_version_source', []), '%PDF-1.4');
+    assert.strictEqual(await splitVersion('test_version_override', ['--pdf-version', '2.0']), '%PDF-2.0');
+  });
+  
+  it('returns error code 2 for an unknown PDF version', async () => {
+    const { code } = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--pdf-version', '1.9']);
+    assert.strictEqual(code, 2);
+  });
+  
+  it('derives the document IDs of the parts from the source with --document-ids derived', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const splitIds = async (basename, mode) => {
+      const { code, stdout, stderr } = await runCLI([
+        '--file', TEST_PDF_PATH,
+        '--parts', '2',
+        '--output-dir', TEST_OUTPUT_DIR,
+        '--output-basename', basename,
+        '--document-ids', mode,
+        '--json-events'
+      ]);
+      assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+      const ids = JSON.parse(stdout).parts.map(part => part.documentId);
+      // The ID written into the file matches the one in the result
+      const partPdf = await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, `${basename}_part1.pdf`)));
+      const writtenId = partPdf.context.lookup(partPdf.context.trailerInfo.ID).lookup(0).asString().toLowerCase();
+      assert.strictEqual(writtenId, ids[0]);
+      return ids;
+    };
+    
+    const derived = await splitIds('test_ids_derived', 'derived');
+    assert.notStrictEqual(derived[0], derived[1], 'Each part has its own ID');
+    assert.deepStrictEqual(await splitIds('test_ids_derived_again', 'derived'), derived, 'Derived IDs are repeatable');
+    
+    const random = await splitIds('test_ids_random', 'random');
+    assert.notDeepStrictEqual(await splitIds('test_ids_random_again', 'random'), random);
+  });
+  
+  it('returns error code 2 for an unknown document ID mode', async () => {
+    const { code } = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--document-ids', 'source']);
+    assert.strictEqual(code, 2);
+  });
+  
+  it('stamps Bates numbers continuing across parts with --bates', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { code, stdout, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_bates',
+      '--bates', 'ABC',
+      '--bates-start', '10',
+      '--bates-digits', '4',
+      '--json-events'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const [part1, part2] = JSON.parse(stdout).parts;
+    const part1PageCount = await getPdfPageCount(part1.outputPath);
+    assert.deepStrictEqual(part1.batesNumbers, {
+      first: 'ABC0010',
+      last: `ABC${String(10 + part1PageCount - 1).padStart(4, '0')}`
+    });
+    assert.strictEqual(part2.batesNumbers.first, `ABC${String(10 + part1PageCount).padStart(4, '0')}`);
+    
+    // The number is drawn into the content of the part's last page
+    const partPdf = await PDFDocument.load(await fs.promises.readFile(part1.outputPath));
+    const contents = partPdf.getPage(part1PageCount - 1).node.Contents();
+    const stream = contents instanceof PDFArray ? contents.lookup(contents.size() - 1) : contents;
+    const operators = Buffer.from(decodePDFRawStream(stream).decode()).toString('latin1');
+    const hex = Buffer.from(part1.batesNumbers.last, 'latin1').toString('hex');
+    assert.match(operators, new RegExp(`<${hex}> Tj`, 'i'));
+  });
+  
+  it('returns error code 2 for a negative first Bates number', async () => {
+    const { code } = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--bates', 'ABC', '--bates-start', '-1']);
+    assert.strictEqual(code, 2);
+  });
+  
+  it('stamps a text or image watermark onto every page with --watermark', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const splitWatermark = async (basename, extraArgs) => {
+      const { code, stderr } = await runCLI([
+        '--file', TEST_PDF_PATH,
+        '--parts', '2',
+        '--output-dir', TEST_OUTPUT_DIR,
+        '--output-basename', basename,
+        ...extraArgs
+      ]);
+      assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+      return PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, `${basename}_part2.pdf`)));
+    };
+    
+    const textPdf = await splitWatermark('test_watermark_text', ['--watermark', 'COPY', '--watermark-opacity', '0.5']);
+    for (const page of textPdf.getPages()) {
+      const contents = page.node.Contents();
+      const stream = contents instanceof PDFArray ? contents.lookup(contents.size() - 1) : contents;
+      const operators = Buffer.from(decodePDFRawStream(stream).decode()).toString('latin1');
+      assert.match(operators, /<434f5059> Tj/i, 'Every page shows the watermark');
+    }
+    
+    // A 1x1 pixel PNG
+    const imagePath = path.join(TEST_OUTPUT_DIR, 'test_watermark.png');
+    await fs.promises.writeFile(imagePath, Buffer.from(
+      'iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==', 'base64'));
+    const imagePdf = await splitWatermark('test_watermark_image', ['--watermark-image', imagePath]);
+    const xObjects = imagePdf.getPage(0).node.Resources().lookup(PDFName.of('XObject'));
+    assert.ok(xObjects.keys().some(name => xObjects.lookup(name).dict.lookup(PDFName.of('Subtype')) === PDFName.of('Image')));
+  });
+  
+  it('returns error code 2 for an unknown watermark position', async () => {
+    const { code } = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--watermark', 'COPY', '--watermark-position', 'middle']);
+    assert.strictEqual(code, 2);
+  });
+  
+  it('prints the part and page numbers in the footer with --footer', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { code, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_footer',
+      '--footer', 'Part {part} of {total} - {basename} - page {page} of {pages}'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const partPdf = await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, 'test_footer_part2.pdf')));
+    const contents = partPdf.getPage(1).node.Contents();
+    const stream = contents instanceof PDFArray ? contents.lookup(contents.size() - 1) : contents;
+    const operators = Buffer.from(decodePDFRawStream(stream).decode()).toString('latin1');
+    const footer = `Part 2 of 2 - test_footer - page 2 of ${partPdf.getPageCount()}`;
+    assert.match(operators, new RegExp(`<${Buffer.from(footer, 'latin1').toString('hex')}> Tj`, 'i'));
+  });
+  
+  it('returns error code 2 for an unknown footer placeholder', async () => {
+    const { code, stderr } = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--footer', 'Page {number}']);
+    assert.strictEqual(code, 2);
+    assert.match(stderr, /Unknown placeholder \{number\} in the footer template/);
+  });
+  
+  it('starts each part with a cover page with --cover', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { code, stdout, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_cover',
+      '--cover', 'Bundle {part} of {total}\\nPages {page_range}',
+      '--json-events'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const [, part2] = JSON.parse(stdout).parts;
+    const partPdf = await PDFDocument.load(await fs.promises.readFile(part2.outputPath));
+    assert.strictEqual(partPdf.getPageCount(), part2.pages.content.length + 1, 'The cover is added in front of the pages');
+    
+    const contents = partPdf.getPage(0).node.Contents();
+    const stream = contents instanceof PDFArray ? contents.lookup(contents.size() - 1) : contents;
+    const operators = Buffer.from(decodePDFRawStream(stream).decode()).toString('latin1');
+    const first = part2.pages.content[0];
+    const last = part2.pages.content[part2.pages.content.length - 1];
+    for (const line of ['Bundle 2 of 2', `Pages ${first}-${last}`]) {
+      assert.match(operators, new RegExp(`<${Buffer.from(line, 'latin1').toString('hex')}> Tj`, 'i'));
+    }
+  });
+  
+  it('returns error code 2 for an unknown cover placeholder', async () => {
+    const { code } = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--cover', '{source_date}']);
+    assert.strictEqual(code, 2);
+  });
+  
+  it('splits landscape spreads into two pages with --spreads', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    // A double-width first page stands in for a scanned spread
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    const sourcePageCount = sourcePdf.getPageCount();
+    const { width, height } = sourcePdf.getPage(0).getSize();
+    sourcePdf.getPage(0).setMediaBox(0, 0, 2 * width, height);
+    sourcePdf.getPage(0).node.delete(PDFName.of('CropBox'));
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_spreads_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const { code, stdout, stderr } = await runCLI([
+      '--file', sourcePath,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_spreads',
+      '--spreads',
+      '--json-events'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const [part1, part2] = JSON.parse(stdout).parts;
+    assert.strictEqual(part1.pages.content.length + part2.pages.content.length, sourcePageCount + 1, 'The spread becomes two pages');
+    const partPdf = await PDFDocument.load(await fs.promises.readFile(part1.outputPath));
+    const left = partPdf.getPage(0).getCropBox();
+    const right = partPdf.getPage(1).getCropBox();
+    assert.deepStrictEqual([left.x, left.width], [0, width]);
+    assert.deepStrictEqual([right.x, right.width], [width, width]);
+  });
+  
+  it('prints two pages on each sheet with --n-up', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { code, stdout, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_n_up',
+      '--n-up', '2',
+      '--json-events'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    const [part1] = JSON.parse(stdout).parts;
+    const firstPage = sourcePdf.getPage(part1.pages.content[0] - 1).getSize();
+    const partPdf = await PDFDocument.load(await fs.promises.readFile(part1.outputPath));
+    assert.strictEqual(partPdf.getPageCount(), Math.ceil(part1.pages.content.length / 2));
+    assert.deepStrictEqual(partPdf.getPage(0).getSize(), { width: firstPage.height, height: firstPage.width }, 'Two portrait pages share a landscape sheet');
+  });
+  
+  it('returns error code 2 for an unsupported --n-up value', async () => {
+    const { code, stderr } = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--n-up', '3']);
+    assert.strictEqual(code, 2);
+    assert.match(stderr, /Unsupported pages per sheet: 3/);
+  });
+  
+  it('imposes each part as a booklet with --booklet', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { code, stdout, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_booklet',
+      '--booklet',
+      '--json-events'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    for (const part of JSON.parse(stdout).parts) {
+      const partPdf = await PDFDocument.load(await fs.promises.readFile(part.outputPath));
+      // Each folded sheet holds four pages, printed on two sides
+      assert.strictEqual(partPdf.getPageCount(), Math.ceil(part.pages.content.length / 4) * 2);
+    }
+    
+    const combined = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--booklet', '--n-up', '4']);
+    assert.strictEqual(combined.code, 2);
+  });
+  
+  it('puts every page onto the same paper size with --page-size', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    // A landscape page among portrait pages
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    sourcePdf.getPage(1).setMediaBox(0, 0, 900, 300);
+    sourcePdf.getPage(1).node.delete(PDFName.of('CropBox'));
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_page_size_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const { code, stdout, stderr } = await runCLI([
+      '--file', sourcePath,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_page_size',
+      '--page-size', 'a4',
+      '--json-events'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const [part1] = JSON.parse(stdout).parts;
+    const partPdf = await PDFDocument.load(await fs.promises.readFile(part1.outputPath));
+    for (const page of partPdf.getPages()) {
+      const { width, height } = page.getSize();
+      assert.deepStrictEqual([Math.min(width, height), Math.max(width, height)], [595.28, 841.89]);
+    }
+    assert.deepStrictEqual(partPdf.getPage(1).getSize(), { width: 841.89, height: 595.28 }, 'Landscape pages get landscape paper');
+    
+    const unknown = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--page-size', 'A4x']);
+    assert.strictEqual(unknown.code, 2);
+    assert.match(unknown.stderr, /Unknown page size: A4x/);
+  });
+  
+  it('renders each part to a folder of page images with --output-format png', async function() {
+    if (!await fileExists(TEST_PDF_PATH) || !isPdftoppmInstalled()) {
+      this.skip('Test PDF or pdftoppm not found.');
+      return;
+    }
+    
+    const { code, stdout, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_render',
+      '--output-format', 'png',
+      '--render-dpi', '36',
+      '--json-events'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const [part1] = JSON.parse(stdout).parts;
+    assert.strictEqual(part1.outputPath, path.join(TEST_OUTPUT_DIR, 'test_render_part1'));
+    assert.strictEqual(part1.images.length, part1.pages.content.length, 'One image per page');
+    for (const imagePath of part1.images) {
+      const signature = (await fs.promises.readFile(imagePath)).subarray(1, 4).toString('latin1');
+      assert.strictEqual(signature, 'PNG');
+    }
+    assert.strictEqual(await fileExists(path.join(TEST_OUTPUT_DIR, 'test_render_part1.pdf')), false, 'No PDF is written');
+  });
+  
+  it('returns error code 2 for an unknown output format', async () => {
+    const { code, stderr } = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--output-format', 'gif']);
+    assert.strictEqual(code, 2);
+    assert.match(stderr, /Unknown output format: gif/);
+  });
+  
+  it('writes the text of each part next to it with --text-sidecar', async function() {
+    if (!await fileExists(TEST_PDF_PATH) || !isPdftotextInstalled()) {
+      this.skip('Test PDF or pdftotext not found.');
+      return;
+    }
+    
+    const { code, stdout, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_text',
+      '--text-sidecar',
+      '--json-events'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    for (const part of JSON.parse(stdout).parts) {
+      assert.strictEqual(part.textPath, path.join(TEST_OUTPUT_DIR, `test_text_part${part.index}.txt`));
+      const text = await fs.promises.readFile(part.textPath, 'utf8');
+      // pdftotext ends every page with a form feed
+      assert.strictEqual(text.split('\f').length - 1, part.pages.content.length);
+    }
+  });
+  
+  it('writes a thumbnail of each part with --thumbnails', async function() {
+    if (!await fileExists(TEST_PDF_PATH) || !isPdftoppmInstalled()) {
+      this.skip('Test PDF or pdftoppm not found.');
+      return;
+    }
+    
+    const { code, stdout, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_thumbnails',
+      '--thumbnails', '64',
+      '--json-events'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    for (const part of JSON.parse(stdout).parts) {
+      assert.strictEqual(part.thumbnailPath, path.join(TEST_OUTPUT_DIR, `test_thumbnails_part${part.index}.png`));
+      assert.strictEqual(part.thumbnail, undefined, 'The CLI result holds the path, not the bytes');
+      const png = await fs.promises.readFile(part.thumbnailPath);
+      // Width and height are the first fields of the IHDR chunk
+      assert.strictEqual(Math.max(png.readUInt32BE(16), png.readUInt32BE(20)), 64);
+    }
+  });
+  
+  it('leaves blank pages out of the parts with --drop-blank-pages', async function() {
+    if (!await fileExists(TEST_PDF_PATH) || !isPdftoppmInstalled()) {
+      this.skip('Test PDF or pdftoppm not found.');
+      return;
+    }
+    
+    // An empty page stands in for the blank back of a scanned sheet
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    const { width, height } = sourcePdf.getPage(0).getSize();
+    sourcePdf.insertPage(2, [width, height]);
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_blank_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const { code, stdout, stderr } = await runCLI([
+      '--file', sourcePath,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_blank',
+      '--drop-blank-pages',
+      '--json-events'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const result = JSON.parse(stdout);
+    const [part1] = result.parts;
+    assert.deepStrictEqual(part1.droppedPages, [3]);
+    assert.ok(!part1.pages.content.includes(3));
+    assert.deepStrictEqual(result.warnings.map(warning => warning.code), ['blankPagesDropped']);
+    const partPdf = await PDFDocument.load(await fs.promises.readFile(part1.outputPath));
+    assert.strictEqual(partPdf.getPageCount(), part1.pages.content.length);
+  });
+  
+  it('reports and drops repeated pages with --duplicate-pages', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    // A copy of the first page added again as the fourth page
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    const [copy] = await sourcePdf.copyPages(sourcePdf, [0]);
+    sourcePdf.insertPage(3, copy);
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_duplicates_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    const args = ['--file', sourcePath, '--parts', '2', '--output-dir', TEST_OUTPUT_DIR, '--output-basename', 'test_duplicates', '--json-events'];
+    
+    const reported = await runCLI([...args, '--duplicate-pages', 'report']);
+    assert.strictEqual(reported.code, 0, `CLI exits with code 0, but got ${reported.code}. Stderr: ${reported.stderr}`);
+    const reportedResult = JSON.parse(reported.stdout);
+    assert.deepStrictEqual(reportedResult.duplicatePages, [{ page: 4, duplicateOf: 1 }]);
+    assert.ok(reportedResult.parts[0].pages.content.includes(4), 'Reported pages stay in the parts');
+    
+    const dropped = await runCLI([...args, '--duplicate-pages', 'drop']);
+    assert.strictEqual(dropped.code, 0, `CLI exits with code 0, but got ${dropped.code}. Stderr: ${dropped.stderr}`);
+    const [part1] = JSON.parse(dropped.stdout).parts;
+    assert.deepStrictEqual(part1.droppedPages, [4]);
+    assert.ok(!part1.pages.content.includes(4));
+  });
+  
+  it('makes one part per paper size with --group-by size', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    // Two A3 fold-outs, one of them landscape, among Letter pages
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    sourcePdf.getPage(1).setMediaBox(0, 0, 1190.55, 841.89);
+    sourcePdf.getPage(4).setMediaBox(0, 0, 841.89, 1190.55);
+    for (const pageIndex of [1, 4]) {
+      sourcePdf.getPage(pageIndex).node.delete(PDFName.of('CropBox'));
+    }
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_group_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const { code, stdout, stderr } = await runCLI([
+      '--file', sourcePath,
+      '--group-by', 'size',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_group',
+      '--name-template', '{basename}_{group}.pdf',
+      '--json-events'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const parts = JSON.parse(stdout).parts;
+    assert.deepStrictEqual(parts.map(part => part.group), ['Letter', 'A3']);
+    assert.deepStrictEqual(parts[1].pages.content, [2, 5]);
+    assert.strictEqual(parts[0].pages.content.length, sourcePdf.getPageCount() - 2);
+    assert.strictEqual(parts[1].outputPath, path.join(TEST_OUTPUT_DIR, 'test_group_A3.pdf'));
+    assert.strictEqual(await getPdfPageCount(parts[1].outputPath), 2);
+  });
+  
+  it('separates portrait and landscape pages with --group-by orientation', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    // One page turned sideways by its rotation and one by its page box
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    sourcePdf.getPage(2).setRotation(degrees(90));
+    sourcePdf.getPage(6).setMediaBox(0, 0, 792, 612);
+    sourcePdf.getPage(6).node.delete(PDFName.of('CropBox'));
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_orientation_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const { code, stdout, stderr } = await runCLI([
+      '--file', sourcePath,
+      '--group-by', 'orientation',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_orientation',
+      '--name-template', '{basename}_{group}.pdf',
+      '--json-events'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const parts = JSON.parse(stdout).parts;
+    assert.deepStrictEqual(parts.map(part => part.group), ['portrait', 'landscape']);
+    assert.deepStrictEqual(parts[1].pages.content, [3, 7]);
+    assert.ok(await fileExists(path.join(TEST_OUTPUT_DIR, 'test_orientation_landscape.pdf')));
+  });
+  
+  it('separates color and black and white pages with --group-by color', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    // A red box on one page and a gray one, which prints in black ink, on another
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    sourcePdf.getPage(3).drawRectangle({ x: 100, y: 100, width: 50, height: 50, color: rgb(0.8, 0.1, 0.1) });
+    sourcePdf.getPage(5).drawRectangle({ x: 100, y: 100, width: 50, height: 50, color: rgb(0.5, 0.5, 0.5) });
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_color_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const { code, stdout, stderr } = await runCLI([
+      '--file', sourcePath,
+      '--group-by', 'color',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_color',
+      '--name-template', '{basename}_{group}.pdf',
+      '--json-events'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const parts = JSON.parse(stdout).parts;
+    assert.deepStrictEqual(parts.map(part => part.group), ['mono', 'color']);
+    assert.deepStrictEqual(parts[1].pages.content, [4]);
+    assert.ok(await fileExists(path.join(TEST_OUTPUT_DIR, 'test_color_color.pdf')));
+  });
+  
+  it('downsamples images and keeps identical streams once with --optimize', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    // The same 600x600 pixel image, embedded once per page, shown 100 points (1.4 inches) wide
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    const pixels = crypto.randomBytes(600 * 600 * 3);
+    for (const pageIndex of [0, 1]) {
+      const page = sourcePdf.getPage(pageIndex);
+      const image = sourcePdf.context.register(sourcePdf.context.flateStream(pixels, {
+        Type: 'XObject', Subtype: 'Image', Width: 600, Height: 600, ColorSpace: 'DeviceRGB', BitsPerComponent: 8
+      }));
+      page.node.setXObject(PDFName.of('Photo'), image);
+      page.pushOperators(
+        pushGraphicsState(),
+        concatTransformationMatrix(100, 0, 0, 100, 400, 500),
+        drawObject('Photo'),
+        popGraphicsState()
+      );
+    }
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_optimize_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const { code, stderr } = await runCLI([
+      '--file', sourcePath,
+      '--parts', '25',
+      '--optimize', '144',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_optimize'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    const partPdf = await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, 'test_optimize_part1.pdf')));
+    const images = partPdf.context.enumerateIndirectObjects()
+      .map(([, object]) => object)
+      .filter(object => object.dict && object.dict.get(PDFName.of('Subtype')) === PDFName.of('Image'));
+    assert.strictEqual(images.length, 1, 'Both pages share one image');
+    // 100 points at 144 dpi
+    assert.strictEqual(images[0].dict.get(PDFName.of('Width')).asNumber(), 200);
+    assert.strictEqual(images[0].dict.get(PDFName.of('Height')).asNumber(), 200);
+  });
+  
+  it('saves linearized parts with --linearize', async function() {
+    if (!await fileExists(TEST_PDF_PATH) || !isQpdfInstalled()) {
+      this.skip('Test PDF or qpdf not found.');
+      return;
+    }
+    
+    const { code, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--linearize',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_linearize'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    
+    // The linearization dictionary is the first object of the file
+    for (const index of [1, 2]) {
+      const bytes = await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, `test_linearize_part${index}.pdf`));
+      assert.match(bytes.subarray(0, 1024).toString('latin1'), /\/Linearized/);
+    }
+  });
+  
+  it('removes resources the pages of a part do not use', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    // Every page lists an image that no page draws
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    const image = sourcePdf.context.register(sourcePdf.context.flateStream(crypto.randomBytes(100 * 100 * 3), {
+      Type: 'XObject', Subtype: 'Image', Width: 100, Height: 100, ColorSpace: 'DeviceRGB', BitsPerComponent: 8
+    }));
+    for (const page of sourcePdf.getPages()) {
+      page.node.setXObject(PDFName.of('Unused'), image);
+    }
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_prune_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const countImages = async (filePath) => {
+      const partPdf = await PDFDocument.load(await fs.promises.readFile(filePath));
+      return partPdf.context.enumerateIndirectObjects()
+        .filter(([, object]) => object.dict && object.dict.get(PDFName.of('Subtype')) === PDFName.of('Image'))
+        .length;
+    };
+    
+    for (const [basename, extraArgs, expectedImages] of [['test_prune', [], 0], ['test_no_prune', ['--no-prune-resources'], 1]]) {
+      const { code, stderr } = await runCLI([
+        '--file', sourcePath,
+        '--parts', '2',
+        '--output-dir', TEST_OUTPUT_DIR,
+        '--output-basename', basename,
+        ...extraArgs
+      ]);
+      assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+      assert.strictEqual(await countImages(path.join(TEST_OUTPUT_DIR, `${basename}_part1.pdf`)), expectedImages);
+    }
+    assert.strictEqual(await getPdfPageCount(path.join(TEST_OUTPUT_DIR, 'test_prune_part1.pdf')), 25);
+  });
+  
+  it('returns error code 7 and writes nothing when the parts exceed --max-parts', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { code, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '20',
+      '--max-parts', '10',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_max_parts'
+    ]);
+    assert.strictEqual(code, 7, `CLI exits with code 7, but got ${code}. Stderr: ${stderr}`);
+    assert.match(stderr, /20 parts, more than the maximum of 10/);
+    assert.ok(!await fileExists(path.join(TEST_OUTPUT_DIR, 'test_max_parts_part1.pdf')));
+  });
+  
+  it('refuses output basenames and name templates leading out of the output directory', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    for (const extraArgs of [
+      ['--output-basename', '../test_escape'],
+      ['--output-basename', 'test:escape'],
+      ['--output-basename', 'test_escape', '--name-template', '../{basename}_{index}.pdf']
+    ]) {
+      const { code, stderr } = await runCLI([
+        '--file', TEST_PDF_PATH,
+        '--parts', '2',
+        '--output-dir', TEST_OUTPUT_DIR,
+        ...extraArgs
+      ]);
+      assert.strictEqual(code, 2, `CLI exits with code 2 for ${extraArgs.join(' ')}, but got ${code}. Stderr: ${stderr}`);
+    }
+    assert.ok(!await fileExists(path.join(TEST_OUTPUT_DIR, '..', 'test_escape_part1.pdf')));
+    assert.ok(!await fileExists(path.join(TEST_OUTPUT_DIR, '..', 'test_escape_1.pdf')));
+  });
+  
+  it('passes part and progress events to the onProgress callback', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { splitPdf } = require('../src/index');
+    const events = [];
+    const result = await splitPdf({
+      filePath: TEST_PDF_PATH,
+      parts: 2,
+      intro: null,
+      outputDir: TEST_OUTPUT_DIR,
+      outputBasename: 'test_on_progress',
+      onProgress: event => events.push(event)
+    });
+    
+    const partEvents = events.filter(event => event.event === 'partComplete');
+    assert.deepStrictEqual(partEvents.map(event => event.outputPath), result.parts.map(part => part.outputPath));
+    const progressEvents = events.filter(event => event.event === 'progress');
+    assert.strictEqual(progressEvents.length, 2, 'One progress event is emitted per part');
+    assert.strictEqual(progressEvents[1].percent, 100);
+    assert.strictEqual(events[events.length - 1].event, 'complete', 'The last event is complete');
+  });
+  
+  it('ends the events of splitPdfStream when the split succeeds or fails', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { splitPdfStream } = require('../src/index');
+    const options = { filePath: TEST_PDF_PATH, parts: 2, intro: null, outputDir: TEST_OUTPUT_DIR, outputBasename: 'test_stream' };
+    
+    const succeeding = splitPdfStream(options);
+    const events = [];
+    for await (const event of succeeding.events) {
+      events.push(event.event);
+    }
+    const result = await succeeding.result;
+    assert.strictEqual(result.parts.length, 2);
+    assert.strictEqual(events.filter(event => event === 'partComplete').length, 2);
+    assert.strictEqual(events[events.length - 1], 'complete');
+    
+    const failing = splitPdfStream({ ...options, filePath: path.join(TEST_OUTPUT_DIR, 'nonexistent.pdf') });
+    const failedEvents = [];
+    for await (const event of failing.events) {
+      failedEvents.push(event.event);
+    }
+    await assert.rejects(failing.result, error => error.code === 3);
+    assert.ok(!failedEvents.includes('complete'));
+  });
+  
+  it('splits a PDF held in memory with splitPdfFromBytes', async () => {
+    const { generatePdf, splitPdfFromBytes } = require('../src/index');
+    const sourceBytes = await generatePdf({ pages: 10 });
+    const result = await splitPdfFromBytes(sourceBytes, {
+      parts: 3,
+      intro: { start: 1, end: 1 },
+      outputDir: TEST_OUTPUT_DIR,
+      outputBasename: 'test_from_bytes'
+    });
+    
+    assert.deepStrictEqual(result.parts.map(part => path.basename(part.outputPath)), ['test_from_bytes_part1.pdf', 'test_from_bytes_part2.pdf', 'test_from_bytes_part3.pdf']);
+    for (const part of result.parts) {
+      assert.deepStrictEqual(part.pages.intro, [1]);
+      assert.strictEqual(part.pages.content.length, 3);
+      assert.strictEqual(await getPdfPageCount(part.outputPath), 4, 'Each part holds the intro page and its content pages');
+    }
+  });
+  
+  it('cancels a split with options.signal and removes the parts already written', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { splitPdf } = require('../src/index');
+    const abortController = new AbortController();
+    const written = [];
+    const split = splitPdf({
+      filePath: TEST_PDF_PATH,
+      parts: 3,
+      intro: null,
+      outputDir: TEST_OUTPUT_DIR,
+      outputBasename: 'test_abort',
+      threads: 1,
+      signal: abortController.signal,
+      onProgress: (event) => {
+        // Abort once the first part is on disk
+        if (event.event === 'partComplete') {
+          written.push(event.outputPath);
+          abortController.abort();
+        }
+      }
+    });
+    
+    await assert.rejects(split, error => error.code === 130);
+    assert.ok(written.length > 0 && written.length < 3, 'The split is cancelled before its last part');
+    for (const outputPath of written) {
+      assert.ok(!await fileExists(outputPath), `${outputPath} should be removed`);
+    }
+  });
+  
+  it('cancels a split on worker threads with options.signal and removes the parts already written', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { splitPdf } = require('../src/index');
+    const abortController = new AbortController();
+    const written = [];
+    const split = splitPdf({
+      filePath: TEST_PDF_PATH,
+      parts: 3,
+      intro: null,
+      outputDir: TEST_OUTPUT_DIR,
+      outputBasename: 'test_abort_threads',
+      threads: 2,
+      signal: abortController.signal,
+      onProgress: (event) => {
+        // Abort once the first part is on disk, while the workers build the others
+        if (event.event === 'partComplete') {
+          written.push(event.outputPath);
+          abortController.abort();
+        }
+      }
+    });
+    
+    await assert.rejects(split, error => error.code === 130);
+    assert.ok(written.length > 0 && written.length < 3, 'The split is cancelled before its last part');
+    for (const outputPath of written) {
+      assert.ok(!await fileExists(outputPath), `${outputPath} should be removed`);
+    }
+  });
+  
+  it('cancels a split with options.signal between the page copies of a part', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { splitPdf } = require('../src/index');
+    const abortController = new AbortController();
+    const pageProgress = [];
+    let partsCompleted = 0;
+    const split = splitPdf({
+      filePath: TEST_PDF_PATH,
+      parts: 1,
+      intro: null,
+      outputDir: TEST_OUTPUT_DIR,
+      outputBasename: 'test_abort_pages',
+      signal: abortController.signal,
+      onProgress: (event) => {
+        // Abort once the first page of the only part is copied
+        if (event.event === 'partProgress') {
+          pageProgress.push(event);
+          abortController.abort();
+        } else if (event.event === 'partComplete') {
+          partsCompleted++;
+        }
+      }
+    });
+    
+    await assert.rejects(split, error => error.code === 130);
+    assert.strictEqual(pageProgress.length, 1, 'No page is copied after the abort');
+    assert.ok(pageProgress[0].pagesCopied < pageProgress[0].pages, 'The part is cancelled before its last page');
+    assert.strictEqual(partsCompleted, 0);
+    assert.ok(!await fileExists(path.join(TEST_OUTPUT_DIR, 'test_abort_pages_part1.pdf')));
+  });
+  
+  it('writes each part into the stream the writer factory returns, without files', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { PassThrough } = require('node:stream');
+    const { splitPdfToWriters, SplitOptions } = require('../src/index');
+    const options = SplitOptions.builder()
+      .input(TEST_PDF_PATH)
+      .parts(2)
+      .outputDir(TEST_OUTPUT_DIR)
+      .outputBasename('test_writers')
+      .build();
+    const chunksByPart = new Map();
+    const result = await splitPdfToWriters(options, part => {
+      const stream = new PassThrough();
+      chunksByPart.set(part.index, []);
+      stream.on('data', chunk => chunksByPart.get(part.index).push(chunk));
+      return stream;
+    });
+    
+    assert.deepStrictEqual([...chunksByPart.keys()], [1, 2]);
+    for (const part of result.parts) {
+      const partPdf = await PDFDocument.load(Buffer.concat(chunksByPart.get(part.index)));
+      assert.strictEqual(partPdf.getPageCount(), part.pages.content.length);
+      assert.strictEqual(await fileExists(part.outputPath), false);
+    }
+  });
+  
+  it('builds each part only when the parts iterator asks for it', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { Splitter } = require('../src/index');
+    const splitter = await Splitter.open(TEST_PDF_PATH);
+    try {
+      const parts = splitter.parts({ parts: 3, outputDir: TEST_OUTPUT_DIR, outputBasename: 'test_iterator' });
+      const first = await parts.next();
+      assert.strictEqual(first.value.part.index, 1);
+      const firstPdf = await PDFDocument.load(first.value.bytes);
+      assert.strictEqual(firstPdf.getPageCount(), first.value.part.pages.content.length);
+      
+      const rest = [];
+      for await (const { part } of parts) {
+        rest.push(part.index);
+        assert.strictEqual(await fileExists(part.outputPath), false);
+      }
+      assert.deepStrictEqual(rest, [2, 3]);
+    } finally {
+      await splitter.close();
+    }
+  });
+  
+  it('leaves pages that fail to copy out of the parts with --repair', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { code, stdout, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', 'test_repair',
+      '--repair',
+      '--json-events'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    const { parts, warnings } = JSON.parse(stdout);
+    assert.ok(!warnings.some(warning => warning.code.startsWith('damaged')), 'Sound pages are all kept');
+    assert.deepStrictEqual(parts[0].droppedPages || [], []);
+    
+    // A backend failing on the second page stands in for a damaged source
+    const pdfLibBackend = require('../src/backends/pdf-lib');
+    const { findBrokenPages, dropBrokenPages } = require('../src/repair');
+    const damagedBackend = {
+      ...pdfLibBackend,
+      copyPages: async (source, pageIndices) => {
+        if (pageIndices.includes(1)) {
+          throw new Error('Expected instance of PDFDict, but got undefined');
+        }
+        return pdfLibBackend.copyPages(source, pageIndices);
+      }
+    };
+    const source = await pdfLibBackend.open(await fs.promises.readFile(TEST_PDF_PATH));
+    const brokenPages = await findBrokenPages(damagedBackend, source, [1, 2, 3, 4]);
+    assert.deepStrictEqual(brokenPages.map(brokenPage => brokenPage.page), [2]);
+    
+    const partInfos = [
+      { index: 1, pages: { intro: [], content: [1, 2] } },
+      { index: 2, pages: { intro: [], content: [2] } },
+      { index: 3, pages: { intro: [], content: [3, 4] } }
+    ];
+    const repairWarnings = dropBrokenPages(partInfos, brokenPages);
+    assert.deepStrictEqual(partInfos.map(partInfo => [partInfo.index, partInfo.pages.content]), [[1, [1]], [3, [3, 4]]]);
+    assert.deepStrictEqual(partInfos[0].droppedPages, [2]);
+    assert.deepStrictEqual(repairWarnings.map(warning => warning.code), ['damagedPagesSkipped', 'damagedPartsSkipped']);
+  });
+  
+  it('reads the source PDF from stdin with --file -', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const sourceBytes = await fs.promises.readFile(TEST_PDF_PATH);
+    const piped = spawnSync('node', [CLI_PATH, '--file', '-', '--parts', '2', '--output-dir', TEST_OUTPUT_DIR], { input: sourceBytes });
+    assert.strictEqual(piped.status, 0, `CLI exits with code 0, but got ${piped.status}. Stderr: ${piped.stderr}`);
+    const part1PageCount = await getPdfPageCount(path.join(TEST_OUTPUT_DIR, 'stdin_part1.pdf'));
+    const part2PageCount = await getPdfPageCount(path.join(TEST_OUTPUT_DIR, 'stdin_part2.pdf'));
+    assert.strictEqual(part1PageCount + part2PageCount, await getPdfPageCount(TEST_PDF_PATH));
+    
+    const empty = spawnSync('node', [CLI_PATH, '--file', '-', '--parts', '2', '--output-dir', TEST_OUTPUT_DIR], { input: '' });
+    assert.strictEqual(empty.status, 3, 'Exits with code 3 when nothing is piped in');
+  });
+  
+  it('reads the source from and uploads the parts to S3 URLs', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    // A local stand-in for S3, serving path-style URLs from memory
+    const http = require('node:http');
+    const objects = new Map([['/scans/in/report.pdf', await fs.promises.readFile(TEST_PDF_PATH)]]);
+    const authorizations = [];
+    const server = http.createServer((request, response) => {
+      authorizations.push(request.headers.authorization);
+      const chunks = [];
+      request.on('data', chunk => chunks.push(chunk));
+      request.on('end', () => {
+        if (request.method === 'PUT') {
+          objects.set(request.url, Buffer.concat(chunks));
+          response.end();
+        } else if (objects.has(request.url)) {
+          response.end(objects.get(request.url));
+        } else {
+          response.statusCode = 404;
+          response.end('<Error><Code>NoSuchKey</Code></Error>');
+        }
+      });
+    });
+    await new Promise(resolve => server.listen(0, '127.0.0.1', resolve));
+    
+    const savedEnv = { ...process.env };
+    Object.assign(process.env, {
+      AWS_ACCESS_KEY_ID: 'AKIDEXAMPLE',
+      AWS_SECRET_ACCESS_KEY: 'secret',
+      AWS_REGION: 'eu-west-1',
+      SPLITPDF_S3_ENDPOINT: `http://127.0.0.1:${server.address().port}`
+    });
+    try {
+      const { code, stderr } = await runCLI(['--file', 's3://scans/in/report.pdf', '--parts', '2', '--output-dir', 's3://scans/out']);
+      assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+      const part1Pdf = await PDFDocument.load(objects.get('/scans/out/report_part1.pdf'));
+      const part2Pdf = await PDFDocument.load(objects.get('/scans/out/report_part2.pdf'));
+      assert.strictEqual(part1Pdf.getPageCount() + part2Pdf.getPageCount(), await getPdfPageCount(TEST_PDF_PATH));
+      assert.ok(authorizations.every(authorization => /^AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE\/\d{8}\/eu-west-1\/s3\/aws4_request, /.test(authorization)));
+      
+      const missing = await runCLI(['--file', 's3://scans/in/missing.pdf', '--parts', '2', '--output-dir', 's3://scans/out']);
+      assert.strictEqual(missing.code, 3);
+      assert.match(missing.stderr, /NoSuchKey/);
+    } finally {
+      process.env = savedEnv;
+      server.close();
+    }
+  });
+  
+  it('runs the --exec command for each written part and handles its failures', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const hookPath = path.join(TEST_OUTPUT_DIR, 'test_exec_hook.js');
+    const logPath = path.join(TEST_OUTPUT_DIR, 'test_exec_hook.log');
+    await fs.promises.writeFile(hookPath, `require('fs').appendFileSync(${JSON.stringify(logPath)}, JSON.stringify(process.argv.slice(2)) + '\\n');`);
+    await fs.promises.rm(logPath, { force: true });
+    const args = ['--file', TEST_PDF_PATH, '--parts', '2', '--output-dir', TEST_OUTPUT_DIR, '--output-basename', 'test_exec'];
+    
+    const { code, stderr } = await runCLI([...args, '--exec', `node ${JSON.stringify(hookPath)} {index} {total} {range} {path}`]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    const calls = (await fs.promises.readFile(logPath, 'utf8')).trim().split('\n').map(line => JSON.parse(line));
+    assert.deepStrictEqual(calls.map(call => [call[0], call[1], call[3]]), [
+      ['1', '2', path.join(TEST_OUTPUT_DIR, 'test_exec_part1.pdf')],
+      ['2', '2', path.join(TEST_OUTPUT_DIR, 'test_exec_part2.pdf')]
+    ]);
+    assert.match(calls[0][2], /^1-\d+$/);
+    
+    const failing = ['--exec', 'node -e "process.exit(3)"'];
+    const continued = await runCLI([...args, ...failing, '--exec-failure', 'continue', '--json-events']);
+    assert.strictEqual(continued.code, 0);
+    const execWarnings = JSON.parse(continued.stdout).warnings.filter(warning => warning.code === 'execFailed');
+    assert.strictEqual(execWarnings.length, 2);
+    
+    const aborted = await runCLI([...args, ...failing]);
+    assert.strictEqual(aborted.code, 8);
+    assert.match(aborted.stderr, /--exec command for part 1 failed \(exit code 3\)/);
+  });
+  
+  it('runs the jobs of a job file and prints a status line per job', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const jobFilePath = path.join(TEST_OUTPUT_DIR, 'test_jobs.ndjson');
+    const jobs = [
+      { file: TEST_PDF_PATH, parts: 2, outputDir: TEST_OUTPUT_DIR, outputBasename: 'test_jobs_a' },
+      { file: path.join(TEST_OUTPUT_DIR, 'missing.pdf'), parts: 2, outputDir: TEST_OUTPUT_DIR },
+      { file: TEST_PDF_PATH, parts: 3, outputDir: TEST_OUTPUT_DIR, outputBasename: 'test_jobs_b' }
+    ];
+    await fs.promises.writeFile(jobFilePath, `${jobs.map(job => JSON.stringify(job)).join('\n')}\nnot json\n`);
+    
+    const { code, stdout, stderr } = await runCLI(['jobs', jobFilePath, '--concurrency', '2']);
+    assert.strictEqual(code, 3, `CLI exits with the first failed job's code 3, but got ${code}. Stderr: ${stderr}`);
+    const statuses = stdout.trim().split('\n').map(line => JSON.parse(line)).sort((a, b) => a.job - b.job);
+    assert.deepStrictEqual(statuses.map(status => [status.job, status.status]), [
+      [1, 'done'], [2, 'failed'], [3, 'done'], [4, 'failed']
+    ]);
+    assert.strictEqual(statuses[0].parts, 2);
+    assert.strictEqual(statuses[2].parts, 3);
+    for (const outputFile of [...statuses[0].outputFiles, ...statuses[2].outputFiles]) {
+      assert.ok(await fileExists(outputFile), `${outputFile} should exist`);
+    }
+    assert.strictEqual(statuses[1].error.code, 3);
+    assert.strictEqual(statuses[3].error.code, 2);
+  });
+  
+  it('resumes an interrupted split from its checkpoint', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const checkpointPath = path.join(TEST_OUTPUT_DIR, 'test_checkpoint.json');
+    await fs.promises.rm(checkpointPath, { force: true });
+    const args = ['--file', TEST_PDF_PATH, '--parts', '3', '--output-dir', TEST_OUTPUT_DIR, '--output-basename', 'test_checkpoint', '--checkpoint', checkpointPath];
+    
+    // Interrupt the split after its second part
+    const interrupted = await runCLI([...args, '--exec', 'node -e "process.exit(process.argv[1] === \'2\' ? 3 : 0)" {index}']);
+    assert.strictEqual(interrupted.code, 8);
+    const checkpoint = JSON.parse(await fs.promises.readFile(checkpointPath, 'utf8'));
+    assert.ok(checkpoint.completedParts.includes(1) && checkpoint.completedParts.includes(2));
+    assert.ok(await fileExists(path.join(TEST_OUTPUT_DIR, 'test_checkpoint_part2.pdf')), 'Recorded parts should be kept');
+    
+    const resumed = await runCLI([...args, '--json-events']);
+    assert.strictEqual(resumed.code, 0, `CLI exits with code 0, but got ${resumed.code}. Stderr: ${resumed.stderr}`);
+    const events = parseJsonLines(resumed.stderr);
+    const resumedEvent = events.find(event => event.event === 'resumed');
+    assert.deepStrictEqual(resumedEvent.completedParts.slice(0, 2), [1, 2]);
+    assert.ok(!events.some(event => event.event === 'partComplete' && event.part <= 2), 'Recorded parts should not be built again');
+    for (let i = 1; i <= 3; i++) {
+      assert.ok(await fileExists(path.join(TEST_OUTPUT_DIR, `test_checkpoint_part${i}.pdf`)), `Part ${i} should exist`);
+    }
+    assert.ok(!await fileExists(checkpointPath), 'The checkpoint should be removed once the split completes');
+    
+    // A checkpoint of another split is refused
+    await fs.promises.writeFile(checkpointPath, JSON.stringify({ version: 1, source: 'other', parts: [], completedParts: [] }));
+    const mismatched = await runCLI(args);
+    assert.strictEqual(mismatched.code, 2);
+    assert.match(mismatched.stderr, /different source PDF/);
+  });
+  
+  it('finishes a resumed split with no parts left to build on worker threads', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+
+    const { Splitter } = require('../src/index');
+    const { checkpointPlan, writeCheckpoint } = require('../src/checkpoint');
+    const checkpointPath = path.join(TEST_OUTPUT_DIR, 'test_checkpoint_done.json');
+    const options = { parts: 2, intro: null, outputDir: TEST_OUTPUT_DIR, outputBasename: 'test_checkpoint_done', threads: 2 };
+    const splitter = await Splitter.open(TEST_PDF_PATH);
+    try {
+      await splitter.split(options);
+      // As left by a split interrupted right after recording its last part
+      await writeCheckpoint(checkpointPath, checkpointPlan(splitter.sourceBytes, splitter.plan(options)), [1, 2]);
+
+      const events = [];
+      const result = await splitter.split({ ...options, checkpoint: checkpointPath, onProgress: event => events.push(event) });
+      assert.strictEqual(result.parts.length, 2);
+      assert.deepStrictEqual(events.find(event => event.event === 'resumed').completedParts, [1, 2]);
+      assert.ok(!events.some(event => event.event === 'partComplete'), 'No part should be built again');
+    } finally {
+      await splitter.close();
+    }
+    assert.ok(!await fileExists(checkpointPath), 'The checkpoint should be removed once the split completes');
+  });
+
+  it('applies split and part time limits', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const args = ['--file', TEST_PDF_PATH, '--parts', '2', '--output-dir', TEST_OUTPUT_DIR, '--output-basename', 'test_timeout'];
+    
+    const generous = await runCLI([...args, '--timeout', '60', '--part-timeout', '60']);
+    assert.strictEqual(generous.code, 0, `CLI exits with code 0, but got ${generous.code}. Stderr: ${generous.stderr}`);
+    assert.ok(await fileExists(path.join(TEST_OUTPUT_DIR, 'test_timeout_part2.pdf')), 'Parts should be written within the time limits');
+    
+    const tooShort = await runCLI([...args, '--output-basename', 'test_timeout_short', '--timeout', '0.001', '--json-events']);
+    assert.strictEqual(tooShort.code, 9);
+    const events = parseJsonLines(tooShort.stderr);
+    assert.ok(events.some(event => event.event === 'timedOut'), 'A timedOut event should be emitted');
+    assert.ok(!await fileExists(path.join(TEST_OUTPUT_DIR, 'test_timeout_short_part1.pdf')), 'Files of a timed out split should be removed');
+    
+    const invalid = await runCLI([...args, '--part-timeout', '0']);
+    assert.strictEqual(invalid.code, 2);
+  });
+
+  it('builds parts on a worker when the library is given a part time limit without threads', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+
+    const { splitPdf } = require('../src/index');
+    const result = await splitPdf({
+      filePath: TEST_PDF_PATH,
+      parts: 2,
+      intro: null,
+      outputDir: TEST_OUTPUT_DIR,
+      outputBasename: 'test_library_timeout',
+      partTimeout: 60000
+    });
+    assert.strictEqual(result.parts.length, 2);
+    for (const part of result.parts) {
+      assert.strictEqual(await getPdfPageCount(part.outputPath), part.pages.content.length);
+    }
+  });
+  
+  it('reports memory use and reacts to the memory limit', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const args = ['--file', TEST_PDF_PATH, '--parts', '2', '--output-dir', TEST_OUTPUT_DIR, '--output-basename', 'test_memory', '--json-events'];
+    
+    const monitored = await runCLI([...args, '--memory-interval', '0.01']);
+    assert.strictEqual(monitored.code, 0, `CLI exits with code 0, but got ${monitored.code}. Stderr: ${monitored.stderr}`);
+    const memoryEvent = parseJsonLines(monitored.stderr).find(event => event.event === 'memory');
+    assert.ok(memoryEvent && memoryEvent.rss > 0 && memoryEvent.heapUsed > 0, 'Memory events should report the memory use');
+    
+    // Any process is above a 1 MB limit
+    const reduced = await runCLI([...args, '--memory-limit', '1']);
+    assert.strictEqual(reduced.code, 0);
+    assert.ok(JSON.parse(reduced.stdout).warnings.some(warning => warning.code === 'memoryLimitExceeded'));
+    
+    const aborted = await runCLI([...args, '--output-basename', 'test_memory_abort', '--memory-limit', '1', '--memory-limit-action', 'abort']);
+    assert.strictEqual(aborted.code, 10);
+    assert.match(aborted.stderr, /Memory limit exceeded/);
+    assert.ok(!await fileExists(path.join(TEST_OUTPUT_DIR, 'test_memory_abort_part1.pdf')), 'Files of an aborted split should be removed');
+  });
+  
+  it('refuses a source too large to read into memory with code 10', async function() {
+    // A sparse file takes no disk space but reports its full size
+    const largePath = path.join(TEST_OUTPUT_DIR, 'test_too_large.pdf');
+    await fs.promises.writeFile(largePath, '');
+    await fs.promises.truncate(largePath, 2 ** 31);
+
+    const { splitPdf } = require('../src/index');
+    await assert.rejects(
+      splitPdf({ filePath: largePath, parts: 2, intro: null, outputDir: TEST_OUTPUT_DIR, outputBasename: 'test_too_large' }),
+      error => error.code === 10 && /too large to split in memory/.test(error.message) && /low memory mode/.test(error.message)
+    );
+  });
+  
+  it('splits with qpdf from disk with --low-memory and refuses options that need the source in memory', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+
+    const conflicting = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--output-dir', TEST_OUTPUT_DIR, '--low-memory', '--watermark', 'COPY']);
+    assert.strictEqual(conflicting.code, 2);
+    assert.match(conflicting.stderr, /Low memory mode .* watermarks/);
+
+    if (!isQpdfInstalled()) {
+      this.skip('qpdf not found.');
+      return;
+    }
+    const outputBasename = 'test_low_memory';
+    const { code, stderr } = await runCLI([
+      '--file', TEST_PDF_PATH,
+      '--parts', '2',
+      '--intro', '1:3',
+      '--output-dir', TEST_OUTPUT_DIR,
+      '--output-basename', outputBasename,
+      '--low-memory'
+    ]);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+
+    const sourcePdfPageCount = await getPdfPageCount(TEST_PDF_PATH);
+    const part1PageCount = await getPdfPageCount(path.join(TEST_OUTPUT_DIR, `${outputBasename}_part1.pdf`));
+    const part2PageCount = await getPdfPageCount(path.join(TEST_OUTPUT_DIR, `${outputBasename}_part2.pdf`));
+    assert.strictEqual(part1PageCount + part2PageCount, sourcePdfPageCount + 3);
+  });
+  
+  it('writes byte-identical parts with --deterministic', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const split = basename => runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--output-dir', TEST_OUTPUT_DIR, '--output-basename', basename, '--deterministic', '--cover']);
+    assert.strictEqual((await split('test_deterministic_a')).code, 0);
+    // Runs at different times must not differ
+    await new Promise(resolve => setTimeout(resolve, 1100));
+    assert.strictEqual((await split('test_deterministic_b')).code, 0);
+    
+    for (let i = 1; i <= 2; i++) {
+      const first = await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, `test_deterministic_a_part${i}.pdf`));
+      const second = await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, `test_deterministic_b_part${i}.pdf`));
+      assert.ok(first.equals(second), `Part ${i} should be byte-identical across runs`);
+    }
+  });
+  
+  it('generates synthetic PDFs with the generate command', async () => {
+    const outputPath = path.join(TEST_OUTPUT_DIR, 'test_generated.pdf');
+    const args = ['generate', outputPath, '--pages', '6', '--page-size', 'A4,300x400', '--bookmarks', '1=One;>2=Two;4=Four', '--page-labels', '1=r;3=D:P-'];
+    
+    const { code, stderr } = await runCLI(args);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    const bytes = await fs.promises.readFile(outputPath);
+    const pdf = await PDFDocument.load(bytes);
+    assert.strictEqual(pdf.getPageCount(), 6);
+    assert.deepStrictEqual(pdf.getPage(1).getSize(), { width: 300, height: 400 });
+    const { readOutline } = require('../src/outline');
+    const outline = readOutline(pdf);
+    assert.deepStrictEqual(outline.map(item => [item.title, item.pageIndex]), [['One', 0], ['Four', 3]]);
+    assert.deepStrictEqual(outline[0].children.map(item => [item.title, item.pageIndex]), [['Two', 1]]);
+    const { readPageLabels } = require('../src/page-labels');
+    assert.deepStrictEqual(readPageLabels(pdf).map(range => [range.startIndex, range.style, range.prefix]), [[0, 'r', ''], [2, 'D', 'P-']]);
+    
+    // The same description gives the same bytes
+    assert.strictEqual((await runCLI(args)).code, 0);
+    assert.ok(bytes.equals(await fs.promises.readFile(outputPath)));
+    
+    const invalid = await runCLI(['generate', outputPath, '--pages', '3', '--bookmarks', '5=Beyond']);
+    assert.strictEqual(invalid.code, 2);
+  });
+  
+  it('carries page sizes, bookmarks and page labels of a generated PDF into the parts', async function() {
+    // Skip if the structured fixture doesn't exist
+    if (!await fileExists(STRUCTURED_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { code, stderr } = await runCLI(['--file', STRUCTURED_PDF_PATH, '--parts', '3', '--output-dir', TEST_OUTPUT_DIR, '--output-basename', 'test_structured']);
+    assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+    const { readOutline } = require('../src/outline');
+    const { readPageLabels } = require('../src/page-labels');
+    const parts = [];
+    for (let i = 1; i <= 3; i++) {
+      parts.push(await PDFDocument.load(await fs.promises.readFile(path.join(TEST_OUTPUT_DIR, `test_structured_part${i}.pdf`))));
+    }
+    
+    // A4 and Letter pages alternate in every part
+    for (const part of parts) {
+      assert.deepStrictEqual(part.getPages().map(page => Math.round(page.getWidth())), [595, 612, 595, 612]);
+    }
+    // Pages 5-8 hold chapter 1 and its section
+    const outline = readOutline(parts[1]);
+    assert.deepStrictEqual(outline.map(item => [item.title, item.pageIndex]), [['Chapter 1', 0]]);
+    assert.deepStrictEqual(outline[0].children.map(item => [item.title, item.pageIndex]), [['Section 1.1', 1]]);
+    // The preface keeps its roman labels, and the body its arabic numbers
+    assert.deepStrictEqual(readPageLabels(parts[0]).map(range => [range.style, range.start]), [['r', 1]]);
+    assert.deepStrictEqual(readPageLabels(parts[2]).map(range => [range.style, range.start]), [['D', 5]]);
+  });
+  
+  it('refuses or reduces parts when there are fewer content pages than parts', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    // The intro leaves 5 content pages for 10 parts
+    const args = ['--file', TEST_PDF_PATH, '--intro', '1:45', '--parts', '10', '--output-dir', TEST_OUTPUT_DIR, '--output-basename', 'test_few_pages'];
+    
+    const refused = await runCLI(args);
+    assert.strictEqual(refused.code, 2);
+    assert.match(refused.stderr, /Cannot split 5 content pages into 10 parts/);
+    assert.ok(!await fileExists(path.join(TEST_OUTPUT_DIR, 'test_few_pages_part1.pdf')), 'No parts should be written');
+    
+    const reduced = await runCLI([...args, '--too-few-pages', 'reduce', '--json-events']);
+    assert.strictEqual(reduced.code, 0, `CLI exits with code 0, but got ${reduced.code}. Stderr: ${reduced.stderr}`);
+    const result = JSON.parse(reduced.stdout);
+    assert.deepStrictEqual(result.parts.map(part => part.pages.content), [[46], [47], [48], [49], [50]]);
+    assert.ok(result.warnings.some(warning => warning.code === 'partsReduced'));
+    
+    const allIntro = await runCLI(['--file', TEST_PDF_PATH, '--intro', '1:50', '--parts', '2', '--output-dir', TEST_OUTPUT_DIR, '--too-few-pages', 'reduce']);
+    assert.strictEqual(allIntro.code, 2);
+  });
+  
+  it('splits in the browser build without Node.js modules such as crypto and zlib', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    // Bundlers without Node.js polyfills can't provide these modules
+    const Module = require('node:module');
+    const originalLoad = Module._load;
+    Module._load = function(request, ...rest) {
+      if (['crypto', 'zlib'].includes(request.replace(/^node:/, ''))) {
+        throw new Error(`The browser build requires ${request}`);
+      }
+      return originalLoad.call(this, request, ...rest);
+    };
+    // Load the browser build and everything it requires afresh, not from modules other tests loaded
+    const srcDir = path.join(__dirname, '../src') + path.sep;
+    const loadedModules = Object.entries(require.cache).filter(([key]) => key.startsWith(srcDir));
+    for (const [key] of loadedModules) {
+      delete require.cache[key];
+    }
+    let browser;
+    try {
+      browser = require('../src/browser');
+    } finally {
+      Module._load = originalLoad;
+      Object.assign(require.cache, Object.fromEntries(loadedModules));
+    }
+    
+    const parts = await browser.splitPdfBytes(await fs.promises.readFile(TEST_PDF_PATH), { parts: 2 });
+    assert.strictEqual(parts.length, 2);
+    for (const part of parts) {
+      const partPdf = await PDFDocument.load(part.bytes);
+      assert.strictEqual(partPdf.getPageCount(), part.pages.content.length);
+    }
+  });
+  
+  it('returns error code 2 for an unknown backend', async () => {
+    const { code } = await runCLI(['--file', TEST_PDF_PATH, '--parts', '2', '--backend', 'nonexistent']);
+    assert.strictEqual(code, 2);
+  });
+  
+  it('splits every file matching an --input glob with a combined summary', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const inputDir = path.join(TEST_OUTPUT_DIR, 'batch-input');
+    await fs.promises.mkdir(inputDir, { recursive: true });
+    try {
+      await fs.promises.copyFile(TEST_PDF_PATH, path.join(inputDir, 'first.pdf'));
+      await fs.promises.copyFile(TEST_PDF_PATH, path.join(inputDir, 'second.pdf'));
+      
+      const { code, stdout, stderr } = await runCLI([
+        '--input', path.join(inputDir, '*.pdf'),
+        '--parts', '2',
+        '--output-dir', TEST_OUTPUT_DIR,
+        '--json-events'
+      ]);
+      
+      assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+      
+      const result = JSON.parse(stdout);
+      assert.strictEqual(result.files.length, 2);
+      
+      const events = parseJsonLines(stderr);
+      const summary = events[events.length - 1];
+      assert.strictEqual(summary.event, 'batchComplete', 'The last event is the combined summary');
+      assert.strictEqual(summary.files, 2);
+      assert.strictEqual(summary.parts, 4);
+      for (const event of events.slice(0, -1)) {
+        assert.ok(event.file.endsWith('.pdf'), 'Every file event names its source file');
+      }
+      
+      for (const outputFile of summary.outputFiles) {
+        assert.ok(await fileExists(outputFile), `Output file ${outputFile} exists`);
+      }
+    } finally {
+      await fs.promises.rm(inputDir, { recursive: true, force: true });
+    }
+  });
+  
+  it('returns error code 3 when no file matches --input', async () => {
+    const { code } = await runCLI(['--input', path.join(TEST_OUTPUT_DIR, 'missing-*.pdf'), '--parts', '2']);
+    assert.strictEqual(code, 3);
+  });
+  
+  it('mirrors the directory structure with --recursive and skips corrupt files', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const inputDir = path.join(TEST_OUTPUT_DIR, 'recursive-input');
+    const outputDir = path.join(TEST_OUTPUT_DIR, 'recursive-output');
+    await fs.promises.mkdir(path.join(inputDir, 'nested'), { recursive: true });
+    try {
+      await fs.promises.copyFile(TEST_PDF_PATH, path.join(inputDir, 'top.pdf'));
+      await fs.promises.copyFile(TEST_PDF_PATH, path.join(inputDir, 'nested', 'inner.pdf'));
+      await fs.promises.writeFile(path.join(inputDir, 'nested', 'corrupt.pdf'), 'not a pdf');
+      
+      const { code, stdout } = await runCLI([
+        '--recursive', inputDir,
+        '--parts', '2',
+        '--output-dir', outputDir,
+        '--json-events'
+      ]);
+      
+      assert.notStrictEqual(code, 0, 'Exits with the error code of the corrupt file');
+      
+      const result = JSON.parse(stdout);
+      assert.strictEqual(result.files.length, 2, 'The valid files are still split');
+      assert.strictEqual(result.failed.length, 1);
+      assert.ok(result.failed[0].filePath.endsWith('corrupt.pdf'));
+      
+      assert.ok(await fileExists(path.join(outputDir, 'top_part1.pdf')));
+      assert.ok(await fileExists(path.join(outputDir, 'nested', 'inner_part2.pdf')));
+    } finally {
+      await fs.promises.rm(inputDir, { recursive: true, force: true });
+      await fs.promises.rm(outputDir, { recursive: true, force: true });
+    }
+  });
+  
+  it('reads default options from a --config file and lets flags override them', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const configPath = path.join(TEST_OUTPUT_DIR, 'config.toml');
+    await fs.promises.writeFile(configPath, [
+      '# Defaults for the test',
+      'parts = 3',
+      `output_dir = ${JSON.stringify(TEST_OUTPUT_DIR)}`,
+      'name_template = "{basename}-{index}-of-{total}.pdf"'
+    ].join('\n'));
+    try {
+      const { code, stderr } = await runCLI([
+        '--file', TEST_PDF_PATH,
+        '--config', configPath,
+        '--parts', '2',
+        '--output-basename', 'test_config'
+      ]);
+      
+      assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+      assert.ok(await fileExists(path.join(TEST_OUTPUT_DIR, 'test_config-1-of-2.pdf')), 'Uses the template and the --parts flag');
+      assert.ok(await fileExists(path.join(TEST_OUTPUT_DIR, 'test_config-2-of-2.pdf')));
+      assert.ok(!await fileExists(path.join(TEST_OUTPUT_DIR, 'test_config-3-of-2.pdf')));
+    } finally {
+      await fs.promises.unlink(configPath);
+    }
+  });
+  
+  it('applies a named profile from the config file with --profile', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const configPath = path.join(TEST_OUTPUT_DIR, 'profile-config.toml');
+    await fs.promises.writeFile(configPath, [
+      'parts = 5',
+      '',
+      '[profile.lecture-notes]',
+      'parts = 2',
+      'intro = "1-2"'
+    ].join('\n'));
+    try {
+      const { code, stdout, stderr } = await runCLI([
+        '--file', TEST_PDF_PATH,
+        '--config', configPath,
+        '--profile', 'lecture-notes',
+        '--dry-run'
+      ]);
+      
+      assert.strictEqual(code, 0, `CLI exits with code 0, but got ${code}. Stderr: ${stderr}`);
+      const result = JSON.parse(stdout);
+      assert.strictEqual(result.parts.length, 2, 'The profile overrides the top-level parts');
+      assert.deepStrictEqual(result.parts[0].pages.intro, [1, 2]);
+      
+      const unknown = await runCLI(['--file', TEST_PDF_PATH, '--config', configPath, '--profile', 'missing']);
+      assert.strictEqual(unknown.code, 2);
+    } finally {
+      await fs.promises.unlink(configPath);
+    }
+  });
+  
+  it('returns error code 2 for an invalid config file', async () => {
+    const configPath = path.join(TEST_OUTPUT_DIR, 'invalid-config.toml');
+    await fs.promises.writeFile(configPath, 'parts = \n');
+    try {
+      const { code } = await runCLI(['--file', TEST_PDF_PATH, '--config', configPath]);
+      assert.strictEqual(code, 2);
+    } finally {
+      await fs.promises.unlink(configPath);
+    }
+  });
+  
+  it('prints document information as JSON with the info command', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { code, stdout } = await runCLI(['info', TEST_PDF_PATH]);
+    assert.strictEqual(code, 0);
+    
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    const info = JSON.parse(stdout);
+    assert.strictEqual(info.pageCount, sourcePdf.getPageCount());
+    assert.strictEqual(info.pages.length, info.pageCount, 'Lists the size of every page');
+    assert.ok(info.pages[0].width > 0 && info.pages[0].height > 0);
+    assert.strictEqual(info.pages[0].color, false, 'The test pages are black and white');
+    assert.match(info.pdfVersion, /^\d+\.\d+$/);
+    assert.strictEqual(info.encrypted, false);
+    assert.strictEqual(typeof info.hasOutline, 'boolean');
+    assert.strictEqual(info.tagged, false);
+    assert.strictEqual(info.pdfa, null);
+    assert.ok('title' in info.metadata);
+  });
+  
+  it('reads the document information with getPdfMetadata', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    sourcePdf.setTitle('Annual report');
+    sourcePdf.setAuthor('Finance');
+    sourcePdf.setCreator('Writer');
+    sourcePdf.setProducer('Typesetter');
+    sourcePdf.setCreationDate(new Date('2020-01-02T03:04:05Z'));
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_metadata_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const { getPdfMetadata } = require('../src/index');
+    const metadata = await getPdfMetadata(sourcePath);
+    assert.strictEqual(metadata.title, 'Annual report');
+    assert.strictEqual(metadata.author, 'Finance');
+    assert.strictEqual(metadata.creator, 'Writer');
+    assert.strictEqual(metadata.producer, 'Typesetter', 'Reading keeps the stored producer');
+    assert.strictEqual(metadata.creationDate, '2020-01-02T03:04:05.000Z');
+    assert.ok('modificationDate' in metadata && 'subject' in metadata && 'keywords' in metadata);
+  });
+  
+  it('reads the bookmark tree with getPdfOutline', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { writeOutline } = require('../src/outline');
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    writeOutline(sourcePdf, [
+      {
+        title: 'Chapter 1', pageIndex: 0, view: null, open: true, children: [
+          { title: 'Section 1.1', pageIndex: 1, view: null, open: true, children: [] }
+        ]
+      },
+      { title: 'Chapter 2', pageIndex: 2, view: null, open: true, children: [] }
+    ]);
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_outline_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const { getPdfOutline } = require('../src/index');
+    assert.deepStrictEqual(await getPdfOutline(sourcePath), [
+      {
+        title: 'Chapter 1', level: 1, page: 1, children: [
+          { title: 'Section 1.1', level: 2, page: 2, children: [] }
+        ]
+      },
+      { title: 'Chapter 2', level: 1, page: 3, children: [] }
+    ]);
+  });
+  
+  it('reads the size and rotation of every page with getPdfPageDimensions', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    sourcePdf.getPage(1).setSize(842, 595);
+    sourcePdf.getPage(1).setRotation(degrees(90));
+    const sourcePath = path.join(TEST_OUTPUT_DIR, 'test_dimensions_source.pdf');
+    await fs.promises.writeFile(sourcePath, await sourcePdf.save());
+    
+    const { getPdfPageDimensions } = require('../src/index');
+    const dimensions = await getPdfPageDimensions(sourcePath);
+    assert.strictEqual(dimensions.length, sourcePdf.getPageCount());
+    assert.deepStrictEqual(dimensions[1], { number: 2, width: 842, height: 595, rotation: 90 });
+    const { width, height } = sourcePdf.getPage(0).getSize();
+    assert.deepStrictEqual(dimensions[0], { number: 1, width, height, rotation: 0 });
+  });
+  
+  it('tells with probePdf whether a PDF needs a password and what it permits', async function() {
+    // Skip if test PDF doesn't exist or qpdf is missing
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const { probePdf } = require('../src/index');
+    assert.deepStrictEqual(await probePdf(TEST_PDF_PATH), { encrypted: false, requiresPassword: false, permissions: null });
+    
+    if (!isQpdfInstalled()) {
+      this.skip('qpdf is not installed.');
+      return;
+    }
+    const qpdf = process.env.SPLITPDF_QPDF || 'qpdf';
+    const lockedPath = path.join(TEST_OUTPUT_DIR, 'test_probe_locked.pdf');
+    spawnSync(qpdf, ['--encrypt', 'secret', 'owner', '256', '--', TEST_PDF_PATH, lockedPath]);
+    const locked = await probePdf(lockedPath);
+    assert.strictEqual(locked.encrypted, true);
+    assert.strictEqual(locked.requiresPassword, true);
+    
+    const restrictedPath = path.join(TEST_OUTPUT_DIR, 'test_probe_restricted.pdf');
+    spawnSync(qpdf, ['--encrypt', '', 'owner', '256', '--print=none', '--extract=n', '--', TEST_PDF_PATH, restrictedPath]);
+    const restricted = await probePdf(restrictedPath);
+    assert.strictEqual(restricted.requiresPassword, false, 'Opens with the empty user password');
+    assert.ok(!restricted.permissions.includes('print') && !restricted.permissions.includes('copy'));
+    assert.ok(restricted.permissions.includes('annotate'));
+  });
+  
+  it('reports structural problems with the validate command', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const sound = await runCLI(['validate', TEST_PDF_PATH]);
+    assert.strictEqual(sound.code, 0);
+    const soundReport = JSON.parse(sound.stdout);
+    assert.strictEqual(soundReport.valid, true);
+    assert.deepStrictEqual(soundReport.problems, []);
+    assert.ok(soundReport.pageCount > 0);
+    
+    const emptyPath = path.join(TEST_OUTPUT_DIR, 'test_validate_empty.pdf');
+    await fs.promises.writeFile(emptyPath, await (await PDFDocument.create()).save());
+    const empty = await runCLI(['validate', emptyPath]);
+    assert.strictEqual(empty.code, 4);
+    assert.deepStrictEqual(JSON.parse(empty.stdout).problems.map(problem => problem.code), ['no-pages']);
+    
+    const garbagePath = path.join(TEST_OUTPUT_DIR, 'test_validate_garbage.pdf');
+    await fs.promises.writeFile(garbagePath, 'not a PDF');
+    const garbage = await runCLI(['validate', garbagePath]);
+    assert.strictEqual(garbage.code, 4);
+    const garbageReport = JSON.parse(garbage.stdout);
+    assert.strictEqual(garbageReport.pageCount, null);
+    assert.strictEqual(garbageReport.problems[0].code, 'unreadable');
+  });
+  
+  it('prints the page count with the pagecount command', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    
+    const plain = await runCLI(['pagecount', TEST_PDF_PATH]);
+    assert.strictEqual(plain.code, 0);
+    assert.strictEqual(plain.stdout.trim(), String(sourcePdf.getPageCount()));
+    
+    const json = await runCLI(['pagecount', TEST_PDF_PATH, '--json']);
+    assert.strictEqual(json.code, 0);
+    assert.deepStrictEqual(JSON.parse(json.stdout), { pageCount: sourcePdf.getPageCount() });
+    
+    const missing = await runCLI(['pagecount', 'nonexistent.pdf']);
+    assert.strictEqual(missing.code, 3);
+  });
+  
+  it('returns error code 2 for the planner without a terminal', async () => {
+    const { code } = await runCLI(['plan', '--file', TEST_PDF_PATH]);
+    assert.strictEqual(code, 2);
+  });
+  
+  it('starts parts at bookmarks picked in the outline pane of the planner', async () => {
+    const { PassThrough } = require('node:stream');
+    const { runPlanner } = require('../src/planner');
+    const input = new PassThrough();
+    input.setRawMode = () => {};
+    const output = new PassThrough();
+    let screen = '';
+    output.on('data', (data) => {
+      screen = data.toString();
+    });
+    
+    const planned = runPlanner({
+      fileName: 'book.pdf',
+      totalPages: 20,
+      fileSize: 20000,
+      intro: null,
+      ranges: [{ start: 1, end: 20 }],
+      outline: [
+        { title: 'Chapter 1', level: 1, page: 1, children: [] },
+        { title: 'Chapter 2', level: 1, page: 8, children: [
+          { title: 'External link', level: 2, page: null, children: [] },
+          { title: 'Section 2.1', level: 2, page: 12, children: [] }
+        ] }
+      ],
+      input,
+      output
+    });
+    // Tab to the outline, pick Chapter 2 and Section 2.1, then Tab back and split
+    for (const keys of ['\t', '\x1b[B', '\r', '\x1b[B', '\r']) {
+      input.write(keys);
+      await new Promise(resolve => setImmediate(resolve));
+    }
+    assert.match(screen, /\*\s+Section 2\.1 \(page 12\)/, 'Marks the bookmarks parts start at');
+    assert.ok(!screen.includes('External link'), 'Lists only bookmarks opening a page');
+    input.write('\t');
+    input.write('\r');
+    
+    assert.deepStrictEqual(await planned, [{ start: 1, end: 7 }, { start: 8, end: 11 }, { start: 12, end: 20 }]);
+  });
+  
+  it('extracts pages and merges files with the extract and merge commands', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const extractedPath = path.join(TEST_OUTPUT_DIR, 'test_extract.pdf');
+    const extract = await runCLI(['extract', '--file', TEST_PDF_PATH, '--pages', '1-2,4', '--output', extractedPath]);
+    assert.strictEqual(extract.code, 0, `extract exits with code 0. Stderr: ${extract.stderr}`);
+    const extractedPdf = await PDFDocument.load(await fs.promises.readFile(extractedPath));
+    assert.strictEqual(extractedPdf.getPageCount(), 3);
+    
+    const mergedPath = path.join(TEST_OUTPUT_DIR, 'test_merge.pdf');
+    const merge = await runCLI(['merge', extractedPath, extractedPath, '--output', mergedPath]);
+    assert.strictEqual(merge.code, 0, `merge exits with code 0. Stderr: ${merge.stderr}`);
+    const mergedPdf = await PDFDocument.load(await fs.promises.readFile(mergedPath));
+    assert.strictEqual(mergedPdf.getPageCount(), 6);
+    
+    const invalidPages = await runCLI(['extract', '--file', TEST_PDF_PATH, '--pages', '0-1', '--output', extractedPath]);
+    assert.strictEqual(invalidPages.code, 2);
+  });
+  
+  it('splits an uploaded PDF through the HTTP server', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const server = spawn('node', [CLI_PATH, 'serve', '--listen', '127.0.0.1:0']);
+    try {
+      // Wait for the server to print its address
+      const baseUrl = await new Promise((resolve, reject) => {
+        let output = '';
+        server.stdout.on('data', (data) => {
+          output += data.toString();
+          const match = /Listening on (http:\/\/\S+)/.exec(output);
+          if (match) {
+            resolve(match[1]);
+          }
+        });
+        server.on('close', () => reject(new Error(`Server exited: ${output}`)));
+      });
+      
+      const createResponse = await fetch(`${baseUrl}/jobs?parts=2&intro=1:1`, {
+        method: 'POST',
+        body: await fs.promises.readFile(TEST_PDF_PATH)
+      });
+      assert.strictEqual(createResponse.status, 202);
+      const { id } = await createResponse.json();
+      
+      // Poll until the job finishes
+      let job;
+      do {
+        await new Promise(resolve => setTimeout(resolve, 100));
+        job = await (await fetch(`${baseUrl}/jobs/${id}`)).json();
+      } while (job.status === 'running');
+      
+      assert.strictEqual(job.status, 'done');
+      assert.strictEqual(job.parts.length, 2);
+      
+      const partResponse = await fetch(`${baseUrl}${job.parts[0].url}`);
+      assert.strictEqual(partResponse.status, 200);
+      const partPdf = await PDFDocument.load(await partResponse.arrayBuffer());
+      assert.strictEqual(partPdf.getPageCount(), job.parts[0].pages.intro.length + job.parts[0].pages.content.length);
+      
+      const invalidResponse = await fetch(`${baseUrl}/jobs?parts=0`, { method: 'POST', body: 'not a pdf' });
+      assert.strictEqual(invalidResponse.status, 400);
+    } finally {
+      server.kill();
+    }
+  });
+  
+  it('limits uploads and running jobs and deletes finished jobs in the HTTP server', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const http = require('node:http');
+    const os = require('node:os');
+    const { startServer } = require('../src/server');
+    const sourceBytes = await fs.promises.readFile(TEST_PDF_PATH);
+    const server = await startServer({ host: '127.0.0.1', port: 0, maxUploadSize: sourceBytes.length, maxJobs: 1, jobTtl: 1000 });
+    const baseUrl = `http://127.0.0.1:${server.address().port}`;
+    try {
+      const tooLarge = await fetch(`${baseUrl}/jobs?parts=2`, { method: 'POST', body: Buffer.concat([sourceBytes, Buffer.alloc(1)]) });
+      assert.strictEqual(tooLarge.status, 413);
+      
+      // An upload still in progress takes the only job slot
+      const pending = http.request(`${baseUrl}/jobs?parts=2`, { method: 'POST' });
+      const pendingResponse = new Promise(resolve => pending.on('response', resolve));
+      pending.write(sourceBytes.subarray(0, 100));
+      await new Promise(resolve => setTimeout(resolve, 100));
+      const refused = await fetch(`${baseUrl}/jobs?parts=2`, { method: 'POST', body: sourceBytes });
+      assert.strictEqual(refused.status, 429);
+      pending.end(sourceBytes.subarray(100));
+      const accepted = await pendingResponse;
+      assert.strictEqual(accepted.statusCode, 202);
+      const { id } = JSON.parse(await new Promise((resolve) => {
+        let body = '';
+        accepted.on('data', (chunk) => {
+          body += chunk;
+        });
+        accepted.on('end', () => resolve(body));
+      }));
+      
+      let job;
+      do {
+        await new Promise(resolve => setTimeout(resolve, 20));
+        job = await (await fetch(`${baseUrl}/jobs/${id}`)).json();
+      } while (job.status === 'running');
+      assert.strictEqual(job.status, 'done');
+      
+      const download = await fetch(`${baseUrl}${job.parts[0].url}`);
+      assert.strictEqual(download.status, 200);
+      assert.strictEqual((await PDFDocument.load(await download.arrayBuffer())).getPageCount(), job.parts[0].pages.intro.length + job.parts[0].pages.content.length);
+      // A part whose file was removed behind the server's back is not found, and the server keeps running
+      let jobDir = null;
+      for (const name of await fs.promises.readdir(os.tmpdir())) {
+        if (name.startsWith('splitpdf-server-') && fs.existsSync(path.join(os.tmpdir(), name, id))) {
+          jobDir = path.join(os.tmpdir(), name, id);
+        }
+      }
+      for (const name of await fs.promises.readdir(jobDir)) {
+        if (name !== 'source.pdf') {
+          await fs.promises.rm(path.join(jobDir, name));
+        }
+      }
+      assert.strictEqual((await fetch(`${baseUrl}${job.parts[1].url}`)).status, 404);
+      assert.strictEqual((await fetch(`${baseUrl}/jobs/${id}`)).status, 200);
+      
+      // Once the job lifetime has passed, the job and its parts are gone
+      await new Promise(resolve => setTimeout(resolve, 1200));
+      assert.strictEqual((await fetch(`${baseUrl}/jobs/${id}`)).status, 404);
+    } finally {
+      server.close();
+    }
+  });
+  
+  it('answers JSON-RPC requests in daemon mode', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    const requests = [
+      { jsonrpc: '2.0', id: 1, method: 'pageCount', params: { file: TEST_PDF_PATH } },
+      { jsonrpc: '2.0', id: 2, method: 'plan', params: { file: TEST_PDF_PATH, parts: 2, outputDir: TEST_OUTPUT_DIR } },
+      { jsonrpc: '2.0', id: 3, method: 'unknown' }
+    ];
+    const { code, stdout } = await new Promise((resolve) => {
+      const daemon = spawn('node', [CLI_PATH, 'daemon']);
+      let output = '';
+      daemon.stdout.on('data', (data) => {
+        output += data.toString();
+      });
+      daemon.on('close', (exitCode) => resolve({ code: exitCode, stdout: output }));
+      daemon.stdin.end(requests.map(request => JSON.stringify(request)).join('\n'));
+    });
+    
+    assert.strictEqual(code, 0);
+    const responses = new Map();
+    for (const response of parseJsonLines(stdout)) {
+      responses.set(response.id, response);
+    }
+    
+    const sourcePdf = await PDFDocument.load(await fs.promises.readFile(TEST_PDF_PATH));
+    assert.strictEqual(responses.get(1).result, sourcePdf.getPageCount());
+    assert.strictEqual(responses.get(2).result.parts.length, 2);
+    assert.strictEqual(responses.get(3).error.code, -32601);
+  });
+  
+  it('splits over gRPC with streamed progress events', async function() {
+    // Skip if test PDF doesn't exist
+    if (!await fileExists(TEST_PDF_PATH)) {
+      this.skip('Test PDF not found. Run npm run test:setup first.');
+      return;
+    }
+    
+    let grpc;
+    let protoLoader;
+    try {
+      grpc = require('@grpc/grpc-js');
+      protoLoader = require('@grpc/proto-loader');
+    } catch (error) {
+      this.skip('The optional gRPC packages are not installed.');
+      return;
+    }
+    const { startGrpcServer, PROTO_PATH, LOADER_OPTIONS } = require('../src/grpc-server');
+    const { server, port } = await startGrpcServer({ host: '127.0.0.1', port: 0 });
+    const { Splitter: SplitterClient } = grpc.loadPackageDefinition(protoLoader.loadSync(PROTO_PATH, LOADER_OPTIONS)).splitpdf;
+    const client = new SplitterClient(`127.0.0.1:${port}`, grpc.credentials.createInsecure());
+    try {
+      const sourcePageCount = await getPdfPageCount(TEST_PDF_PATH);
+      const info = await promisify(client.info.bind(client))({ file: TEST_PDF_PATH });
+      assert.strictEqual(info.pageCount, sourcePageCount);
+      assert.strictEqual(JSON.parse(info.infoJson).pageCount, sourcePageCount);
+      
+      const plan = await promisify(client.plan.bind(client))({ file: TEST_PDF_PATH, parts: 2, outputDir: TEST_OUTPUT_DIR });
+      assert.strictEqual(plan.parts.length, 2);
+      
+      const updates = [];
+      const call = client.split({ file: TEST_PDF_PATH, parts: 2, intro: '1:2', outputDir: TEST_OUTPUT_DIR, outputBasename: 'test_grpc', optionsJson: JSON.stringify({ bookmarks: 'none' }) });
+      for await (const update of call) {
+        updates.push(update);
+      }
+      const events = updates.filter(update => update.update === 'event').map(update => JSON.parse(update.event.eventJson));
+      assert.strictEqual(events.filter(event => event.event === 'partComplete').length, 2);
+      const last = updates[updates.length - 1];
+      assert.strictEqual(last.update, 'result');
+      assert.strictEqual(last.result.parts.length, 2);
+      for (const part of last.result.parts) {
+        assert.deepStrictEqual(part.introPages, [1, 2]);
+        assert.strictEqual(await getPdfPageCount(part.outputPath), part.introPages.length + part.contentPages.length);
+      }
+      
+      // Failures carry the exit code
+      await assert.rejects(
+        promisify(client.plan.bind(client))({ file: TEST_PDF_PATH, parts: 2, optionsJson: JSON.stringify({ bookmarks: 'sideways' }) }),
+        error => error.code === grpc.status.INVALID_ARGUMENT && error.metadata.get('splitpdf-exit-code')[0] === '2'
+      );
+    } finally {
+      client.close();
+      server.forceShutdown();
+    }
+  });
+});
@@ -7,8 +7,12 @@
 const fs = require('fs');
 const path = require('path');
 const PDFDocument = require('pdfkit');
+const { PDFDocument: PDFLibDocument, PDFName, PDFString } = require('pdf-lib');
+const { generatePdf } = require('../src/generate');
 
 const OUTPUT_PATH = path.join(__dirname, 'fixtures/test.pdf');
+const FORMS_OUTPUT_PATH = path.join(__dirname, 'fixtures/forms.pdf');
+const STRUCTURED_OUTPUT_PATH = path.join(__dirname, 'fixtures/structured.pdf');
 
 // Create a document with 50 pages
 function createTestPDF() {
@@ -47,11 +51,73 @@ function createTestPDF() {
   });
 }
 
+// Create a 4-page document with form fields and annotations:
+// a text field on page 1, a comment and a highlight on page 2,
+// a text field on page 3 and a checkbox on page 4
+async function createFormsPDF() {
+  const doc = await PDFLibDocument.create();
+  const form = doc.getForm();
+  const pages = [];
+  for (let i = 1; i <= 4; i++) {
+    const page = doc.addPage([612, 792]);
+    page.drawText(`Form page ${i}`, { x: 100, y: 700, size: 24 });
+    pages.push(page);
+  }
+  
+  const name = form.createTextField('name');
+  name.setText('Ada');
+  name.addToPage(pages[0], { x: 100, y: 600, width: 200, height: 24 });
+  
+  const comment = doc.context.register(doc.context.obj({
+    Type: 'Annot',
+    Subtype: 'Text',
+    Rect: [100, 600, 120, 620],
+    Contents: PDFString.of('Check this page'),
+    P: pages[1].ref
+  }));
+  const highlight = doc.context.register(doc.context.obj({
+    Type: 'Annot',
+    Subtype: 'Highlight',
+    Rect: [100, 695, 300, 725],
+    QuadPoints: [100, 725, 300, 725, 100, 695, 300, 695],
+    C: [1, 1, 0],
+    P: pages[1].ref
+  }));
+  pages[1].node.set(PDFName.of('Annots'), doc.context.obj([comment, highlight]));
+  
+  const city = form.createTextField('city');
+  city.setText('London');
+  city.addToPage(pages[2], { x: 100, y: 600, width: 200, height: 24 });
+  
+  const agree = form.createCheckBox('agree');
+  agree.check();
+  agree.addToPage(pages[3], { x: 100, y: 600, width: 20, height: 20 });
+  
+  fs.writeFileSync(FORMS_OUTPUT_PATH, await doc.save());
+  console.log(`Created test PDF with form fields and annotations at ${FORMS_OUTPUT_PATH}`);
+}
+
+// Create a 12-page document with A4 and Letter pages in turn, nested
+// bookmarks and page labels (i-iv, then 1-8), using the splitter's generator
+async function createStructuredPDF() {
+  fs.writeFileSync(STRUCTURED_OUTPUT_PATH, await generatePdf({
+    pages: 12,
+    pageSizes: ['A4', 'Letter'],
+    bookmarks: [
+      { title: 'Preface', page: 1 },
+      { title: 'Chapter 1', page: 5, children: [{ title: 'Section 1.1', page: 6 }] },
+      { title: 'Chapter 2', page: 9 }
+    ],
+    pageLabels: [{ page: 1, style: 'r' }, { page: 5, style: 'D' }]
+  }));
+  console.log(`Created test PDF with page sizes, bookmarks and page labels at ${STRUCTURED_OUTPUT_PATH}`);
+}
+
 // Ensure fixtures directory exists
 fs.mkdirSync(path.dirname(OUTPUT_PATH), { recursive: true });
 
-// Generate the test PDF
-createTestPDF().catch(err => {
+// Generate the test PDFs
+createTestPDF().then(createFormsPDF).then(createStructuredPDF).catch(err => {
   console.error('Error generating test PDF:', err);
   process.exit(1);
 }); 
\ No newline at end of file